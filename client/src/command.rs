@@ -1,70 +1,265 @@
-use crosscutting::networking;
-use gateway::proxy_client::proxy::{CommandResponse, CommandType};
+use crate::models::EphemeralKeys;
+use crosscutting::crypto::{Identity, PublicIdentity};
+use crosscutting::{networking, settings};
+use gateway::proxy_client::proxy::{CommandResponse, CommandType, SourceRoute};
 use gateway::proxy_client::{ProxyClientFactory, ProxyFactory};
-use gateway::route_client::{RouteClientFactory, RouterFactory};
+use gateway::route_client::route::{ContactAction, GroupAction};
+use gateway::route_client::{Recipient, RouteClientFactory, RouterFactory};
+use gateway::{group, onion};
+use prost::Message;
 use std::error::Error;
+use std::sync::Arc;
 use tonic::Status;
 use tonic::transport::Uri;
 
 pub enum Command {
-    Send(String, Vec<u8>),
+    Send(String, Vec<u8>, u32),
+    Anonymous(String, Vec<u8>, u32),
+    Reply(String, Vec<u8>, u32),
+    Onion(String, Vec<u8>, u32),
     Status,
+    ChangePassword(String, String),
+    UpdateContact(ContactAction, String),
+    Who(Vec<String>),
+    SetVisibility(bool),
+    GroupSend(String, Vec<u8>),
+    UpdateGroup(GroupAction, String, String),
 }
 
 impl Command {
     const SEND: &'static str = "/send";
+    const ANON: &'static str = "/anon";
+    const REPLY: &'static str = "/reply";
+    const ONION: &'static str = "/onion";
     const STATUS: &'static str = "/status";
+    const PASSWD: &'static str = "/passwd";
+    const BLOCK: &'static str = "/block";
+    const UNBLOCK: &'static str = "/unblock";
+    const ALLOW: &'static str = "/allow";
+    const WHO: &'static str = "/who";
+    const PRESENCE: &'static str = "/presence";
+    const GSEND: &'static str = "/gsend";
+    const GROUP: &'static str = "/group";
+    const HOPS: &'static str = "--hops";
 
     pub fn from_str(command: &str) -> Result<Self, String> {
         let mut wording = command.split_whitespace();
         let cmd = wording.nth(0).unwrap_or("");
         match cmd.to_lowercase().as_str() {
-            Command::SEND => {
-                if let Some(to) = wording.next() {
-                    let index = command.find(to).unwrap();
-                    let message: Vec<u8> = command[index + to.len()..].trim().as_bytes().into();
-                    if !message.is_empty() {
-                        return Ok(Command::Send(to.to_string(), message));
-                    }
+            Command::SEND => Self::parse_routed_message(command, Command::SEND)
+                .map(|(to, message, hops)| Command::Send(to, message, hops)),
+            Command::ANON => Self::parse_routed_message(command, Command::ANON)
+                .map(|(to, message, hops)| Command::Anonymous(to, message, hops)),
+            Command::REPLY => {
+                let (hops, arguments) = Self::parse_hops(command)?;
+                match Self::parse_message(
+                    arguments,
+                    arguments.split_whitespace().next(),
+                    Command::REPLY,
+                ) {
+                    Ok((reply_handle, message)) => Ok(Command::Reply(reply_handle, message, hops)),
+                    Err(_) => Err(format!(
+                        "Invalid command format. Usage: {} <reply_handle> <message>",
+                        Command::REPLY
+                    )),
                 }
-
-                Err("Invalid command format. Usage: /send <to> <message>".into())
             }
+            Command::ONION => Self::parse_routed_message(command, Command::ONION)
+                .map(|(to, message, hops)| Command::Onion(to, message, hops)),
             Command::STATUS => Ok(Command::Status),
+            Command::PASSWD => match (wording.next(), wording.next(), wording.next()) {
+                (Some(old_pwd), Some(new_pwd), None) => Ok(Command::ChangePassword(
+                    old_pwd.to_string(),
+                    new_pwd.to_string(),
+                )),
+                _ => Err(format!(
+                    "Invalid command format. Usage: {} <old_password> <new_password>",
+                    Command::PASSWD
+                )),
+            },
+            Command::BLOCK => Self::parse_contact(wording, Command::BLOCK)
+                .map(|uid| Command::UpdateContact(ContactAction::Block, uid)),
+            Command::UNBLOCK => Self::parse_contact(wording, Command::UNBLOCK)
+                .map(|uid| Command::UpdateContact(ContactAction::Unblock, uid)),
+            Command::ALLOW => Self::parse_contact(wording, Command::ALLOW)
+                .map(|uid| Command::UpdateContact(ContactAction::Allow, uid)),
+            Command::WHO => Ok(Command::Who(wording.map(str::to_string).collect())),
+            Command::PRESENCE => match (wording.next(), wording.next()) {
+                (Some("on"), None) => Ok(Command::SetVisibility(true)),
+                (Some("off"), None) => Ok(Command::SetVisibility(false)),
+                _ => Err(format!(
+                    "Invalid command format. Usage: {} <on|off>",
+                    Command::PRESENCE
+                )),
+            },
+            Command::GSEND => match Self::parse_message(
+                Self::get_arguments(command),
+                wording.next(),
+                Command::GSEND,
+            ) {
+                Ok((group, message)) => Ok(Command::GroupSend(group, message)),
+                Err(_) => Err(format!(
+                    "Invalid command format. Usage: {} <group> <message>",
+                    Command::GSEND
+                )),
+            },
+            Command::GROUP => match (
+                wording.next(),
+                wording.next(),
+                wording.next(),
+                wording.next(),
+            ) {
+                (Some("create"), Some(group), None, None) => Ok(Command::UpdateGroup(
+                    GroupAction::Create,
+                    group.to_string(),
+                    String::default(),
+                )),
+                (Some("delete"), Some(group), None, None) => Ok(Command::UpdateGroup(
+                    GroupAction::Delete,
+                    group.to_string(),
+                    String::default(),
+                )),
+                (Some("add"), Some(group), Some(uid), None) => Ok(Command::UpdateGroup(
+                    GroupAction::Add,
+                    group.to_string(),
+                    uid.to_string(),
+                )),
+                (Some("remove"), Some(group), Some(uid), None) => Ok(Command::UpdateGroup(
+                    GroupAction::Remove,
+                    group.to_string(),
+                    uid.to_string(),
+                )),
+                _ => Err(format!(
+                    "Invalid command format. Usage: {} <create|delete> <group> or {} <add|remove> <group> <uid>",
+                    Command::GROUP,
+                    Command::GROUP
+                )),
+            },
             _ => Err(format!("Unknown command: {}", command)),
         }
     }
+
+    fn parse_contact<'a>(
+        mut wording: impl Iterator<Item = &'a str>,
+        name: &str,
+    ) -> Result<String, String> {
+        match (wording.next(), wording.next()) {
+            (Some(uid), None) => Ok(uid.to_string()),
+            _ => Err(format!("Invalid command format. Usage: {} <uid>", name)),
+        }
+    }
+
+    fn parse_routed_message(command: &str, name: &str) -> Result<(String, Vec<u8>, u32), String> {
+        let (hops, arguments) = Self::parse_hops(command)?;
+        Self::parse_message(arguments, arguments.split_whitespace().next(), name)
+            .map(|(to, message)| (to, message, hops))
+    }
+
+    /// Splits the optional hop count from the arguments of the command, where
+    /// zero leaves the number of hops up to the controller.
+    fn parse_hops(command: &str) -> Result<(u32, &str), String> {
+        let arguments = Self::get_arguments(command);
+        match arguments.split_once(char::is_whitespace) {
+            Some((Command::HOPS, rest)) => {
+                let rest = rest.trim_start();
+                let (value, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                match value.parse::<u32>() {
+                    Ok(hops) if hops > 0 => Ok((hops, rest.trim_start())),
+                    _ => Err(format!(
+                        "Invalid hop count: '{}'. Usage: {} <number>",
+                        value,
+                        Command::HOPS
+                    )),
+                }
+            }
+            _ => Ok((0, arguments)),
+        }
+    }
+
+    fn get_arguments(command: &str) -> &str {
+        command
+            .trim()
+            .split_once(char::is_whitespace)
+            .map(|(_, arguments)| arguments.trim_start())
+            .unwrap_or_default()
+    }
+
+    fn parse_message(
+        command: &str,
+        to: Option<&str>,
+        name: &str,
+    ) -> Result<(String, Vec<u8>), String> {
+        if let Some(to) = to {
+            let index = command.find(to).unwrap();
+            let message: Vec<u8> = command[index + to.len()..].trim().as_bytes().into();
+            if !message.is_empty() {
+                return Ok((to.to_string(), message));
+            }
+        }
+
+        Err(format!(
+            "Invalid command format. Usage: {} <to> <message>",
+            name
+        ))
+    }
 }
 
 struct Route {
     conversation_id: String,
     nonce: String,
+    nonce_ticket: Vec<u8>,
     public_key: Vec<u8>,
     domain_name: String,
     uri: Uri,
+    recipient_key: Vec<u8>,
+    tickets: Vec<Vec<u8>>,
 }
 
 pub struct Commander {
     access_key: String,
+    identity: Identity,
+    ephemeral_keys: Arc<EphemeralKeys>,
     router_factory: Box<dyn RouterFactory>,
     proxy_factory: Box<dyn ProxyFactory>,
+    source_routing: bool,
 }
 
 impl Commander {
-    pub fn new(access_key: String) -> Self {
+    pub fn new(access_key: String, identity: Identity, ephemeral_keys: Arc<EphemeralKeys>) -> Self {
         Commander {
             access_key,
+            identity,
+            ephemeral_keys,
             router_factory: Box::new(RouteClientFactory),
             proxy_factory: Box::new(ProxyClientFactory),
+            source_routing: settings::service::is_source_routing_enabled(),
         }
     }
 
     pub async fn send_message(
         &mut self,
-        to: &str,
+        recipient: Recipient,
+        anonymous: bool,
+        hops: u32,
         content: &[u8],
     ) -> Result<CommandResponse, Box<dyn Error>> {
-        let route = self.initialize(to).await?;
+        // Anonymous messages are sealed with a key of their own, so they can't
+        // be linked to each other or to the member
+        let sender = if anonymous {
+            self.ephemeral_keys.generate()
+        } else {
+            self.identity.clone()
+        };
+        let anonymous_key = anonymous.then(|| sender.public().to_bytes());
+        let route = self
+            .initialize(recipient, anonymous_key, hops, self.source_routing)
+            .await?;
+        if route.recipient_key.is_empty() {
+            return Err("Recipient is not available".into());
+        }
+
+        let recipient = PublicIdentity::from_bytes(&route.recipient_key)?;
+        let sealed_content = sender.seal(&recipient, content)?;
         let mut proxy_client =
             self.proxy_factory
                 .get_proxy(route.uri, route.public_key, route.domain_name);
@@ -72,19 +267,140 @@ impl Commander {
             Status::internal(format!("Impossible to initialize proxy client: {}", e))
         })?;
 
+        if !route.tickets.is_empty() {
+            let source_route = SourceRoute {
+                tickets: route.tickets,
+                payload: sealed_content,
+            };
+            return proxy_client
+                .send_command(
+                    String::default(),
+                    String::default(),
+                    Vec::new(),
+                    CommandType::SourceRouted,
+                    source_route.encode_to_vec(),
+                )
+                .await;
+        }
+
         let response = proxy_client
             .send_command(
                 route.conversation_id,
                 route.nonce,
+                route.nonce_ticket,
                 CommandType::Send,
-                content.to_vec(),
+                sealed_content,
+            )
+            .await?;
+        Ok(response)
+    }
+
+    pub async fn send_onion(
+        &mut self,
+        to: &str,
+        hops: u32,
+        content: &[u8],
+    ) -> Result<CommandResponse, Box<dyn Error>> {
+        let mut router = self.router_factory.get_router();
+        router.initialize().await?;
+        let init_response = router
+            .init_conversation(
+                self.access_key.clone(),
+                Recipient::Uid(to.to_string()),
+                None,
+                hops,
+                false,
+            )
+            .await?;
+        if init_response.recipient_key.is_empty() {
+            return Err("Recipient is not available".into());
+        }
+
+        let recipient = PublicIdentity::from_bytes(&init_response.recipient_key)?;
+        let sealed_content = self.identity.seal(&recipient, content)?;
+        let circuit = router
+            .get_circuit(
+                init_response.conversation_id.clone(),
+                self.access_key.clone(),
+            )
+            .await?;
+        let (entry_hop, onion_content) = onion::wrap(
+            &init_response.conversation_id,
+            &circuit.hops,
+            &sealed_content,
+        )?;
+
+        let uri = networking::to_https_endpoint(&entry_hop.ip_address, entry_hop.port_number)?;
+        let mut proxy_client = self.proxy_factory.get_proxy(
+            uri,
+            entry_hop.public_key.clone(),
+            entry_hop.domain_name.clone(),
+        );
+        proxy_client.initialize().await.map_err(|e| {
+            Status::internal(format!("Impossible to initialize proxy client: {}", e))
+        })?;
+
+        let response = proxy_client
+            .send_command(
+                String::default(),
+                String::default(),
+                Vec::new(),
+                CommandType::Onion,
+                onion_content,
+            )
+            .await?;
+        Ok(response)
+    }
+
+    pub async fn send_group(
+        &mut self,
+        name: &str,
+        content: &[u8],
+    ) -> Result<CommandResponse, Box<dyn Error>> {
+        let mut router = self.router_factory.get_router();
+        router.initialize().await?;
+        let init_response = router
+            .init_group(self.access_key.clone(), name.to_string())
+            .await?;
+        if init_response.recipients.is_empty() {
+            return Err("No group members are available".into());
+        }
+
+        let envelope = group::seal(&self.identity, &init_response.recipients, content)?;
+        let route_response = router
+            .get_route(
+                init_response.conversation_id.clone(),
+                self.access_key.clone(),
+            )
+            .await?;
+        let uri =
+            networking::to_https_endpoint(&route_response.ip_address, route_response.port_number)?;
+
+        let mut proxy_client = self.proxy_factory.get_proxy(
+            uri,
+            route_response.public_key,
+            route_response.domain_name,
+        );
+        proxy_client.initialize().await.map_err(|e| {
+            Status::internal(format!("Impossible to initialize proxy client: {}", e))
+        })?;
+
+        let response = proxy_client
+            .send_command(
+                init_response.conversation_id,
+                route_response.nonce,
+                route_response.nonce_ticket,
+                CommandType::GroupSend,
+                envelope,
             )
             .await?;
         Ok(response)
     }
 
     pub async fn get_status(&mut self) -> Result<CommandResponse, Box<dyn Error>> {
-        let route = self.initialize(&String::default()).await?;
+        let route = self
+            .initialize(Recipient::Uid(String::default()), None, 0, false)
+            .await?;
         let mut proxy_client =
             self.proxy_factory
                 .get_proxy(route.uri, route.public_key, route.domain_name);
@@ -95,6 +411,7 @@ impl Commander {
             .send_command(
                 route.conversation_id,
                 route.nonce,
+                route.nonce_ticket,
                 CommandType::Status,
                 vec![],
             )
@@ -102,25 +419,108 @@ impl Commander {
         Ok(response)
     }
 
-    async fn initialize(&mut self, to: &str) -> Result<Route, Box<dyn Error>> {
+    pub async fn get_online_contacts(
+        &mut self,
+        uids: &[String],
+    ) -> Result<CommandResponse, Box<dyn Error>> {
+        let route = self
+            .initialize(Recipient::Uid(String::default()), None, 0, false)
+            .await?;
+        let mut proxy_client =
+            self.proxy_factory
+                .get_proxy(route.uri, route.public_key, route.domain_name);
+        proxy_client.initialize().await.map_err(|e| {
+            Status::internal(format!("Impossible to initialize proxy client: {}", e))
+        })?;
+        let response = proxy_client
+            .send_command(
+                route.conversation_id,
+                route.nonce,
+                route.nonce_ticket,
+                CommandType::Who,
+                uids.join(" ").into_bytes(),
+            )
+            .await?;
+        Ok(response)
+    }
+
+    pub async fn set_visibility(&mut self, visible: bool) -> Result<(), Box<dyn Error>> {
+        let mut router = self.router_factory.get_router();
+        router.initialize().await?;
+        router
+            .set_visibility(self.access_key.clone(), visible)
+            .await
+    }
+
+    pub async fn update_contact(
+        &mut self,
+        action: ContactAction,
+        uid: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut router = self.router_factory.get_router();
+        router.initialize().await?;
+        router
+            .update_contact(self.access_key.clone(), action, uid.to_string())
+            .await
+    }
+
+    pub async fn update_group(
+        &mut self,
+        action: GroupAction,
+        name: &str,
+        uid: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut router = self.router_factory.get_router();
+        router.initialize().await?;
+        router
+            .update_group(
+                self.access_key.clone(),
+                action,
+                name.to_string(),
+                uid.to_string(),
+            )
+            .await
+    }
+
+    async fn initialize(
+        &mut self,
+        recipient: Recipient,
+        anonymous_key: Option<Vec<u8>>,
+        hops: u32,
+        source_routed: bool,
+    ) -> Result<Route, Box<dyn Error>> {
         let mut router = self.router_factory.get_router();
         router.initialize().await?;
         let init_response = router
-            .init_conversation(self.access_key.clone(), to.to_string())
+            .init_conversation(
+                self.access_key.clone(),
+                recipient,
+                anonymous_key,
+                hops,
+                source_routed,
+            )
             .await?;
         let conversation_id = init_response.conversation_id;
-        let route_response = router
-            .get_route(conversation_id.clone(), self.access_key.clone())
-            .await?;
+        let route_response = match init_response.entry {
+            Some(entry) => entry,
+            None => {
+                router
+                    .get_route(conversation_id.clone(), self.access_key.clone())
+                    .await?
+            }
+        };
         let uri =
             networking::to_https_endpoint(&route_response.ip_address, route_response.port_number)?;
 
         Ok(Route {
             conversation_id: conversation_id.clone(),
             nonce: route_response.nonce,
+            nonce_ticket: route_response.nonce_ticket,
             public_key: route_response.public_key,
             domain_name: route_response.domain_name,
             uri: uri.clone(),
+            recipient_key: init_response.recipient_key,
+            tickets: init_response.tickets,
         })
     }
 }
@@ -139,7 +539,7 @@ mod tests {
         let command = Command::from_str(CMD_STR);
 
         assert!(command.is_ok());
-        if let Command::Send(to, content) = command.unwrap() {
+        if let Command::Send(to, content, _) = command.unwrap() {
             assert_eq!(to, EXPECTED_UID);
             assert_eq!(content, EXPECTED_CONTENT);
         }
@@ -167,6 +567,100 @@ mod tests {
         assert_eq!(command.err().unwrap(), EXPECTED_ERROR);
     }
 
+    #[test]
+    fn anon_command_from_str_is_well_formatted() {
+        const CMD_STR: &str = "/anon user123 Hello, World!";
+
+        let command = Command::from_str(CMD_STR);
+
+        if let Ok(Command::Anonymous(to, content, _)) = command {
+            assert_eq!(to, "user123");
+            assert_eq!(content, b"Hello, World!");
+        } else {
+            panic!("Expected an anonymous command");
+        }
+    }
+
+    #[test]
+    fn reply_command_from_str_is_well_formatted() {
+        const CMD_STR: &str = "/reply 9f2c4e Hello back!";
+
+        let command = Command::from_str(CMD_STR);
+
+        if let Ok(Command::Reply(reply_handle, content, _)) = command {
+            assert_eq!(reply_handle, "9f2c4e");
+            assert_eq!(content, b"Hello back!");
+        } else {
+            panic!("Expected a reply command");
+        }
+    }
+
+    #[test]
+    fn reply_command_from_str_with_no_content_returns_error() {
+        const CMD_STR: &str = "/reply 9f2c4e";
+        const EXPECTED_ERROR: &str =
+            "Invalid command format. Usage: /reply <reply_handle> <message>";
+
+        let command = Command::from_str(CMD_STR);
+
+        assert!(command.is_err());
+        assert_eq!(command.err().unwrap(), EXPECTED_ERROR);
+    }
+
+    #[test]
+    fn onion_command_from_str_is_well_formatted() {
+        const CMD_STR: &str = "/onion user123 Hello, World!";
+        const EXPECTED_UID: &str = "user123";
+        const EXPECTED_CONTENT: &[u8] = b"Hello, World!";
+
+        let command = Command::from_str(CMD_STR);
+
+        assert!(command.is_ok());
+        if let Command::Onion(to, content, _) = command.unwrap() {
+            assert_eq!(to, EXPECTED_UID);
+            assert_eq!(content, EXPECTED_CONTENT);
+        } else {
+            panic!("Expected an onion command");
+        }
+    }
+
+    #[test]
+    fn send_command_from_str_with_hops_is_well_formatted() {
+        const CMD_STR: &str = "/send --hops 5 user123 Hello, World!";
+
+        let command = Command::from_str(CMD_STR);
+
+        if let Ok(Command::Send(to, content, hops)) = command {
+            assert_eq!(to, "user123");
+            assert_eq!(content, b"Hello, World!");
+            assert_eq!(hops, 5);
+        } else {
+            panic!("Expected a send command");
+        }
+    }
+
+    #[test]
+    fn send_command_from_str_with_invalid_hops_returns_error() {
+        const CMD_STR: &str = "/send --hops many user123 Hello, World!";
+        const EXPECTED_ERROR: &str = "Invalid hop count: 'many'. Usage: --hops <number>";
+
+        let command = Command::from_str(CMD_STR);
+
+        assert!(command.is_err());
+        assert_eq!(command.err().unwrap(), EXPECTED_ERROR);
+    }
+
+    #[test]
+    fn onion_command_from_str_with_no_content_returns_error() {
+        const CMD_STR: &str = "/onion uid132132";
+        const EXPECTED_ERROR: &str = "Invalid command format. Usage: /onion <to> <message>";
+
+        let command = Command::from_str(CMD_STR);
+
+        assert!(command.is_err());
+        assert_eq!(command.err().unwrap(), EXPECTED_ERROR);
+    }
+
     #[test]
     fn status_command_from_str_is_well_formatted() {
         const CMD_STR: &str = "/status";
@@ -176,6 +670,162 @@ mod tests {
         assert!(command.is_ok());
     }
 
+    #[test]
+    fn passwd_command_from_str_is_well_formatted() {
+        const CMD_STR: &str = "/passwd old_secret new_secret";
+
+        let command = Command::from_str(CMD_STR);
+
+        if let Ok(Command::ChangePassword(old_pwd, new_pwd)) = command {
+            assert_eq!(old_pwd, "old_secret");
+            assert_eq!(new_pwd, "new_secret");
+        } else {
+            panic!("Expected a change password command");
+        }
+    }
+
+    #[test]
+    fn passwd_command_from_str_with_missing_password_returns_error() {
+        const CMD_STR: &str = "/passwd old_secret";
+        const EXPECTED_ERROR: &str =
+            "Invalid command format. Usage: /passwd <old_password> <new_password>";
+
+        let command = Command::from_str(CMD_STR);
+
+        assert!(command.is_err());
+        assert_eq!(command.err().unwrap(), EXPECTED_ERROR);
+    }
+
+    #[test]
+    fn contact_commands_from_str_are_well_formatted() {
+        for (cmd_str, expected_action, expected_uid) in [
+            ("/block user123", ContactAction::Block, "user123"),
+            (
+                "/unblock user123@beta",
+                ContactAction::Unblock,
+                "user123@beta",
+            ),
+            ("/allow *", ContactAction::Allow, "*"),
+        ] {
+            let command = Command::from_str(cmd_str);
+
+            if let Ok(Command::UpdateContact(action, uid)) = command {
+                assert_eq!(action, expected_action);
+                assert_eq!(uid, expected_uid);
+            } else {
+                panic!("Expected an update contact command");
+            }
+        }
+    }
+
+    #[test]
+    fn block_command_from_str_with_missing_uid_returns_error() {
+        const CMD_STR: &str = "/block";
+        const EXPECTED_ERROR: &str = "Invalid command format. Usage: /block <uid>";
+
+        let command = Command::from_str(CMD_STR);
+
+        assert!(command.is_err());
+        assert_eq!(command.err().unwrap(), EXPECTED_ERROR);
+    }
+
+    #[test]
+    fn who_command_from_str_is_well_formatted() {
+        const CMD_STR: &str = "/who client2 client3";
+
+        let command = Command::from_str(CMD_STR);
+
+        if let Ok(Command::Who(uids)) = command {
+            assert_eq!(uids, vec!["client2", "client3"]);
+        } else {
+            panic!("Expected a who command");
+        }
+    }
+
+    #[test]
+    fn presence_command_from_str_is_well_formatted() {
+        assert!(matches!(
+            Command::from_str("/presence on"),
+            Ok(Command::SetVisibility(true))
+        ));
+        assert!(matches!(
+            Command::from_str("/presence off"),
+            Ok(Command::SetVisibility(false))
+        ));
+    }
+
+    #[test]
+    fn presence_command_from_str_with_invalid_value_returns_error() {
+        const CMD_STR: &str = "/presence maybe";
+        const EXPECTED_ERROR: &str = "Invalid command format. Usage: /presence <on|off>";
+
+        let command = Command::from_str(CMD_STR);
+
+        assert!(command.is_err());
+        assert_eq!(command.err().unwrap(), EXPECTED_ERROR);
+    }
+
+    #[test]
+    fn gsend_command_from_str_is_well_formatted() {
+        const CMD_STR: &str = "/gsend friends Hello, everyone!";
+
+        let command = Command::from_str(CMD_STR);
+
+        if let Ok(Command::GroupSend(group, content)) = command {
+            assert_eq!(group, "friends");
+            assert_eq!(content, b"Hello, everyone!");
+        } else {
+            panic!("Expected a group send command");
+        }
+    }
+
+    #[test]
+    fn gsend_command_with_single_letter_group_from_str_is_well_formatted() {
+        const CMD_STR: &str = "/gsend g hi";
+
+        let command = Command::from_str(CMD_STR);
+
+        if let Ok(Command::GroupSend(group, content)) = command {
+            assert_eq!(group, "g");
+            assert_eq!(content, b"hi");
+        } else {
+            panic!("Expected a group send command");
+        }
+    }
+
+    #[test]
+    fn group_commands_from_str_are_well_formatted() {
+        for (cmd_str, expected_action, expected_uid) in [
+            ("/group create friends", GroupAction::Create, ""),
+            ("/group delete friends", GroupAction::Delete, ""),
+            ("/group add friends user123", GroupAction::Add, "user123"),
+            (
+                "/group remove friends user123",
+                GroupAction::Remove,
+                "user123",
+            ),
+        ] {
+            let command = Command::from_str(cmd_str);
+
+            if let Ok(Command::UpdateGroup(action, group, uid)) = command {
+                assert_eq!(action, expected_action);
+                assert_eq!(group, "friends");
+                assert_eq!(uid, expected_uid);
+            } else {
+                panic!("Expected an update group command");
+            }
+        }
+    }
+
+    #[test]
+    fn group_command_from_str_with_missing_uid_returns_error() {
+        const CMD_STR: &str = "/group add friends";
+
+        let command = Command::from_str(CMD_STR);
+
+        assert!(command.is_err());
+    }
+
     #[test]
     fn invalid_command_from_str_returns_error() {
         const CMD_STR: &str = "/invalid_command";
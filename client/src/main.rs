@@ -4,14 +4,17 @@ mod services;
 
 use gateway::auth::start_auth_handler;
 use gateway::auth_client::AuthClientFactory;
+use gateway::auth_client::Authenticator;
 use gateway::auth_client::AuthenticatorFactory;
 use gateway::auth_client::ClientSession;
 use gateway::proxy_client::proxy::CommandResponse;
+use gateway::route_client::Recipient;
 use command::Command;
 use command::Commander;
+use crosscutting::crypto::Identity;
 use crosscutting::{Component, ComponentDescriptor, settings::logging};
 use log::{debug, error, warn};
-use models::TextMessage;
+use models::{EphemeralKeys, TextMessage};
 use std::error::Error;
 use std::sync::Arc;
 use std::thread;
@@ -32,7 +35,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .get_local_socket_address();
     let (tx, rx) = tokio::sync::mpsc::channel::<TextMessage>(100);
     start_listener_handler(rx);
-    let server_handle = services::start_server_handler(socket_address, tx);
+    let identity = descriptor
+        .get_identity()
+        .ok_or("Client identity is not available")?
+        .clone();
+    let ephemeral_keys = Arc::new(EphemeralKeys::default());
+    let server_handle = services::start_server_handler(
+        socket_address,
+        tx,
+        identity.clone(),
+        Arc::clone(&ephemeral_keys),
+    );
     let client_session = Arc::new(RwLock::new(ClientSession::default()));
     let cmd_session = Arc::clone(&client_session);
     let cancellation_token = CancellationToken::new();
@@ -41,12 +54,20 @@ async fn main() -> Result<(), Box<dyn Error>> {
     authenticator.initialize().await?;
     let authenticator = Arc::new(RwLock::new(authenticator));
 
+    let cmd_authenticator = Arc::clone(&authenticator);
     let auth_handler = start_auth_handler(authenticator, cancellation_token.child_token());
 
     let (sender, receiver) = std::sync::mpsc::channel();
     start_input_thread(sender);
 
-    let cmd_handler = start_cmd_handler(cmd_session, receiver, cancellation_token.child_token());
+    let cmd_handler = start_cmd_handler(
+        cmd_session,
+        cmd_authenticator,
+        identity,
+        ephemeral_keys,
+        receiver,
+        cancellation_token.child_token(),
+    );
 
     _ = signal::ctrl_c().await;
     debug!("Received shutdown signal, terminating gracefully...");
@@ -62,6 +83,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
 fn start_cmd_handler(
     client_session: Arc<RwLock<ClientSession>>,
+    authenticator: Arc<RwLock<Box<dyn Authenticator>>>,
+    identity: Identity,
+    ephemeral_keys: Arc<EphemeralKeys>,
     receiver: std::sync::mpsc::Receiver<String>,
     cancellation_token: CancellationToken,
 ) -> tokio::task::JoinHandle<()> {
@@ -84,10 +108,79 @@ fn start_cmd_handler(
                     return;
                 }
 
-                let mut commander = Commander::new(access_key.unwrap());
+                let mut commander = Commander::new(
+                    access_key.unwrap(),
+                    identity.clone(),
+                    Arc::clone(&ephemeral_keys),
+                );
                 let response: Result<CommandResponse, Box<dyn Error>> = match cmd.unwrap() {
                     Command::Status => commander.get_status().await,
-                    Command::Send(to, content) => commander.send_message(&to, &content).await,
+                    Command::Send(to, content, hops) => {
+                        commander
+                            .send_message(Recipient::Uid(to), false, hops, &content)
+                            .await
+                    }
+                    Command::Anonymous(to, content, hops) => {
+                        commander
+                            .send_message(Recipient::Uid(to), true, hops, &content)
+                            .await
+                    }
+                    Command::Reply(reply_handle, content, hops) => {
+                        commander
+                            .send_message(
+                                Recipient::ReplyHandle(reply_handle),
+                                false,
+                                hops,
+                                &content,
+                            )
+                            .await
+                    }
+                    Command::Onion(to, content, hops) => {
+                        commander.send_onion(&to, hops, &content).await
+                    }
+                    Command::GroupSend(group, content) => {
+                        commander.send_group(&group, &content).await
+                    }
+                    Command::ChangePassword(old_pwd, new_pwd) => {
+                        let result = authenticator
+                            .write()
+                            .await
+                            .change_password(&old_pwd, &new_pwd)
+                            .await;
+                        match result {
+                            Ok(()) => println!("Password changed successfully"),
+                            Err(e) => warn!("Error changing password: {}", e),
+                        }
+                        continue;
+                    }
+                    Command::UpdateContact(action, uid) => {
+                        match commander.update_contact(action, &uid).await {
+                            Ok(()) => println!("Contact list updated"),
+                            Err(e) => warn!("Error updating contact list: {}", e),
+                        }
+                        continue;
+                    }
+                    Command::UpdateGroup(action, group, uid) => {
+                        match commander.update_group(action, &group, &uid).await {
+                            Ok(()) => println!("Group updated"),
+                            Err(e) => warn!("Error updating group: {}", e),
+                        }
+                        continue;
+                    }
+                    Command::SetVisibility(visible) => {
+                        match commander.set_visibility(visible).await {
+                            Ok(()) => println!("Presence updated"),
+                            Err(e) => warn!("Error updating presence: {}", e),
+                        }
+                        continue;
+                    }
+                    Command::Who(uids) => {
+                        match commander.get_online_contacts(&uids).await {
+                            Ok(response) => println!("{}", response.result.unwrap_or_default()),
+                            Err(e) => warn!("Error getting online contacts: {}", e),
+                        }
+                        continue;
+                    }
                 };
 
                 if let Ok(response) = response {
@@ -1,4 +1,8 @@
-use tokio::time::Instant;
+use crosscutting::crypto::{Identity, PublicIdentity};
+use std::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+const EPHEMERAL_KEY_LIFETIME: Duration = Duration::from_secs(3600);
 
 pub mod client_proto {
     tonic::include_proto!("client");
@@ -14,6 +18,8 @@ pub struct TextMessage {
     pub from: String,
     pub to: String,
     pub content: String,
+    pub reply_handle: Option<String>,
+    pub group: Option<String>,
     pub at: Instant,
 }
 
@@ -23,7 +29,46 @@ impl TextMessage {
             from,
             to,
             content: String::from_utf8_lossy(content).into(),
+            reply_handle: None,
+            group: None,
             at: Instant::now(),
         }
     }
+
+    pub fn with_reply_handle(mut self, reply_handle: String) -> Self {
+        if !reply_handle.is_empty() {
+            self.reply_handle = Some(reply_handle);
+        }
+        self
+    }
+
+    pub fn with_group(mut self, group: String) -> Self {
+        if !group.is_empty() {
+            self.group = Some(group);
+        }
+        self
+    }
+}
+
+/// Identities used for anonymous messages, kept for as long as replies to
+/// them can arrive.
+#[derive(Default)]
+pub struct EphemeralKeys {
+    keys: Mutex<Vec<(Instant, Identity)>>,
+}
+
+impl EphemeralKeys {
+    pub fn generate(&self) -> Identity {
+        let identity = Identity::generate();
+        let mut keys = self.keys.lock().unwrap();
+        keys.retain(|(created_at, _)| created_at.elapsed() < EPHEMERAL_KEY_LIFETIME);
+        keys.push((Instant::now(), identity.clone()));
+        identity
+    }
+
+    pub fn open(&self, sender: &PublicIdentity, envelope: &[u8]) -> Option<Vec<u8>> {
+        let keys = self.keys.lock().unwrap();
+        keys.iter()
+            .find_map(|(_, identity)| identity.open(sender, envelope).ok())
+    }
 }
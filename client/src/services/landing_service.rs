@@ -1,20 +1,29 @@
 use super::*;
 use crate::models::{
-    TextMessage,
+    EphemeralKeys, TextMessage,
     client_proto::{TextRequest, TextResponse, landing_service_server::LandingService},
 };
 
+use crosscutting::crypto::PublicIdentity;
 use gateway::route_client::{RouteClientFactory, RouterFactory};
 
 pub struct LandingServiceImpl {
     tx: Sender<TextMessage>,
+    identity: Identity,
+    ephemeral_keys: Arc<EphemeralKeys>,
     router_factory: Box<dyn RouterFactory>,
 }
 
 impl LandingServiceImpl {
-    pub fn new(tx: Sender<TextMessage>) -> Self {
+    pub fn new(
+        tx: Sender<TextMessage>,
+        identity: Identity,
+        ephemeral_keys: Arc<EphemeralKeys>,
+    ) -> Self {
         Self {
             tx,
+            identity,
+            ephemeral_keys,
             router_factory: Box::new(RouteClientFactory),
         }
     }
@@ -42,9 +51,24 @@ impl LandingService for LandingServiceImpl {
             .await
             .map_err(|_| Status::internal("Failed to redeem"))?;
 
-        info!("A new message has been received");
         let source = redeem_response.source_info.unwrap();
-        let message = TextMessage::new(source.from.clone(), "myself".into(), &text_request.content);
+        // Replies to anonymous messages are sealed for their ephemeral key
+        let content = PublicIdentity::from_bytes(&source.identity_key)
+            .and_then(|sender| {
+                self.identity
+                    .open(&sender, &text_request.content)
+                    .or_else(|e| {
+                        self.ephemeral_keys
+                            .open(&sender, &text_request.content)
+                            .ok_or(e)
+                    })
+            })
+            .map_err(|_| Status::invalid_argument("Failed to open the message content"))?;
+
+        info!("A new message has been received");
+        let message = TextMessage::new(source.from.clone(), "myself".into(), &content)
+            .with_reply_handle(source.reply_handle)
+            .with_group(source.group);
 
         _ = self.tx.send(message).await;
         Ok(Response::new(TextResponse {}))
@@ -60,12 +84,13 @@ mod tests {
         MockRouter, MockRouterFactory,
         route::{RedeemResponse, SourceInfo},
     };
-    use std::io::ErrorKind;
 
     const EXPECTED_ACCESS_KEY: &str = "test_access_key";
     const EXPECTED_NONCE: &str = "test_nonce";
     const EXPECTED_CONVERSATION_ID: &str = "test_conversation";
     const EXPECTED_SENDER_UID: &str = "sender_uid";
+    const EXPECTED_REPLY_HANDLE: &str = "test_reply_handle";
+    const EXPECTED_GROUP: &str = "test_group";
     const EXPECTED_MESSAGE: &str = "test_message";
 
     #[tokio::test]
@@ -77,8 +102,7 @@ mod tests {
             let mut mock_router = MockRouter::new();
             mock_router.expect_redeem().returning(|_, _, _| {
                 Box::pin(async {
-                    Err(Box::<dyn std::error::Error>::from(Error::new(
-                        ErrorKind::Other,
+                    Err(Box::<dyn std::error::Error>::from(Error::other(
                         "Something went wrong",
                     )))
                 })
@@ -89,6 +113,8 @@ mod tests {
 
         let service = LandingServiceImpl {
             tx,
+            identity: Identity::generate(),
+            ephemeral_keys: Arc::default(),
             router_factory: Box::new(router_factory),
         };
 
@@ -110,33 +136,23 @@ mod tests {
     #[tokio::test]
     async fn given_redeem_succeeds_when_receiving_message_then_channels_message() {
         let (tx, mut rx) = tokio::sync::mpsc::channel(100);
-
-        let mut router_factory = MockRouterFactory::new();
-        router_factory.expect_get_router().returning(|| {
-            let mut mock_router = MockRouter::new();
-            mock_router.expect_redeem().returning(|_, _, _| {
-                Box::pin(async {
-                    Ok(RedeemResponse {
-                        source_info: Some(SourceInfo {
-                            from: EXPECTED_SENDER_UID.into(),
-                        }),
-                    })
-                })
-            });
-
-            Box::new(mock_router)
-        });
+        let sender = Identity::generate();
+        let recipient = Identity::generate();
 
         let service = LandingServiceImpl {
             tx,
-            router_factory: Box::new(router_factory),
+            router_factory: create_router_factory(sender.public().to_bytes()),
+            identity: recipient.clone(),
+            ephemeral_keys: Arc::default(),
         };
 
         let request = Request::new(TextRequest {
             conversation_id: EXPECTED_CONVERSATION_ID.into(),
             access_key: EXPECTED_ACCESS_KEY.into(),
             nonce: EXPECTED_NONCE.into(),
-            content: EXPECTED_MESSAGE.into(),
+            content: sender
+                .seal(&recipient.public(), EXPECTED_MESSAGE.as_bytes())
+                .unwrap(),
         });
 
         let response = service.receive(request).await;
@@ -148,7 +164,95 @@ mod tests {
                 assert_eq!(message.from, EXPECTED_SENDER_UID);
                 assert_eq!(message.to, "myself");
                 assert_eq!(message.content, EXPECTED_MESSAGE);
+                assert_eq!(message.reply_handle.as_deref(), Some(EXPECTED_REPLY_HANDLE));
+                assert_eq!(message.group.as_deref(), Some(EXPECTED_GROUP));
             })
             .expect("Failed to receive message from channel");
     }
+
+    #[tokio::test]
+    async fn given_anonymous_reply_when_receiving_message_then_opens_with_ephemeral_key() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        let sender = Identity::generate();
+        let ephemeral_keys = Arc::new(EphemeralKeys::default());
+        let ephemeral = ephemeral_keys.generate();
+
+        let service = LandingServiceImpl {
+            tx,
+            router_factory: create_router_factory(sender.public().to_bytes()),
+            identity: Identity::generate(),
+            ephemeral_keys,
+        };
+
+        let request = Request::new(TextRequest {
+            conversation_id: EXPECTED_CONVERSATION_ID.into(),
+            access_key: EXPECTED_ACCESS_KEY.into(),
+            nonce: EXPECTED_NONCE.into(),
+            content: sender
+                .seal(&ephemeral.public(), EXPECTED_MESSAGE.as_bytes())
+                .unwrap(),
+        });
+
+        let response = service.receive(request).await;
+        assert!(response.is_ok());
+        assert_eq!(rx.recv().await.unwrap().content, EXPECTED_MESSAGE);
+    }
+
+    #[tokio::test]
+    async fn given_content_not_sealed_by_sender_when_receiving_message_then_returns_invalid_argument()
+     {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        let sender = Identity::generate();
+        let impostor = Identity::generate();
+        let recipient = Identity::generate();
+
+        let service = LandingServiceImpl {
+            tx,
+            router_factory: create_router_factory(sender.public().to_bytes()),
+            identity: recipient.clone(),
+            ephemeral_keys: Arc::default(),
+        };
+
+        let request = Request::new(TextRequest {
+            conversation_id: EXPECTED_CONVERSATION_ID.into(),
+            access_key: EXPECTED_ACCESS_KEY.into(),
+            nonce: EXPECTED_NONCE.into(),
+            content: impostor
+                .seal(&recipient.public(), EXPECTED_MESSAGE.as_bytes())
+                .unwrap(),
+        });
+
+        let response = service.receive(request).await;
+        assert!(response.is_err());
+
+        let error = response.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+        assert_eq!(error.message(), "Failed to open the message content");
+        assert!(rx.try_recv().is_err());
+    }
+
+    fn create_router_factory(sender_key: Vec<u8>) -> Box<dyn RouterFactory> {
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().returning(move || {
+            let sender_key = sender_key.clone();
+            let mut mock_router = MockRouter::new();
+            mock_router.expect_redeem().returning(move |_, _, _| {
+                let sender_key = sender_key.clone();
+                Box::pin(async move {
+                    Ok(RedeemResponse {
+                        source_info: Some(SourceInfo {
+                            from: EXPECTED_SENDER_UID.into(),
+                            identity_key: sender_key,
+                            reply_handle: EXPECTED_REPLY_HANDLE.into(),
+                            group: EXPECTED_GROUP.into(),
+                        }),
+                    })
+                })
+            });
+
+            Box::new(mock_router)
+        });
+
+        Box::new(router_factory)
+    }
 }
@@ -1,10 +1,13 @@
 pub mod landing_service;
-use crate::models::{TextMessage, client_proto::landing_service_server::LandingServiceServer};
-use crosscutting::{settings::service, tracing};
+use crate::models::{
+    EphemeralKeys, TextMessage, client_proto::landing_service_server::LandingServiceServer,
+};
+use crosscutting::{crypto::Identity, rate_limit, settings::service, tracing};
 use landing_service::LandingServiceImpl;
 use log::{error, info};
 use std::error::Error;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
 use tonic::transport::{Server, ServerTlsConfig};
 use tonic::{Request, Response, Status};
@@ -12,15 +15,31 @@ use tonic::{Request, Response, Status};
 pub struct ClientGrpcServer {
     socket_address: SocketAddr,
     tx: Sender<TextMessage>,
+    identity: Identity,
+    ephemeral_keys: Arc<EphemeralKeys>,
 }
 
 impl ClientGrpcServer {
-    pub fn new(socket_address: SocketAddr, tx: Sender<TextMessage>) -> Self {
-        Self { socket_address, tx }
+    pub fn new(
+        socket_address: SocketAddr,
+        tx: Sender<TextMessage>,
+        identity: Identity,
+        ephemeral_keys: Arc<EphemeralKeys>,
+    ) -> Self {
+        Self {
+            socket_address,
+            tx,
+            identity,
+            ephemeral_keys,
+        }
     }
 
     pub async fn start(&self) -> Result<(), Box<dyn Error>> {
-        let landing_service = LandingServiceImpl::new(self.tx.clone());
+        let landing_service = LandingServiceImpl::new(
+            self.tx.clone(),
+            self.identity.clone(),
+            Arc::clone(&self.ephemeral_keys),
+        );
         let identity = service::load_tls_identity("server.crt", "server.key").unwrap();
         let tls_config = ServerTlsConfig::new().identity(identity);
 
@@ -28,6 +47,7 @@ impl ClientGrpcServer {
             .tls_config(tls_config)
             .unwrap()
             .layer(tracing::UriTracingLayer)
+            .layer(rate_limit::RateLimitLayer::get_from_env())
             .add_service(LandingServiceServer::new(landing_service))
             .serve(self.socket_address)
             .await?;
@@ -39,9 +59,11 @@ impl ClientGrpcServer {
 pub fn start_server_handler(
     socket_address: SocketAddr,
     tx: Sender<TextMessage>,
+    identity: Identity,
+    ephemeral_keys: Arc<EphemeralKeys>,
 ) -> tokio::task::JoinHandle<()> {
     info!("Starting gRPC server on {}...", socket_address);
-    let grpc_server = ClientGrpcServer::new(socket_address, tx);
+    let grpc_server = ClientGrpcServer::new(socket_address, tx, identity, ephemeral_keys);
     tokio::spawn(async move {
         if let Err(e) = grpc_server.start().await {
             error!("gRPC server error: {}", e);
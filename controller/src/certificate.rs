@@ -0,0 +1,148 @@
+use std::error::Error;
+use x509_parser::pem::Pem;
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+/// Tells whether the certificate was issued directly by one of the CAs in the
+/// PEM bundle. Members and federation peers are trusted through different CAs
+/// sharing the same listener, so a certificate can only be used for what its
+/// own CA vouches for.
+pub fn is_issued_by(der: &[u8], ca_pem: &[u8]) -> bool {
+    let Ok((_, certificate)) = X509Certificate::from_der(der) else {
+        return false;
+    };
+
+    Pem::iter_from_buffer(ca_pem)
+        .filter_map(Result::ok)
+        .any(|pem| {
+            pem.parse_x509().is_ok_and(|ca| {
+                certificate.issuer() == ca.subject()
+                    && certificate.verify_signature(Some(ca.public_key())).is_ok()
+            })
+        })
+}
+
+pub fn get_certificate_uids(der: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+    let (_, certificate) =
+        X509Certificate::from_der(der).map_err(|e| format!("Invalid client certificate: {}", e))?;
+    let mut uids: Vec<String> = certificate
+        .subject()
+        .iter_common_name()
+        .filter_map(|common_name| common_name.as_str().ok())
+        .map(String::from)
+        .collect();
+
+    if let Ok(Some(alternative_names)) = certificate.subject_alternative_name() {
+        for name in alternative_names.value.general_names.iter() {
+            match name {
+                GeneralName::DNSName(value)
+                | GeneralName::RFC822Name(value)
+                | GeneralName::URI(value) => uids.push(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    uids.dedup();
+    Ok(uids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{
+        BasicConstraints, Certificate, CertificateParams, DistinguishedName, DnType, IsCa, KeyPair,
+        SanType,
+    };
+
+    const EXPECTED_COMMON_NAME: &str = "client1";
+    const EXPECTED_ALTERNATIVE_NAME: &str = "client1@fuzzy-chat";
+
+    fn create_certificate(common_name: Option<&str>, alternative_names: Vec<SanType>) -> Vec<u8> {
+        let mut params = CertificateParams::default();
+        let mut distinguished_name = DistinguishedName::new();
+        if let Some(common_name) = common_name {
+            distinguished_name.push(DnType::CommonName, common_name);
+        }
+        params.distinguished_name = distinguished_name;
+        params.subject_alt_names = alternative_names;
+
+        let key_pair = KeyPair::generate().unwrap();
+        params.self_signed(&key_pair).unwrap().der().to_vec()
+    }
+
+    #[test]
+    fn given_certificate_with_common_name_and_san_when_getting_uids_then_returns_both() {
+        let der = create_certificate(
+            Some(EXPECTED_COMMON_NAME),
+            vec![SanType::Rfc822Name(
+                EXPECTED_ALTERNATIVE_NAME.try_into().unwrap(),
+            )],
+        );
+
+        let uids = get_certificate_uids(&der).unwrap();
+
+        assert_eq!(uids, vec![EXPECTED_COMMON_NAME, EXPECTED_ALTERNATIVE_NAME]);
+    }
+
+    #[test]
+    fn given_certificate_without_common_name_when_getting_uids_then_returns_san() {
+        let der = create_certificate(
+            None,
+            vec![SanType::DnsName(EXPECTED_COMMON_NAME.try_into().unwrap())],
+        );
+
+        let uids = get_certificate_uids(&der).unwrap();
+
+        assert_eq!(uids, vec![EXPECTED_COMMON_NAME]);
+    }
+
+    fn create_ca(common_name: &str) -> (Certificate, KeyPair) {
+        let mut params = CertificateParams::default();
+        let mut distinguished_name = DistinguishedName::new();
+        distinguished_name.push(DnType::CommonName, common_name);
+        params.distinguished_name = distinguished_name;
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+
+        let key_pair = KeyPair::generate().unwrap();
+        (params.self_signed(&key_pair).unwrap(), key_pair)
+    }
+
+    fn create_issued_certificate(ca: &Certificate, ca_key: &KeyPair) -> Vec<u8> {
+        let params = CertificateParams::new(vec![EXPECTED_COMMON_NAME.to_string()]).unwrap();
+        let key_pair = KeyPair::generate().unwrap();
+        params
+            .signed_by(&key_pair, ca, ca_key)
+            .unwrap()
+            .der()
+            .to_vec()
+    }
+
+    #[test]
+    fn given_certificate_issued_by_ca_when_checking_issuer_then_returns_true() {
+        let (ca, ca_key) = create_ca("members");
+        let der = create_issued_certificate(&ca, &ca_key);
+
+        assert!(is_issued_by(&der, ca.pem().as_bytes()));
+    }
+
+    #[test]
+    fn given_certificate_issued_by_other_ca_when_checking_issuer_then_returns_false() {
+        let (members_ca, members_key) = create_ca("members");
+        let (federation_ca, _) = create_ca("federation");
+        let (impostor_ca, impostor_key) = create_ca("federation");
+        let der = create_issued_certificate(&members_ca, &members_key);
+        let forged = create_issued_certificate(&impostor_ca, &impostor_key);
+
+        assert!(!is_issued_by(&der, federation_ca.pem().as_bytes()));
+        assert!(!is_issued_by(&forged, federation_ca.pem().as_bytes()));
+        let bundle = format!("{}\n{}", federation_ca.pem(), members_ca.pem());
+        assert!(is_issued_by(&der, bundle.as_bytes()));
+    }
+
+    #[test]
+    fn given_invalid_der_when_getting_uids_then_returns_error() {
+        let result = get_certificate_uids(b"not a certificate");
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::models::ContactList;
+use crate::storage::{self, ContactRepository, RepositoryType};
+
+const EVERYONE: &str = "*";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContactAction {
+    Block,
+    Unblock,
+    Allow,
+}
+
+impl ContactList {
+    pub fn new(uid: &str) -> Self {
+        Self {
+            uid: uid.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn permits(&self, from: &str) -> bool {
+        if self.blocked.iter().any(|uid| uid == from) {
+            return false;
+        }
+
+        self.allowed.is_empty() || self.allowed.iter().any(|uid| uid == from)
+    }
+
+    fn apply(&mut self, action: ContactAction, contact: &str) {
+        match action {
+            ContactAction::Block => {
+                self.allowed.retain(|uid| uid != contact);
+                if !self.blocked.iter().any(|uid| uid == contact) {
+                    self.blocked.push(contact.to_string());
+                }
+            }
+            ContactAction::Unblock => self.blocked.retain(|uid| uid != contact),
+            ContactAction::Allow if contact == EVERYONE => self.allowed.clear(),
+            ContactAction::Allow => {
+                self.blocked.retain(|uid| uid != contact);
+                if !self.allowed.iter().any(|uid| uid == contact) {
+                    self.allowed.push(contact.to_string());
+                }
+            }
+        }
+    }
+}
+
+pub struct ContactManager {
+    repository: Box<dyn ContactRepository>,
+}
+
+impl ContactManager {
+    pub fn new(repository_type: RepositoryType) -> Self {
+        Self {
+            repository: storage::create_contact_repository(repository_type).unwrap(),
+        }
+    }
+
+    pub async fn get_contacts(&self, uid: &str) -> ContactList {
+        self.repository
+            .get_contacts(uid)
+            .await
+            .unwrap_or_else(|| ContactList::new(uid))
+    }
+
+    pub async fn update(&self, uid: &str, action: ContactAction, contact: &str) -> ContactList {
+        let mut contacts = self.get_contacts(uid).await;
+        contacts.apply(action, contact);
+        self.repository.set_contacts(&contacts).await;
+        contacts
+    }
+
+    pub async fn set_visible(&self, uid: &str, visible: bool) {
+        let mut contacts = self.get_contacts(uid).await;
+        contacts.visible = visible;
+        self.repository.set_contacts(&contacts).await;
+    }
+
+    pub async fn is_allowed(&self, from: &str, to: &str) -> bool {
+        self.repository
+            .get_contacts(to)
+            .await
+            .is_none_or(|contacts| contacts.permits(from))
+    }
+
+    /// Presence is opt-in, and members who don't accept messages from the
+    /// viewer look offline to them as well.
+    pub async fn is_visible(&self, uid: &str, to: &str) -> bool {
+        self.repository
+            .get_contacts(uid)
+            .await
+            .is_some_and(|contacts| contacts.visible && contacts.permits(to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MockContactRepository;
+
+    const EXPECTED_UID: &str = "test_uid";
+    const EXPECTED_CONTACT: &str = "test_contact";
+    const OTHER_CONTACT: &str = "other_contact";
+
+    impl ContactManager {
+        fn with_repository(repository: Box<dyn ContactRepository>) -> Self {
+            Self { repository }
+        }
+    }
+
+    #[test]
+    fn given_empty_lists_when_checking_then_everyone_is_permitted() {
+        let contacts = ContactList::new(EXPECTED_UID);
+
+        assert!(contacts.permits(EXPECTED_CONTACT));
+    }
+
+    #[test]
+    fn given_blocked_contact_when_checking_then_is_not_permitted() {
+        let mut contacts = ContactList::new(EXPECTED_UID);
+        contacts.apply(ContactAction::Block, EXPECTED_CONTACT);
+
+        assert!(!contacts.permits(EXPECTED_CONTACT));
+        assert!(contacts.permits(OTHER_CONTACT));
+
+        contacts.apply(ContactAction::Unblock, EXPECTED_CONTACT);
+
+        assert!(contacts.permits(EXPECTED_CONTACT));
+    }
+
+    #[test]
+    fn given_allowlist_when_checking_then_only_allowed_contacts_are_permitted() {
+        let mut contacts = ContactList::new(EXPECTED_UID);
+        contacts.apply(ContactAction::Allow, EXPECTED_CONTACT);
+
+        assert!(contacts.permits(EXPECTED_CONTACT));
+        assert!(!contacts.permits(OTHER_CONTACT));
+
+        contacts.apply(ContactAction::Allow, EVERYONE);
+
+        assert!(contacts.permits(OTHER_CONTACT));
+    }
+
+    #[test]
+    fn given_allowed_contact_when_blocking_then_is_moved_to_blocklist() {
+        let mut contacts = ContactList::new(EXPECTED_UID);
+        contacts.apply(ContactAction::Allow, EXPECTED_CONTACT);
+        contacts.apply(ContactAction::Block, EXPECTED_CONTACT);
+        contacts.apply(ContactAction::Block, EXPECTED_CONTACT);
+
+        assert!(contacts.allowed.is_empty());
+        assert_eq!(contacts.blocked, vec![EXPECTED_CONTACT]);
+    }
+
+    #[tokio::test]
+    async fn given_no_contact_list_when_updating_then_new_list_is_stored() {
+        let mut mock_repo = MockContactRepository::new();
+        mock_repo.expect_get_contacts().returning(|_| None);
+        mock_repo
+            .expect_set_contacts()
+            .withf(|contacts| {
+                contacts.uid == EXPECTED_UID && contacts.blocked == vec![EXPECTED_CONTACT]
+            })
+            .times(1)
+            .returning(|_| ());
+
+        let manager = ContactManager::with_repository(Box::new(mock_repo));
+        manager
+            .update(EXPECTED_UID, ContactAction::Block, EXPECTED_CONTACT)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn given_blocked_sender_when_checking_then_is_not_allowed() {
+        let manager = ContactManager::new(RepositoryType::InMemory);
+        manager
+            .update(EXPECTED_UID, ContactAction::Block, EXPECTED_CONTACT)
+            .await;
+
+        assert!(!manager.is_allowed(EXPECTED_CONTACT, EXPECTED_UID).await);
+        assert!(manager.is_allowed(OTHER_CONTACT, EXPECTED_UID).await);
+        assert!(manager.is_allowed(EXPECTED_UID, EXPECTED_CONTACT).await);
+    }
+
+    #[tokio::test]
+    async fn given_presence_settings_when_checking_visibility_then_only_opted_in_members_are_visible()
+     {
+        let manager = ContactManager::new(RepositoryType::InMemory);
+        assert!(!manager.is_visible(EXPECTED_UID, EXPECTED_CONTACT).await);
+
+        manager.set_visible(EXPECTED_UID, true).await;
+        assert!(manager.is_visible(EXPECTED_UID, EXPECTED_CONTACT).await);
+
+        manager
+            .update(EXPECTED_UID, ContactAction::Block, EXPECTED_CONTACT)
+            .await;
+        assert!(!manager.is_visible(EXPECTED_UID, EXPECTED_CONTACT).await);
+        assert!(manager.is_visible(EXPECTED_UID, OTHER_CONTACT).await);
+
+        manager.set_visible(EXPECTED_UID, false).await;
+        assert!(!manager.is_visible(EXPECTED_UID, OTHER_CONTACT).await);
+    }
+}
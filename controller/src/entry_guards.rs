@@ -0,0 +1,376 @@
+use crate::models::{EntryGuard, EntryGuards, SessionInfo};
+use crate::storage::{self, EntryGuardRepository, RepositoryType};
+use crosscutting::settings::environment;
+use rand::seq::IteratorRandom;
+use std::collections::HashSet;
+use std::time::Duration;
+
+const ENTRY_GUARDS_KEY: &str = "ENTRY_GUARDS";
+const ENTRY_GUARD_LIFETIME_DAYS_KEY: &str = "ENTRY_GUARD_LIFETIME_DAYS";
+const DEFAULT_ENTRY_GUARDS: usize = 3;
+const DEFAULT_ENTRY_GUARD_LIFETIME_DAYS: u64 = 60;
+const SECONDS_PER_DAY: u64 = 86400;
+const MAX_SELECT_ATTEMPTS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuardPolicy {
+    pub count: usize,
+    pub lifetime: Duration,
+}
+
+impl Default for GuardPolicy {
+    fn default() -> Self {
+        Self {
+            count: DEFAULT_ENTRY_GUARDS,
+            lifetime: Duration::from_secs(DEFAULT_ENTRY_GUARD_LIFETIME_DAYS * SECONDS_PER_DAY),
+        }
+    }
+}
+
+impl GuardPolicy {
+    pub fn get_from_env() -> Self {
+        let count = environment::get_env_variable(ENTRY_GUARDS_KEY)
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_ENTRY_GUARDS)
+            .max(1);
+        let lifetime_days = environment::get_env_variable(ENTRY_GUARD_LIFETIME_DAYS_KEY)
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_ENTRY_GUARD_LIFETIME_DAYS);
+
+        Self {
+            count,
+            lifetime: Duration::from_secs(lifetime_days * SECONDS_PER_DAY),
+        }
+    }
+}
+
+pub struct EntryGuardManager {
+    repository: Box<dyn EntryGuardRepository>,
+    policy: GuardPolicy,
+}
+
+impl EntryGuardManager {
+    pub fn new(repository_type: RepositoryType) -> Self {
+        Self {
+            repository: storage::create_entry_guard_repository(repository_type).unwrap(),
+            policy: GuardPolicy::get_from_env(),
+        }
+    }
+
+    #[cfg(test)]
+    pub async fn get_guards(&self, uid: &str) -> Vec<EntryGuard> {
+        self.repository
+            .get_guards(uid)
+            .await
+            .map(|entry| entry.guards)
+            .unwrap_or_default()
+    }
+
+    /// Narrows the entry candidates down to the member's guards. Expired guards
+    /// are dropped and missing ones are pinned among the candidates, while
+    /// guards which are offline are kept, so a member only ever enters through
+    /// the same few proxies until they expire. When none of them is online, one
+    /// more guard is pinned, and past twice the configured count the oldest one
+    /// makes room for it. Guards are only stored if nobody changed them since
+    /// they were read, and are picked again from the fresh ones otherwise.
+    pub async fn select(&self, uid: &str, candidates: Vec<SessionInfo>) -> Vec<SessionInfo> {
+        let mut guards = Vec::new();
+        for _ in 0..MAX_SELECT_ATTEMPTS {
+            let stored = self.repository.get_guards(uid).await;
+            let stored_guards = stored
+                .as_ref()
+                .map(|entry| entry.guards.as_slice())
+                .unwrap_or_default();
+            guards = self.pin_guards(stored_guards, &candidates);
+            if guards == stored_guards {
+                break;
+            }
+
+            let entry = EntryGuards {
+                uid: uid.to_string(),
+                guards: guards.clone(),
+            };
+            if self.repository.replace_guards(stored, &entry).await {
+                break;
+            }
+        }
+
+        candidates
+            .into_iter()
+            .filter(|candidate| guards.iter().any(|guard| guard.proxy_uid == candidate.uid))
+            .collect()
+    }
+
+    fn pin_guards(&self, stored: &[EntryGuard], candidates: &[SessionInfo]) -> Vec<EntryGuard> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let lifetime = self.policy.lifetime.as_millis() as i64;
+        let mut guards: Vec<EntryGuard> = stored
+            .iter()
+            .filter(|guard| now - guard.pinned_at < lifetime)
+            .cloned()
+            .collect();
+
+        let mut missing = self.policy.count.saturating_sub(guards.len());
+        let is_online = |guard: &EntryGuard| {
+            candidates
+                .iter()
+                .any(|candidate| candidate.uid == guard.proxy_uid)
+        };
+        if missing == 0 && !candidates.is_empty() && !guards.iter().any(is_online) {
+            if guards.len() >= self.policy.count * 2 {
+                let oldest = guards
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, guard)| guard.pinned_at)
+                    .map(|(index, _)| index);
+                if let Some(oldest) = oldest {
+                    guards.remove(oldest);
+                }
+            }
+            missing = 1;
+        }
+
+        if missing > 0 {
+            let pinned: HashSet<&str> = guards
+                .iter()
+                .map(|guard| guard.proxy_uid.as_str())
+                .collect();
+            let available: HashSet<&str> = candidates
+                .iter()
+                .map(|candidate| candidate.uid.as_str())
+                .filter(|proxy_uid| !pinned.contains(proxy_uid))
+                .collect();
+            let new_guards: Vec<EntryGuard> = available
+                .into_iter()
+                .choose_multiple(&mut rand::rng(), missing)
+                .into_iter()
+                .map(|proxy_uid| EntryGuard {
+                    proxy_uid: proxy_uid.to_string(),
+                    pinned_at: now,
+                })
+                .collect();
+            guards.extend(new_guards);
+        }
+
+        guards
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MockEntryGuardRepository;
+    use crosscutting::networking::to_socket_address;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const EXPECTED_UID: &str = "test_uid";
+    const MILLIS_PER_DAY: i64 = 86_400_000;
+
+    impl EntryGuardManager {
+        fn with_repository(repository: Box<dyn EntryGuardRepository>) -> Self {
+            Self {
+                repository,
+                policy: GuardPolicy {
+                    count: 2,
+                    ..GuardPolicy::default()
+                },
+            }
+        }
+    }
+
+    fn create_proxy(uid: &str) -> SessionInfo {
+        SessionInfo {
+            access_key: uid.to_string(),
+            uid: uid.to_string(),
+            client_ip: to_socket_address("127.0.0.1", 5000).unwrap(),
+            on_port_number: 5000,
+            on_ip_address: "127.0.0.1".to_string(),
+            component_type: 1,
+            public_key: Vec::new(),
+            domain_name: "localhost".to_string(),
+            identity_key: Vec::new(),
+            created_at: 0,
+            rtt_micros: 0,
+            load: None,
+            tags: HashMap::new(),
+            roles: None,
+        }
+    }
+
+    fn create_guard(proxy_uid: &str, age_days: i64) -> EntryGuard {
+        EntryGuard {
+            proxy_uid: proxy_uid.to_string(),
+            pinned_at: chrono::Utc::now().timestamp_millis() - age_days * MILLIS_PER_DAY,
+        }
+    }
+
+    #[test]
+    fn given_no_env_when_getting_guard_policy_then_returns_default() {
+        assert_eq!(GuardPolicy::get_from_env(), GuardPolicy::default());
+    }
+
+    #[tokio::test]
+    async fn given_no_guards_when_selecting_then_pins_new_guards() {
+        let mut mock_repo = MockEntryGuardRepository::new();
+        mock_repo.expect_get_guards().returning(|_| None);
+        mock_repo
+            .expect_replace_guards()
+            .withf(|_, entry| entry.uid == EXPECTED_UID && entry.guards.len() == 2)
+            .times(1)
+            .returning(|_, _| true);
+
+        let manager = EntryGuardManager::with_repository(Box::new(mock_repo));
+        let selected = manager
+            .select(
+                EXPECTED_UID,
+                vec![create_proxy("a"), create_proxy("b"), create_proxy("c")],
+            )
+            .await;
+
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn given_guards_changed_meanwhile_when_selecting_then_fresh_guards_are_used() {
+        let reads = AtomicUsize::new(0);
+        let mut mock_repo = MockEntryGuardRepository::new();
+        mock_repo.expect_get_guards().returning(move |uid| {
+            if reads.fetch_add(1, Ordering::SeqCst) == 0 {
+                return None;
+            }
+
+            Some(EntryGuards {
+                uid: uid.to_string(),
+                guards: vec![create_guard("a", 1), create_guard("b", 1)],
+            })
+        });
+        mock_repo
+            .expect_replace_guards()
+            .withf(|stored, _| stored.is_none())
+            .times(1)
+            .returning(|_, _| false);
+
+        let manager = EntryGuardManager::with_repository(Box::new(mock_repo));
+        let selected = manager
+            .select(
+                EXPECTED_UID,
+                vec![create_proxy("a"), create_proxy("b"), create_proxy("c")],
+            )
+            .await;
+
+        let mut selected: Vec<&str> = selected.iter().map(|proxy| proxy.uid.as_str()).collect();
+        selected.sort();
+        assert_eq!(selected, vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn given_pinned_guards_when_selecting_then_only_guards_are_returned() {
+        let mut mock_repo = MockEntryGuardRepository::new();
+        mock_repo.expect_get_guards().returning(|uid| {
+            Some(EntryGuards {
+                uid: uid.to_string(),
+                guards: vec![create_guard("a", 1), create_guard("offline", 1)],
+            })
+        });
+        mock_repo.expect_replace_guards().times(0);
+
+        let manager = EntryGuardManager::with_repository(Box::new(mock_repo));
+        let selected = manager
+            .select(
+                EXPECTED_UID,
+                vec![create_proxy("a"), create_proxy("b"), create_proxy("c")],
+            )
+            .await;
+
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].uid, "a");
+    }
+
+    #[tokio::test]
+    async fn given_expired_guard_when_selecting_then_it_is_replaced() {
+        let mut mock_repo = MockEntryGuardRepository::new();
+        mock_repo.expect_get_guards().returning(|uid| {
+            Some(EntryGuards {
+                uid: uid.to_string(),
+                guards: vec![create_guard("a", 1), create_guard("b", 365)],
+            })
+        });
+        mock_repo
+            .expect_replace_guards()
+            .withf(|_, entry| {
+                entry.guards.len() == 2
+                    && entry.guards[0].proxy_uid == "a"
+                    && entry.guards[1].proxy_uid == "c"
+            })
+            .times(1)
+            .returning(|_, _| true);
+
+        let manager = EntryGuardManager::with_repository(Box::new(mock_repo));
+        let selected = manager
+            .select(EXPECTED_UID, vec![create_proxy("a"), create_proxy("c")])
+            .await;
+
+        let uids: Vec<&str> = selected.iter().map(|proxy| proxy.uid.as_str()).collect();
+        assert_eq!(uids, vec!["a", "c"]);
+    }
+
+    #[tokio::test]
+    async fn given_every_guard_offline_when_selecting_then_pins_one_more() {
+        let mut mock_repo = MockEntryGuardRepository::new();
+        mock_repo.expect_get_guards().returning(|uid| {
+            Some(EntryGuards {
+                uid: uid.to_string(),
+                guards: vec![create_guard("a", 1), create_guard("b", 1)],
+            })
+        });
+        mock_repo
+            .expect_replace_guards()
+            .withf(|_, entry| entry.guards.len() == 3 && entry.guards[2].proxy_uid == "c")
+            .times(1)
+            .returning(|_, _| true);
+
+        let manager = EntryGuardManager::with_repository(Box::new(mock_repo));
+        let selected = manager.select(EXPECTED_UID, vec![create_proxy("c")]).await;
+
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].uid, "c");
+    }
+
+    #[tokio::test]
+    async fn given_every_guard_offline_and_too_many_guards_when_selecting_then_oldest_is_replaced()
+    {
+        let mut mock_repo = MockEntryGuardRepository::new();
+        mock_repo.expect_get_guards().returning(|uid| {
+            Some(EntryGuards {
+                uid: uid.to_string(),
+                guards: vec![
+                    create_guard("a", 1),
+                    create_guard("b", 3),
+                    create_guard("c", 2),
+                    create_guard("d", 1),
+                ],
+            })
+        });
+        mock_repo
+            .expect_replace_guards()
+            .withf(|_, entry| {
+                let uids: Vec<&str> = entry
+                    .guards
+                    .iter()
+                    .map(|guard| guard.proxy_uid.as_str())
+                    .collect();
+                uids == vec!["a", "c", "d", "e"]
+            })
+            .times(1)
+            .returning(|_, _| true);
+
+        let manager = EntryGuardManager::with_repository(Box::new(mock_repo));
+        let selected = manager.select(EXPECTED_UID, vec![create_proxy("e")]).await;
+
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].uid, "e");
+    }
+}
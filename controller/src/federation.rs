@@ -0,0 +1,307 @@
+use crate::models::federation_proto::{
+    OpenRequest, OpenResponse, federation_service_client::FederationServiceClient,
+};
+use crosscutting::ConnectionSettings;
+use crosscutting::settings::{environment, service};
+use log::warn;
+use mockall::automock;
+use std::error::Error;
+use std::fs;
+use std::time::Duration;
+use tonic::async_trait;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig};
+
+const FEDERATION_DOMAIN_KEY: &str = "FEDERATION_DOMAIN";
+const FEDERATION_PEERS_KEY: &str = "FEDERATION_PEERS";
+const FEDERATION_CA_FILE_KEY: &str = "FEDERATION_CA_FILE";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Clone)]
+pub struct FederationPeer {
+    pub domain: String,
+    pub connection_settings: ConnectionSettings,
+}
+
+#[derive(Clone, Default)]
+pub struct FederationSettings {
+    pub domain: Option<String>,
+    pub peers: Vec<FederationPeer>,
+    pub ca_certificate: Vec<u8>,
+}
+
+pub enum Recipient<'a> {
+    Local(&'a str),
+    Remote(&'a FederationPeer, &'a str),
+}
+
+#[automock]
+#[async_trait]
+pub trait FederationClient: Send + Sync {
+    async fn open(
+        &self,
+        peer: &FederationPeer,
+        request: OpenRequest,
+    ) -> Result<OpenResponse, Box<dyn Error>>;
+}
+
+struct GrpcFederationClient;
+
+pub struct FederationManager {
+    settings: FederationSettings,
+    client: Box<dyn FederationClient>,
+}
+
+impl FederationSettings {
+    pub fn get_from_env() -> Self {
+        let domain = match environment::get_env_variable(FEDERATION_DOMAIN_KEY) {
+            Ok(domain) if !domain.trim().is_empty() => domain.trim().to_string(),
+            _ => return Self::default(),
+        };
+
+        let Some(ca_certificate) = environment::get_env_variable(FEDERATION_CA_FILE_KEY)
+            .ok()
+            .and_then(|ca_path| fs::read(ca_path).ok())
+        else {
+            warn!(
+                "Federation is disabled as {} is not set or not readable",
+                FEDERATION_CA_FILE_KEY
+            );
+            return Self::default();
+        };
+
+        let peers = match environment::get_env_variable(FEDERATION_PEERS_KEY) {
+            Ok(value) => Self::parse_peers(&value, &ca_certificate).unwrap_or_else(|e| {
+                warn!("Ignoring {}: {}", FEDERATION_PEERS_KEY, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        Self {
+            domain: Some(domain),
+            peers,
+            ca_certificate,
+        }
+    }
+
+    pub fn parse_peers(value: &str, ca_certificate: &[u8]) -> Result<Vec<FederationPeer>, String> {
+        let mut peers = Vec::new();
+
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (domain, address) = entry
+                .split_once('=')
+                .map(|(domain, address)| (domain.trim(), address.trim()))
+                .filter(|(domain, _)| !domain.is_empty())
+                .ok_or_else(|| format!("Invalid federation peer entry: '{}'", entry))?;
+            let mut endpoints =
+                service::parse_controller_endpoints(address, Some(domain), ca_certificate)
+                    .map_err(|e| e.to_string())?;
+
+            peers.push(FederationPeer {
+                domain: domain.to_string(),
+                connection_settings: endpoints.remove(0),
+            });
+        }
+
+        Ok(peers)
+    }
+}
+
+#[async_trait]
+impl FederationClient for GrpcFederationClient {
+    async fn open(
+        &self,
+        peer: &FederationPeer,
+        request: OpenRequest,
+    ) -> Result<OpenResponse, Box<dyn Error>> {
+        let identity = service::load_tls_identity("server.crt", "server.key")?;
+        let tls_config = ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(
+                peer.connection_settings.certificate.clone(),
+            ))
+            .domain_name(peer.connection_settings.domain_name.clone())
+            .identity(identity);
+
+        let channel = Channel::builder(peer.connection_settings.get_public_endpoint())
+            .connect_timeout(CONNECT_TIMEOUT)
+            .tls_config(tls_config)?
+            .connect()
+            .await?;
+
+        let response = FederationServiceClient::new(channel).open(request).await?;
+        Ok(response.into_inner())
+    }
+}
+
+impl Default for FederationManager {
+    fn default() -> Self {
+        Self::new(FederationSettings::default())
+    }
+}
+
+impl FederationManager {
+    pub fn new(settings: FederationSettings) -> Self {
+        Self::with_client(settings, Box::new(GrpcFederationClient))
+    }
+
+    pub fn with_client(settings: FederationSettings, client: Box<dyn FederationClient>) -> Self {
+        Self { settings, client }
+    }
+
+    pub fn get_from_env() -> Self {
+        Self::new(FederationSettings::get_from_env())
+    }
+
+    pub fn get_domain(&self) -> Option<&str> {
+        self.settings.domain.as_deref()
+    }
+
+    pub fn get_ca_certificate(&self) -> Option<&[u8]> {
+        self.get_domain()
+            .map(|_| self.settings.ca_certificate.as_slice())
+    }
+
+    pub fn resolve<'a>(&'a self, address: &'a str) -> Recipient<'a> {
+        if let (Some(local_domain), Some((uid, domain))) =
+            (self.get_domain(), address.rsplit_once('@'))
+        {
+            if domain == local_domain {
+                return Recipient::Local(uid);
+            }
+
+            if let Some(peer) = self
+                .settings
+                .peers
+                .iter()
+                .find(|peer| peer.domain == domain)
+            {
+                return Recipient::Remote(peer, uid);
+            }
+        }
+
+        Recipient::Local(address)
+    }
+
+    pub fn authorize_peer(&self, peer_uids: &[String]) -> Option<&FederationPeer> {
+        self.get_domain()?;
+        self.settings
+            .peers
+            .iter()
+            .find(|peer| peer_uids.contains(&peer.connection_settings.domain_name))
+    }
+
+    pub fn get_federated_address(uid: &str, peer: &FederationPeer) -> String {
+        format!("{}@{}", uid, peer.domain)
+    }
+
+    pub async fn open(
+        &self,
+        peer: &FederationPeer,
+        request: OpenRequest,
+    ) -> Result<OpenResponse, Box<dyn Error>> {
+        self.client.open(peer, request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPECTED_LOCAL_DOMAIN: &str = "alpha";
+    const EXPECTED_REMOTE_DOMAIN: &str = "beta";
+    const EXPECTED_TLS_DOMAIN: &str = "controller.beta";
+    const EXPECTED_UID: &str = "client1";
+    const EXPECTED_CA: &[u8] = b"test_ca";
+
+    fn create_settings() -> FederationSettings {
+        FederationSettings {
+            domain: Some(EXPECTED_LOCAL_DOMAIN.to_string()),
+            peers: FederationSettings::parse_peers(
+                &format!(
+                    "{}={}@127.0.0.1:50052",
+                    EXPECTED_REMOTE_DOMAIN, EXPECTED_TLS_DOMAIN
+                ),
+                EXPECTED_CA,
+            )
+            .unwrap(),
+            ca_certificate: EXPECTED_CA.to_vec(),
+        }
+    }
+
+    #[test]
+    fn given_peer_entries_when_parsing_then_returns_peers() {
+        let peers = FederationSettings::parse_peers(
+            "beta=10.0.0.2:50051, gamma=controller.gamma@10.0.0.3:50051",
+            EXPECTED_CA,
+        )
+        .unwrap();
+
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].domain, "beta");
+        assert_eq!(peers[0].connection_settings.domain_name, "beta");
+        assert_eq!(peers[0].connection_settings.ip, "10.0.0.2");
+        assert_eq!(peers[1].domain, "gamma");
+        assert_eq!(peers[1].connection_settings.domain_name, "controller.gamma");
+        assert_eq!(
+            peers[1].connection_settings.certificate,
+            EXPECTED_CA.to_vec()
+        );
+    }
+
+    #[test]
+    fn given_invalid_peer_entries_when_parsing_then_returns_error() {
+        assert!(FederationSettings::parse_peers("10.0.0.2:50051", EXPECTED_CA).is_err());
+        assert!(FederationSettings::parse_peers("=10.0.0.2:50051", EXPECTED_CA).is_err());
+        assert!(FederationSettings::parse_peers("beta=10.0.0.2", EXPECTED_CA).is_err());
+    }
+
+    #[test]
+    fn given_addresses_when_resolving_then_returns_local_or_remote_recipient() {
+        let manager = FederationManager::new(create_settings());
+
+        assert!(matches!(
+            manager.resolve(EXPECTED_UID),
+            Recipient::Local(EXPECTED_UID)
+        ));
+        assert!(matches!(
+            manager.resolve("client1@alpha"),
+            Recipient::Local(EXPECTED_UID)
+        ));
+        assert!(matches!(
+            manager.resolve("client1@unknown"),
+            Recipient::Local("client1@unknown")
+        ));
+        assert!(matches!(
+            manager.resolve("client1@beta"),
+            Recipient::Remote(peer, EXPECTED_UID) if peer.domain == EXPECTED_REMOTE_DOMAIN
+        ));
+    }
+
+    #[test]
+    fn given_federation_disabled_when_resolving_then_address_is_local() {
+        let manager = FederationManager::default();
+
+        assert!(matches!(
+            manager.resolve("client1@beta"),
+            Recipient::Local("client1@beta")
+        ));
+        assert!(manager.get_ca_certificate().is_none());
+    }
+
+    #[test]
+    fn given_peer_certificate_names_when_authorizing_then_returns_matching_peer() {
+        let manager = FederationManager::new(create_settings());
+
+        let peer = manager.authorize_peer(&[EXPECTED_TLS_DOMAIN.to_string()]);
+        assert_eq!(
+            peer.map(|peer| peer.domain.as_str()),
+            Some(EXPECTED_REMOTE_DOMAIN)
+        );
+        assert!(
+            manager
+                .authorize_peer(&["controller.gamma".to_string()])
+                .is_none()
+        );
+        assert!(manager.authorize_peer(&[]).is_none());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::models::Group;
+use crate::storage::{self, GroupRepository, RepositoryType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupAction {
+    Add,
+    Remove,
+}
+
+impl Group {
+    pub fn new(name: &str, owner: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            owner: owner.to_string(),
+            members: Vec::new(),
+        }
+    }
+
+    pub fn includes(&self, uid: &str) -> bool {
+        self.owner == uid || self.members.iter().any(|member| member == uid)
+    }
+
+    fn apply(&mut self, action: GroupAction, uid: &str) {
+        match action {
+            GroupAction::Add => {
+                if !self.includes(uid) {
+                    self.members.push(uid.to_string());
+                }
+            }
+            GroupAction::Remove => self.members.retain(|member| member != uid),
+        }
+    }
+}
+
+pub struct GroupManager {
+    repository: Box<dyn GroupRepository>,
+}
+
+impl GroupManager {
+    pub fn new(repository_type: RepositoryType) -> Self {
+        Self {
+            repository: storage::create_group_repository(repository_type).unwrap(),
+        }
+    }
+
+    pub async fn get_group(&self, name: &str) -> Option<Group> {
+        self.repository.get_group(name).await
+    }
+
+    pub async fn create_group(&self, name: &str, owner: &str) -> Result<Group, String> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            return Err(format!("Invalid group name: '{}'", name));
+        }
+
+        if self.repository.get_group(name).await.is_some() {
+            return Err(format!("Group {} already exists", name));
+        }
+
+        let group = Group::new(name, owner);
+        self.repository.set_group(&group).await;
+        Ok(group)
+    }
+
+    pub async fn remove_group(&self, name: &str) -> bool {
+        self.repository.remove_group(name).await
+    }
+
+    pub async fn update(&self, mut group: Group, action: GroupAction, uid: &str) -> Group {
+        group.apply(action, uid);
+        self.repository.set_group(&group).await;
+        group
+    }
+
+    /// Returns every member of the group but the sender, as long as the sender
+    /// belongs to it.
+    pub async fn get_recipients(&self, name: &str, from: &str) -> Option<Vec<String>> {
+        let group = self
+            .repository
+            .get_group(name)
+            .await
+            .filter(|group| group.includes(from))?;
+
+        Some(
+            std::iter::once(group.owner)
+                .chain(group.members)
+                .filter(|uid| uid != from)
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MockGroupRepository;
+
+    const EXPECTED_GROUP: &str = "test_group";
+    const EXPECTED_OWNER: &str = "test_owner";
+    const EXPECTED_MEMBER: &str = "test_member";
+    const OTHER_MEMBER: &str = "other_member";
+
+    impl GroupManager {
+        fn with_repository(repository: Box<dyn GroupRepository>) -> Self {
+            Self { repository }
+        }
+    }
+
+    #[test]
+    fn given_group_when_adding_and_removing_members_then_owner_is_never_duplicated() {
+        let mut group = Group::new(EXPECTED_GROUP, EXPECTED_OWNER);
+        group.apply(GroupAction::Add, EXPECTED_MEMBER);
+        group.apply(GroupAction::Add, EXPECTED_MEMBER);
+        group.apply(GroupAction::Add, EXPECTED_OWNER);
+
+        assert_eq!(group.members, vec![EXPECTED_MEMBER]);
+        assert!(group.includes(EXPECTED_OWNER));
+
+        group.apply(GroupAction::Remove, EXPECTED_MEMBER);
+
+        assert!(!group.includes(EXPECTED_MEMBER));
+    }
+
+    #[tokio::test]
+    async fn given_existing_group_when_creating_group_then_returns_error() {
+        let mut mock_repo = MockGroupRepository::new();
+        mock_repo
+            .expect_get_group()
+            .returning(|name| Some(Group::new(name, OTHER_MEMBER)));
+        mock_repo.expect_set_group().times(0);
+
+        let manager = GroupManager::with_repository(Box::new(mock_repo));
+        let result = manager.create_group(EXPECTED_GROUP, EXPECTED_OWNER).await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn given_invalid_name_when_creating_group_then_returns_error() {
+        let manager = GroupManager::new(RepositoryType::InMemory);
+
+        assert!(manager.create_group("", EXPECTED_OWNER).await.is_err());
+        assert!(
+            manager
+                .create_group("my group", EXPECTED_OWNER)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_group_when_getting_recipients_then_returns_everyone_but_sender() {
+        let manager = GroupManager::new(RepositoryType::InMemory);
+        let group = manager
+            .create_group(EXPECTED_GROUP, EXPECTED_OWNER)
+            .await
+            .unwrap();
+        let group = manager
+            .update(group, GroupAction::Add, EXPECTED_MEMBER)
+            .await;
+        manager.update(group, GroupAction::Add, OTHER_MEMBER).await;
+
+        assert_eq!(
+            manager
+                .get_recipients(EXPECTED_GROUP, EXPECTED_MEMBER)
+                .await,
+            Some(vec![EXPECTED_OWNER.to_string(), OTHER_MEMBER.to_string()])
+        );
+        assert!(
+            manager
+                .get_recipients(EXPECTED_GROUP, "outsider")
+                .await
+                .is_none()
+        );
+        assert!(
+            manager
+                .get_recipients("unknown", EXPECTED_OWNER)
+                .await
+                .is_none()
+        );
+    }
+}
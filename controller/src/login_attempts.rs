@@ -0,0 +1,295 @@
+use crate::storage::{self, LoginAttemptRepository, RepositoryType};
+use crosscutting::settings::environment;
+use log::warn;
+use std::net::IpAddr;
+use std::time::Duration;
+
+const LOGIN_MAX_FAILURES_KEY: &str = "LOGIN_MAX_FAILURES";
+const LOGIN_MAX_IP_FAILURES_KEY: &str = "LOGIN_MAX_IP_FAILURES";
+const LOGIN_BACKOFF_MS_KEY: &str = "LOGIN_BACKOFF_MS";
+const LOGIN_LOCKOUT_SECS_KEY: &str = "LOGIN_LOCKOUT_SECS";
+const DEFAULT_MAX_FAILURES: u32 = 5;
+const DEFAULT_MAX_IP_FAILURES: u32 = 20;
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_LOCKOUT_DURATION: Duration = Duration::from_secs(900);
+const UID_KEY_PREFIX: &str = "uid";
+const IP_KEY_PREFIX: &str = "ip";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockoutPolicy {
+    pub max_failures: u32,
+    pub max_ip_failures: u32,
+    pub backoff: Duration,
+    pub lockout_duration: Duration,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures: DEFAULT_MAX_FAILURES,
+            max_ip_failures: DEFAULT_MAX_IP_FAILURES,
+            backoff: DEFAULT_BACKOFF,
+            lockout_duration: DEFAULT_LOCKOUT_DURATION,
+        }
+    }
+}
+
+impl LockoutPolicy {
+    pub fn get_from_env() -> Self {
+        let default = Self::default();
+        let get_number = |key: &str| {
+            environment::get_env_variable(key)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+
+        Self {
+            max_failures: get_number(LOGIN_MAX_FAILURES_KEY)
+                .map(|value| value as u32)
+                .unwrap_or(default.max_failures),
+            max_ip_failures: get_number(LOGIN_MAX_IP_FAILURES_KEY)
+                .map(|value| value as u32)
+                .unwrap_or(default.max_ip_failures),
+            backoff: get_number(LOGIN_BACKOFF_MS_KEY)
+                .map(Duration::from_millis)
+                .unwrap_or(default.backoff),
+            lockout_duration: get_number(LOGIN_LOCKOUT_SECS_KEY)
+                .map(Duration::from_secs)
+                .unwrap_or(default.lockout_duration),
+        }
+    }
+
+    fn get_backoff(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(31);
+        self.backoff
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.lockout_duration)
+    }
+}
+
+pub struct LoginAttemptManager {
+    repository: Box<dyn LoginAttemptRepository>,
+    policy: LockoutPolicy,
+}
+
+impl LoginAttemptManager {
+    pub fn new(repository_type: RepositoryType) -> Self {
+        Self {
+            repository: storage::create_login_attempt_repository(repository_type).unwrap(),
+            policy: LockoutPolicy::get_from_env(),
+        }
+    }
+
+    pub async fn get_lockout(&self, uid: &str, ip_address: &IpAddr) -> Option<Duration> {
+        let uid_lockout = self.repository.get_lockout(&get_uid_key(uid)).await;
+        let ip_lockout = self.repository.get_lockout(&get_ip_key(ip_address)).await;
+        uid_lockout.max(ip_lockout)
+    }
+
+    /// Failures back off exponentially per uid only. Source IPs are just
+    /// locked out once they reach their own threshold, so a single client
+    /// behind a shared address can't slow down logins for everyone else.
+    pub async fn register_failure(&self, uid: &str, ip_address: &IpAddr) {
+        self.register_key_failure(&get_uid_key(uid), self.policy.max_failures, true)
+            .await;
+        self.register_key_failure(&get_ip_key(ip_address), self.policy.max_ip_failures, false)
+            .await;
+    }
+
+    pub async fn register_success(&self, uid: &str) {
+        self.repository.remove_failures(&get_uid_key(uid)).await;
+    }
+
+    pub async fn clear_uid_lockout(&self, uid: &str) -> bool {
+        self.clear_key_lockout(&get_uid_key(uid)).await
+    }
+
+    pub async fn clear_ip_lockout(&self, ip_address: &IpAddr) -> bool {
+        self.clear_key_lockout(&get_ip_key(ip_address)).await
+    }
+
+    async fn register_key_failure(&self, key: &str, max_failures: u32, backoff: bool) {
+        let failures = self
+            .repository
+            .increment_failures(key, self.policy.lockout_duration)
+            .await;
+
+        if failures >= max_failures {
+            warn!(
+                "Locking out {} for {:?} after {} failed login attempts",
+                key, self.policy.lockout_duration, failures
+            );
+            self.repository
+                .set_lockout(key, self.policy.lockout_duration)
+                .await;
+        } else if backoff {
+            self.repository
+                .set_lockout(key, self.policy.get_backoff(failures))
+                .await;
+        }
+    }
+
+    async fn clear_key_lockout(&self, key: &str) -> bool {
+        self.repository.remove_failures(key).await;
+        self.repository.remove_lockout(key).await
+    }
+}
+
+fn get_uid_key(uid: &str) -> String {
+    format!("{}:{}", UID_KEY_PREFIX, uid)
+}
+
+fn get_ip_key(ip_address: &IpAddr) -> String {
+    format!("{}:{}", IP_KEY_PREFIX, ip_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MockLoginAttemptRepository;
+
+    const EXPECTED_UID: &str = "test_uid";
+    const EXPECTED_IP: &str = "127.0.0.1";
+
+    impl LoginAttemptManager {
+        fn with_policy(repository: Box<dyn LoginAttemptRepository>, policy: LockoutPolicy) -> Self {
+            Self { repository, policy }
+        }
+    }
+
+    fn create_policy() -> LockoutPolicy {
+        LockoutPolicy {
+            max_failures: 3,
+            max_ip_failures: 10,
+            backoff: Duration::from_millis(100),
+            lockout_duration: Duration::from_secs(60),
+        }
+    }
+
+    fn create_manager() -> LoginAttemptManager {
+        let repository = storage::create_login_attempt_repository(RepositoryType::InMemory);
+        LoginAttemptManager::with_policy(repository.unwrap(), create_policy())
+    }
+
+    #[test]
+    fn given_consecutive_failures_when_getting_backoff_then_it_doubles_up_to_lockout() {
+        let policy = create_policy();
+
+        assert_eq!(policy.get_backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.get_backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.get_backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.get_backoff(40), policy.lockout_duration);
+    }
+
+    #[tokio::test]
+    async fn given_failure_below_threshold_when_registering_then_backoff_is_applied_to_uid_only() {
+        let mut mock_repo = MockLoginAttemptRepository::new();
+        mock_repo.expect_increment_failures().returning(|_, _| 1);
+        mock_repo
+            .expect_set_lockout()
+            .withf(|key, duration| key == "uid:test_uid" && *duration == Duration::from_millis(100))
+            .times(1)
+            .returning(|_, _| ());
+
+        let manager = LoginAttemptManager::with_policy(Box::new(mock_repo), create_policy());
+        manager
+            .register_failure(EXPECTED_UID, &EXPECTED_IP.parse().unwrap())
+            .await;
+    }
+
+    #[tokio::test]
+    async fn given_failures_reaching_threshold_when_registering_then_uid_is_locked_out() {
+        let manager = create_manager();
+        let ip_address: IpAddr = EXPECTED_IP.parse().unwrap();
+
+        for _ in 0..3 {
+            manager.register_failure(EXPECTED_UID, &ip_address).await;
+        }
+
+        let uid_lockout = manager
+            .repository
+            .get_lockout(&get_uid_key(EXPECTED_UID))
+            .await
+            .unwrap();
+        assert!(uid_lockout > Duration::from_secs(30));
+        assert!(
+            manager
+                .repository
+                .get_lockout(&get_ip_key(&ip_address))
+                .await
+                .is_none()
+        );
+        assert!(
+            manager
+                .get_lockout(EXPECTED_UID, &ip_address)
+                .await
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_locked_out_uid_when_clearing_lockout_then_login_is_allowed() {
+        let manager = create_manager();
+        let ip_address: IpAddr = EXPECTED_IP.parse().unwrap();
+        for _ in 0..3 {
+            manager.register_failure(EXPECTED_UID, &ip_address).await;
+        }
+
+        assert!(manager.clear_uid_lockout(EXPECTED_UID).await);
+        assert!(
+            manager
+                .get_lockout(EXPECTED_UID, &ip_address)
+                .await
+                .is_none()
+        );
+        assert!(!manager.clear_uid_lockout(EXPECTED_UID).await);
+    }
+
+    #[tokio::test]
+    async fn given_failures_from_many_uids_when_registering_then_ip_is_locked_out_at_its_threshold()
+    {
+        let manager = create_manager();
+        let ip_address: IpAddr = EXPECTED_IP.parse().unwrap();
+
+        for index in 0..9 {
+            manager
+                .register_failure(&format!("uid_{}", index), &ip_address)
+                .await;
+        }
+        assert!(
+            manager
+                .get_lockout("other_uid", &ip_address)
+                .await
+                .is_none()
+        );
+
+        manager.register_failure("uid_9", &ip_address).await;
+        assert!(
+            manager
+                .get_lockout("other_uid", &ip_address)
+                .await
+                .is_some()
+        );
+
+        assert!(manager.clear_ip_lockout(&ip_address).await);
+        assert!(
+            manager
+                .get_lockout("other_uid", &ip_address)
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_successful_login_when_registering_then_uid_failures_are_reset() {
+        let mut mock_repo = MockLoginAttemptRepository::new();
+        mock_repo
+            .expect_remove_failures()
+            .withf(|key| key == "uid:test_uid")
+            .times(1)
+            .returning(|_| ());
+
+        let manager = LoginAttemptManager::with_policy(Box::new(mock_repo), create_policy());
+        manager.register_success(EXPECTED_UID).await;
+    }
+}
@@ -1,21 +1,32 @@
+mod certificate;
+mod contacts;
+mod entry_guards;
+mod federation;
+mod groups;
+mod login_attempts;
 mod membership;
 mod models;
+mod registry;
 mod routing;
 mod services;
 mod session;
 mod storage;
+mod tickets;
+mod token;
 
+use contacts::ContactManager;
 use crosscutting::{Component, ComponentDescriptor, settings::environment, settings::logging};
 use log::{debug, error};
+use login_attempts::LoginAttemptManager;
 use membership::MemberManager;
+use registry::ControllerRegistry;
 use routing::RouteManager;
 use session::SessionManager;
-use std::{error::Error, sync::Arc, time::Duration};
+use std::{error::Error, sync::Arc};
 use storage::RepositoryType;
-use tokio::{signal, time::sleep};
+use tokio::signal;
 use tokio_util::sync::CancellationToken;
 
-const EXPIRATION_SESSION_TIME: Duration = Duration::from_secs(2);
 const MEMBERS_CSV_FILE_KEY: &str = "MEMBERS_CSV_FILE";
 
 #[tokio::main]
@@ -27,66 +38,58 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let descriptor: ComponentDescriptor = ComponentDescriptor::load(Component::Controller)?;
     let cancellation_token = CancellationToken::new();
-    let (session_manager, route_manager, member_manager) =
+    let (session_manager, route_manager, member_manager, login_attempt_manager) =
         create_domain_components(&cancellation_token);
+    let controller_registry = Arc::new(ControllerRegistry::new(RepositoryType::get_from_env()));
+    let contact_manager = Arc::new(ContactManager::new(RepositoryType::get_from_env()));
 
-    initialize(
-        descriptor.clone(),
-        Arc::clone(&member_manager),
-        Arc::clone(&session_manager),
-        cancellation_token.child_token(),
-    )
-    .await?;
+    initialize(Arc::clone(&member_manager)).await?;
 
-    let server_handle =
-        services::start_server_handler(descriptor, session_manager, route_manager, member_manager);
+    let heartbeat_handle = registry::start_heartbeat_handler(
+        Arc::clone(&controller_registry),
+        registry::create_instance(&descriptor),
+        cancellation_token.child_token(),
+    );
+
+    let server_handle = services::start_server_handler(
+        descriptor,
+        session_manager,
+        route_manager,
+        member_manager,
+        login_attempt_manager,
+        controller_registry,
+        contact_manager,
+    );
 
     _ = signal::ctrl_c().await;
     debug!("Received shutdown signal, terminating gracefully...");
 
     cancellation_token.cancel();
+    _ = heartbeat_handle.await;
     server_handle.abort();
     _ = server_handle.await;
 
     Ok(())
 }
 
-async fn initialize(
-    descriptor: ComponentDescriptor,
-    member_manager: Arc<MemberManager>,
-    session_manager: Arc<SessionManager>,
-    cancellation_token: CancellationToken,
-) -> Result<(), Box<dyn Error>> {
+async fn initialize(member_manager: Arc<MemberManager>) -> Result<(), Box<dyn Error>> {
     let file_path = environment::get_env_variable(MEMBERS_CSV_FILE_KEY).unwrap();
     if let Err(e) = member_manager.seed_members_from_csv(&file_path).await {
         error!("Failed to seed members: {}", e);
         return Err("Initialization failed".into());
     }
 
-    tokio::spawn(async move {
-        let credentials = descriptor.get_credentials();
-        let connection_settings = descriptor.get_connection_settings();
-
-        while !cancellation_token.is_cancelled() {
-            session_manager
-                .set_session(
-                    &credentials.uid,
-                    Component::Controller,
-                    &connection_settings.get_public_socket_address(),
-                    connection_settings,
-                )
-                .await;
-
-            sleep(EXPIRATION_SESSION_TIME).await;
-        }
-    });
-
     Ok(())
 }
 
 fn create_domain_components(
     cancellation_token: &CancellationToken,
-) -> (Arc<SessionManager>, Arc<RouteManager>, Arc<MemberManager>) {
+) -> (
+    Arc<SessionManager>,
+    Arc<RouteManager>,
+    Arc<MemberManager>,
+    Arc<LoginAttemptManager>,
+) {
     let repository_type = RepositoryType::get_from_env();
     let member_manager = Arc::new(MemberManager::new(repository_type));
     let route_manager = Arc::new(RouteManager::new(
@@ -98,5 +101,12 @@ fn create_domain_components(
         cancellation_token.child_token(),
     ));
 
-    (session_manager, route_manager, member_manager)
+    let login_attempt_manager = Arc::new(LoginAttemptManager::new(repository_type));
+
+    (
+        session_manager,
+        route_manager,
+        member_manager,
+        login_attempt_manager,
+    )
 }
@@ -1,12 +1,111 @@
-use crate::models::Member;
+use crate::models::{Member, ProxyRole};
 use crate::storage::{self, MemberRepository, RepositoryType};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Argon2, Params, password_hash::rand_core::OsRng};
+use crosscutting::settings::environment;
 use csv::ReaderBuilder;
+use log::warn;
 use std::fs::File;
+use std::sync::LazyLock;
+
+const HASH_PREFIX: &str = "$argon2";
+const PASSWORD_MIN_LENGTH_KEY: &str = "PASSWORD_MIN_LENGTH";
+const PASSWORD_REQUIRE_UPPERCASE_KEY: &str = "PASSWORD_REQUIRE_UPPERCASE";
+const PASSWORD_REQUIRE_LOWERCASE_KEY: &str = "PASSWORD_REQUIRE_LOWERCASE";
+const PASSWORD_REQUIRE_DIGIT_KEY: &str = "PASSWORD_REQUIRE_DIGIT";
+const PASSWORD_REQUIRE_SYMBOL_KEY: &str = "PASSWORD_REQUIRE_SYMBOL";
+const DEFAULT_PASSWORD_MIN_LENGTH: usize = 12;
+
+static DUMMY_HASH: LazyLock<String> =
+    LazyLock::new(|| MemberManager::hash_password("dummy-password").unwrap());
 
 pub struct MemberManager {
     repository: Box<dyn MemberRepository>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: DEFAULT_PASSWORD_MIN_LENGTH,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn get_from_env() -> Self {
+        let default = Self::default();
+        let get_flag = |key: &str, default: bool| {
+            environment::get_env_variable(key)
+                .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(default)
+        };
+
+        Self {
+            min_length: environment::get_env_variable(PASSWORD_MIN_LENGTH_KEY)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default.min_length),
+            require_uppercase: get_flag(PASSWORD_REQUIRE_UPPERCASE_KEY, default.require_uppercase),
+            require_lowercase: get_flag(PASSWORD_REQUIRE_LOWERCASE_KEY, default.require_lowercase),
+            require_digit: get_flag(PASSWORD_REQUIRE_DIGIT_KEY, default.require_digit),
+            require_symbol: get_flag(PASSWORD_REQUIRE_SYMBOL_KEY, default.require_symbol),
+        }
+    }
+
+    pub fn validate(&self, pwd: &str) -> Result<(), String> {
+        if pwd.chars().count() < self.min_length {
+            return Err(format!(
+                "Password must be at least {} characters long",
+                self.min_length
+            ));
+        }
+
+        let rules = [
+            (
+                self.require_uppercase,
+                pwd.chars().any(char::is_uppercase),
+                "an uppercase letter",
+            ),
+            (
+                self.require_lowercase,
+                pwd.chars().any(char::is_lowercase),
+                "a lowercase letter",
+            ),
+            (
+                self.require_digit,
+                pwd.chars().any(|c| c.is_ascii_digit()),
+                "a digit",
+            ),
+            (
+                self.require_symbol,
+                pwd.chars().any(|c| !c.is_alphanumeric()),
+                "a symbol",
+            ),
+        ];
+
+        match rules
+            .iter()
+            .find(|(required, found, _)| *required && !found)
+        {
+            Some((_, _, rule)) => Err(format!("Password must contain {}", rule)),
+            None => Ok(()),
+        }
+    }
+}
+
 impl MemberManager {
     pub fn new(repository_type: RepositoryType) -> Self {
         Self {
@@ -15,11 +114,103 @@ impl MemberManager {
     }
 
     pub async fn get_member(&self, uid: &str) -> Option<Member> {
-        self.repository.get_member(uid).await
+        let mut member = self.repository.get_member(uid).await?;
+        if !member.pwd_hash.starts_with(HASH_PREFIX) {
+            warn!(
+                "Member {} has a plaintext password stored, replacing it by its hash",
+                uid
+            );
+            if let Ok(pwd_hash) = Self::hash_password_blocking(&member.pwd_hash).await {
+                member.pwd_hash = pwd_hash;
+                self.repository.set_member(&member).await;
+            }
+        }
+
+        Some(member)
+    }
+
+    pub async fn verify_credentials(&self, uid: &str, pwd: &str) -> bool {
+        let member = self.get_member(uid).await;
+        let exists = member.is_some();
+        let disabled = member.as_ref().is_some_and(|member| member.disabled);
+        let pwd_hash = member
+            .map(|member| member.pwd_hash)
+            .unwrap_or_else(|| DUMMY_HASH.clone());
+        let pwd = pwd.to_string();
+
+        let is_valid = tokio::task::spawn_blocking(move || Self::verify_password(&pwd, &pwd_hash))
+            .await
+            .unwrap_or(false);
+
+        exists && is_valid && !disabled
+    }
+
+    pub async fn is_active(&self, uid: &str) -> bool {
+        self.get_member(uid)
+            .await
+            .is_some_and(|member| !member.disabled)
+    }
+
+    pub async fn add_member(&self, uid: &str, pwd: &str) -> Result<(), String> {
+        if self.get_member(uid).await.is_some() {
+            return Err(format!("Member {} already exists", uid));
+        }
+
+        let pwd_hash = Self::hash_password_blocking(pwd).await?;
+        if !self
+            .repository
+            .insert_member(&Member::new(uid.to_string(), pwd_hash))
+            .await
+        {
+            return Err(format!("Member {} already exists", uid));
+        }
+
+        Ok(())
+    }
+
+    pub async fn remove_member(&self, uid: &str) -> bool {
+        self.repository.remove_member(uid).await
+    }
+
+    pub async fn list_members(&self, offset: usize, limit: usize) -> (Vec<Member>, usize) {
+        self.repository.list_members(offset, limit).await
+    }
+
+    pub async fn reset_password(&self, uid: &str, pwd: &str) -> Result<bool, String> {
+        if let Some(mut member) = self.get_member(uid).await {
+            member.pwd_hash = Self::hash_password_blocking(pwd).await?;
+            self.repository.set_member(&member).await;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    pub async fn disable_member(&self, uid: &str) -> bool {
+        if let Some(mut member) = self.get_member(uid).await {
+            member.disabled = true;
+            self.repository.set_member(&member).await;
+            return true;
+        }
+
+        false
+    }
+
+    pub async fn approve_roles(&self, uid: &str, roles: &[ProxyRole]) -> bool {
+        if let Some(mut member) = self.get_member(uid).await {
+            member.roles = roles.to_vec();
+            self.repository.set_member(&member).await;
+            return true;
+        }
+
+        false
     }
 
     pub async fn seed_members_from_csv(&self, file_path: &str) -> Result<(), String> {
-        let members: Vec<Member> = MemberManager::read_members_from_csv(file_path);
+        let path = file_path.to_string();
+        let members = tokio::task::spawn_blocking(move || Self::read_members_from_csv(&path))
+            .await
+            .map_err(|e| e.to_string())??;
         self.set_members(&members).await;
         Ok(())
     }
@@ -30,22 +221,63 @@ impl MemberManager {
         }
     }
 
-    fn read_members_from_csv(path: &str) -> Vec<Member> {
-        let file = File::open(path).unwrap();
+    pub fn hash_password(pwd: &str) -> Result<String, String> {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(pwd.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| format!("Failed to hash password: {}", e))
+    }
+
+    async fn hash_password_blocking(pwd: &str) -> Result<String, String> {
+        let pwd = pwd.to_string();
+        tokio::task::spawn_blocking(move || Self::hash_password(&pwd))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    fn verify_password(pwd: &str, pwd_hash: &str) -> bool {
+        PasswordHash::new(pwd_hash)
+            .map(|hash| {
+                Argon2::default()
+                    .verify_password(pwd.as_bytes(), &hash)
+                    .is_ok()
+            })
+            .unwrap_or(false)
+    }
+
+    fn to_pwd_hash(uid: &str, pwd: &str) -> Result<String, String> {
+        if pwd.starts_with(HASH_PREFIX) {
+            let hash =
+                PasswordHash::new(pwd).map_err(|e| format!("Invalid hash for {}: {}", uid, e))?;
+            Params::try_from(&hash).map_err(|e| format!("Invalid hash for {}: {}", uid, e))?;
+            if hash.salt.is_none() || hash.hash.is_none() {
+                return Err(format!("Invalid hash for {}: missing salt or output", uid));
+            }
+
+            return Ok(pwd.to_string());
+        }
+
+        warn!(
+            "Member {} has a plaintext password, hashing it on import. Consider replacing it by its hash",
+            uid
+        );
+        Self::hash_password(pwd)
+    }
+
+    fn read_members_from_csv(path: &str) -> Result<Vec<Member>, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
         let mut rdr = ReaderBuilder::new()
             .has_headers(false)
             .delimiter(b';')
             .from_reader(file);
 
         rdr.records()
-            .filter_map(|record| {
-                if let Ok(item) = record {
-                    let uid = item.get(0).unwrap().to_string();
-                    let pwd = item.get(1).unwrap().to_string();
-                    Some(Member::new(uid, pwd))
-                } else {
-                    None
-                }
+            .filter_map(|record| record.ok())
+            .map(|item| {
+                let uid = item.get(0).unwrap_or_default().to_string();
+                let pwd = item.get(1).unwrap_or_default();
+                Self::to_pwd_hash(&uid, pwd).map(|pwd_hash| Member::new(uid, pwd_hash))
             })
             .collect()
     }
@@ -55,6 +287,7 @@ impl MemberManager {
 mod tests {
     use super::*;
     use crate::storage::{MockMemberRepository, RepositoryType};
+    use std::io::Write;
 
     const EXPECTED_UID: &str = "1234567890";
     const EXPECTED_PWD: &str = "password";
@@ -67,10 +300,42 @@ mod tests {
 
     impl PartialEq for Member {
         fn eq(&self, other: &Self) -> bool {
-            self.uid == other.uid && self.pwd == other.pwd
+            self.uid == other.uid && self.pwd_hash == other.pwd_hash
         }
     }
 
+    fn write_csv(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}.csv", name, std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn given_short_password_when_validating_policy_then_returns_error() {
+        let policy = PasswordPolicy::default();
+
+        assert!(policy.validate("short").is_err());
+        assert!(policy.validate("long-enough-password").is_ok());
+    }
+
+    #[test]
+    fn given_missing_character_class_when_validating_policy_then_returns_error() {
+        let policy = PasswordPolicy {
+            min_length: 8,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: true,
+        };
+
+        assert_eq!(
+            policy.validate("Password123"),
+            Err("Password must contain a symbol".to_string())
+        );
+        assert!(policy.validate("Password123!").is_ok());
+    }
+
     #[tokio::test]
     async fn new_creates_member_manager() {
         _ = MemberManager::new(RepositoryType::InMemory);
@@ -92,7 +357,10 @@ mod tests {
     #[tokio::test]
     async fn get_member_returns_member_if_exists() {
         let mut mock_repo = MockMemberRepository::new();
-        let expected_member = Member::new(EXPECTED_UID.to_string(), EXPECTED_PWD.to_string());
+        let expected_member = Member::new(
+            EXPECTED_UID.to_string(),
+            MemberManager::hash_password(EXPECTED_PWD).unwrap(),
+        );
         let ref_expected_member = expected_member.clone();
 
         mock_repo
@@ -105,4 +373,153 @@ mod tests {
 
         assert_eq!(member, expected_member);
     }
+
+    #[tokio::test]
+    async fn get_member_rehashes_legacy_plaintext_password() {
+        let legacy: Member =
+            serde_json::from_str(r#"{"uid":"1234567890","pwd":"password"}"#).unwrap();
+        assert_eq!(legacy.pwd_hash, EXPECTED_PWD);
+
+        let mut mock_repo = MockMemberRepository::new();
+        mock_repo
+            .expect_get_member()
+            .returning(move |_| Some(legacy.clone()));
+        mock_repo
+            .expect_set_member()
+            .withf(|member| member.pwd_hash.starts_with(HASH_PREFIX))
+            .times(1)
+            .returning(|_| ());
+
+        let member_manager = MemberManager::with_repository(Box::new(mock_repo));
+
+        assert!(
+            member_manager
+                .verify_credentials(EXPECTED_UID, EXPECTED_PWD)
+                .await
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_add_member_creates_member_only_once() {
+        let member_manager = std::sync::Arc::new(MemberManager::new(RepositoryType::InMemory));
+
+        let handles: Vec<_> = (0..5)
+            .map(|index| {
+                let member_manager = std::sync::Arc::clone(&member_manager);
+                tokio::spawn(async move {
+                    member_manager
+                        .add_member(EXPECTED_UID, &format!("{}{}", EXPECTED_PWD, index))
+                        .await
+                })
+            })
+            .collect();
+
+        let mut added = 0;
+        for handle in handles {
+            if handle.await.unwrap().is_ok() {
+                added += 1;
+            }
+        }
+
+        assert_eq!(added, 1);
+    }
+
+    #[tokio::test]
+    async fn hash_password_does_not_store_plaintext() {
+        let pwd_hash = MemberManager::hash_password(EXPECTED_PWD).unwrap();
+
+        assert!(pwd_hash.starts_with("$argon2id"));
+        assert!(!pwd_hash.contains(EXPECTED_PWD));
+    }
+
+    #[tokio::test]
+    async fn verify_credentials_succeeds_with_right_password() {
+        let member_manager = MemberManager::new(RepositoryType::InMemory);
+        let pwd_hash = MemberManager::hash_password(EXPECTED_PWD).unwrap();
+        member_manager
+            .set_members(&[Member::new(EXPECTED_UID.to_string(), pwd_hash)])
+            .await;
+
+        assert!(
+            member_manager
+                .verify_credentials(EXPECTED_UID, EXPECTED_PWD)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn verify_credentials_fails_with_wrong_password() {
+        let member_manager = MemberManager::new(RepositoryType::InMemory);
+        let pwd_hash = MemberManager::hash_password(EXPECTED_PWD).unwrap();
+        member_manager
+            .set_members(&[Member::new(EXPECTED_UID.to_string(), pwd_hash)])
+            .await;
+
+        assert!(
+            !member_manager
+                .verify_credentials(EXPECTED_UID, "wrong_password")
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn verify_credentials_fails_for_unknown_member() {
+        let member_manager = MemberManager::new(RepositoryType::InMemory);
+
+        assert!(
+            !member_manager
+                .verify_credentials(EXPECTED_UID, EXPECTED_PWD)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn seed_members_from_csv_hashes_plaintext_and_keeps_hashes() {
+        const HASHED_UID: &str = "hashed_uid";
+        const HASHED_PWD: &str = "hashed_password";
+
+        let existing_hash = MemberManager::hash_password(HASHED_PWD).unwrap();
+        let path = write_csv(
+            "members",
+            &format!(
+                "{};{}\n{};{}\n",
+                EXPECTED_UID, EXPECTED_PWD, HASHED_UID, existing_hash
+            ),
+        );
+
+        let member_manager = MemberManager::new(RepositoryType::InMemory);
+        let result = member_manager.seed_members_from_csv(&path).await;
+        _ = std::fs::remove_file(&path);
+        assert!(result.is_ok());
+
+        let member = member_manager.get_member(EXPECTED_UID).await.unwrap();
+        assert_ne!(member.pwd_hash, EXPECTED_PWD);
+        assert!(
+            member_manager
+                .verify_credentials(EXPECTED_UID, EXPECTED_PWD)
+                .await
+        );
+
+        let member = member_manager.get_member(HASHED_UID).await.unwrap();
+        assert_eq!(member.pwd_hash, existing_hash);
+        assert!(
+            member_manager
+                .verify_credentials(HASHED_UID, HASHED_PWD)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn seed_members_from_csv_with_malformed_hash_returns_error() {
+        let path = write_csv(
+            "malformed-members",
+            &format!("{};$argon2id$v=19$m=abc\n", EXPECTED_UID),
+        );
+
+        let member_manager = MemberManager::new(RepositoryType::InMemory);
+        let result = member_manager.seed_members_from_csv(&path).await;
+        _ = std::fs::remove_file(&path);
+
+        assert!(result.is_err());
+    }
 }
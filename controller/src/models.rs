@@ -10,9 +10,22 @@ pub mod route_proto {
     tonic::include_proto!("route");
 }
 
+pub mod admin_proto {
+    tonic::include_proto!("admin");
+}
+
+pub mod federation_proto {
+    tonic::include_proto!("federation");
+}
+
 use crosscutting::ConnectionSettings;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 use std::net::SocketAddr;
+use std::str::FromStr;
+
+pub const DEFAULT_HOPS: u8 = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Route {
@@ -22,6 +35,8 @@ pub struct Route {
     pub domain_name: String,
     pub nonce: String,
     pub end_route: bool,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,12 +46,46 @@ pub struct Conversation {
     pub to: String,
     pub routing_id: u8,
     pub routes: Vec<Route>,
+    pub sender_key: Vec<u8>,
+    #[serde(default)]
+    pub recipient_key: Vec<u8>,
+    #[serde(default)]
+    pub handoff: Option<Route>,
+    #[serde(default)]
+    pub pseudonym: Option<Pseudonym>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default = "default_hops")]
+    pub hops: u8,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Pseudonym {
+    pub name: String,
+    pub reply_handle: String,
+}
+
+/// Who answers to a reply handle. Replies are sealed for the ephemeral key of
+/// the anonymous conversation, while the device key only picks the session to
+/// deliver them to and never leaves the controller.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ReplyTarget {
+    pub uid: String,
+    pub reply_key: Vec<u8>,
+    pub device_key: Vec<u8>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Member {
     pub uid: String,
-    pub pwd: String,
+    #[serde(alias = "pwd")]
+    pub pwd_hash: String,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default)]
+    pub roles: Vec<ProxyRole>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +98,78 @@ pub struct SessionInfo {
     pub component_type: u8,
     pub public_key: Vec<u8>,
     pub domain_name: String,
+    pub identity_key: Vec<u8>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub rtt_micros: i64,
+    #[serde(default)]
+    pub load: Option<ProxyLoad>,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+    #[serde(default)]
+    pub roles: Option<Vec<ProxyRole>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyRole {
+    Entry,
+    Middle,
+    Exit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProxyLoad {
+    pub in_flight: u32,
+    pub throughput: f64,
+    pub available_capacity: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerInstance {
+    pub instance_id: String,
+    pub uid: String,
+    pub version: String,
+    pub ip_address: String,
+    pub port_number: u16,
+    pub domain_name: String,
+    pub started_at: i64,
+    pub last_heartbeat: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    pub name: String,
+    pub owner: String,
+    pub members: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContactList {
+    pub uid: String,
+    pub allowed: Vec<String>,
+    pub blocked: Vec<String>,
+    #[serde(default)]
+    pub visible: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EntryGuards {
+    pub uid: String,
+    pub guards: Vec<EntryGuard>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryGuard {
+    pub proxy_uid: String,
+    pub pinned_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SessionCount {
+    pub members: usize,
+    pub sessions: usize,
 }
 
 impl SessionInfo {
@@ -63,19 +184,58 @@ impl SessionInfo {
 }
 
 impl Conversation {
-    pub fn new(id: String, from: String, to: String, routing_id: u8) -> Self {
+    pub fn new(id: String, from: String, to: String, routing_id: u8, sender_key: Vec<u8>) -> Self {
         Self {
             id,
             from,
             to,
             routing_id,
             routes: Vec::new(),
+            sender_key,
+            recipient_key: Vec::new(),
+            handoff: None,
+            pseudonym: None,
+            group: None,
+            parent: None,
+            hops: DEFAULT_HOPS,
         }
     }
 }
 
+fn default_hops() -> u8 {
+    DEFAULT_HOPS
+}
+
 impl Member {
-    pub fn new(uid: String, pwd: String) -> Self {
-        Self { uid, pwd }
+    pub fn new(uid: String, pwd_hash: String) -> Self {
+        Self {
+            uid,
+            pwd_hash,
+            disabled: false,
+            roles: Vec::new(),
+        }
+    }
+}
+
+impl FromStr for ProxyRole {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "entry" => Ok(ProxyRole::Entry),
+            "middle" => Ok(ProxyRole::Middle),
+            "exit" => Ok(ProxyRole::Exit),
+            _ => Err(format!("Invalid proxy role: {}", value)),
+        }
+    }
+}
+
+impl fmt::Display for ProxyRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyRole::Entry => write!(f, "entry"),
+            ProxyRole::Middle => write!(f, "middle"),
+            ProxyRole::Exit => write!(f, "exit"),
+        }
     }
 }
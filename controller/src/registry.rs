@@ -0,0 +1,176 @@
+use crate::models::ControllerInstance;
+use crate::storage::{self, ControllerRepository, RepositoryType};
+use crosscutting::ComponentDescriptor;
+use crosscutting::settings::environment;
+use log::{debug, info};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+const CONTROLLER_INSTANCE_ID_KEY: &str = "CONTROLLER_INSTANCE_ID";
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+const HEARTBEAT_EXPIRATION_TIME: Duration = Duration::from_secs(10);
+
+pub struct ControllerRegistry {
+    repository: Box<dyn ControllerRepository>,
+}
+
+impl ControllerRegistry {
+    pub fn new(repository_type: RepositoryType) -> Self {
+        Self {
+            repository: storage::create_controller_repository(repository_type).unwrap(),
+        }
+    }
+
+    pub async fn heartbeat(&self, instance: &mut ControllerInstance) {
+        instance.last_heartbeat = chrono::Utc::now().timestamp();
+        self.repository
+            .set_controller(instance, HEARTBEAT_EXPIRATION_TIME)
+            .await;
+    }
+
+    pub async fn deregister(&self, instance_id: &str) -> bool {
+        self.repository.remove_controller(instance_id).await
+    }
+
+    pub async fn list_controllers(&self) -> Vec<ControllerInstance> {
+        let mut controllers = self.repository.list_controllers().await;
+        controllers.sort_by(|a, b| {
+            a.started_at
+                .cmp(&b.started_at)
+                .then_with(|| a.instance_id.cmp(&b.instance_id))
+        });
+        controllers
+    }
+}
+
+pub fn create_instance(descriptor: &ComponentDescriptor) -> ControllerInstance {
+    let instance_id = environment::get_env_variable(CONTROLLER_INSTANCE_ID_KEY)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let version = match descriptor {
+        ComponentDescriptor::Controller { version, .. } => version.clone(),
+        _ => String::default(),
+    };
+    let connection_settings = descriptor.get_connection_settings();
+    let now = chrono::Utc::now().timestamp();
+
+    ControllerInstance {
+        instance_id,
+        uid: descriptor.get_credentials().uid.clone(),
+        version,
+        ip_address: connection_settings.ip.clone(),
+        port_number: connection_settings.port,
+        domain_name: connection_settings.domain_name.clone(),
+        started_at: now,
+        last_heartbeat: now,
+    }
+}
+
+pub fn start_heartbeat_handler(
+    registry: Arc<ControllerRegistry>,
+    mut instance: ControllerInstance,
+    cancellation_token: CancellationToken,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Registering controller instance {}", instance.instance_id);
+
+        while !cancellation_token.is_cancelled() {
+            registry.heartbeat(&mut instance).await;
+
+            tokio::select! {
+                _ = cancellation_token.cancelled() => break,
+                _ = tokio::time::sleep(HEARTBEAT_INTERVAL) => {}
+            }
+        }
+
+        debug!("Deregistering controller instance {}", instance.instance_id);
+        registry.deregister(&instance.instance_id).await;
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MockControllerRepository;
+
+    const EXPECTED_INSTANCE_ID: &str = "test_instance_id";
+
+    impl ControllerRegistry {
+        fn with_repository(repository: Box<dyn ControllerRepository>) -> Self {
+            Self { repository }
+        }
+    }
+
+    fn create_controller(instance_id: &str, started_at: i64) -> ControllerInstance {
+        ControllerInstance {
+            instance_id: instance_id.to_string(),
+            uid: "test_uid".to_string(),
+            version: "1.0.0".to_string(),
+            ip_address: "127.0.0.1".to_string(),
+            port_number: 50051,
+            domain_name: "controller".to_string(),
+            started_at,
+            last_heartbeat: started_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn given_instance_when_sending_heartbeat_then_record_is_renewed() {
+        let mut mock_repo = MockControllerRepository::new();
+        mock_repo
+            .expect_set_controller()
+            .withf(|controller, expiration_time| {
+                controller.instance_id == EXPECTED_INSTANCE_ID
+                    && controller.last_heartbeat > 0
+                    && *expiration_time == HEARTBEAT_EXPIRATION_TIME
+            })
+            .times(1)
+            .returning(|_, _| ());
+
+        let registry = ControllerRegistry::with_repository(Box::new(mock_repo));
+        let mut instance = create_controller(EXPECTED_INSTANCE_ID, 0);
+        registry.heartbeat(&mut instance).await;
+    }
+
+    #[tokio::test]
+    async fn given_registered_instances_when_listing_then_returns_them_by_start_time() {
+        let registry = ControllerRegistry::new(RepositoryType::InMemory);
+        registry
+            .heartbeat(&mut create_controller("second", 200))
+            .await;
+        registry
+            .heartbeat(&mut create_controller("first", 100))
+            .await;
+
+        let controllers = registry.list_controllers().await;
+
+        let ids: Vec<&str> = controllers
+            .iter()
+            .map(|controller| controller.instance_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn given_running_heartbeat_when_cancelled_then_instance_is_deregistered() {
+        let registry = Arc::new(ControllerRegistry::new(RepositoryType::InMemory));
+        let cancellation_token = CancellationToken::new();
+        let handle = start_heartbeat_handler(
+            Arc::clone(&registry),
+            create_controller(EXPECTED_INSTANCE_ID, 100),
+            cancellation_token.clone(),
+        );
+
+        while registry.list_controllers().await.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        cancellation_token.cancel();
+        handle.await.unwrap();
+
+        assert!(registry.list_controllers().await.is_empty());
+        assert!(!registry.deregister(EXPECTED_INSTANCE_ID).await);
+    }
+}
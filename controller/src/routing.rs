@@ -1,17 +1,90 @@
-use crate::models::{Conversation, Route, SessionInfo};
+use crate::entry_guards::EntryGuardManager;
+use crate::models::{
+    Conversation, DEFAULT_HOPS, ProxyLoad, ProxyRole, Pseudonym, ReplyTarget, Route, SessionInfo,
+};
 use crate::storage::{self, RepositoryType, RouteRepository};
 use crosscutting::ConnectionSettings;
+use crosscutting::settings::environment;
+use log::warn;
 use mockall::automock;
-use rand::seq::IteratorRandom;
+use rand::seq::{IndexedRandom, IteratorRandom};
+use std::collections::HashMap;
 use tokio_util::sync::CancellationToken;
 use tonic::async_trait;
 use uuid::Uuid;
 
+const ROUTE_MIN_HOPS_KEY: &str = "ROUTE_MIN_HOPS";
+const ROUTE_MAX_HOPS_KEY: &str = "ROUTE_MAX_HOPS";
+const ROUTE_DEFAULT_HOPS_KEY: &str = "ROUTE_DEFAULT_HOPS";
+const ROUTE_STRATEGY_KEY: &str = "ROUTE_STRATEGY";
+const ROUTE_REQUIRE_ROLES_KEY: &str = "ROUTE_REQUIRE_ROLES";
+const RANDOM_STRATEGY_NAME: &str = "random";
+const LATENCY_STRATEGY_NAME: &str = "latency";
+const LOAD_STRATEGY_NAME: &str = "load";
+const DIVERSITY_STRATEGY_NAME: &str = "diversity";
+const DIVERSITY_TAGS: [&str; 2] = ["operator", "region"];
+const MIN_RTT_MICROS: f64 = 1000.0;
+const MAX_LATENCY_WEIGHT_RATIO: f64 = 10.0;
+const DEFAULT_MIN_HOPS: u8 = 3;
+const DEFAULT_MAX_HOPS: u8 = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HopPolicy {
+    pub min_hops: u8,
+    pub max_hops: u8,
+    pub default_hops: u8,
+}
+
+impl Default for HopPolicy {
+    fn default() -> Self {
+        Self {
+            min_hops: DEFAULT_MIN_HOPS,
+            max_hops: DEFAULT_MAX_HOPS,
+            default_hops: DEFAULT_HOPS,
+        }
+    }
+}
+
+impl HopPolicy {
+    pub fn get_from_env() -> Self {
+        let default = Self::default();
+        let get_number = |key: &str, default: u8| {
+            environment::get_env_variable(key)
+                .ok()
+                .and_then(|value| value.parse::<u8>().ok())
+                .unwrap_or(default)
+        };
+
+        let min_hops = get_number(ROUTE_MIN_HOPS_KEY, default.min_hops).max(1);
+        let max_hops = get_number(ROUTE_MAX_HOPS_KEY, default.max_hops).max(min_hops);
+        let default_hops =
+            get_number(ROUTE_DEFAULT_HOPS_KEY, default.default_hops).clamp(min_hops, max_hops);
+
+        Self {
+            min_hops,
+            max_hops,
+            default_hops,
+        }
+    }
+
+    /// Clamps the requested number of hops to the policy, where zero stands
+    /// for no preference.
+    pub fn get_hops(&self, requested: u32) -> u8 {
+        if requested == 0 {
+            return self.default_hops;
+        }
+
+        requested.clamp(self.min_hops as u32, self.max_hops as u32) as u8
+    }
+}
+
 #[automock]
 #[async_trait]
 trait RouteStrategy: Sync + Send {
     fn get_id(&self) -> u8;
-    fn has_reached_final_route(&self, conversation: &Conversation) -> bool;
+    fn has_reached_final_route(&self, conversation: &Conversation) -> bool {
+        conversation.routes.len() >= conversation.hops as usize
+    }
     async fn get_next_route(
         &self,
         conversation: &Conversation,
@@ -22,13 +95,26 @@ trait RouteStrategy: Sync + Send {
 #[derive(Default)]
 struct RandomRouteStrategy;
 
+#[derive(Default)]
+struct LatencyRouteStrategy;
+
+#[derive(Default)]
+struct LoadRouteStrategy;
+
+#[derive(Default)]
+struct DiversityRouteStrategy;
+
 struct RouteStrategyFactory {
     strategies: Vec<Box<dyn RouteStrategy>>,
+    routing_id: u8,
 }
 
 pub struct RouteManager {
     repository: Box<dyn RouteRepository>,
     route_strategy_factory: RouteStrategyFactory,
+    hop_policy: HopPolicy,
+    entry_guard_manager: EntryGuardManager,
+    require_roles: bool,
 }
 
 #[async_trait]
@@ -37,10 +123,6 @@ impl RouteStrategy for RandomRouteStrategy {
         1
     }
 
-    fn has_reached_final_route(&self, conversation: &Conversation) -> bool {
-        conversation.routes.len() == 3
-    }
-
     async fn get_next_route(
         &self,
         _: &Conversation,
@@ -58,16 +140,192 @@ impl RouteStrategy for RandomRouteStrategy {
     }
 }
 
+#[async_trait]
+impl RouteStrategy for LatencyRouteStrategy {
+    fn get_id(&self) -> u8 {
+        2
+    }
+
+    async fn get_next_route(
+        &self,
+        _: &Conversation,
+        proxies: &[SessionInfo],
+    ) -> Option<SessionInfo> {
+        let index = choose_weighted(get_latency_weights(proxies)).await?;
+        proxies.get(index).cloned()
+    }
+}
+
+/// Picks an index with a probability proportional to its weight, falling back
+/// to a uniform pick when every weight is zero.
+async fn choose_weighted(weights: Vec<f64>) -> Option<usize> {
+    tokio::task::spawn_blocking(move || {
+        let mut rng = rand::rng();
+        let indexes: Vec<usize> = (0..weights.len()).collect();
+        indexes
+            .choose_weighted(&mut rng, |index| weights[*index])
+            .or_else(|_| indexes.choose(&mut rng).ok_or(()))
+            .ok()
+            .copied()
+    })
+    .await
+    .unwrap()
+}
+
+/// Proxies which haven't reported a metric yet are given the average of the
+/// reported ones, so they weigh as much as the average proxy.
+fn fill_unknown_metrics(metrics: Vec<Option<f64>>) -> Vec<f64> {
+    let known: Vec<f64> = metrics.iter().flatten().copied().collect();
+    let average = if known.is_empty() {
+        1.0
+    } else {
+        known.iter().sum::<f64>() / known.len() as f64
+    };
+
+    metrics
+        .into_iter()
+        .map(|metric| metric.unwrap_or(average))
+        .collect()
+}
+
+/// Weighs every proxy by the inverse of its round trip, so faster proxies are
+/// picked more often while slower ones still have a chance. Round trips below
+/// `MIN_RTT_MICROS` count as that floor, and no proxy weighs more than
+/// `MAX_LATENCY_WEIGHT_RATIO` times another, so a single proxy can't attract
+/// most routes.
+fn get_latency_weights(proxies: &[SessionInfo]) -> Vec<f64> {
+    let rtts = proxies
+        .iter()
+        .map(|proxy| (proxy.rtt_micros > 0).then_some(proxy.rtt_micros as f64))
+        .collect();
+    let rtts: Vec<f64> = fill_unknown_metrics(rtts)
+        .into_iter()
+        .map(|rtt| rtt.max(MIN_RTT_MICROS))
+        .collect();
+    let fastest = rtts.iter().copied().fold(f64::INFINITY, f64::min);
+
+    rtts.into_iter()
+        .map(|rtt| 1.0 / rtt.min(fastest * MAX_LATENCY_WEIGHT_RATIO))
+        .collect()
+}
+
+#[async_trait]
+impl RouteStrategy for LoadRouteStrategy {
+    fn get_id(&self) -> u8 {
+        3
+    }
+
+    async fn get_next_route(
+        &self,
+        _: &Conversation,
+        proxies: &[SessionInfo],
+    ) -> Option<SessionInfo> {
+        let index = choose_weighted(get_load_weights(proxies)).await?;
+        proxies.get(index).cloned()
+    }
+}
+
+/// Weighs every proxy by the share of its capacity which is still available,
+/// so saturated proxies are left out unless every proxy is saturated.
+fn get_load_weights(proxies: &[SessionInfo]) -> Vec<f64> {
+    let get_free_share = |load: &ProxyLoad| {
+        let capacity = load.in_flight.saturating_add(load.available_capacity);
+        if capacity == 0 {
+            0.0
+        } else {
+            load.available_capacity as f64 / capacity as f64
+        }
+    };
+
+    fill_unknown_metrics(
+        proxies
+            .iter()
+            .map(|proxy| proxy.load.as_ref().map(get_free_share))
+            .collect(),
+    )
+}
+
+#[async_trait]
+impl RouteStrategy for DiversityRouteStrategy {
+    fn get_id(&self) -> u8 {
+        4
+    }
+
+    async fn get_next_route(
+        &self,
+        conversation: &Conversation,
+        proxies: &[SessionInfo],
+    ) -> Option<SessionInfo> {
+        let scores = get_diversity_scores(conversation.routes.last(), proxies);
+        let index = tokio::task::spawn_blocking(move || {
+            let best = scores.iter().max().copied().unwrap_or_default();
+            let mut rng = rand::rng();
+            (0..scores.len())
+                .filter(|index| scores[*index] == best)
+                .choose(&mut rng)
+        })
+        .await
+        .unwrap()?;
+
+        proxies.get(index).cloned()
+    }
+}
+
+/// Scores every proxy by how many of the diversity tags differ from the
+/// previous hop, so the best candidates share neither operator nor region with
+/// it. When the pool is too small for that, the most diverse ones are kept.
+/// Tags only count as different when both sides advertise them, so leaving
+/// them out never ranks a proxy above a tagged diverse one.
+fn get_diversity_scores(previous: Option<&Route>, proxies: &[SessionInfo]) -> Vec<usize> {
+    proxies
+        .iter()
+        .map(|proxy| {
+            DIVERSITY_TAGS
+                .iter()
+                .filter(|tag| {
+                    let previous = previous.and_then(|route| route.tags.get(**tag));
+                    match (previous, proxy.tags.get(**tag)) {
+                        (Some(previous), Some(current)) => previous != current,
+                        _ => false,
+                    }
+                })
+                .count()
+        })
+        .collect()
+}
+
 impl RouteStrategyFactory {
     fn new() -> Self {
+        let configured_strategy = environment::get_env_variable(ROUTE_STRATEGY_KEY)
+            .unwrap_or_default()
+            .to_lowercase();
+        let routing_id = match configured_strategy.as_str() {
+            LATENCY_STRATEGY_NAME => LatencyRouteStrategy.get_id(),
+            LOAD_STRATEGY_NAME => LoadRouteStrategy.get_id(),
+            DIVERSITY_STRATEGY_NAME => DiversityRouteStrategy.get_id(),
+            RANDOM_STRATEGY_NAME | "" => RandomRouteStrategy.get_id(),
+            strategy => {
+                warn!(
+                    "Unknown route strategy {}, falling back to {}",
+                    strategy, RANDOM_STRATEGY_NAME
+                );
+                RandomRouteStrategy.get_id()
+            }
+        };
+
         Self {
-            strategies: vec![Box::new(RandomRouteStrategy)],
+            strategies: vec![
+                Box::new(RandomRouteStrategy),
+                Box::new(LatencyRouteStrategy),
+                Box::new(LoadRouteStrategy),
+                Box::new(DiversityRouteStrategy),
+            ],
+            routing_id,
         }
     }
 
     fn get_routing_id(&self, _from: &str, _to: &str) -> u8 {
-        // TODO: Detect routing strategy
-        self.strategies.first().unwrap().get_id()
+        self.routing_id
     }
 
     fn get_strategy(&self, conversation: &Conversation) -> &dyn RouteStrategy {
@@ -85,18 +343,134 @@ impl RouteManager {
             route_strategy_factory: RouteStrategyFactory::new(),
             repository: storage::create_route_repository(repository_type, cancellation_token)
                 .unwrap(),
+            hop_policy: HopPolicy::get_from_env(),
+            entry_guard_manager: EntryGuardManager::new(repository_type),
+            require_roles: environment::get_env_variable(ROUTE_REQUIRE_ROLES_KEY)
+                .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or_default(),
         }
     }
 
-    pub async fn initialize(&self, from: &str, to: &str) -> Option<String> {
-        let conversation_id = Self::create_conversation_id();
-        let routing_id = self.route_strategy_factory.get_routing_id(from, to);
-        let conversation = Conversation::new(
-            conversation_id,
-            from.to_string(),
-            to.to_string(),
-            routing_id,
+    pub async fn initialize(
+        &self,
+        from: &str,
+        to: &str,
+        sender_key: &[u8],
+        recipient_key: &[u8],
+        hops: u32,
+    ) -> Option<String> {
+        let conversation = self.create_conversation(
+            Self::create_conversation_id(),
+            from,
+            to,
+            sender_key,
+            recipient_key,
+            hops,
+        );
+        self.repository.set_conversation(&conversation).await
+    }
+
+    /// Anonymous conversations are sealed and signed with an ephemeral key,
+    /// which is also the one replies are sealed for, so the member's own key
+    /// is never shown to the recipient.
+    pub async fn initialize_anonymous(
+        &self,
+        from: &str,
+        to: &str,
+        ephemeral_key: &[u8],
+        device_key: &[u8],
+        recipient_key: &[u8],
+        hops: u32,
+    ) -> Option<String> {
+        let mut conversation = self.create_conversation(
+            Self::create_conversation_id(),
+            from,
+            to,
+            ephemeral_key,
+            recipient_key,
+            hops,
+        );
+        let pseudonym = Self::create_pseudonym();
+        let target = ReplyTarget {
+            uid: from.to_string(),
+            reply_key: ephemeral_key.to_vec(),
+            device_key: device_key.to_vec(),
+        };
+        self.repository
+            .set_reply_handle(&pseudonym.reply_handle, &target)
+            .await;
+        conversation.pseudonym = Some(pseudonym);
+        self.repository.set_conversation(&conversation).await
+    }
+
+    pub async fn initialize_group(
+        &self,
+        from: &str,
+        group: &str,
+        sender_key: &[u8],
+    ) -> Option<String> {
+        let mut conversation = self.create_conversation(
+            Self::create_conversation_id(),
+            from,
+            group,
+            sender_key,
+            &[],
+            0,
+        );
+        conversation.group = Some(group.to_string());
+        self.repository.set_conversation(&conversation).await
+    }
+
+    pub async fn initialize_group_member(
+        &self,
+        parent: &Conversation,
+        to: &str,
+        recipient_key: &[u8],
+    ) -> Option<String> {
+        let mut conversation = self.create_conversation(
+            Self::create_conversation_id(),
+            &parent.from,
+            to,
+            &parent.sender_key,
+            recipient_key,
+            0,
+        );
+        conversation.group = parent.group.clone();
+        conversation.parent = Some(parent.id.clone());
+        self.repository.set_conversation(&conversation).await
+    }
+
+    pub async fn resolve_reply_handle(&self, reply_handle: &str) -> Option<ReplyTarget> {
+        self.repository.get_reply_handle(reply_handle).await
+    }
+
+    pub async fn initialize_federated(
+        &self,
+        conversation_id: &str,
+        from: &str,
+        to: &str,
+        sender_key: &[u8],
+        recipient_key: &[u8],
+        handoff: Option<Route>,
+    ) -> Option<String> {
+        if self
+            .repository
+            .get_conversation(conversation_id)
+            .await
+            .is_some()
+        {
+            return None;
+        }
+
+        let mut conversation = self.create_conversation(
+            conversation_id.to_string(),
+            from,
+            to,
+            sender_key,
+            recipient_key,
+            0,
         );
+        conversation.handoff = handoff;
         self.repository.set_conversation(&conversation).await
     }
 
@@ -112,6 +486,7 @@ impl RouteManager {
         &self,
         conversation_id: &str,
         connection_settings: &ConnectionSettings,
+        tags: &HashMap<String, String>,
         end_route: bool,
     ) -> Option<String> {
         let route = Route {
@@ -121,18 +496,14 @@ impl RouteManager {
             domain_name: connection_settings.domain_name.clone(),
             nonce: Self::create_nonce(),
             end_route,
+            tags: tags.clone(),
         };
 
         self.repository.set_route(conversation_id, &route).await
     }
 
     pub async fn redeem_route(&self, conversation_id: &str, nonce: &str) -> Option<Route> {
-        if let Some(route) = self.repository.get_route(conversation_id, nonce).await {
-            self.repository.remove_route(nonce).await;
-            return Some(route);
-        }
-
-        None
+        self.repository.take_route(conversation_id, nonce).await
     }
 
     pub async fn get_next_route(
@@ -140,22 +511,102 @@ impl RouteManager {
         conversation: &Conversation,
         proxies: &[SessionInfo],
     ) -> Option<SessionInfo> {
+        let roles = Self::get_hop_roles(conversation);
+        let mut candidates: Vec<SessionInfo> = proxies
+            .iter()
+            .filter(|proxy| self.can_serve(proxy, &roles))
+            .cloned()
+            .collect();
+        if roles.contains(&ProxyRole::Entry) {
+            candidates = self
+                .entry_guard_manager
+                .select(&conversation.from, candidates)
+                .await;
+        }
+
+        if candidates.is_empty() {
+            return None;
+        }
+
         let strategy = self.route_strategy_factory.get_strategy(conversation);
-        strategy.get_next_route(conversation, proxies).await
+        strategy.get_next_route(conversation, &candidates).await
+    }
+
+    /// The first hop enters the network and the last one leaves it, so a
+    /// single hop route has to be both.
+    fn get_hop_roles(conversation: &Conversation) -> Vec<ProxyRole> {
+        let hop = conversation.routes.len();
+        let is_last = hop + 1 >= conversation.hops as usize;
+        let mut roles = Vec::new();
+        if hop == 0 {
+            roles.push(ProxyRole::Entry);
+        }
+        if is_last {
+            roles.push(ProxyRole::Exit);
+        }
+        if roles.is_empty() {
+            roles.push(ProxyRole::Middle);
+        }
+        roles
+    }
+
+    /// Proxies which haven't declared any role can take any hop, unless roles
+    /// are required.
+    fn can_serve(&self, proxy: &SessionInfo, roles: &[ProxyRole]) -> bool {
+        match &proxy.roles {
+            Some(accepted) => roles.iter().all(|role| accepted.contains(role)),
+            None => !self.require_roles,
+        }
     }
 
+    /// Member conversations of a group start at the fan-out point, so their
+    /// only route is the final one.
     pub fn check_for_final_route(&self, conversation: &Conversation) -> bool {
+        if conversation.parent.is_some() {
+            return true;
+        }
+
         let strategy = self.route_strategy_factory.get_strategy(conversation);
         strategy.has_reached_final_route(conversation)
     }
 
-    fn create_conversation_id() -> String {
+    pub fn create_conversation_id() -> String {
         Uuid::new_v4().to_string().replace('-', "")
     }
 
+    fn create_conversation(
+        &self,
+        conversation_id: String,
+        from: &str,
+        to: &str,
+        sender_key: &[u8],
+        recipient_key: &[u8],
+        hops: u32,
+    ) -> Conversation {
+        let routing_id = self.route_strategy_factory.get_routing_id(from, to);
+        let mut conversation = Conversation::new(
+            conversation_id,
+            from.to_string(),
+            to.to_string(),
+            routing_id,
+            sender_key.to_vec(),
+        );
+        conversation.recipient_key = recipient_key.to_vec();
+        conversation.hops = self.hop_policy.get_hops(hops);
+        conversation
+    }
+
     fn create_nonce() -> String {
         Uuid::new_v4().to_string()
     }
+
+    fn create_pseudonym() -> Pseudonym {
+        let name = Uuid::new_v4().simple().to_string();
+        Pseudonym {
+            name: format!("anon-{}", &name[..8]),
+            reply_handle: Uuid::new_v4().simple().to_string(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -164,6 +615,7 @@ mod tests {
     use super::*;
     use crate::storage::MockRouteRepository;
     use crosscutting::networking::to_socket_address;
+    use std::sync::Arc;
 
     const EXPECTED_CONVERSATION_ID: &str = "test_conversation_id";
     const EXPECTED_NONCE: &str = "test_nonce";
@@ -175,12 +627,16 @@ mod tests {
     const EXPECTED_STRATEGY_ID: u8 = 1;
     const EXPECTED_PUBLIC_KEY: &[u8] = b"test_public_key";
     const EXPECTED_DOMAIN_NAME: &str = "test_domain_name";
+    const EXPECTED_IDENTITY_KEY: &[u8] = b"test_identity_key";
 
     impl RouteManager {
         fn with_repository(repository: Box<dyn RouteRepository>) -> Self {
             Self {
                 route_strategy_factory: RouteStrategyFactory::new(),
                 repository,
+                hop_policy: HopPolicy::default(),
+                entry_guard_manager: EntryGuardManager::new(RepositoryType::InMemory),
+                require_roles: false,
             }
         }
 
@@ -188,6 +644,9 @@ mod tests {
             Self {
                 route_strategy_factory: strategy_factory,
                 repository: Box::new(MockRouteRepository::new()),
+                hop_policy: HopPolicy::default(),
+                entry_guard_manager: EntryGuardManager::new(RepositoryType::InMemory),
+                require_roles: false,
             }
         }
     }
@@ -217,11 +676,194 @@ mod tests {
             .returning(|_| Some(EXPECTED_CONVERSATION_ID.to_string()));
 
         let manager = RouteManager::with_repository(Box::new(mock_repo));
-        let result = manager.initialize(EXPECTED_FROM, EXPECTED_TO).await;
+        let result = manager
+            .initialize(EXPECTED_FROM, EXPECTED_TO, EXPECTED_IDENTITY_KEY, &[], 0)
+            .await;
 
         assert_eq!(result, Some(EXPECTED_CONVERSATION_ID.to_string()));
     }
 
+    #[test]
+    fn hop_policy_clamps_requested_hops() {
+        let policy = HopPolicy {
+            min_hops: 2,
+            max_hops: 5,
+            default_hops: 3,
+        };
+
+        assert_eq!(policy.get_hops(0), 3);
+        assert_eq!(policy.get_hops(1), 2);
+        assert_eq!(policy.get_hops(4), 4);
+        assert_eq!(policy.get_hops(50), 5);
+    }
+
+    #[tokio::test]
+    async fn initialize_stores_clamped_hops_honoured_by_strategy() {
+        let mut mock_repo = MockRouteRepository::new();
+        mock_repo
+            .expect_set_conversation()
+            .withf(|conversation| conversation.hops == DEFAULT_MAX_HOPS)
+            .returning(|conversation| Some(conversation.id.clone()));
+
+        let manager = RouteManager::with_repository(Box::new(mock_repo));
+        let result = manager
+            .initialize(EXPECTED_FROM, EXPECTED_TO, EXPECTED_IDENTITY_KEY, &[], 100)
+            .await;
+        assert!(result.is_some());
+
+        let mut conversation = Conversation::new(
+            EXPECTED_CONVERSATION_ID.to_string(),
+            EXPECTED_FROM.to_string(),
+            EXPECTED_TO.to_string(),
+            EXPECTED_STRATEGY_ID,
+            vec![],
+        );
+        let route = Route {
+            on_ip_address: EXPECTED_IP.to_string(),
+            on_port_number: EXPECTED_PORT,
+            public_key: EXPECTED_PUBLIC_KEY.to_vec(),
+            domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            nonce: EXPECTED_NONCE.to_string(),
+            end_route: false,
+            tags: HashMap::new(),
+        };
+        conversation.hops = 5;
+        conversation.routes = vec![route.clone(); 4];
+        assert!(!RandomRouteStrategy.has_reached_final_route(&conversation));
+
+        conversation.routes.push(route);
+        assert!(RandomRouteStrategy.has_reached_final_route(&conversation));
+    }
+
+    #[tokio::test]
+    async fn initialize_anonymous_stores_pseudonym_and_reply_handle() {
+        let cancellation_token = CancellationToken::new();
+        let manager = RouteManager::new(RepositoryType::InMemory, cancellation_token);
+
+        let conversation_id = manager
+            .initialize_anonymous(
+                EXPECTED_FROM,
+                EXPECTED_TO,
+                EXPECTED_PUBLIC_KEY,
+                EXPECTED_IDENTITY_KEY,
+                &[],
+                0,
+            )
+            .await
+            .unwrap();
+
+        let conversation = manager.get_conversation(&conversation_id).await.unwrap();
+        assert_eq!(conversation.sender_key, EXPECTED_PUBLIC_KEY);
+        let pseudonym = conversation.pseudonym.unwrap();
+        assert!(pseudonym.name.starts_with("anon-"));
+        assert_ne!(pseudonym.name, EXPECTED_FROM);
+        assert_eq!(
+            manager.resolve_reply_handle(&pseudonym.reply_handle).await,
+            Some(ReplyTarget {
+                uid: EXPECTED_FROM.to_string(),
+                reply_key: EXPECTED_PUBLIC_KEY.to_vec(),
+                device_key: EXPECTED_IDENTITY_KEY.to_vec(),
+            })
+        );
+        assert!(manager.resolve_reply_handle(EXPECTED_NONCE).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn initialize_group_member_links_conversation_to_its_parent() {
+        let cancellation_token = CancellationToken::new();
+        let manager = RouteManager::new(RepositoryType::InMemory, cancellation_token);
+        let parent_id = manager
+            .initialize_group(EXPECTED_FROM, "group", EXPECTED_IDENTITY_KEY)
+            .await
+            .unwrap();
+        let parent = manager.get_conversation(&parent_id).await.unwrap();
+        assert!(!manager.check_for_final_route(&parent));
+
+        let member_id = manager
+            .initialize_group_member(&parent, EXPECTED_TO, EXPECTED_PUBLIC_KEY)
+            .await
+            .unwrap();
+
+        let member = manager.get_conversation(&member_id).await.unwrap();
+        assert_eq!(member.from, EXPECTED_FROM);
+        assert_eq!(member.to, EXPECTED_TO);
+        assert_eq!(member.sender_key, EXPECTED_IDENTITY_KEY);
+        assert_eq!(member.group.as_deref(), Some("group"));
+        assert_eq!(member.parent, Some(parent_id));
+        assert!(manager.check_for_final_route(&member));
+    }
+
+    #[tokio::test]
+    async fn initialize_federated_stores_conversation_with_given_id_and_handoff() {
+        let mut mock_repo = MockRouteRepository::new();
+        mock_repo
+            .expect_get_conversation()
+            .withf(|conversation_id| conversation_id == EXPECTED_CONVERSATION_ID)
+            .returning(|_| None);
+        mock_repo
+            .expect_set_conversation()
+            .withf(|conversation| {
+                conversation.id == EXPECTED_CONVERSATION_ID
+                    && conversation
+                        .handoff
+                        .as_ref()
+                        .is_some_and(|handoff| handoff.nonce == EXPECTED_NONCE)
+            })
+            .returning(|conversation| Some(conversation.id.clone()));
+
+        let handoff = Route {
+            on_ip_address: EXPECTED_IP.to_string(),
+            on_port_number: EXPECTED_PORT,
+            public_key: EXPECTED_PUBLIC_KEY.to_vec(),
+            domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            nonce: EXPECTED_NONCE.to_string(),
+            end_route: false,
+            tags: HashMap::new(),
+        };
+        let manager = RouteManager::with_repository(Box::new(mock_repo));
+        let result = manager
+            .initialize_federated(
+                EXPECTED_CONVERSATION_ID,
+                EXPECTED_FROM,
+                EXPECTED_TO,
+                EXPECTED_IDENTITY_KEY,
+                &[],
+                Some(handoff),
+            )
+            .await;
+
+        assert_eq!(result, Some(EXPECTED_CONVERSATION_ID.to_string()));
+    }
+
+    #[tokio::test]
+    async fn initialize_federated_rejects_existing_conversation() {
+        let mut mock_repo = MockRouteRepository::new();
+        mock_repo.expect_get_conversation().returning(|_| {
+            Some(Conversation::new(
+                EXPECTED_CONVERSATION_ID.to_string(),
+                EXPECTED_FROM.to_string(),
+                EXPECTED_TO.to_string(),
+                EXPECTED_STRATEGY_ID,
+                EXPECTED_IDENTITY_KEY.to_vec(),
+            ))
+        });
+        mock_repo.expect_set_conversation().times(0);
+
+        let manager = RouteManager::with_repository(Box::new(mock_repo));
+        let result = manager
+            .initialize_federated(
+                EXPECTED_CONVERSATION_ID,
+                EXPECTED_FROM,
+                EXPECTED_TO,
+                EXPECTED_IDENTITY_KEY,
+                &[],
+                None,
+            )
+            .await;
+
+        assert!(result.is_none());
+    }
+
     #[tokio::test]
     async fn store_route_calls_set_route() {
         let mut mock_repo = MockRouteRepository::new();
@@ -231,23 +873,28 @@ mod tests {
                 conversation_id == EXPECTED_CONVERSATION_ID
                     && route.on_ip_address == EXPECTED_IP
                     && route.on_port_number == EXPECTED_PORT
-                    && route.end_route == false
+                    && !route.end_route
             })
             .returning(|_, _| Some(EXPECTED_NONCE.to_string()));
 
         let manager = RouteManager::with_repository(Box::new(mock_repo));
         let result = manager
-            .store_route(EXPECTED_CONVERSATION_ID, &get_connection_settings(), false)
+            .store_route(
+                EXPECTED_CONVERSATION_ID,
+                &get_connection_settings(),
+                &HashMap::new(),
+                false,
+            )
             .await;
 
         assert_eq!(result, Some(EXPECTED_NONCE.to_string()));
     }
 
     #[tokio::test]
-    async fn redeem_route_calls_get_route_and_remove_route() {
+    async fn redeem_route_calls_take_route() {
         let mut mock_repo = MockRouteRepository::new();
         mock_repo
-            .expect_get_route()
+            .expect_take_route()
             .withf(|conversation_id, nonce| {
                 conversation_id == EXPECTED_CONVERSATION_ID && nonce == EXPECTED_NONCE
             })
@@ -259,14 +906,10 @@ mod tests {
                     domain_name: EXPECTED_DOMAIN_NAME.to_string(),
                     nonce: EXPECTED_NONCE.to_string(),
                     end_route: false,
+                    tags: HashMap::new(),
                 })
             });
 
-        mock_repo
-            .expect_remove_route()
-            .withf(|nonce| nonce == EXPECTED_NONCE)
-            .returning(|_| ());
-
         let manager = RouteManager::with_repository(Box::new(mock_repo));
         let result = manager
             .redeem_route(EXPECTED_CONVERSATION_ID, EXPECTED_NONCE)
@@ -277,17 +920,15 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn redeem_route_avoids_removing_route_when_get_route_returns_none() {
+    async fn redeem_route_returns_none_when_take_route_returns_none() {
         let mut mock_repo = MockRouteRepository::new();
         mock_repo
-            .expect_get_route()
+            .expect_take_route()
             .withf(|conversation_id, nonce| {
                 conversation_id == EXPECTED_CONVERSATION_ID && nonce == EXPECTED_NONCE
             })
             .returning(|_, _| None);
 
-        mock_repo.expect_remove_route().times(0);
-
         let manager = RouteManager::with_repository(Box::new(mock_repo));
         let result = manager
             .redeem_route(EXPECTED_CONVERSATION_ID, EXPECTED_NONCE)
@@ -296,6 +937,59 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_redeems_get_every_route_exactly_once() {
+        let cancellation_token = CancellationToken::new();
+        let manager = Arc::new(RouteManager::new(
+            RepositoryType::InMemory,
+            cancellation_token.child_token(),
+        ));
+        let conversation_id = manager
+            .initialize(EXPECTED_FROM, EXPECTED_TO, &[], &[], 0)
+            .await
+            .unwrap();
+
+        let mut nonces = Vec::new();
+        for _ in 0..5 {
+            let nonce = manager
+                .store_route(
+                    &conversation_id,
+                    &get_connection_settings(),
+                    &HashMap::new(),
+                    false,
+                )
+                .await
+                .unwrap();
+            nonces.push(nonce);
+        }
+
+        let mut handles = Vec::new();
+        for nonce in &nonces {
+            for _ in 0..10 {
+                let manager = Arc::clone(&manager);
+                let conversation_id = conversation_id.clone();
+                let nonce = nonce.clone();
+                handles.push(tokio::spawn(async move {
+                    manager
+                        .redeem_route(&conversation_id, &nonce)
+                        .await
+                        .map(|route| route.nonce)
+                }));
+            }
+        }
+
+        let mut redeemed = Vec::new();
+        for handle in handles {
+            if let Some(nonce) = handle.await.unwrap() {
+                redeemed.push(nonce);
+            }
+        }
+
+        redeemed.sort();
+        nonces.sort();
+        assert_eq!(redeemed, nonces);
+    }
+
     #[tokio::test]
     async fn get_next_route_calls_get_next_route_on_strategy() {
         let mut mock_strategy = MockRouteStrategy::new();
@@ -309,6 +1003,7 @@ mod tests {
 
         let factory = RouteStrategyFactory {
             strategies: vec![Box::new(mock_strategy) as Box<dyn RouteStrategy>],
+            routing_id: EXPECTED_STRATEGY_ID,
         };
 
         let conversation = Conversation::new(
@@ -316,6 +1011,7 @@ mod tests {
             EXPECTED_FROM.to_string(),
             EXPECTED_TO.to_string(),
             EXPECTED_STRATEGY_ID,
+            EXPECTED_IDENTITY_KEY.to_vec(),
         );
 
         let session_info = SessionInfo {
@@ -327,6 +1023,12 @@ mod tests {
             component_type: 2,
             public_key: EXPECTED_PUBLIC_KEY.to_vec(),
             domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            identity_key: EXPECTED_IDENTITY_KEY.to_vec(),
+            created_at: 0,
+            rtt_micros: 0,
+            load: None,
+            tags: HashMap::new(),
+            roles: None,
         };
 
         let available_proxies = vec![session_info.clone()];
@@ -351,6 +1053,7 @@ mod tests {
 
         let factory = RouteStrategyFactory {
             strategies: vec![Box::new(mock_strategy) as Box<dyn RouteStrategy>],
+            routing_id: EXPECTED_STRATEGY_ID,
         };
 
         let conversation = Conversation::new(
@@ -358,6 +1061,7 @@ mod tests {
             EXPECTED_FROM.to_string(),
             EXPECTED_TO.to_string(),
             EXPECTED_STRATEGY_ID,
+            EXPECTED_IDENTITY_KEY.to_vec(),
         );
 
         let manager = RouteManager::with_strategy_factory(factory);
@@ -365,4 +1069,370 @@ mod tests {
 
         assert!(result);
     }
+
+    fn create_proxy(uid: &str, rtt_micros: i64) -> SessionInfo {
+        SessionInfo {
+            access_key: EXPECTED_SESSION_ID.to_string(),
+            uid: uid.to_string(),
+            on_ip_address: EXPECTED_IP.to_string(),
+            on_port_number: EXPECTED_PORT,
+            client_ip: to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+            component_type: 2,
+            public_key: EXPECTED_PUBLIC_KEY.to_vec(),
+            domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            identity_key: EXPECTED_IDENTITY_KEY.to_vec(),
+            created_at: 0,
+            rtt_micros,
+            load: None,
+            tags: HashMap::new(),
+            roles: None,
+        }
+    }
+
+    #[test]
+    fn latency_weights_favour_faster_proxies() {
+        let weights = get_latency_weights(&[
+            create_proxy(EXPECTED_FROM, 1000),
+            create_proxy(EXPECTED_FROM, 3000),
+            create_proxy(EXPECTED_FROM, 0),
+        ]);
+
+        assert!(weights[0] > weights[2]);
+        assert!(weights[2] > weights[1]);
+        assert_eq!(weights[2], 1.0 / 2000.0);
+        assert_eq!(
+            get_latency_weights(&[create_proxy(EXPECTED_FROM, 0)]),
+            vec![1.0 / MIN_RTT_MICROS]
+        );
+    }
+
+    #[test]
+    fn latency_weights_are_floored_and_capped() {
+        let weights = get_latency_weights(&[
+            create_proxy(EXPECTED_FROM, 1),
+            create_proxy(EXPECTED_FROM, 1000),
+            create_proxy(EXPECTED_FROM, 1_000_000),
+        ]);
+
+        assert_eq!(weights[0], weights[1]);
+        assert_eq!(weights[0], weights[2] * MAX_LATENCY_WEIGHT_RATIO);
+    }
+
+    #[tokio::test]
+    async fn latency_strategy_picks_faster_proxies_more_often() {
+        let proxies = vec![create_proxy("slow", 1_000_000), create_proxy("fast", 1000)];
+        let conversation = Conversation::new(
+            EXPECTED_CONVERSATION_ID.to_string(),
+            EXPECTED_FROM.to_string(),
+            EXPECTED_TO.to_string(),
+            LatencyRouteStrategy.get_id(),
+            vec![],
+        );
+
+        let mut fast_picks = 0;
+        for _ in 0..100 {
+            let proxy = LatencyRouteStrategy
+                .get_next_route(&conversation, &proxies)
+                .await
+                .unwrap();
+            if proxy.uid == "fast" {
+                fast_picks += 1;
+            }
+        }
+
+        assert!(fast_picks > 70);
+    }
+
+    #[test]
+    fn load_weights_leave_saturated_proxies_out() {
+        let with_load = |in_flight: u32, available_capacity: u32| {
+            let mut proxy = create_proxy(EXPECTED_FROM, 0);
+            proxy.load = Some(ProxyLoad {
+                in_flight,
+                throughput: 0.0,
+                available_capacity,
+            });
+            proxy
+        };
+
+        let weights = get_load_weights(&[
+            with_load(100, 0),
+            with_load(25, 75),
+            with_load(75, 25),
+            create_proxy(EXPECTED_FROM, 0),
+        ]);
+
+        assert_eq!(weights, vec![0.0, 0.75, 0.25, 1.0 / 3.0]);
+    }
+
+    #[tokio::test]
+    async fn load_strategy_avoids_saturated_proxies() {
+        let mut saturated = create_proxy("saturated", 0);
+        saturated.load = Some(ProxyLoad {
+            in_flight: 100,
+            throughput: 50.0,
+            available_capacity: 0,
+        });
+        let mut idle = create_proxy("idle", 0);
+        idle.load = Some(ProxyLoad {
+            in_flight: 0,
+            throughput: 0.0,
+            available_capacity: 100,
+        });
+        let conversation = Conversation::new(
+            EXPECTED_CONVERSATION_ID.to_string(),
+            EXPECTED_FROM.to_string(),
+            EXPECTED_TO.to_string(),
+            LoadRouteStrategy.get_id(),
+            vec![],
+        );
+
+        for _ in 0..20 {
+            let proxy = LoadRouteStrategy
+                .get_next_route(&conversation, &[saturated.clone(), idle.clone()])
+                .await
+                .unwrap();
+            assert_eq!(proxy.uid, "idle");
+        }
+
+        let proxy = LoadRouteStrategy
+            .get_next_route(&conversation, &[saturated.clone()])
+            .await;
+        assert_eq!(proxy.unwrap().uid, "saturated");
+    }
+
+    fn create_tags(operator: &str, region: &str) -> HashMap<String, String> {
+        HashMap::from([
+            ("operator".to_string(), operator.to_string()),
+            ("region".to_string(), region.to_string()),
+        ])
+    }
+
+    fn create_tagged_proxy(uid: &str, operator: &str, region: &str) -> SessionInfo {
+        let mut proxy = create_proxy(uid, 0);
+        proxy.tags = create_tags(operator, region);
+        proxy
+    }
+
+    fn create_tagged_conversation(operator: &str, region: &str) -> Conversation {
+        let mut conversation = Conversation::new(
+            EXPECTED_CONVERSATION_ID.to_string(),
+            EXPECTED_FROM.to_string(),
+            EXPECTED_TO.to_string(),
+            DiversityRouteStrategy.get_id(),
+            vec![],
+        );
+        conversation.routes.push(Route {
+            on_ip_address: EXPECTED_IP.to_string(),
+            on_port_number: EXPECTED_PORT,
+            public_key: EXPECTED_PUBLIC_KEY.to_vec(),
+            domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            nonce: EXPECTED_NONCE.to_string(),
+            end_route: false,
+            tags: create_tags(operator, region),
+        });
+        conversation
+    }
+
+    #[test]
+    fn diversity_scores_count_tags_differing_from_previous_hop() {
+        let previous = create_tagged_conversation("acme", "eu-west")
+            .routes
+            .remove(0);
+        let scores = get_diversity_scores(
+            Some(&previous),
+            &[
+                create_tagged_proxy(EXPECTED_FROM, "acme", "eu-west"),
+                create_tagged_proxy(EXPECTED_FROM, "acme", "us-east"),
+                create_tagged_proxy(EXPECTED_FROM, "globex", "us-east"),
+                create_proxy(EXPECTED_FROM, 0),
+            ],
+        );
+
+        assert_eq!(scores, vec![0, 1, 2, 0]);
+        assert_eq!(
+            get_diversity_scores(
+                None,
+                &[create_tagged_proxy(EXPECTED_FROM, "acme", "eu-west")]
+            ),
+            vec![0]
+        );
+    }
+
+    #[tokio::test]
+    async fn given_untagged_proxy_when_getting_diverse_route_then_tagged_diverse_one_wins() {
+        let conversation = create_tagged_conversation("acme", "eu-west");
+        let proxies = vec![
+            create_proxy("untagged", 0),
+            create_tagged_proxy("diverse", "globex", "us-east"),
+        ];
+
+        for _ in 0..20 {
+            let proxy = DiversityRouteStrategy
+                .get_next_route(&conversation, &proxies)
+                .await
+                .unwrap();
+            assert_eq!(proxy.uid, "diverse");
+        }
+    }
+
+    #[tokio::test]
+    async fn diversity_strategy_avoids_same_operator_and_region_in_a_row() {
+        let conversation = create_tagged_conversation("acme", "eu-west");
+        let proxies = vec![
+            create_tagged_proxy("same", "acme", "eu-west"),
+            create_tagged_proxy("same operator", "acme", "us-east"),
+            create_tagged_proxy("same region", "globex", "eu-west"),
+            create_tagged_proxy("diverse", "globex", "us-east"),
+        ];
+
+        for _ in 0..20 {
+            let proxy = DiversityRouteStrategy
+                .get_next_route(&conversation, &proxies)
+                .await
+                .unwrap();
+            assert_eq!(proxy.uid, "diverse");
+        }
+    }
+
+    #[tokio::test]
+    async fn given_small_pool_when_getting_diverse_route_then_falls_back() {
+        let conversation = create_tagged_conversation("acme", "eu-west");
+
+        let proxy = DiversityRouteStrategy
+            .get_next_route(
+                &conversation,
+                &[
+                    create_tagged_proxy("same", "acme", "eu-west"),
+                    create_tagged_proxy("same operator", "acme", "us-east"),
+                ],
+            )
+            .await;
+        assert_eq!(proxy.unwrap().uid, "same operator");
+
+        let proxy = DiversityRouteStrategy
+            .get_next_route(
+                &conversation,
+                &[create_tagged_proxy("same", "acme", "eu-west")],
+            )
+            .await;
+        assert_eq!(proxy.unwrap().uid, "same");
+    }
+
+    fn create_role_proxy(uid: &str, roles: Option<Vec<ProxyRole>>) -> SessionInfo {
+        let mut proxy = create_proxy(uid, 0);
+        proxy.roles = roles;
+        proxy
+    }
+
+    fn create_first_proxy_factory() -> RouteStrategyFactory {
+        let mut mock_strategy = MockRouteStrategy::new();
+        mock_strategy
+            .expect_get_id()
+            .returning(|| EXPECTED_STRATEGY_ID);
+        mock_strategy
+            .expect_get_next_route()
+            .returning(|_, proxies| proxies.first().cloned());
+
+        RouteStrategyFactory {
+            strategies: vec![Box::new(mock_strategy) as Box<dyn RouteStrategy>],
+            routing_id: EXPECTED_STRATEGY_ID,
+        }
+    }
+
+    #[test]
+    fn hop_roles_follow_the_position_in_the_route() {
+        let mut conversation = create_tagged_conversation("acme", "eu-west");
+        conversation.hops = 3;
+        assert_eq!(
+            RouteManager::get_hop_roles(&conversation),
+            vec![ProxyRole::Middle]
+        );
+
+        conversation.routes.push(conversation.routes[0].clone());
+        assert_eq!(
+            RouteManager::get_hop_roles(&conversation),
+            vec![ProxyRole::Exit]
+        );
+
+        conversation.routes.clear();
+        assert_eq!(
+            RouteManager::get_hop_roles(&conversation),
+            vec![ProxyRole::Entry]
+        );
+
+        conversation.hops = 1;
+        assert_eq!(
+            RouteManager::get_hop_roles(&conversation),
+            vec![ProxyRole::Entry, ProxyRole::Exit]
+        );
+    }
+
+    #[tokio::test]
+    async fn given_proxy_roles_when_getting_next_route_then_only_eligible_proxies_are_chosen() {
+        let proxies = vec![
+            create_role_proxy("exit", Some(vec![ProxyRole::Exit])),
+            create_role_proxy("unapproved", Some(vec![])),
+            create_role_proxy("legacy", None),
+            create_role_proxy("entry", Some(vec![ProxyRole::Entry, ProxyRole::Middle])),
+        ];
+        let mut conversation = Conversation::new(
+            EXPECTED_CONVERSATION_ID.to_string(),
+            EXPECTED_FROM.to_string(),
+            EXPECTED_TO.to_string(),
+            EXPECTED_STRATEGY_ID,
+            vec![],
+        );
+
+        let mut manager = RouteManager::with_strategy_factory(create_first_proxy_factory());
+        let proxy = manager.get_next_route(&conversation, &proxies).await;
+        assert_eq!(proxy.unwrap().uid, "legacy");
+
+        manager.require_roles = true;
+        let proxy = manager.get_next_route(&conversation, &proxies).await;
+        assert_eq!(proxy.unwrap().uid, "entry");
+
+        conversation.routes = create_tagged_conversation("acme", "eu-west").routes;
+        conversation.hops = 2;
+        let proxy = manager.get_next_route(&conversation, &proxies).await;
+        assert_eq!(proxy.unwrap().uid, "exit");
+
+        let proxy = manager.get_next_route(&conversation, &proxies[1..]).await;
+        assert!(proxy.is_none());
+    }
+
+    #[tokio::test]
+    async fn given_pinned_entry_guards_when_getting_first_route_then_guards_are_reused() {
+        let manager = RouteManager::with_strategy_factory(create_first_proxy_factory());
+        let conversation = Conversation::new(
+            EXPECTED_CONVERSATION_ID.to_string(),
+            EXPECTED_FROM.to_string(),
+            EXPECTED_TO.to_string(),
+            EXPECTED_STRATEGY_ID,
+            vec![],
+        );
+        let proxies: Vec<SessionInfo> = (0..5)
+            .map(|index| create_proxy(&format!("proxy_{}", index), 0))
+            .collect();
+
+        let first = manager
+            .get_next_route(&conversation, &proxies)
+            .await
+            .unwrap();
+        let guards = manager.entry_guard_manager.get_guards(EXPECTED_FROM).await;
+
+        assert_eq!(guards.len(), 3);
+        assert!(guards.iter().any(|guard| guard.proxy_uid == first.uid));
+        for _ in 0..10 {
+            let proxy = manager
+                .get_next_route(&conversation, &proxies)
+                .await
+                .unwrap();
+            assert!(guards.iter().any(|guard| guard.proxy_uid == proxy.uid));
+        }
+        assert_eq!(
+            manager.entry_guard_manager.get_guards(EXPECTED_FROM).await,
+            guards
+        );
+    }
 }
@@ -0,0 +1,623 @@
+use super::*;
+use crate::models::ProxyRole;
+use crate::models::admin_proto::{
+    AddMemberRequest, AdminResponse, ClearLockoutRequest, ListMembersRequest, ListMembersResponse,
+    MemberInfo, MemberRequest, ResetPasswordRequest, RolesRequest,
+    admin_service_server::AdminService,
+};
+use std::net::IpAddr;
+
+const ADMIN_KEY: &str = "ADMIN_KEY";
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+pub struct AdminServiceImpl {
+    session_manager: Arc<SessionManager>,
+    member_manager: Arc<MemberManager>,
+    login_attempt_manager: Arc<LoginAttemptManager>,
+    admin_key: Option<String>,
+}
+
+impl AdminServiceImpl {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        member_manager: Arc<MemberManager>,
+        login_attempt_manager: Arc<LoginAttemptManager>,
+    ) -> Self {
+        let admin_key = settings::environment::get_env_variable(ADMIN_KEY)
+            .ok()
+            .filter(|key| !key.is_empty());
+
+        if admin_key.is_none() {
+            warn!(
+                "{} is not set, the admin service will reject every request",
+                ADMIN_KEY
+            );
+        }
+
+        Self {
+            session_manager,
+            member_manager,
+            login_attempt_manager,
+            admin_key,
+        }
+    }
+
+    fn check_uid(uid: &str) -> Result<(), &'static str> {
+        if uid.is_empty() {
+            return Err("UID cannot be empty");
+        }
+
+        Ok(())
+    }
+
+    fn check_credentials(uid: &str, pwd: &str) -> Result<(), &'static str> {
+        Self::check_uid(uid)?;
+        if pwd.is_empty() {
+            return Err("PWD cannot be empty");
+        }
+
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl AdminService for AdminServiceImpl {
+    async fn add_member(
+        &self,
+        request: Request<AddMemberRequest>,
+    ) -> Result<Response<AdminResponse>, Status> {
+        let add_request = request.into_inner();
+        guards::check_admin(&self.admin_key, &add_request.admin_key).await?;
+        Self::check_credentials(&add_request.uid, &add_request.pwd)
+            .map_err(Status::invalid_argument)?;
+
+        self.member_manager
+            .add_member(&add_request.uid, &add_request.pwd)
+            .await
+            .map_err(Status::already_exists)?;
+
+        info!("Member {} has been added", add_request.uid);
+        Ok(Response::new(AdminResponse {
+            message: "Member added".to_string(),
+        }))
+    }
+
+    async fn remove_member(
+        &self,
+        request: Request<MemberRequest>,
+    ) -> Result<Response<AdminResponse>, Status> {
+        let member_request = request.into_inner();
+        guards::check_admin(&self.admin_key, &member_request.admin_key).await?;
+        Self::check_uid(&member_request.uid).map_err(Status::invalid_argument)?;
+
+        if !self.member_manager.remove_member(&member_request.uid).await {
+            return Err(Status::not_found("Member not found"));
+        }
+
+        self.session_manager
+            .remove_sessions(&member_request.uid)
+            .await;
+
+        info!("Member {} has been removed", member_request.uid);
+        Ok(Response::new(AdminResponse {
+            message: "Member removed".to_string(),
+        }))
+    }
+
+    async fn list_members(
+        &self,
+        request: Request<ListMembersRequest>,
+    ) -> Result<Response<ListMembersResponse>, Status> {
+        let list_request = request.into_inner();
+        guards::check_admin(&self.admin_key, &list_request.admin_key).await?;
+
+        let limit = match list_request.limit as usize {
+            0 => DEFAULT_PAGE_SIZE,
+            limit => limit.min(MAX_PAGE_SIZE),
+        };
+
+        let (members, total) = self
+            .member_manager
+            .list_members(list_request.offset as usize, limit)
+            .await;
+
+        let members = members
+            .into_iter()
+            .map(|member| MemberInfo {
+                uid: member.uid,
+                disabled: member.disabled,
+                roles: member.roles.iter().map(ProxyRole::to_string).collect(),
+            })
+            .collect();
+
+        Ok(Response::new(ListMembersResponse {
+            members,
+            total: u32::try_from(total).unwrap_or(u32::MAX),
+        }))
+    }
+
+    async fn reset_password(
+        &self,
+        request: Request<ResetPasswordRequest>,
+    ) -> Result<Response<AdminResponse>, Status> {
+        let reset_request = request.into_inner();
+        guards::check_admin(&self.admin_key, &reset_request.admin_key).await?;
+        Self::check_credentials(&reset_request.uid, &reset_request.pwd)
+            .map_err(Status::invalid_argument)?;
+
+        let found = self
+            .member_manager
+            .reset_password(&reset_request.uid, &reset_request.pwd)
+            .await
+            .map_err(Status::internal)?;
+
+        if !found {
+            return Err(Status::not_found("Member not found"));
+        }
+
+        info!("Password of member {} has been reset", reset_request.uid);
+        Ok(Response::new(AdminResponse {
+            message: "Password reset".to_string(),
+        }))
+    }
+
+    async fn disable_member(
+        &self,
+        request: Request<MemberRequest>,
+    ) -> Result<Response<AdminResponse>, Status> {
+        let member_request = request.into_inner();
+        guards::check_admin(&self.admin_key, &member_request.admin_key).await?;
+        Self::check_uid(&member_request.uid).map_err(Status::invalid_argument)?;
+
+        if !self
+            .member_manager
+            .disable_member(&member_request.uid)
+            .await
+        {
+            return Err(Status::not_found("Member not found"));
+        }
+
+        self.session_manager
+            .remove_sessions(&member_request.uid)
+            .await;
+
+        info!("Member {} has been disabled", member_request.uid);
+        Ok(Response::new(AdminResponse {
+            message: "Member disabled".to_string(),
+        }))
+    }
+
+    async fn clear_lockout(
+        &self,
+        request: Request<ClearLockoutRequest>,
+    ) -> Result<Response<AdminResponse>, Status> {
+        let clear_request = request.into_inner();
+        guards::check_admin(&self.admin_key, &clear_request.admin_key).await?;
+        if clear_request.uid.is_empty() && clear_request.ip_address.is_empty() {
+            return Err(Status::invalid_argument(
+                "Either UID or IP address must be provided",
+            ));
+        }
+
+        let ip_address = match clear_request.ip_address.as_str() {
+            "" => None,
+            ip_address => Some(
+                ip_address
+                    .parse::<IpAddr>()
+                    .map_err(|_| Status::invalid_argument("Invalid IP address"))?,
+            ),
+        };
+
+        let mut cleared = false;
+        if !clear_request.uid.is_empty() {
+            cleared |= self
+                .login_attempt_manager
+                .clear_uid_lockout(&clear_request.uid)
+                .await;
+        }
+
+        if let Some(ip_address) = ip_address {
+            cleared |= self
+                .login_attempt_manager
+                .clear_ip_lockout(&ip_address)
+                .await;
+        }
+
+        if !cleared {
+            return Err(Status::not_found("Lockout not found"));
+        }
+
+        info!(
+            "Lockout cleared for uid: '{}', ip: '{}'",
+            clear_request.uid, clear_request.ip_address
+        );
+        Ok(Response::new(AdminResponse {
+            message: "Lockout cleared".to_string(),
+        }))
+    }
+
+    async fn approve_roles(
+        &self,
+        request: Request<RolesRequest>,
+    ) -> Result<Response<AdminResponse>, Status> {
+        let roles_request = request.into_inner();
+        guards::check_admin(&self.admin_key, &roles_request.admin_key).await?;
+        Self::check_uid(&roles_request.uid).map_err(Status::invalid_argument)?;
+
+        let mut roles = Vec::new();
+        for role in &roles_request.roles {
+            let role = role
+                .parse::<ProxyRole>()
+                .map_err(Status::invalid_argument)?;
+            if !roles.contains(&role) {
+                roles.push(role);
+            }
+        }
+
+        if !self
+            .member_manager
+            .approve_roles(&roles_request.uid, &roles)
+            .await
+        {
+            return Err(Status::not_found("Member not found"));
+        }
+
+        info!(
+            "Roles approved for member {}: {:?}",
+            roles_request.uid, roles_request.roles
+        );
+        Ok(Response::new(AdminResponse {
+            message: "Roles approved".to_string(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::RepositoryType;
+    use crosscutting::{Component, ConnectionSettings};
+    use tokio_util::sync::CancellationToken;
+
+    const EXPECTED_ADMIN_KEY: &str = "test_admin_key";
+    const EXPECTED_UID: &str = "test_uid";
+    const EXPECTED_PWD: &str = "test_pwd";
+    const EXPECTED_IP: &str = "127.0.0.1";
+    const EXPECTED_PORT: u16 = 8080;
+
+    impl AdminServiceImpl {
+        fn with_admin_key(admin_key: Option<&str>) -> Self {
+            let cancellation_token = CancellationToken::new();
+            let repository_type = RepositoryType::InMemory;
+            Self {
+                session_manager: Arc::new(SessionManager::new(
+                    repository_type,
+                    cancellation_token.child_token(),
+                )),
+                member_manager: Arc::new(MemberManager::new(repository_type)),
+                login_attempt_manager: Arc::new(LoginAttemptManager::new(repository_type)),
+                admin_key: admin_key.map(String::from),
+            }
+        }
+    }
+
+    fn add_member_request(admin_key: &str, uid: &str) -> Request<AddMemberRequest> {
+        Request::new(AddMemberRequest {
+            admin_key: admin_key.to_string(),
+            uid: uid.to_string(),
+            pwd: EXPECTED_PWD.to_string(),
+        })
+    }
+
+    fn member_request(uid: &str) -> Request<MemberRequest> {
+        Request::new(MemberRequest {
+            admin_key: EXPECTED_ADMIN_KEY.to_string(),
+            uid: uid.to_string(),
+        })
+    }
+
+    async fn login(service: &AdminServiceImpl) -> String {
+        let connection_settings = ConnectionSettings {
+            ip: EXPECTED_IP.to_string(),
+            port: EXPECTED_PORT,
+            domain_name: String::default(),
+            certificate: vec![],
+        };
+
+        service
+            .session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &connection_settings,
+                &[],
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn given_wrong_admin_key_when_adding_member_then_returns_permission_denied() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+
+        let result = service
+            .add_member(add_member_request("wrong_key", EXPECTED_UID))
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn given_no_admin_key_configured_when_adding_member_then_returns_permission_denied() {
+        let service = AdminServiceImpl::with_admin_key(None);
+
+        let result = service
+            .add_member(add_member_request("", EXPECTED_UID))
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn given_admin_key_when_adding_member_then_member_can_log_in() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+
+        let result = service
+            .add_member(add_member_request(EXPECTED_ADMIN_KEY, EXPECTED_UID))
+            .await;
+
+        assert!(result.is_ok());
+        assert!(
+            service
+                .member_manager
+                .verify_credentials(EXPECTED_UID, EXPECTED_PWD)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn given_existing_member_when_adding_member_then_returns_already_exists() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+        _ = service
+            .add_member(add_member_request(EXPECTED_ADMIN_KEY, EXPECTED_UID))
+            .await;
+
+        let result = service
+            .add_member(add_member_request(EXPECTED_ADMIN_KEY, EXPECTED_UID))
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().code(), tonic::Code::AlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn given_member_with_session_when_removing_member_then_session_is_dropped() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+        _ = service
+            .add_member(add_member_request(EXPECTED_ADMIN_KEY, EXPECTED_UID))
+            .await;
+        let access_key = login(&service).await;
+
+        let result = service.remove_member(member_request(EXPECTED_UID)).await;
+
+        assert!(result.is_ok());
+        assert!(
+            service
+                .member_manager
+                .get_member(EXPECTED_UID)
+                .await
+                .is_none()
+        );
+        assert!(
+            service
+                .session_manager
+                .get_session(&access_key)
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_non_existing_member_when_removing_member_then_returns_not_found() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+
+        let result = service.remove_member(member_request(EXPECTED_UID)).await;
+
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn given_member_with_session_when_disabling_member_then_login_fails_and_session_is_dropped()
+     {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+        _ = service
+            .add_member(add_member_request(EXPECTED_ADMIN_KEY, EXPECTED_UID))
+            .await;
+        let access_key = login(&service).await;
+
+        let result = service.disable_member(member_request(EXPECTED_UID)).await;
+
+        assert!(result.is_ok());
+        assert!(
+            service
+                .session_manager
+                .get_session(&access_key)
+                .await
+                .is_none()
+        );
+        assert!(
+            !service
+                .member_manager
+                .verify_credentials(EXPECTED_UID, EXPECTED_PWD)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn given_member_when_resetting_password_then_only_new_password_is_valid() {
+        const NEW_PWD: &str = "new_test_pwd";
+
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+        _ = service
+            .add_member(add_member_request(EXPECTED_ADMIN_KEY, EXPECTED_UID))
+            .await;
+
+        let result = service
+            .reset_password(Request::new(ResetPasswordRequest {
+                admin_key: EXPECTED_ADMIN_KEY.to_string(),
+                uid: EXPECTED_UID.to_string(),
+                pwd: NEW_PWD.to_string(),
+            }))
+            .await;
+
+        assert!(result.is_ok());
+        assert!(
+            service
+                .member_manager
+                .verify_credentials(EXPECTED_UID, NEW_PWD)
+                .await
+        );
+        assert!(
+            !service
+                .member_manager
+                .verify_credentials(EXPECTED_UID, EXPECTED_PWD)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn given_members_when_listing_members_then_returns_requested_page() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+        for uid in ["member_a", "member_b", "member_c"] {
+            _ = service
+                .add_member(add_member_request(EXPECTED_ADMIN_KEY, uid))
+                .await;
+        }
+
+        let result = service
+            .list_members(Request::new(ListMembersRequest {
+                admin_key: EXPECTED_ADMIN_KEY.to_string(),
+                offset: 1,
+                limit: 1,
+            }))
+            .await;
+
+        assert!(result.is_ok());
+        let response = result.unwrap().into_inner();
+        assert_eq!(response.total, 3);
+        assert_eq!(response.members.len(), 1);
+        assert_eq!(response.members[0].uid, "member_b");
+    }
+
+    fn clear_lockout_request(uid: &str, ip_address: &str) -> Request<ClearLockoutRequest> {
+        Request::new(ClearLockoutRequest {
+            admin_key: EXPECTED_ADMIN_KEY.to_string(),
+            uid: uid.to_string(),
+            ip_address: ip_address.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn given_locked_out_member_when_clearing_lockout_then_lockout_is_removed() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+        let ip_address: IpAddr = EXPECTED_IP.parse().unwrap();
+        service
+            .login_attempt_manager
+            .register_failure(EXPECTED_UID, &ip_address)
+            .await;
+
+        let result = service
+            .clear_lockout(clear_lockout_request(EXPECTED_UID, EXPECTED_IP))
+            .await;
+
+        assert!(result.is_ok());
+        assert!(
+            service
+                .login_attempt_manager
+                .get_lockout(EXPECTED_UID, &ip_address)
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_no_lockout_when_clearing_lockout_then_returns_not_found() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+
+        let result = service
+            .clear_lockout(clear_lockout_request(EXPECTED_UID, ""))
+            .await;
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn given_invalid_ip_address_when_clearing_lockout_then_returns_invalid_argument() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+
+        let missing = service.clear_lockout(clear_lockout_request("", "")).await;
+        let invalid = service
+            .clear_lockout(clear_lockout_request("", "not_an_ip"))
+            .await;
+
+        assert_eq!(missing.unwrap_err().code(), tonic::Code::InvalidArgument);
+        assert_eq!(invalid.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    fn roles_request(uid: &str, roles: &[&str]) -> Request<RolesRequest> {
+        Request::new(RolesRequest {
+            admin_key: EXPECTED_ADMIN_KEY.to_string(),
+            uid: uid.to_string(),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        })
+    }
+
+    #[tokio::test]
+    async fn given_member_when_approving_roles_then_roles_are_listed() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+        service
+            .add_member(add_member_request(EXPECTED_ADMIN_KEY, EXPECTED_UID))
+            .await
+            .unwrap();
+
+        let result = service
+            .approve_roles(roles_request(EXPECTED_UID, &["entry", "Exit", "entry"]))
+            .await;
+
+        assert!(result.is_ok());
+        let members = service
+            .list_members(Request::new(ListMembersRequest {
+                admin_key: EXPECTED_ADMIN_KEY.to_string(),
+                offset: 0,
+                limit: 0,
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .members;
+        assert_eq!(members[0].roles, vec!["entry", "exit"]);
+    }
+
+    #[tokio::test]
+    async fn given_invalid_role_or_unknown_member_when_approving_roles_then_returns_error() {
+        let service = AdminServiceImpl::with_admin_key(Some(EXPECTED_ADMIN_KEY));
+        service
+            .add_member(add_member_request(EXPECTED_ADMIN_KEY, EXPECTED_UID))
+            .await
+            .unwrap();
+
+        let invalid = service
+            .approve_roles(roles_request(EXPECTED_UID, &["guard"]))
+            .await;
+        let unknown = service
+            .approve_roles(roles_request("unknown_uid", &["entry"]))
+            .await;
+
+        assert_eq!(invalid.unwrap_err().code(), tonic::Code::InvalidArgument);
+        assert_eq!(unknown.unwrap_err().code(), tonic::Code::NotFound);
+    }
+}
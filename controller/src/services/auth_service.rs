@@ -1,11 +1,16 @@
-use crosscutting::{Component, ConnectionSettings};
+use crosscutting::{AuthMethod, Component, ConnectionSettings};
 
 use super::*;
+use crate::certificate;
+use crate::login_attempts::LoginAttemptManager;
+use crate::membership::{MemberManager, PasswordPolicy};
 use crate::models::auth_proto::{
-    LoginRequest, LoginResponse, LogoutRequest, LogoutResponse, PingRequest, PingResponse,
-    auth_service_server::AuthService,
+    ChangePasswordRequest, ChangePasswordResponse, LoginRequest, LoginResponse, LogoutRequest,
+    LogoutResponse, PingRequest, PingResponse, auth_service_server::AuthService,
 };
-use crate::{membership::MemberManager, session::SessionManager};
+use crate::models::{ProxyLoad, ProxyRole};
+use crate::session::SessionManager;
+use crate::tickets::TicketIssuer;
 use std::net::SocketAddr;
 
 fn get_remote_address<T>(request: &Request<T>) -> Option<SocketAddr> {
@@ -19,6 +24,12 @@ fn get_remote_address<T>(request: &Request<T>) -> Option<SocketAddr> {
 pub struct AuthServiceImpl {
     session_manager: Arc<SessionManager>,
     member_manager: Arc<MemberManager>,
+    login_attempt_manager: Arc<LoginAttemptManager>,
+    password_policy: PasswordPolicy,
+    proxy_auth_methods: Vec<AuthMethod>,
+    client_auth_methods: Vec<AuthMethod>,
+    client_ca: Option<Vec<u8>>,
+    ticket_issuer: Arc<TicketIssuer>,
 }
 
 pub trait RemoteAddress {
@@ -37,20 +48,135 @@ impl RemoteAddress for Request<PingRequest> {
     }
 }
 
+impl RemoteAddress for Request<ChangePasswordRequest> {
+    fn get_remote_address(&self) -> Option<SocketAddr> {
+        get_remote_address(self)
+    }
+}
+
+pub trait PeerCertificate {
+    /// Returns the names in the peer certificate, provided it was issued by
+    /// the given CA.
+    fn get_peer_uids(&self, issuer_ca: Option<&[u8]>) -> Vec<String>;
+}
+
+impl PeerCertificate for Request<LoginRequest> {
+    fn get_peer_uids(&self, issuer_ca: Option<&[u8]>) -> Vec<String> {
+        get_peer_uids(self, issuer_ca)
+    }
+}
+
+pub fn get_peer_uids<T>(request: &Request<T>, issuer_ca: Option<&[u8]>) -> Vec<String> {
+    request
+        .peer_certs()
+        .and_then(|certs| certs.first().cloned())
+        .filter(|cert| issuer_ca.is_some_and(|ca| certificate::is_issued_by(cert.as_ref(), ca)))
+        .and_then(|cert| certificate::get_certificate_uids(cert.as_ref()).ok())
+        .unwrap_or_default()
+}
+
 impl AuthServiceImpl {
-    pub fn new(session_manager: Arc<SessionManager>, member_manager: Arc<MemberManager>) -> Self {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        member_manager: Arc<MemberManager>,
+        login_attempt_manager: Arc<LoginAttemptManager>,
+        ticket_issuer: Arc<TicketIssuer>,
+    ) -> Self {
         Self {
             session_manager,
             member_manager,
+            login_attempt_manager,
+            password_policy: PasswordPolicy::get_from_env(),
+            proxy_auth_methods: settings::auth::get_allowed_auth_methods(&Component::Proxy),
+            client_auth_methods: settings::auth::get_allowed_auth_methods(&Component::Client),
+            client_ca: settings::auth::get_client_ca_certificate(),
+            ticket_issuer,
         }
     }
 
     async fn validate_credentials(&self, uid: &str, pwd: &str) -> bool {
-        self.member_manager
+        self.member_manager.verify_credentials(uid, pwd).await
+    }
+
+    async fn validate_password_login(
+        &self,
+        uid: &str,
+        pwd: &str,
+        client_ip: &SocketAddr,
+    ) -> Result<bool, Status> {
+        let ip_address = client_ip.ip();
+        if let Some(remaining) = self
+            .login_attempt_manager
+            .get_lockout(uid, &ip_address)
+            .await
+        {
+            warn!("Rejected login attempt for {} from {}", uid, ip_address);
+            return Err(Status::resource_exhausted(format!(
+                "Too many failed login attempts. Retry in {} seconds",
+                remaining.as_secs().max(1)
+            )));
+        }
+
+        if self.validate_credentials(uid, pwd).await {
+            self.login_attempt_manager.register_success(uid).await;
+            Ok(true)
+        } else {
+            self.login_attempt_manager
+                .register_failure(uid, &ip_address)
+                .await;
+            Ok(false)
+        }
+    }
+
+    async fn validate_certificate(&self, uid: &str, peer_uids: &[String]) -> Option<String> {
+        let uid = if uid.is_empty() {
+            peer_uids.first()
+        } else {
+            peer_uids.iter().find(|peer_uid| *peer_uid == uid)
+        }?;
+
+        if self.member_manager.is_active(uid).await {
+            Some(uid.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Only the declared roles which an admin has approved for the member are
+    /// granted. Proxies declaring no roles at all are left unrestricted.
+    async fn get_granted_roles(&self, uid: &str, declared: &[String]) -> Option<Vec<ProxyRole>> {
+        if declared.is_empty() {
+            return None;
+        }
+
+        let approved = self
+            .member_manager
             .get_member(uid)
             .await
-            .map(|member| member.pwd == pwd)
-            .unwrap_or(false)
+            .map(|member| member.roles)
+            .unwrap_or_default();
+        let mut granted = Vec::new();
+        for role in declared {
+            match role.parse::<ProxyRole>() {
+                Ok(role) if approved.contains(&role) => {
+                    if !granted.contains(&role) {
+                        granted.push(role);
+                    }
+                }
+                Ok(role) => warn!("Role {} hasn't been approved for {}", role, uid),
+                Err(e) => warn!("{}", e),
+            }
+        }
+
+        Some(granted)
+    }
+
+    fn is_auth_method_allowed(&self, component_type: &Component, auth_method: &AuthMethod) -> bool {
+        match component_type {
+            Component::Proxy => self.proxy_auth_methods.contains(auth_method),
+            Component::Client => self.client_auth_methods.contains(auth_method),
+            Component::Controller => false,
+        }
     }
 }
 
@@ -63,19 +189,25 @@ impl AuthService for AuthServiceImpl {
 
         let ping_request = request.into_inner();
         let access_key = ping_request.access_key.clone();
-        guards::check_session(&self.session_manager, access_key.as_str()).await?;
+        let claims = guards::check_session(&self.session_manager, access_key.as_str()).await?;
 
-        let session = self
-            .session_manager
-            .get_session(access_key.as_str())
+        let load = ping_request.load.map(|load| ProxyLoad {
+            in_flight: load.in_flight,
+            throughput: load.throughput,
+            available_capacity: load.available_capacity,
+        });
+        self.session_manager
+            .renew_session(&access_key, &client_ip, load)
             .await
-            .unwrap();
-        if session.client_ip != client_ip {
-            warn!(
-                "Session IP mismatch: expected {}, got {}",
-                session.client_ip, client_ip
-            );
-            return Err(Status::unauthenticated("Invalid connection"));
+            .map_err(|e| {
+                warn!("{}", e);
+                Status::unauthenticated("Invalid connection")
+            })?;
+        if claims.component_type == Component::Proxy {
+            let session_manager = Arc::clone(&self.session_manager);
+            tokio::spawn(async move {
+                session_manager.measure_round_trip(&access_key).await;
+            });
         }
 
         let reply = PingResponse {
@@ -94,8 +226,13 @@ impl AuthService for AuthServiceImpl {
             .get_remote_address()
             .ok_or_else(|| Status::internal("Could not get client IP address"))?;
 
+        let peer_uids = request.get_peer_uids(self.client_ca.as_deref());
         let login_request = request.into_inner();
-        if login_request.uid.is_empty() || login_request.uid.is_empty() {
+        let auth_method =
+            AuthMethod::try_from(login_request.auth_method).map_err(Status::invalid_argument)?;
+        if auth_method == AuthMethod::Password
+            && (login_request.uid.is_empty() || login_request.pwd.is_empty())
+        {
             warn!("UID or PWD are empty");
             return Err(Status::invalid_argument("UID and PWD cannot be empty"));
         }
@@ -105,10 +242,48 @@ impl AuthService for AuthServiceImpl {
             return Err(Status::invalid_argument("Invalid component type"));
         }
 
-        if self
-            .validate_credentials(&login_request.uid, &login_request.pwd)
-            .await
-        {
+        if !self.is_auth_method_allowed(&component_type, &auth_method) {
+            warn!(
+                "{} authentication is not allowed for {}",
+                auth_method, component_type
+            );
+            return Err(Status::unauthenticated("Authentication method not allowed"));
+        }
+
+        let uid = match auth_method {
+            AuthMethod::Password => self
+                .validate_password_login(&login_request.uid, &login_request.pwd, &client_ip)
+                .await?
+                .then(|| login_request.uid.clone()),
+            AuthMethod::Certificate => {
+                match self
+                    .validate_certificate(&login_request.uid, &peer_uids)
+                    .await
+                {
+                    Some(uid) => Some(uid),
+                    // Password login remains as a fallback when it's allowed too
+                    None if self.is_auth_method_allowed(&component_type, &AuthMethod::Password)
+                        && !login_request.uid.is_empty()
+                        && !login_request.pwd.is_empty() =>
+                    {
+                        debug!(
+                            "Certificate doesn't map to {}, falling back to password",
+                            login_request.uid
+                        );
+                        self.validate_password_login(
+                            &login_request.uid,
+                            &login_request.pwd,
+                            &client_ip,
+                        )
+                        .await?
+                        .then(|| login_request.uid.clone())
+                    }
+                    None => None,
+                }
+            }
+        };
+
+        if let Some(uid) = uid {
             let connection_settings = ConnectionSettings {
                 ip: login_request.on_ip.clone(),
                 port: login_request.on_port as u16,
@@ -119,13 +294,23 @@ impl AuthService for AuthServiceImpl {
             let access_key = self
                 .session_manager
                 .set_session(
-                    &login_request.uid,
+                    &uid,
                     component_type.clone(),
                     &client_ip,
                     &connection_settings,
+                    &login_request.identity_key,
                 )
                 .await;
 
+            let mut ticket_key = Vec::new();
+            if component_type == Component::Proxy {
+                let roles = self.get_granted_roles(&uid, &login_request.roles).await;
+                self.session_manager
+                    .set_proxy_profile(&access_key, login_request.tags.clone(), roles)
+                    .await;
+                ticket_key = self.ticket_issuer.get_key();
+            }
+
             debug!(
                 "Storing session for {}: on: {}:{}",
                 access_key, login_request.on_ip, login_request.on_port
@@ -134,6 +319,8 @@ impl AuthService for AuthServiceImpl {
             let reply = LoginResponse {
                 access_key,
                 message: "Login successful".to_string(),
+                uid,
+                ticket_key,
             };
 
             info!(
@@ -161,15 +348,67 @@ impl AuthService for AuthServiceImpl {
         info!("Session dropped: {}", access_key);
         Ok(Response::new(LogoutResponse {}))
     }
+
+    async fn change_password(
+        &self,
+        request: Request<ChangePasswordRequest>,
+    ) -> Result<Response<ChangePasswordResponse>, Status> {
+        let client_ip = request
+            .get_remote_address()
+            .ok_or_else(|| Status::internal("Could not get client IP address"))?;
+
+        let change_request = request.into_inner();
+        let access_key = change_request.access_key.clone();
+        let session = guards::check_session(&self.session_manager, access_key.as_str()).await?;
+
+        if change_request.old_pwd == change_request.new_pwd {
+            return Err(Status::invalid_argument(
+                "New password must differ from the current one",
+            ));
+        }
+
+        self.password_policy
+            .validate(&change_request.new_pwd)
+            .map_err(Status::invalid_argument)?;
+
+        if !self
+            .validate_password_login(&session.uid, &change_request.old_pwd, &client_ip)
+            .await?
+        {
+            warn!("Password change rejected for {}", session.uid);
+            return Err(Status::unauthenticated("Invalid credentials"));
+        }
+
+        match self
+            .member_manager
+            .reset_password(&session.uid, &change_request.new_pwd)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => return Err(Status::not_found("Member not found")),
+            Err(e) => return Err(Status::internal(e)),
+        }
+
+        self.session_manager
+            .remove_other_sessions(&session.uid, access_key.as_str())
+            .await;
+
+        info!("Password changed for {}", session.uid);
+        Ok(Response::new(ChangePasswordResponse {
+            message: "Password changed".to_string(),
+        }))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::vec;
 
     use super::*;
     use crate::membership::MemberManager;
     use crate::models::Member;
+    use crate::models::auth_proto::LoadMetrics;
     use crate::session::SessionManager;
     use crate::storage::RepositoryType;
     use tokio_util::sync::CancellationToken;
@@ -179,6 +418,7 @@ mod tests {
     const EXPECTED_IP: &str = "127.0.0.1";
     const EXPECTED_PORT: u16 = 8080;
     const EXPECTED_DOMAIN_NAME: &str = "localhost";
+    const EXPECTED_NEW_PWD: &str = "Vq7#mZ2!pLx9@rTe";
 
     fn create_service() -> AuthServiceImpl {
         let cancellation_token = CancellationToken::new();
@@ -186,7 +426,13 @@ mod tests {
         let session_manager =
             SessionManager::new(repository_type, cancellation_token.child_token());
         let member_manager = MemberManager::new(repository_type);
-        AuthServiceImpl::new(Arc::new(session_manager), Arc::new(member_manager))
+        let login_attempt_manager = LoginAttemptManager::new(repository_type);
+        AuthServiceImpl::new(
+            Arc::new(session_manager),
+            Arc::new(member_manager),
+            Arc::new(login_attempt_manager),
+            Arc::default(),
+        )
     }
 
     fn create_login_request() -> Request<LoginRequest> {
@@ -198,6 +444,22 @@ mod tests {
             on_port: EXPECTED_PORT as u32,
             public_key: vec![],
             domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            identity_key: vec![],
+            auth_method: i32::from(AuthMethod::Password),
+            tags: HashMap::new(),
+            roles: Vec::new(),
+        })
+    }
+
+    fn create_change_password_request(
+        access_key: &str,
+        old_pwd: &str,
+        new_pwd: &str,
+    ) -> Request<ChangePasswordRequest> {
+        Request::new(ChangePasswordRequest {
+            access_key: access_key.to_string(),
+            old_pwd: old_pwd.to_string(),
+            new_pwd: new_pwd.to_string(),
         })
     }
 
@@ -205,7 +467,7 @@ mod tests {
         async fn load_memebers(&self) {
             let members = vec![Member::new(
                 EXPECTED_UID.to_string(),
-                EXPECTED_PWD.to_string(),
+                MemberManager::hash_password(EXPECTED_PWD).unwrap(),
             )];
 
             self.set_members(&members).await;
@@ -226,6 +488,56 @@ mod tests {
         assert!(!login_response.access_key.is_empty());
     }
 
+    #[tokio::test]
+    async fn given_proxy_with_tags_and_roles_when_login_is_called_then_approved_roles_are_granted()
+    {
+        let service = create_service();
+        service.member_manager.load_memebers().await;
+        service
+            .member_manager
+            .approve_roles(EXPECTED_UID, &[ProxyRole::Entry, ProxyRole::Middle])
+            .await;
+
+        let mut request = create_login_request();
+        request.get_mut().component_type = i32::from(Component::Proxy);
+        request.get_mut().tags = HashMap::from([
+            ("region".to_string(), "eu-west".to_string()),
+            ("operator".to_string(), "acme".to_string()),
+        ]);
+        request.get_mut().roles = vec!["entry".to_string(), "exit".to_string()];
+        let login_response = service.login(request).await.unwrap().into_inner();
+
+        let session = service
+            .session_manager
+            .get_session(&login_response.access_key)
+            .await
+            .unwrap();
+        assert_eq!(session.tags.get("region"), Some(&"eu-west".to_string()));
+        assert_eq!(session.tags.get("operator"), Some(&"acme".to_string()));
+        assert_eq!(session.roles, Some(vec![ProxyRole::Entry]));
+    }
+
+    #[tokio::test]
+    async fn given_source_routing_when_proxy_logs_in_then_ticket_key_is_returned() {
+        let mut service = create_service();
+        let issuer = TicketIssuer::new(crosscutting::crypto::Identity::generate(), true, false);
+        let expected_key = issuer.get_key();
+        service.ticket_issuer = Arc::new(issuer);
+        service.member_manager.load_memebers().await;
+
+        let login_response = service
+            .login(create_login_request())
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(login_response.ticket_key.is_empty());
+
+        let mut request = create_login_request();
+        request.get_mut().component_type = i32::from(Component::Proxy);
+        let login_response = service.login(request).await.unwrap().into_inner();
+        assert_eq!(login_response.ticket_key, expected_key);
+    }
+
     #[tokio::test]
     async fn given_non_existing_member_when_login_is_called_then_login_is_unsuccessful() {
         let service = create_service();
@@ -278,6 +590,11 @@ mod tests {
         let login_response = service.login(request).await.unwrap().into_inner();
         let request = Request::new(PingRequest {
             access_key: login_response.access_key.clone(),
+            load: Some(LoadMetrics {
+                in_flight: 2,
+                throughput: 4.5,
+                available_capacity: 98,
+            }),
         });
 
         let response = service.ping(request).await;
@@ -285,6 +602,12 @@ mod tests {
         assert!(response.is_ok());
         let inner_response = response.unwrap().into_inner();
         assert_eq!(inner_response.status, "PONG");
+        let session = service
+            .session_manager
+            .get_session(&login_response.access_key)
+            .await
+            .unwrap();
+        assert_eq!(session.load.unwrap().available_capacity, 98);
     }
 
     #[tokio::test]
@@ -293,6 +616,7 @@ mod tests {
         let expected_status = Status::unauthenticated("Invalid access key");
         let request = Request::new(PingRequest {
             access_key: "some_invalid_key".to_string(),
+            ..Default::default()
         });
 
         let response = service.ping(request).await;
@@ -302,4 +626,221 @@ mod tests {
         assert_eq!(status.code(), expected_status.code());
         assert_eq!(status.message(), expected_status.message());
     }
+
+    #[tokio::test]
+    async fn given_valid_credentials_when_change_password_is_called_then_other_sessions_are_dropped()
+     {
+        let service = create_service();
+        service.member_manager.load_memebers().await;
+        let mut request = create_login_request();
+        request.get_mut().component_type = i32::from(Component::Proxy);
+        let other_session = service.login(request).await.unwrap().into_inner();
+        let current_session = service
+            .login(create_login_request())
+            .await
+            .unwrap()
+            .into_inner();
+        let request = create_change_password_request(
+            &current_session.access_key,
+            EXPECTED_PWD,
+            EXPECTED_NEW_PWD,
+        );
+
+        let response = service.change_password(request).await;
+
+        assert!(response.is_ok());
+        assert!(
+            service
+                .session_manager
+                .get_session(&current_session.access_key)
+                .await
+                .is_some()
+        );
+        assert!(
+            service
+                .session_manager
+                .get_session(&other_session.access_key)
+                .await
+                .is_none()
+        );
+        assert!(
+            service
+                .member_manager
+                .verify_credentials(EXPECTED_UID, EXPECTED_NEW_PWD)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn given_wrong_old_password_when_change_password_is_called_then_returns_unauthenticated()
+    {
+        let service = create_service();
+        service.member_manager.load_memebers().await;
+        let login_response = service
+            .login(create_login_request())
+            .await
+            .unwrap()
+            .into_inner();
+        let request = create_change_password_request(
+            &login_response.access_key,
+            "wrong_password",
+            EXPECTED_NEW_PWD,
+        );
+
+        let response = service.change_password(request).await;
+
+        assert_eq!(response.unwrap_err().code(), tonic::Code::Unauthenticated);
+        assert!(
+            service
+                .member_manager
+                .verify_credentials(EXPECTED_UID, EXPECTED_PWD)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn given_weak_password_when_change_password_is_called_then_returns_invalid_argument() {
+        let service = create_service();
+        service.member_manager.load_memebers().await;
+        let login_response = service
+            .login(create_login_request())
+            .await
+            .unwrap()
+            .into_inner();
+        let request =
+            create_change_password_request(&login_response.access_key, EXPECTED_PWD, "short");
+
+        let response = service.change_password(request).await;
+
+        assert_eq!(response.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn given_certificate_method_not_allowed_when_login_is_called_then_login_is_unsuccessful()
+    {
+        let mut service = create_service();
+        service.client_auth_methods = vec![AuthMethod::Password];
+        service.member_manager.load_memebers().await;
+        let mut request = create_login_request();
+        request.get_mut().auth_method = i32::from(AuthMethod::Certificate);
+
+        let response = service.login(request).await;
+
+        let status = response.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        assert_eq!(status.message(), "Authentication method not allowed");
+    }
+
+    #[tokio::test]
+    async fn given_certificate_method_without_peer_certificate_when_login_is_called_then_login_is_unsuccessful()
+     {
+        let mut service = create_service();
+        service.client_auth_methods = vec![AuthMethod::Certificate];
+        service.member_manager.load_memebers().await;
+        let mut request = create_login_request();
+        request.get_mut().auth_method = i32::from(AuthMethod::Certificate);
+        request.get_mut().pwd = String::default();
+
+        let response = service.login(request).await;
+
+        let status = response.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        assert_eq!(status.message(), "Invalid credentials");
+    }
+
+    #[tokio::test]
+    async fn given_both_methods_allowed_and_unmapped_certificate_when_login_is_called_then_falls_back_to_password()
+     {
+        let mut service = create_service();
+        service.client_auth_methods = vec![AuthMethod::Certificate, AuthMethod::Password];
+        service.member_manager.load_memebers().await;
+        let mut request = create_login_request();
+        request.get_mut().auth_method = i32::from(AuthMethod::Certificate);
+
+        let response = service.login(request).await;
+        assert!(response.is_ok());
+
+        let mut request = create_login_request();
+        request.get_mut().auth_method = i32::from(AuthMethod::Certificate);
+        request.get_mut().pwd = "wrong_password".to_string();
+
+        let response = service.login(request).await;
+        assert_eq!(response.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn given_peer_certificate_matching_member_when_validating_certificate_then_returns_uid() {
+        let service = create_service();
+        service.member_manager.load_memebers().await;
+        let peer_uids = vec!["other_uid".to_string(), EXPECTED_UID.to_string()];
+
+        let uid = service.validate_certificate(EXPECTED_UID, &peer_uids).await;
+
+        assert_eq!(uid, Some(EXPECTED_UID.to_string()));
+    }
+
+    #[tokio::test]
+    async fn given_no_requested_uid_when_validating_certificate_then_first_peer_uid_is_used() {
+        let service = create_service();
+        service.member_manager.load_memebers().await;
+        let peer_uids = vec![EXPECTED_UID.to_string()];
+
+        let uid = service.validate_certificate("", &peer_uids).await;
+
+        assert_eq!(uid, Some(EXPECTED_UID.to_string()));
+    }
+
+    #[tokio::test]
+    async fn given_peer_certificate_for_another_uid_when_validating_certificate_then_returns_none()
+    {
+        let service = create_service();
+        service.member_manager.load_memebers().await;
+        let peer_uids = vec!["other_uid".to_string()];
+
+        assert!(
+            service
+                .validate_certificate(EXPECTED_UID, &peer_uids)
+                .await
+                .is_none()
+        );
+        assert!(service.validate_certificate("", &peer_uids).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn given_failed_login_when_login_is_retried_immediately_then_login_is_throttled() {
+        let service = create_service();
+        service.member_manager.load_memebers().await;
+        let mut request = create_login_request();
+        request.get_mut().pwd = "wrong_password".to_string();
+        let first_response = service.login(request).await;
+
+        let response = service.login(create_login_request()).await;
+
+        assert_eq!(
+            first_response.unwrap_err().code(),
+            tonic::Code::Unauthenticated
+        );
+        assert_eq!(response.unwrap_err().code(), tonic::Code::ResourceExhausted);
+    }
+
+    #[tokio::test]
+    async fn given_cleared_lockout_when_login_is_called_then_login_is_successful() {
+        let service = create_service();
+        service.member_manager.load_memebers().await;
+        let mut request = create_login_request();
+        request.get_mut().pwd = "wrong_password".to_string();
+        _ = service.login(request).await;
+
+        service
+            .login_attempt_manager
+            .clear_uid_lockout(EXPECTED_UID)
+            .await;
+        service
+            .login_attempt_manager
+            .clear_ip_lockout(&EXPECTED_IP.parse().unwrap())
+            .await;
+        let response = service.login(create_login_request()).await;
+
+        assert!(response.is_ok());
+    }
 }
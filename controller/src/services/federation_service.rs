@@ -0,0 +1,300 @@
+use super::*;
+use crate::contacts::ContactManager;
+use crate::federation::{FederationManager, FederationPeer};
+use crate::models::federation_proto::{
+    OpenRequest, OpenResponse, RouteTicket, federation_service_server::FederationService,
+};
+use auth_service::PeerCertificate;
+
+pub struct FederationServiceImpl {
+    session_manager: Arc<SessionManager>,
+    route_manager: Arc<RouteManager>,
+    federation_manager: Arc<FederationManager>,
+    contact_manager: Arc<ContactManager>,
+}
+
+impl PeerCertificate for Request<OpenRequest> {
+    fn get_peer_uids(&self, issuer_ca: Option<&[u8]>) -> Vec<String> {
+        auth_service::get_peer_uids(self, issuer_ca)
+    }
+}
+
+impl FederationServiceImpl {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        route_manager: Arc<RouteManager>,
+        federation_manager: Arc<FederationManager>,
+        contact_manager: Arc<ContactManager>,
+    ) -> Self {
+        Self {
+            session_manager,
+            route_manager,
+            federation_manager,
+            contact_manager,
+        }
+    }
+
+    async fn open_conversation(
+        &self,
+        peer: &FederationPeer,
+        open_request: OpenRequest,
+    ) -> Result<OpenResponse, Status> {
+        let from = FederationManager::get_federated_address(&open_request.from, peer);
+        let recipient_key = if self
+            .contact_manager
+            .is_allowed(&from, &open_request.to)
+            .await
+        {
+            self.session_manager
+                .get_identity_key(&open_request.to)
+                .await
+        } else {
+            None
+        }
+        .ok_or_else(|| Status::not_found("Recipient not found"))?;
+
+        let conversation_id = self
+            .route_manager
+            .initialize_federated(
+                &open_request.conversation_id,
+                &from,
+                &open_request.to,
+                &open_request.sender_key,
+                &recipient_key,
+                None,
+            )
+            .await
+            .ok_or_else(|| Status::already_exists("Conversation already exists"))?;
+
+        let conversation = self
+            .route_manager
+            .get_conversation(&conversation_id)
+            .await
+            .ok_or_else(|| Status::internal("Conversation is no longer available"))?;
+
+        let proxies = self
+            .session_manager
+            .get_proxies("")
+            .await
+            .ok_or_else(|| Status::not_found("No proxies found"))?;
+
+        let proxy_session = self
+            .route_manager
+            .get_next_route(&conversation, &proxies)
+            .await
+            .ok_or_else(|| Status::not_found("Entry route wasn't found"))?;
+
+        let nonce = self
+            .route_manager
+            .store_route(
+                &conversation_id,
+                &proxy_session.to_connection_settings(),
+                &proxy_session.tags,
+                false,
+            )
+            .await
+            .ok_or(Status::internal("Failed to store route"))?;
+
+        Ok(OpenResponse {
+            recipient_key,
+            ticket: Some(RouteTicket {
+                ip_address: proxy_session.on_ip_address,
+                port_number: proxy_session.on_port_number as u32,
+                public_key: proxy_session.public_key,
+                domain_name: proxy_session.domain_name,
+                nonce,
+            }),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl FederationService for FederationServiceImpl {
+    async fn open(&self, request: Request<OpenRequest>) -> Result<Response<OpenResponse>, Status> {
+        let peer_uids = request.get_peer_uids(self.federation_manager.get_ca_certificate());
+        let peer = self
+            .federation_manager
+            .authorize_peer(&peer_uids)
+            .ok_or_else(|| Status::unauthenticated("Unknown federation peer"))?;
+
+        debug!("Opening federated conversation from {}", peer.domain);
+
+        self.open_conversation(peer, request.into_inner())
+            .await
+            .map(Response::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contacts::ContactAction;
+    use crate::federation::FederationSettings;
+    use crate::storage::RepositoryType;
+    use crosscutting::{Component, ConnectionSettings};
+    use tokio_util::sync::CancellationToken;
+
+    const EXPECTED_CONVERSATION_ID: &str = "test_conversation_id";
+    const EXPECTED_FROM: &str = "test_sender";
+    const EXPECTED_TO: &str = "test_recipient";
+    const EXPECTED_PROXY_UID: &str = "test_proxy";
+    const EXPECTED_IP: &str = "127.0.0.1";
+    const EXPECTED_PORT: u16 = 8080;
+    const EXPECTED_DOMAIN_NAME: &str = "test_domain_name";
+    const EXPECTED_PUBLIC_KEY: &[u8] = b"test_public_key";
+    const EXPECTED_SENDER_KEY: &[u8] = b"test_sender_key";
+    const EXPECTED_RECIPIENT_KEY: &[u8] = b"test_recipient_key";
+
+    fn get_connection_settings() -> ConnectionSettings {
+        ConnectionSettings {
+            ip: EXPECTED_IP.to_string(),
+            port: EXPECTED_PORT,
+            domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            certificate: EXPECTED_PUBLIC_KEY.to_vec(),
+        }
+    }
+
+    fn get_peer() -> FederationPeer {
+        FederationPeer {
+            domain: "beta".to_string(),
+            connection_settings: get_connection_settings(),
+        }
+    }
+
+    fn create_service() -> (
+        FederationServiceImpl,
+        Arc<SessionManager>,
+        Arc<RouteManager>,
+        Arc<ContactManager>,
+    ) {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let federation_manager = Arc::new(FederationManager::new(FederationSettings {
+            domain: Some("alpha".to_string()),
+            peers: vec![get_peer()],
+            ca_certificate: Vec::new(),
+        }));
+        let contact_manager = Arc::new(ContactManager::new(repository_type));
+        let service = FederationServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            federation_manager,
+            contact_manager.clone(),
+        );
+
+        (service, session_manager, route_manager, contact_manager)
+    }
+
+    fn create_open_request() -> OpenRequest {
+        OpenRequest {
+            conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            from: EXPECTED_FROM.to_string(),
+            to: EXPECTED_TO.to_string(),
+            sender_key: EXPECTED_SENDER_KEY.to_vec(),
+        }
+    }
+
+    async fn set_session(session_manager: &SessionManager, uid: &str, component: Component) {
+        session_manager
+            .set_session(
+                uid,
+                component,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                EXPECTED_RECIPIENT_KEY,
+            )
+            .await;
+    }
+
+    #[tokio::test]
+    async fn given_request_without_peer_certificate_when_opening_then_returns_unauthenticated() {
+        let (service, _, _, _) = create_service();
+
+        let result = service.open(Request::new(create_open_request())).await;
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn given_unknown_recipient_when_opening_conversation_then_returns_not_found() {
+        let (service, _, _, _) = create_service();
+
+        let result = service
+            .open_conversation(&get_peer(), create_open_request())
+            .await;
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn given_recipient_and_proxy_when_opening_conversation_then_returns_ticket() {
+        let (service, session_manager, route_manager, _) = create_service();
+        set_session(&session_manager, EXPECTED_TO, Component::Client).await;
+        set_session(&session_manager, EXPECTED_PROXY_UID, Component::Proxy).await;
+
+        let response = service
+            .open_conversation(&get_peer(), create_open_request())
+            .await
+            .unwrap();
+
+        assert_eq!(response.recipient_key, EXPECTED_RECIPIENT_KEY.to_vec());
+        let ticket = response.ticket.unwrap();
+        assert_eq!(ticket.ip_address, EXPECTED_IP);
+        assert_eq!(ticket.port_number, EXPECTED_PORT as u32);
+
+        let conversation = route_manager
+            .get_conversation(EXPECTED_CONVERSATION_ID)
+            .await
+            .unwrap();
+        assert_eq!(conversation.from, "test_sender@beta");
+        assert_eq!(conversation.to, EXPECTED_TO);
+        assert_eq!(conversation.routes.len(), 1);
+        assert!(
+            route_manager
+                .redeem_route(EXPECTED_CONVERSATION_ID, &ticket.nonce)
+                .await
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_existing_conversation_when_opening_conversation_then_returns_already_exists() {
+        let (service, session_manager, _, _) = create_service();
+        set_session(&session_manager, EXPECTED_TO, Component::Client).await;
+        set_session(&session_manager, EXPECTED_PROXY_UID, Component::Proxy).await;
+        service
+            .open_conversation(&get_peer(), create_open_request())
+            .await
+            .unwrap();
+
+        let result = service
+            .open_conversation(&get_peer(), create_open_request())
+            .await;
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::AlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn given_blocked_sender_when_opening_conversation_then_returns_not_found() {
+        let (service, session_manager, _, contact_manager) = create_service();
+        set_session(&session_manager, EXPECTED_TO, Component::Client).await;
+        set_session(&session_manager, EXPECTED_PROXY_UID, Component::Proxy).await;
+        contact_manager
+            .update(EXPECTED_TO, ContactAction::Block, "test_sender@beta")
+            .await;
+
+        let result = service
+            .open_conversation(&get_peer(), create_open_request())
+            .await;
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+    }
+}
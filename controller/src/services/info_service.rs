@@ -1,19 +1,81 @@
 use super::*;
-use crate::models::info_proto::{StatusRequest, StatusResponse, info_service_server::InfoService};
+use crate::contacts::ContactManager;
+use crate::models::{
+    ControllerInstance,
+    info_proto::{
+        ControllerInfo, ListControllersRequest, ListControllersResponse, OnlineContactsRequest,
+        OnlineContactsResponse, StatusRequest, StatusResponse, info_service_server::InfoService,
+    },
+};
+use crate::registry::ControllerRegistry;
+use crate::routing::RouteManager;
 use crate::session::SessionManager;
+use crosscutting::Component;
 
 pub struct InfoServiceImpl {
     session_manager: Arc<SessionManager>,
+    route_manager: Arc<RouteManager>,
+    contact_manager: Arc<ContactManager>,
+    controller_registry: Arc<ControllerRegistry>,
     version: String,
 }
 
 impl InfoServiceImpl {
-    pub fn new(session_manager: Arc<SessionManager>, version: String) -> Self {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        route_manager: Arc<RouteManager>,
+        contact_manager: Arc<ContactManager>,
+        controller_registry: Arc<ControllerRegistry>,
+        version: String,
+    ) -> Self {
         Self {
             session_manager,
+            route_manager,
+            contact_manager,
+            controller_registry,
             version,
         }
     }
+
+    async fn get_online_contacts(&self, uid: &str, uids: Vec<String>) -> Vec<String> {
+        let uids = if uids.is_empty() {
+            self.contact_manager.get_contacts(uid).await.allowed
+        } else {
+            uids
+        };
+
+        let mut online = Vec::new();
+        for contact in uids {
+            if contact != uid
+                && !online.contains(&contact)
+                && self.contact_manager.is_visible(&contact, uid).await
+                && self
+                    .session_manager
+                    .get_client(&contact, &[])
+                    .await
+                    .is_some()
+            {
+                online.push(contact);
+            }
+        }
+
+        online
+    }
+}
+
+impl From<ControllerInstance> for ControllerInfo {
+    fn from(value: ControllerInstance) -> Self {
+        ControllerInfo {
+            instance_id: value.instance_id,
+            uid: value.uid,
+            version: value.version,
+            ip_address: value.ip_address,
+            port_number: value.port_number as u32,
+            domain_name: value.domain_name,
+            started_at: value.started_at,
+            last_heartbeat: value.last_heartbeat,
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -28,24 +90,78 @@ impl InfoService for InfoServiceImpl {
 
         let proxies_count = self.session_manager.count_proxies().await;
         let clients_count = self.session_manager.count_clients().await;
-        let controllers_count = self.session_manager.count_controllers().await;
+        let controllers_count = self.controller_registry.list_controllers().await.len();
 
         let reply = StatusResponse {
             version: self.version.clone(),
-            connected_clients: u32::try_from(clients_count).unwrap_or_default(),
+            connected_clients: u32::try_from(clients_count.members).unwrap_or_default(),
+            client_sessions: u32::try_from(clients_count.sessions).unwrap_or_default(),
             connected_proxies: u32::try_from(proxies_count).unwrap_or_default(),
             connected_controllers: u32::try_from(controllers_count).unwrap_or_default(),
         };
 
         Ok(Response::new(reply))
     }
+
+    async fn list_controllers(
+        &self,
+        request: Request<ListControllersRequest>,
+    ) -> Result<Response<ListControllersResponse>, Status> {
+        let list_request = request.into_inner();
+        guards::check_session(&self.session_manager, &list_request.access_key).await?;
+
+        let controllers = self
+            .controller_registry
+            .list_controllers()
+            .await
+            .into_iter()
+            .map(ControllerInfo::from)
+            .collect();
+
+        Ok(Response::new(ListControllersResponse { controllers }))
+    }
+
+    async fn online_contacts(
+        &self,
+        request: Request<OnlineContactsRequest>,
+    ) -> Result<Response<OnlineContactsResponse>, Status> {
+        let contacts_request = request.into_inner();
+        let access_key = contacts_request.access_key.to_owned();
+        let conversation_id = contacts_request.conversation_id.to_owned();
+
+        let claims = guards::check_session(&self.session_manager, access_key.as_str()).await?;
+        if claims.component_type != Component::Proxy {
+            return Err(Status::permission_denied(
+                "Only proxies can query online contacts",
+            ));
+        }
+
+        guards::check_conversation(&self.route_manager, &conversation_id).await?;
+        let conversation = self
+            .route_manager
+            .get_conversation(&conversation_id)
+            .await
+            .unwrap();
+        if conversation.to != CONTROLLER_UID {
+            return Err(Status::invalid_argument("Invalid conversation"));
+        }
+
+        self.route_manager.finalize(&conversation_id).await;
+
+        let uids = self
+            .get_online_contacts(&conversation.from, contacts_request.uids)
+            .await;
+
+        Ok(Response::new(OnlineContactsResponse { uids }))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::contacts::ContactAction;
     use crate::{session::SessionManager, storage::RepositoryType};
-    use crosscutting::{Component, ConnectionSettings};
+    use crosscutting::ConnectionSettings;
     use tokio_util::sync::CancellationToken;
 
     const EXPECTED_UID: &str = "L.KD<FCjkSA6AEg@";
@@ -77,14 +193,32 @@ mod tests {
                 Component::Client,
                 &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
                 &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
             )
             .await;
 
-        let service = InfoServiceImpl::new(Arc::new(session_manager), EXPECTED_VERSION.to_string());
+        let service = InfoServiceImpl::new(
+            Arc::new(session_manager),
+            Arc::new(RouteManager::new(
+                repository_type,
+                cancellation_token.child_token(),
+            )),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(ControllerRegistry::new(repository_type)),
+            EXPECTED_VERSION.to_string(),
+        );
 
-        let request = StatusRequest {
-            access_key: access_key,
-        };
+        let request = StatusRequest { access_key };
 
         let response = service.status(Request::new(request)).await;
         assert!(response.is_ok());
@@ -92,7 +226,191 @@ mod tests {
         let response = response.unwrap().into_inner();
         assert_eq!(response.version, EXPECTED_VERSION);
         assert_eq!(response.connected_clients, 1);
+        assert_eq!(response.client_sessions, 2);
         assert_eq!(response.connected_proxies, 0);
         assert_eq!(response.connected_controllers, 0);
     }
+
+    #[tokio::test]
+    async fn given_registered_controller_when_listing_controllers_then_returns_it() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let session_manager =
+            SessionManager::new(repository_type, cancellation_token.child_token());
+        let controller_registry = ControllerRegistry::new(repository_type);
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        let mut instance = ControllerInstance {
+            instance_id: "test_instance_id".to_string(),
+            uid: "controller".to_string(),
+            version: EXPECTED_VERSION.to_string(),
+            ip_address: EXPECTED_IP.to_string(),
+            port_number: EXPECTED_PORT,
+            domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            started_at: 100,
+            last_heartbeat: 100,
+        };
+        controller_registry.heartbeat(&mut instance).await;
+
+        let service = InfoServiceImpl::new(
+            Arc::new(session_manager),
+            Arc::new(RouteManager::new(
+                repository_type,
+                cancellation_token.child_token(),
+            )),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(controller_registry),
+            EXPECTED_VERSION.to_string(),
+        );
+
+        let response = service
+            .list_controllers(Request::new(ListControllersRequest { access_key }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.controllers.len(), 1);
+        assert_eq!(response.controllers[0].instance_id, "test_instance_id");
+        assert_eq!(response.controllers[0].port_number, EXPECTED_PORT as u32);
+
+        let status = service
+            .list_controllers(Request::new(ListControllersRequest {
+                access_key: "invalid".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+
+    fn create_service(
+        repository_type: RepositoryType,
+        cancellation_token: &CancellationToken,
+    ) -> (
+        InfoServiceImpl,
+        Arc<SessionManager>,
+        Arc<RouteManager>,
+        Arc<ContactManager>,
+    ) {
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let contact_manager = Arc::new(ContactManager::new(repository_type));
+        let service = InfoServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            contact_manager.clone(),
+            Arc::new(ControllerRegistry::new(repository_type)),
+            EXPECTED_VERSION.to_string(),
+        );
+
+        (service, session_manager, route_manager, contact_manager)
+    }
+
+    async fn set_session(
+        session_manager: &SessionManager,
+        uid: &str,
+        component: Component,
+    ) -> String {
+        session_manager
+            .set_session(
+                uid,
+                component,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn given_client_session_when_getting_online_contacts_then_returns_permission_denied() {
+        let cancellation_token = CancellationToken::new();
+        let (service, session_manager, route_manager, _) =
+            create_service(RepositoryType::InMemory, &cancellation_token);
+        let access_key = set_session(&session_manager, EXPECTED_UID, Component::Client).await;
+        let conversation_id = route_manager
+            .initialize(EXPECTED_UID, CONTROLLER_UID, &[], &[], 0)
+            .await
+            .unwrap();
+
+        let request = OnlineContactsRequest {
+            access_key,
+            conversation_id,
+            uids: Vec::new(),
+        };
+
+        let response = service.online_contacts(Request::new(request)).await;
+        assert_eq!(response.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn given_contacts_when_getting_online_contacts_then_returns_visible_online_ones() {
+        const VISIBLE_ONLINE: &str = "visible_online";
+        const HIDDEN_ONLINE: &str = "hidden_online";
+        const VISIBLE_OFFLINE: &str = "visible_offline";
+        const BLOCKING_ONLINE: &str = "blocking_online";
+
+        let cancellation_token = CancellationToken::new();
+        let (service, session_manager, route_manager, contact_manager) =
+            create_service(RepositoryType::InMemory, &cancellation_token);
+        let access_key = set_session(&session_manager, "test_proxy", Component::Proxy).await;
+
+        for uid in [
+            VISIBLE_ONLINE,
+            HIDDEN_ONLINE,
+            VISIBLE_OFFLINE,
+            BLOCKING_ONLINE,
+        ] {
+            contact_manager
+                .update(EXPECTED_UID, ContactAction::Allow, uid)
+                .await;
+        }
+        for uid in [VISIBLE_ONLINE, HIDDEN_ONLINE, BLOCKING_ONLINE] {
+            set_session(&session_manager, uid, Component::Client).await;
+        }
+        for uid in [VISIBLE_ONLINE, VISIBLE_OFFLINE, BLOCKING_ONLINE] {
+            contact_manager.set_visible(uid, true).await;
+        }
+        contact_manager
+            .update(BLOCKING_ONLINE, ContactAction::Block, EXPECTED_UID)
+            .await;
+
+        let conversation_id = route_manager
+            .initialize(EXPECTED_UID, CONTROLLER_UID, &[], &[], 0)
+            .await
+            .unwrap();
+
+        let request = OnlineContactsRequest {
+            access_key,
+            conversation_id: conversation_id.clone(),
+            uids: Vec::new(),
+        };
+
+        let response = service
+            .online_contacts(Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.uids, vec![VISIBLE_ONLINE]);
+        assert!(
+            route_manager
+                .get_conversation(&conversation_id)
+                .await
+                .is_none()
+        );
+    }
 }
@@ -1,28 +1,44 @@
+pub mod admin_service;
 pub mod auth_service;
+pub mod federation_service;
 pub mod info_service;
 pub mod route_service;
 
 use crate::models::{
+    admin_proto::admin_service_server::AdminServiceServer,
     auth_proto::auth_service_server::AuthServiceServer,
+    federation_proto::federation_service_server::FederationServiceServer,
     info_proto::info_service_server::InfoServiceServer,
     route_proto::route_service_server::RouteServiceServer,
 };
-use crate::{membership::MemberManager, routing::RouteManager, session::SessionManager};
+use crate::{
+    contacts::ContactManager, federation::FederationManager, groups::GroupManager,
+    login_attempts::LoginAttemptManager, membership::MemberManager, registry::ControllerRegistry,
+    routing::RouteManager, session::SessionManager, storage::RepositoryType, tickets::TicketIssuer,
+    token::AccessKeySigner,
+};
+use admin_service::AdminServiceImpl;
 use auth_service::AuthServiceImpl;
-use crosscutting::{ComponentDescriptor, networking, settings, tracing};
+use crosscutting::{ComponentDescriptor, networking, rate_limit, settings, tracing};
+use federation_service::FederationServiceImpl;
 use info_service::InfoServiceImpl;
 use log::{debug, info, warn};
 use route_service::RouteServiceImpl;
 use std::sync::Arc;
-use tonic::transport::{Server, ServerTlsConfig};
+use tonic::transport::{Certificate, Server, ServerTlsConfig};
 
 use tonic::{Request, Response, Status};
 
+const CONTROLLER_UID: &str = "controller_uid";
+
 pub fn start_server_handler(
     descriptor: ComponentDescriptor,
     session_manager: Arc<SessionManager>,
     route_manger: Arc<RouteManager>,
     member_manager: Arc<MemberManager>,
+    login_attempt_manager: Arc<LoginAttemptManager>,
+    controller_registry: Arc<ControllerRegistry>,
+    contact_manager: Arc<ContactManager>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         if let ComponentDescriptor::Controller {
@@ -34,15 +50,82 @@ pub fn start_server_handler(
             let socket_address = connection_settings.get_local_socket_address();
             debug!("Starting server on {}", socket_address);
 
-            let auth_service = AuthServiceImpl::new(Arc::clone(&session_manager), member_manager);
-            let route_service = RouteServiceImpl::new(Arc::clone(&session_manager), route_manger);
-            let info_service = InfoServiceImpl::new(Arc::clone(&session_manager), version);
+            let admin_service = AdminServiceImpl::new(
+                Arc::clone(&session_manager),
+                Arc::clone(&member_manager),
+                Arc::clone(&login_attempt_manager),
+            );
+            let ticket_issuer = Arc::new(TicketIssuer::get_from_env());
+            if ticket_issuer.is_source_routing_enabled() {
+                info!("Source routing enabled");
+            }
+            if ticket_issuer.is_signing_nonces() {
+                info!("Signed nonces enabled");
+            }
+            let auth_service = AuthServiceImpl::new(
+                Arc::clone(&session_manager),
+                member_manager,
+                login_attempt_manager,
+                Arc::clone(&ticket_issuer),
+            );
+            let federation_manager = Arc::new(FederationManager::get_from_env());
+            let route_service = RouteServiceImpl::new(
+                Arc::clone(&session_manager),
+                Arc::clone(&route_manger),
+                Arc::clone(&federation_manager),
+                Arc::clone(&contact_manager),
+                Arc::new(GroupManager::new(RepositoryType::get_from_env())),
+                ticket_issuer,
+            );
+            let federation_service = federation_manager.get_domain().map(|domain| {
+                info!("Federation enabled for domain {}", domain);
+                FederationServiceServer::new(FederationServiceImpl::new(
+                    Arc::clone(&session_manager),
+                    Arc::clone(&route_manger),
+                    Arc::clone(&federation_manager),
+                    Arc::clone(&contact_manager),
+                ))
+            });
+            let info_service = InfoServiceImpl::new(
+                Arc::clone(&session_manager),
+                route_manger,
+                contact_manager,
+                controller_registry,
+                version,
+            );
 
             debug!("Loading certificates ...");
 
             let identity =
                 settings::service::load_tls_identity("server.crt", "server.key").unwrap();
-            let tls_config = ServerTlsConfig::new().identity(identity);
+            let mut tls_config = ServerTlsConfig::new().identity(identity);
+            let mut client_ca = settings::auth::get_client_ca_certificate();
+            if client_ca.is_some() {
+                info!("Client certificate authentication enabled");
+            }
+            if let Some(federation_ca) = federation_manager.get_ca_certificate() {
+                if client_ca.as_deref() == Some(federation_ca) {
+                    warn!("Members and federation peers share the same CA");
+                }
+                let mut roots = client_ca.unwrap_or_default();
+                roots.push(b'\n');
+                roots.extend_from_slice(federation_ca);
+                client_ca = Some(roots);
+            }
+            if let Some(client_ca) = client_ca {
+                tls_config = tls_config
+                    .client_ca_root(Certificate::from_pem(client_ca))
+                    .client_auth_optional(true);
+            }
+
+            // Signed access keys can be checked without hitting the session
+            // storage, so they can tell apart callers sharing an address
+            let mut rate_limit_layer = rate_limit::RateLimitLayer::get_from_env();
+            if let Some(signer) = AccessKeySigner::get_from_env().unwrap() {
+                let signer = Arc::new(signer);
+                rate_limit_layer = rate_limit_layer
+                    .with_key_verifier(Arc::new(move |key| signer.verify(key).is_ok()));
+            }
 
             info!("Starting Controller server with TLS on {}", socket_address);
 
@@ -50,9 +133,12 @@ pub fn start_server_handler(
                 .tls_config(tls_config)
                 .unwrap()
                 .layer(tracing::UriTracingLayer)
+                .layer(rate_limit_layer)
                 .add_service(AuthServiceServer::new(auth_service))
                 .add_service(RouteServiceServer::new(route_service))
                 .add_service(InfoServiceServer::new(info_service))
+                .add_service(AdminServiceServer::new(admin_service))
+                .add_optional_service(federation_service)
                 .serve(socket_address)
                 .await;
         }
@@ -62,19 +148,40 @@ pub fn start_server_handler(
 mod guards {
 
     use super::*;
+    use crate::session::SessionClaims;
 
     const INVALID_ACCESS_KEY: &str = "Invalid access key";
     const INVALID_CONVERSATION: &str = "Invalid conversation";
+    const INVALID_ADMIN_KEY: &str = "Invalid admin key";
 
     pub async fn check_session(
         session_manager: &Arc<SessionManager>,
         access_key: &str,
-    ) -> Result<(), Status> {
+    ) -> Result<SessionClaims, Status> {
         session_manager
-            .get_session(access_key)
+            .verify_access_key(access_key)
             .await
-            .ok_or_else(|| Status::unauthenticated(INVALID_ACCESS_KEY))?;
-        Ok(())
+            .ok_or_else(|| Status::unauthenticated(INVALID_ACCESS_KEY))
+    }
+
+    pub async fn check_admin(admin_key: &Option<String>, provided_key: &str) -> Result<(), Status> {
+        match admin_key {
+            Some(admin_key) if constant_time_eq(admin_key.as_bytes(), provided_key.as_bytes()) => {
+                Ok(())
+            }
+            _ => Err(Status::permission_denied(INVALID_ADMIN_KEY)),
+        }
+    }
+
+    fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+        if left.len() != right.len() {
+            return false;
+        }
+
+        left.iter()
+            .zip(right.iter())
+            .fold(0u8, |acc, (l, r)| acc | (l ^ r))
+            == 0
     }
 
     pub async fn check_conversation(
@@ -142,6 +249,7 @@ mod guards {
                     Component::Client,
                     &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
                     &get_connection_settings(),
+                    &[],
                 )
                 .await;
 
@@ -170,7 +278,7 @@ mod guards {
             let route_manager =
                 Arc::new(RouteManager::new(repository_type, CancellationToken::new()));
             let conversation_id = route_manager
-                .initialize(EXPECTED_FROM_UID, EXPECTED_TO_UID)
+                .initialize(EXPECTED_FROM_UID, EXPECTED_TO_UID, &[], &[], 0)
                 .await
                 .unwrap();
 
@@ -1,56 +1,262 @@
 use crate::{
+    contacts::{ContactAction, ContactManager},
+    federation::{FederationManager, FederationPeer, Recipient},
+    groups::{GroupAction, GroupManager},
     models::{
-        SessionInfo,
+        Conversation, Route, SessionInfo,
+        federation_proto::OpenRequest,
         route_proto::{
-            InitRequest, InitResponse, RedeemRequest, RedeemResponse, RouteRequest, RouteResponse,
-            SourceInfo, route_service_server::RouteService,
+            self, CircuitResponse, ContactRequest, ContactResponse, GroupInitRequest,
+            GroupInitResponse, GroupRecipient, GroupRequest, GroupResponse, InitRequest,
+            InitResponse, RedeemRequest, RedeemResponse, RouteRequest, RouteResponse, SourceInfo,
+            SpendReport, SpendResponse, VisibilityRequest, VisibilityResponse,
+            route_service_server::RouteService,
         },
     },
     routing::RouteManager,
-    session::SessionManager,
+    session::{SessionClaims, SessionManager},
+    tickets::TicketIssuer,
 };
+use crosscutting::Component;
+use std::collections::HashMap;
 
 use super::*;
 
-const CONTROLLER_UID: &str = "controller_uid";
-
 pub struct RouteServiceImpl {
     route_manager: Arc<RouteManager>,
     session_manager: Arc<SessionManager>,
+    federation_manager: Arc<FederationManager>,
+    contact_manager: Arc<ContactManager>,
+    group_manager: Arc<GroupManager>,
+    ticket_issuer: Arc<TicketIssuer>,
 }
 
 impl RouteServiceImpl {
-    pub fn new(session_manager: Arc<SessionManager>, route_manager: Arc<RouteManager>) -> Self {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        route_manager: Arc<RouteManager>,
+        federation_manager: Arc<FederationManager>,
+        contact_manager: Arc<ContactManager>,
+        group_manager: Arc<GroupManager>,
+        ticket_issuer: Arc<TicketIssuer>,
+    ) -> Self {
         Self {
             route_manager,
             session_manager,
+            federation_manager,
+            contact_manager,
+            group_manager,
+            ticket_issuer,
         }
     }
 
+    async fn initialize_federated(
+        &self,
+        claims: &SessionClaims,
+        peer: &FederationPeer,
+        uid: &str,
+        to: &str,
+    ) -> Result<InitResponse, Status> {
+        let open_request = OpenRequest {
+            conversation_id: RouteManager::create_conversation_id(),
+            from: claims.uid.clone(),
+            to: uid.to_string(),
+            sender_key: claims.identity_key.clone(),
+        };
+        let conversation_id = open_request.conversation_id.clone();
+
+        let response = self
+            .federation_manager
+            .open(peer, open_request)
+            .await
+            .map_err(|e| {
+                warn!("Federation with {} failed: {}", peer.domain, e);
+                match e.downcast_ref::<Status>() {
+                    Some(status) => Status::new(status.code(), status.message()),
+                    None => Status::unavailable(format!("Domain {} is unavailable", peer.domain)),
+                }
+            })?;
+
+        let ticket = response
+            .ticket
+            .ok_or_else(|| Status::internal("Missing route ticket"))?;
+        let handoff = Route {
+            on_ip_address: ticket.ip_address,
+            on_port_number: ticket.port_number as u16,
+            public_key: ticket.public_key,
+            domain_name: ticket.domain_name,
+            nonce: ticket.nonce,
+            end_route: false,
+            tags: HashMap::new(),
+        };
+
+        let conversation_id = self
+            .route_manager
+            .initialize_federated(
+                &conversation_id,
+                &claims.uid,
+                to,
+                &claims.identity_key,
+                &response.recipient_key,
+                Some(handoff),
+            )
+            .await
+            .ok_or_else(|| Status::internal("Failed to initialize conversation"))?;
+
+        Ok(InitResponse {
+            conversation_id,
+            recipient_key: response.recipient_key,
+            ..Default::default()
+        })
+    }
+
     async fn handle_route(
         &self,
         conversation_id: &str,
         session_info: &SessionInfo,
         end_route: bool,
-    ) -> Result<Response<RouteResponse>, Status> {
+    ) -> Result<RouteResponse, Status> {
         let connection_settings = session_info.to_connection_settings();
 
         let nonce = self
             .route_manager
-            .store_route(conversation_id, &connection_settings, end_route)
+            .store_route(
+                conversation_id,
+                &connection_settings,
+                &session_info.tags,
+                end_route,
+            )
             .await
             .ok_or(Status::internal("Failed to store route"))?;
 
-        let response = RouteResponse {
+        let mut response = RouteResponse {
             ip_address: session_info.on_ip_address.clone(),
             port_number: session_info.on_port_number as u32,
             public_key: session_info.public_key.clone(),
             domain_name: session_info.domain_name.clone(),
             nonce,
             end_route,
+            identity_key: session_info.identity_key.clone(),
+            nonce_ticket: Vec::new(),
         };
 
-        Ok(Response::new(response))
+        // The landing client still redeems its nonce to learn about the sender.
+        if !end_route {
+            response.nonce_ticket = self
+                .ticket_issuer
+                .sign_nonce(conversation_id, &response)
+                .unwrap_or_default();
+        }
+
+        Ok(response)
+    }
+
+    async fn handle_next_route(
+        &self,
+        conversation: &Conversation,
+        access_key: &str,
+    ) -> Result<RouteResponse, Status> {
+        if self.route_manager.check_for_final_route(conversation) {
+            if let Some(handoff) = &conversation.handoff {
+                self.route_manager.finalize(&conversation.id).await;
+                return Ok(RouteResponse {
+                    ip_address: handoff.on_ip_address.clone(),
+                    port_number: handoff.on_port_number as u32,
+                    public_key: handoff.public_key.clone(),
+                    domain_name: handoff.domain_name.clone(),
+                    nonce: handoff.nonce.clone(),
+                    end_route: false,
+                    identity_key: Vec::new(),
+                    nonce_ticket: Vec::new(),
+                });
+            }
+
+            // An end route without an address tells the proxy holding a group
+            // message that it is the fan-out point for the member conversations.
+            if conversation.group.is_some() && conversation.parent.is_none() {
+                self.route_manager.finalize(&conversation.id).await;
+                return Ok(RouteResponse {
+                    end_route: true,
+                    ..Default::default()
+                });
+            }
+
+            let client = if self
+                .contact_manager
+                .is_allowed(&conversation.from, &conversation.to)
+                .await
+            {
+                self.session_manager
+                    .get_client(&conversation.to, &conversation.recipient_key)
+                    .await
+            } else {
+                None
+            };
+            if let Some(client_session) = client {
+                return self
+                    .handle_route(&conversation.id, &client_session, true)
+                    .await;
+            }
+
+            return Err(Status::not_found(
+                "Reached final route, no more routes available as no client found",
+            ));
+        }
+
+        let proxies = self.session_manager.get_proxies(access_key).await;
+
+        if proxies.is_none() {
+            return Err(Status::not_found("No proxies found"));
+        }
+
+        if let Some(proxy_session) = self
+            .route_manager
+            .get_next_route(conversation, &proxies.unwrap())
+            .await
+        {
+            return self
+                .handle_route(&conversation.id, &proxy_session, false)
+                .await;
+        }
+
+        Err(Status::not_found(
+            "Next route wasn't found, no more routes available",
+        ))
+    }
+
+    async fn handle_path(
+        &self,
+        conversation_id: &str,
+        access_key: &str,
+    ) -> Result<Vec<RouteResponse>, Status> {
+        let mut hops = Vec::new();
+        loop {
+            let conversation = self
+                .route_manager
+                .get_conversation(conversation_id)
+                .await
+                .ok_or_else(|| Status::internal("Conversation is no longer available"))?;
+
+            if conversation.handoff.is_some() {
+                return Err(Status::failed_precondition(
+                    "Circuits are not available for federated conversations",
+                ));
+            }
+
+            if conversation.group.is_some() {
+                return Err(Status::failed_precondition(
+                    "Circuits are not available for group conversations",
+                ));
+            }
+
+            let hop = self.handle_next_route(&conversation, access_key).await?;
+            let end_route = hop.end_route;
+            hops.push(hop);
+
+            if end_route {
+                return Ok(hops);
+            }
+        }
     }
 }
 
@@ -63,22 +269,111 @@ impl RouteService for RouteServiceImpl {
         let init_request = request.into_inner();
         let access_key = init_request.access_key.to_owned();
 
-        guards::check_session(&self.session_manager, access_key.as_str()).await?;
-        let session_info = self.session_manager.get_session(&access_key).await.unwrap();
+        let claims = guards::check_session(&self.session_manager, &access_key).await?;
+
+        if init_request.source_routed && !self.ticket_issuer.is_source_routing_enabled() {
+            return Err(Status::failed_precondition("Source routing is not enabled"));
+        }
+
+        if init_request.anonymous && init_request.sender_key.is_empty() {
+            return Err(Status::invalid_argument(
+                "Anonymous conversations require an ephemeral sender key",
+            ));
+        }
 
-        let to = if init_request.to.is_empty() {
-            CONTROLLER_UID.to_string()
+        let reply_target = if !init_request.reply_handle.is_empty() {
+            Some(
+                self.route_manager
+                    .resolve_reply_handle(&init_request.reply_handle)
+                    .await
+                    .ok_or_else(|| Status::not_found("Reply handle not found or expired"))?,
+            )
         } else {
-            init_request.to
+            None
         };
 
-        let conversation_id = self
-            .route_manager
-            .initialize(&session_info.uid, &to)
-            .await
-            .ok_or_else(|| Status::internal("Failed to initialize conversation"))?;
+        let to = match &reply_target {
+            Some(target) => target.uid.clone(),
+            None if init_request.to.is_empty() => CONTROLLER_UID.to_string(),
+            None => init_request.to.clone(),
+        };
+
+        let to = match self.federation_manager.resolve(&to) {
+            Recipient::Local(uid) => uid.to_string(),
+            Recipient::Remote(..) if init_request.anonymous => {
+                return Err(Status::failed_precondition(
+                    "Anonymous conversations are not available across domains",
+                ));
+            }
+            Recipient::Remote(..) if init_request.source_routed => {
+                return Err(Status::failed_precondition(
+                    "Source routed conversations are not available across domains",
+                ));
+            }
+            Recipient::Remote(peer, uid) => {
+                return self
+                    .initialize_federated(&claims, peer, uid, &to)
+                    .await
+                    .map(Response::new);
+            }
+        };
+
+        // Replies are sealed for the ephemeral key of the anonymous conversation
+        // they answer, and delivered to the device which started it
+        let (recipient_key, device_key) =
+            if !self.contact_manager.is_allowed(&claims.uid, &to).await {
+                (Vec::new(), Vec::new())
+            } else if let Some(target) = reply_target {
+                (target.reply_key, target.device_key)
+            } else {
+                let identity_key = self
+                    .session_manager
+                    .get_identity_key(&to)
+                    .await
+                    .unwrap_or_default();
+                (identity_key.clone(), identity_key)
+            };
+
+        let conversation_id = if init_request.anonymous {
+            self.route_manager
+                .initialize_anonymous(
+                    &claims.uid,
+                    &to,
+                    &init_request.sender_key,
+                    &claims.identity_key,
+                    &device_key,
+                    init_request.hops,
+                )
+                .await
+        } else {
+            self.route_manager
+                .initialize(
+                    &claims.uid,
+                    &to,
+                    &claims.identity_key,
+                    &device_key,
+                    init_request.hops,
+                )
+                .await
+        }
+        .ok_or_else(|| Status::internal("Failed to initialize conversation"))?;
+
+        let mut response = InitResponse {
+            conversation_id,
+            recipient_key,
+            ..Default::default()
+        };
 
-        let response = InitResponse { conversation_id };
+        if init_request.source_routed {
+            let hops = self
+                .handle_path(&response.conversation_id, &access_key)
+                .await?;
+            response.tickets = self
+                .ticket_issuer
+                .issue(&response.conversation_id, &hops)
+                .unwrap_or_default();
+            response.entry = hops.into_iter().next();
+        }
 
         Ok(Response::new(response))
     }
@@ -100,38 +395,192 @@ impl RouteService for RouteServiceImpl {
             .await
             .unwrap();
 
-        if self.route_manager.check_for_final_route(&conversation) {
-            let client = self.session_manager.get_client(&conversation.to).await;
-            if let Some(client_session) = client {
-                return self
-                    .handle_route(&conversation_id, &client_session, true)
-                    .await;
+        if let Some(parent) = &conversation.parent
+            && self.route_manager.get_conversation(parent).await.is_some()
+        {
+            return Err(Status::failed_precondition(
+                "Group message hasn't reached its fan-out point",
+            ));
+        }
+
+        self.handle_next_route(&conversation, &access_key)
+            .await
+            .map(Response::new)
+    }
+
+    async fn circuit(
+        &self,
+        request: Request<RouteRequest>,
+    ) -> Result<Response<CircuitResponse>, Status> {
+        let route_request = request.into_inner();
+        let conversation_id = route_request.conversation_id.to_owned();
+        let access_key = route_request.access_key.to_owned();
+
+        guards::check_session(&self.session_manager, access_key.as_str()).await?;
+        guards::check_conversation(&self.route_manager, &conversation_id).await?;
+
+        let mut hops = self.handle_path(&conversation_id, &access_key).await?;
+        for hop in hops.iter_mut().filter(|hop| !hop.end_route) {
+            if hop.nonce_ticket.is_empty() {
+                hop.nonce_ticket = self
+                    .ticket_issuer
+                    .sign_circuit_nonce(&conversation_id, hop)
+                    .unwrap_or_default();
             }
+        }
 
-            return Err(Status::not_found(
-                "Reached final route, no more routes available as no client found",
+        Ok(Response::new(CircuitResponse { hops }))
+    }
+
+    async fn update_contact(
+        &self,
+        request: Request<ContactRequest>,
+    ) -> Result<Response<ContactResponse>, Status> {
+        let contact_request = request.into_inner();
+        let access_key = contact_request.access_key.to_owned();
+
+        let claims = guards::check_session(&self.session_manager, access_key.as_str()).await?;
+
+        let action = match contact_request.action() {
+            route_proto::ContactAction::Block => ContactAction::Block,
+            route_proto::ContactAction::Unblock => ContactAction::Unblock,
+            route_proto::ContactAction::Allow => ContactAction::Allow,
+            route_proto::ContactAction::Unknown => {
+                return Err(Status::invalid_argument("Invalid contact action"));
+            }
+        };
+
+        if contact_request.uid.trim().is_empty() {
+            return Err(Status::invalid_argument("UID cannot be empty"));
+        }
+
+        self.contact_manager
+            .update(&claims.uid, action, contact_request.uid.trim())
+            .await;
+
+        Ok(Response::new(ContactResponse {}))
+    }
+
+    async fn set_visibility(
+        &self,
+        request: Request<VisibilityRequest>,
+    ) -> Result<Response<VisibilityResponse>, Status> {
+        let visibility_request = request.into_inner();
+        let access_key = visibility_request.access_key.to_owned();
+
+        let claims = guards::check_session(&self.session_manager, access_key.as_str()).await?;
+
+        self.contact_manager
+            .set_visible(&claims.uid, visibility_request.visible)
+            .await;
+
+        Ok(Response::new(VisibilityResponse {}))
+    }
+
+    async fn update_group(
+        &self,
+        request: Request<GroupRequest>,
+    ) -> Result<Response<GroupResponse>, Status> {
+        let group_request = request.into_inner();
+        let access_key = group_request.access_key.to_owned();
+
+        let claims = guards::check_session(&self.session_manager, access_key.as_str()).await?;
+
+        let action = group_request.action();
+        let name = group_request.group.trim();
+        let uid = group_request.uid.trim();
+        let group = self.group_manager.get_group(name).await;
+
+        if action == route_proto::GroupAction::Create {
+            if group.is_some() {
+                return Err(Status::already_exists("Group already exists"));
+            }
+
+            self.group_manager
+                .create_group(name, &claims.uid)
+                .await
+                .map_err(Status::invalid_argument)?;
+            return Ok(Response::new(GroupResponse {}));
+        }
+
+        let group = group.ok_or_else(|| Status::not_found("Group not found"))?;
+        if group.owner != claims.uid {
+            return Err(Status::permission_denied(
+                "Only the group owner can manage it",
             ));
         }
 
-        let proxies = self.session_manager.get_proxies(&access_key).await;
+        let action = match action {
+            route_proto::GroupAction::Delete => {
+                self.group_manager.remove_group(name).await;
+                return Ok(Response::new(GroupResponse {}));
+            }
+            route_proto::GroupAction::Add => GroupAction::Add,
+            route_proto::GroupAction::Remove => GroupAction::Remove,
+            _ => return Err(Status::invalid_argument("Invalid group action")),
+        };
 
-        if proxies.is_none() {
-            return Err(Status::not_found("No proxies found"));
+        if uid.is_empty() {
+            return Err(Status::invalid_argument("UID cannot be empty"));
         }
 
-        if let Some(proxy_session) = self
+        self.group_manager.update(group, action, uid).await;
+
+        Ok(Response::new(GroupResponse {}))
+    }
+
+    async fn init_group(
+        &self,
+        request: Request<GroupInitRequest>,
+    ) -> Result<Response<GroupInitResponse>, Status> {
+        let init_request = request.into_inner();
+        let access_key = init_request.access_key.to_owned();
+
+        let claims = guards::check_session(&self.session_manager, &access_key).await?;
+
+        let members = self
+            .group_manager
+            .get_recipients(&init_request.group, &claims.uid)
+            .await
+            .ok_or_else(|| Status::not_found("Group not found"))?;
+
+        let conversation_id = self
             .route_manager
-            .get_next_route(&conversation, &proxies.unwrap())
+            .initialize_group(&claims.uid, &init_request.group, &claims.identity_key)
             .await
-        {
-            return self
-                .handle_route(&conversation_id, &proxy_session, false)
-                .await;
+            .ok_or_else(|| Status::internal("Failed to initialize conversation"))?;
+        let conversation = self
+            .route_manager
+            .get_conversation(&conversation_id)
+            .await
+            .ok_or_else(|| Status::internal("Conversation is no longer available"))?;
+
+        let mut recipients = Vec::new();
+        for member in members {
+            if !self.contact_manager.is_allowed(&claims.uid, &member).await {
+                continue;
+            }
+
+            let Some(identity_key) = self.session_manager.get_identity_key(&member).await else {
+                continue;
+            };
+
+            if let Some(member_conversation_id) = self
+                .route_manager
+                .initialize_group_member(&conversation, &member, &identity_key)
+                .await
+            {
+                recipients.push(GroupRecipient {
+                    conversation_id: member_conversation_id,
+                    recipient_key: identity_key,
+                });
+            }
         }
 
-        return Err(Status::not_found(
-            "Next route wasn't found, no more routes available",
-        ));
+        Ok(Response::new(GroupInitResponse {
+            conversation_id,
+            recipients,
+        }))
     }
 
     async fn redeem(
@@ -158,8 +607,15 @@ impl RouteService for RouteServiceImpl {
                     .get_conversation(&conversation_id)
                     .await
                     .unwrap();
+                let (from, reply_handle) = match conversation.pseudonym {
+                    Some(pseudonym) => (pseudonym.name, pseudonym.reply_handle),
+                    None => (conversation.from, String::default()),
+                };
                 response.source_info = Some(SourceInfo {
-                    from: conversation.from,
+                    from,
+                    identity_key: conversation.sender_key,
+                    reply_handle,
+                    group: conversation.group.unwrap_or_default(),
                 });
                 self.route_manager.finalize(&conversation_id).await;
             }
@@ -169,16 +625,65 @@ impl RouteService for RouteServiceImpl {
 
         Err(Status::internal("Failed to redeem route"))
     }
+
+    async fn report_spent(
+        &self,
+        request: Request<SpendReport>,
+    ) -> Result<Response<SpendResponse>, Status> {
+        let spend_report = request.into_inner();
+        let access_key = spend_report.access_key.to_owned();
+
+        let claims = guards::check_session(&self.session_manager, access_key.as_str()).await?;
+        if claims.component_type != Component::Proxy {
+            return Err(Status::permission_denied(
+                "Only proxies can report spent nonces",
+            ));
+        }
+
+        let mut redeemed = 0;
+        for spent in &spend_report.nonces {
+            let conversation_id = if spent.conversation_ref.is_empty() {
+                Some(spent.conversation_id.clone())
+            } else {
+                self.ticket_issuer
+                    .open_conversation_ref(&spent.conversation_ref)
+            };
+            let Some(conversation_id) = conversation_id else {
+                continue;
+            };
+
+            if self
+                .route_manager
+                .redeem_route(&conversation_id, &spent.nonce)
+                .await
+                .is_some()
+            {
+                redeemed += 1;
+            }
+        }
+
+        debug!(
+            "Redeemed {} of {} nonces spent by {}",
+            redeemed,
+            spend_report.nonces.len(),
+            claims.uid
+        );
+        Ok(Response::new(SpendResponse {}))
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::federation::{FederationSettings, MockFederationClient};
+    use crate::models::federation_proto::{OpenResponse, RouteTicket};
+    use crate::models::route_proto::{NonceTicket, SpentNonce};
     use crate::routing::RouteManager;
     use crate::session::SessionManager;
     use crate::storage::RepositoryType;
     use crosscutting::{Component, ConnectionSettings};
+    use prost::Message;
     use tokio_util::sync::CancellationToken;
 
     const EXPECTED_CONVERSATION_ID: &str = "test_conversation_id";
@@ -190,6 +695,9 @@ mod tests {
     const EXPECTED_NONCE: &str = "test_nonce";
     const EXPECTED_PUBLIC_KEY: &[u8] = b"test_public_key";
     const EXPECTED_DOMAIN_NAME: &str = "test_domain_name";
+    const EXPECTED_SENDER_KEY: &[u8] = b"test_sender_key";
+    const EXPECTED_RECIPIENT_KEY: &[u8] = b"test_recipient_key";
+    const EXPECTED_EPHEMERAL_KEY: &[u8] = b"test_ephemeral_key";
 
     fn get_connection_settings() -> ConnectionSettings {
         ConnectionSettings {
@@ -212,11 +720,19 @@ mod tests {
             repository_type,
             cancellation_token.child_token(),
         ));
-        let route_service = RouteServiceImpl::new(session_manager, route_manager);
+        let route_service = RouteServiceImpl::new(
+            session_manager,
+            route_manager,
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
 
         let init_request = InitRequest {
             access_key: EXPECTED_ACCESS_KEY.to_string(),
             to: EXPECTED_TARGET.to_string(),
+            ..Default::default()
         };
 
         let request = Request::new(init_request);
@@ -239,7 +755,14 @@ mod tests {
             repository_type,
             cancellation_token.child_token(),
         ));
-        let route_service = RouteServiceImpl::new(session_manager.clone(), route_manager.clone());
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
 
         let access_key = session_manager
             .set_session(
@@ -247,22 +770,25 @@ mod tests {
                 Component::Client,
                 &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
                 &get_connection_settings(),
+                &[],
             )
             .await;
 
         let init_request = InitRequest {
-            access_key: access_key,
+            access_key,
             to: EXPECTED_TARGET.to_string(),
+            ..Default::default()
         };
 
         let request = Request::new(init_request);
         let result = route_service.initialize(request).await;
 
         assert!(result.is_ok());
+        assert!(result.unwrap().into_inner().recipient_key.is_empty());
     }
 
     #[tokio::test]
-    async fn given_non_existing_session_when_routing_then_returns_error() {
+    async fn given_online_recipient_when_initializing_conversation_then_returns_recipient_key() {
         let cancellation_token = CancellationToken::new();
         let repository_type = RepositoryType::InMemory;
         let route_manager = Arc::new(RouteManager::new(
@@ -273,23 +799,53 @@ mod tests {
             repository_type,
             cancellation_token.child_token(),
         ));
-        let route_service = RouteServiceImpl::new(session_manager, route_manager);
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
 
-        let route_request = RouteRequest {
-            access_key: EXPECTED_ACCESS_KEY.to_string(),
-            conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+
+        session_manager
+            .set_session(
+                EXPECTED_TARGET,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                EXPECTED_RECIPIENT_KEY,
+            )
+            .await;
+
+        let init_request = InitRequest {
+            access_key,
+            to: EXPECTED_TARGET.to_string(),
+            ..Default::default()
         };
 
-        let request = Request::new(route_request);
-        let result = route_service.route(request).await;
+        let request = Request::new(init_request);
+        let result = route_service.initialize(request).await;
 
-        assert!(result.is_err());
-        let status = result.unwrap_err();
-        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap().into_inner().recipient_key,
+            EXPECTED_RECIPIENT_KEY
+        );
     }
 
     #[tokio::test]
-    async fn given_existing_session_but_conversation_when_routing_then_returns_error() {
+    async fn given_offline_recipient_when_initializing_conversation_then_returns_recipient_key() {
         let cancellation_token = CancellationToken::new();
         let repository_type = RepositoryType::InMemory;
         let route_manager = Arc::new(RouteManager::new(
@@ -300,19 +856,73 @@ mod tests {
             repository_type,
             cancellation_token.child_token(),
         ));
-        let route_service = RouteServiceImpl::new(session_manager.clone(), route_manager.clone());
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+        let socket_address = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
 
         let access_key = session_manager
             .set_session(
                 EXPECTED_UID,
                 Component::Client,
-                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+        let recipient_access_key = session_manager
+            .set_session(
+                EXPECTED_TARGET,
+                Component::Client,
+                &socket_address,
                 &get_connection_settings(),
+                EXPECTED_RECIPIENT_KEY,
             )
             .await;
+        session_manager.remove_session(&recipient_access_key).await;
+
+        let init_request = InitRequest {
+            access_key,
+            to: EXPECTED_TARGET.to_string(),
+            ..Default::default()
+        };
+
+        let result = route_service.initialize(Request::new(init_request)).await;
+
+        assert_eq!(
+            result.unwrap().into_inner().recipient_key,
+            EXPECTED_RECIPIENT_KEY
+        );
+    }
+
+    #[tokio::test]
+    async fn given_non_existing_session_when_routing_then_returns_error() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager,
+            route_manager,
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
 
         let route_request = RouteRequest {
-            access_key: access_key,
+            access_key: EXPECTED_ACCESS_KEY.to_string(),
             conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
         };
 
@@ -321,11 +931,11 @@ mod tests {
 
         assert!(result.is_err());
         let status = result.unwrap_err();
-        assert_eq!(status.code(), tonic::Code::NotFound);
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
     }
 
     #[tokio::test]
-    async fn given_existing_session_and_conversation_but_proxies_when_routing_then_returns_error() {
+    async fn given_existing_session_but_conversation_when_routing_then_returns_error() {
         let cancellation_token = CancellationToken::new();
         let repository_type = RepositoryType::InMemory;
         let route_manager = Arc::new(RouteManager::new(
@@ -336,7 +946,14 @@ mod tests {
             repository_type,
             cancellation_token.child_token(),
         ));
-        let route_service = RouteServiceImpl::new(session_manager.clone(), route_manager.clone());
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
 
         let access_key = session_manager
             .set_session(
@@ -344,16 +961,61 @@ mod tests {
                 Component::Client,
                 &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
                 &get_connection_settings(),
+                &[],
             )
             .await;
 
-        let conversation_id = route_manager
-            .initialize(EXPECTED_UID, EXPECTED_TARGET)
-            .await
+        let route_request = RouteRequest {
+            access_key,
+            conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+        };
+
+        let request = Request::new(route_request);
+        let result = route_service.route(request).await;
+
+        assert!(result.is_err());
+        let status = result.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn given_existing_session_and_conversation_but_proxies_when_routing_then_returns_error() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        let conversation_id = route_manager
+            .initialize(EXPECTED_UID, EXPECTED_TARGET, &[], &[], 0)
+            .await
             .unwrap();
 
         let route_request = RouteRequest {
-            access_key: access_key,
+            access_key,
             conversation_id: conversation_id.to_string(),
         };
 
@@ -377,7 +1039,14 @@ mod tests {
             repository_type,
             cancellation_token.child_token(),
         ));
-        let route_service = RouteServiceImpl::new(session_manager.clone(), route_manager.clone());
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
 
         let access_key = session_manager
             .set_session(
@@ -385,6 +1054,7 @@ mod tests {
                 Component::Client,
                 &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
                 &get_connection_settings(),
+                &[],
             )
             .await;
 
@@ -394,16 +1064,17 @@ mod tests {
                 Component::Proxy,
                 &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
                 &get_connection_settings(),
+                &[],
             )
             .await;
 
         let conversation_id = route_manager
-            .initialize(EXPECTED_UID, EXPECTED_TARGET)
+            .initialize(EXPECTED_UID, EXPECTED_TARGET, &[], &[], 0)
             .await
             .unwrap();
 
         let route_request = RouteRequest {
-            access_key: access_key,
+            access_key,
             conversation_id: conversation_id.to_string(),
         };
 
@@ -413,6 +1084,302 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn given_proxies_and_online_recipient_when_building_circuit_then_returns_all_hops() {
+        const EXPECTED_PROXY_UID: &str = "test_proxy";
+        const EXPECTED_PROXY_KEY: &[u8] = b"test_proxy_key";
+
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::new(TicketIssuer::new(
+                crosscutting::crypto::Identity::generate(),
+                false,
+                false,
+            )),
+        );
+        let socket_address = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+
+        session_manager
+            .set_session(
+                EXPECTED_PROXY_UID,
+                Component::Proxy,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_PROXY_KEY,
+            )
+            .await;
+
+        session_manager
+            .set_session(
+                EXPECTED_TARGET,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_RECIPIENT_KEY,
+            )
+            .await;
+
+        let conversation_id = route_manager
+            .initialize(EXPECTED_UID, EXPECTED_TARGET, EXPECTED_SENDER_KEY, &[], 0)
+            .await
+            .unwrap();
+
+        let route_request = RouteRequest {
+            access_key,
+            conversation_id,
+        };
+
+        let request = Request::new(route_request);
+        let result = route_service.circuit(request).await;
+
+        assert!(result.is_ok());
+        let hops = result.unwrap().into_inner().hops;
+        assert_eq!(hops.len(), 4);
+
+        let (end_hop, proxy_hops) = hops.split_last().unwrap();
+        assert!(end_hop.end_route);
+        assert!(end_hop.nonce_ticket.is_empty());
+        assert_eq!(end_hop.identity_key, EXPECTED_RECIPIENT_KEY);
+        assert!(proxy_hops.iter().all(|hop| !hop.nonce_ticket.is_empty()));
+        assert!(proxy_hops.iter().all(|hop| !hop.end_route));
+        assert!(
+            proxy_hops
+                .iter()
+                .all(|hop| hop.identity_key == EXPECTED_PROXY_KEY)
+        );
+    }
+
+    #[tokio::test]
+    async fn given_offline_recipient_when_building_circuit_then_returns_not_found() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+        let socket_address = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Proxy,
+                &socket_address,
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        let conversation_id = route_manager
+            .initialize(EXPECTED_UID, EXPECTED_TARGET, &[], &[], 0)
+            .await
+            .unwrap();
+
+        let route_request = RouteRequest {
+            access_key,
+            conversation_id,
+        };
+
+        let request = Request::new(route_request);
+        let result = route_service.circuit(request).await;
+
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn given_source_routing_disabled_when_initializing_source_routed_then_returns_error() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            Arc::new(RouteManager::new(
+                repository_type,
+                cancellation_token.child_token(),
+            )),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+        let socket_address = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+
+        let init_request = InitRequest {
+            access_key,
+            to: EXPECTED_TARGET.to_string(),
+            source_routed: true,
+            ..Default::default()
+        };
+        let result = route_service.initialize(Request::new(init_request)).await;
+
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn given_source_routing_when_initializing_then_returns_entry_and_signed_tickets() {
+        use crate::models::route_proto::HopTicket;
+        use crosscutting::crypto::{Identity, PublicIdentity, SIGNATURE_LENGTH};
+        use prost::Message;
+
+        const EXPECTED_PROXY_UID: &str = "test_proxy";
+        const EXPECTED_PROXY_KEY: &[u8] = b"test_proxy_key";
+
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let ticket_issuer = Arc::new(TicketIssuer::new(Identity::generate(), true, false));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            ticket_issuer.clone(),
+        );
+        let socket_address = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+        session_manager
+            .set_session(
+                EXPECTED_PROXY_UID,
+                Component::Proxy,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_PROXY_KEY,
+            )
+            .await;
+        session_manager
+            .set_session(
+                EXPECTED_TARGET,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_RECIPIENT_KEY,
+            )
+            .await;
+
+        let init_request = InitRequest {
+            access_key: access_key.clone(),
+            to: EXPECTED_TARGET.to_string(),
+            source_routed: true,
+            ..Default::default()
+        };
+        let response = route_service
+            .initialize(Request::new(init_request))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let entry = response.entry.unwrap();
+        assert!(!entry.end_route);
+        assert_eq!(response.tickets.len(), 3);
+
+        let ticket_key = PublicIdentity::from_bytes(&ticket_issuer.get_key()).unwrap();
+        let tickets: Vec<HopTicket> = response
+            .tickets
+            .iter()
+            .map(|signed| {
+                let (signature, ticket) = signed.split_at(SIGNATURE_LENGTH);
+                assert!(ticket_key.verify(ticket, signature).is_ok());
+                HopTicket::decode(ticket).unwrap()
+            })
+            .collect();
+        assert_eq!(tickets[0].nonce, entry.nonce);
+        assert!(
+            tickets
+                .iter()
+                .all(|ticket| ticket.holder_key == EXPECTED_PROXY_KEY)
+        );
+
+        let end_hop = tickets.last().unwrap().next_hop.clone().unwrap();
+        assert!(end_hop.end_route);
+        let redeem_request = RedeemRequest {
+            access_key,
+            conversation_id: response.conversation_id,
+            nonce: end_hop.nonce,
+        };
+        let redeemed = route_service
+            .redeem(Request::new(redeem_request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(redeemed.source_info.unwrap().from, EXPECTED_UID);
+    }
+
     #[tokio::test]
     async fn given_non_existing_session_when_redeeming_then_returns_error() {
         let cancellation_token = CancellationToken::new();
@@ -425,7 +1392,14 @@ mod tests {
             repository_type,
             cancellation_token.child_token(),
         ));
-        let route_service = RouteServiceImpl::new(session_manager, route_manager);
+        let route_service = RouteServiceImpl::new(
+            session_manager,
+            route_manager,
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
 
         let redeem_request = RedeemRequest {
             access_key: EXPECTED_ACCESS_KEY.to_string(),
@@ -453,7 +1427,14 @@ mod tests {
             repository_type,
             cancellation_token.child_token(),
         ));
-        let route_service = RouteServiceImpl::new(session_manager.clone(), route_manager.clone());
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
 
         let access_key = session_manager
             .set_session(
@@ -461,11 +1442,12 @@ mod tests {
                 Component::Client,
                 &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
                 &get_connection_settings(),
+                &[],
             )
             .await;
 
         let redeem_request = RedeemRequest {
-            access_key: access_key,
+            access_key,
             conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
             nonce: EXPECTED_NONCE.to_string(),
         };
@@ -490,7 +1472,14 @@ mod tests {
             repository_type,
             cancellation_token.child_token(),
         ));
-        let route_service = RouteServiceImpl::new(session_manager.clone(), route_manager.clone());
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
 
         let access_key = session_manager
             .set_session(
@@ -498,16 +1487,17 @@ mod tests {
                 Component::Client,
                 &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
                 &get_connection_settings(),
+                &[],
             )
             .await;
 
         let conversation_id = route_manager
-            .initialize(EXPECTED_UID, EXPECTED_TARGET)
+            .initialize(EXPECTED_UID, EXPECTED_TARGET, &[], &[], 0)
             .await
             .unwrap();
 
         let redeem_request = RedeemRequest {
-            access_key: access_key,
+            access_key,
             conversation_id: conversation_id.to_string(),
             nonce: EXPECTED_NONCE.to_string(),
         };
@@ -532,7 +1522,14 @@ mod tests {
             repository_type,
             cancellation_token.child_token(),
         ));
-        let route_service = RouteServiceImpl::new(session_manager.clone(), route_manager.clone());
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
 
         let access_key = session_manager
             .set_session(
@@ -540,23 +1537,29 @@ mod tests {
                 Component::Client,
                 &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
                 &get_connection_settings(),
+                &[],
             )
             .await;
 
         let conversation_id = route_manager
-            .initialize(EXPECTED_UID, EXPECTED_TARGET)
+            .initialize(EXPECTED_UID, EXPECTED_TARGET, &[], &[], 0)
             .await
             .unwrap();
 
         let nonce = route_manager
-            .store_route(&conversation_id, &get_connection_settings(), false)
+            .store_route(
+                &conversation_id,
+                &get_connection_settings(),
+                &HashMap::new(),
+                false,
+            )
             .await
             .unwrap();
 
         let redeem_request = RedeemRequest {
             access_key,
             conversation_id,
-            nonce: nonce,
+            nonce,
         };
 
         let request = Request::new(redeem_request);
@@ -564,4 +1567,1109 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn given_concurrent_redeems_of_the_same_nonce_when_redeeming_then_only_one_succeeds() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = Arc::new(RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        ));
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Proxy,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        let conversation_id = route_manager
+            .initialize(EXPECTED_UID, EXPECTED_TARGET, &[], &[], 0)
+            .await
+            .unwrap();
+
+        let nonce = route_manager
+            .store_route(
+                &conversation_id,
+                &get_connection_settings(),
+                &HashMap::new(),
+                false,
+            )
+            .await
+            .unwrap();
+
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let route_service = Arc::clone(&route_service);
+                let redeem_request = RedeemRequest {
+                    access_key: access_key.clone(),
+                    conversation_id: conversation_id.clone(),
+                    nonce: nonce.clone(),
+                };
+                tokio::spawn(
+                    async move { route_service.redeem(Request::new(redeem_request)).await },
+                )
+            })
+            .collect();
+
+        let mut redeemed = 0;
+        for handle in handles {
+            if handle.await.unwrap().is_ok() {
+                redeemed += 1;
+            }
+        }
+
+        assert_eq!(redeemed, 1);
+    }
+
+    #[tokio::test]
+    async fn given_client_session_when_reporting_spent_nonces_then_returns_permission_denied() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            Arc::new(RouteManager::new(
+                repository_type,
+                cancellation_token.child_token(),
+            )),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        let spend_report = SpendReport {
+            access_key,
+            nonces: Vec::new(),
+        };
+
+        let result = route_service.report_spent(Request::new(spend_report)).await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn given_spent_nonces_when_reporting_then_routes_are_redeemed() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let ticket_issuer = Arc::new(TicketIssuer::new(
+            crosscutting::crypto::Identity::generate(),
+            false,
+            true,
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            ticket_issuer.clone(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Proxy,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        let conversation_id = route_manager
+            .initialize(EXPECTED_UID, EXPECTED_TARGET, &[], &[], 0)
+            .await
+            .unwrap();
+
+        let nonce = route_manager
+            .store_route(
+                &conversation_id,
+                &get_connection_settings(),
+                &HashMap::new(),
+                false,
+            )
+            .await
+            .unwrap();
+
+        let hop = RouteResponse {
+            nonce: nonce.clone(),
+            ..Default::default()
+        };
+        let nonce_ticket = ticket_issuer.sign_nonce(&conversation_id, &hop).unwrap();
+        let nonce_ticket =
+            NonceTicket::decode(&nonce_ticket[crosscutting::crypto::SIGNATURE_LENGTH..]).unwrap();
+        let spend_report = SpendReport {
+            access_key: access_key.clone(),
+            nonces: vec![SpentNonce {
+                conversation_id: String::default(),
+                nonce: nonce.clone(),
+                conversation_ref: nonce_ticket.conversation_ref,
+            }],
+        };
+
+        let result = route_service.report_spent(Request::new(spend_report)).await;
+        assert!(result.is_ok());
+
+        let redeem_request = RedeemRequest {
+            access_key,
+            conversation_id,
+            nonce,
+        };
+
+        let result = route_service.redeem(Request::new(redeem_request)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn given_end_route_when_redeeming_then_returns_sender_identity_key() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_TARGET,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                EXPECTED_RECIPIENT_KEY,
+            )
+            .await;
+
+        let conversation_id = route_manager
+            .initialize(EXPECTED_UID, EXPECTED_TARGET, EXPECTED_SENDER_KEY, &[], 0)
+            .await
+            .unwrap();
+
+        let nonce = route_manager
+            .store_route(
+                &conversation_id,
+                &get_connection_settings(),
+                &HashMap::new(),
+                true,
+            )
+            .await
+            .unwrap();
+
+        let redeem_request = RedeemRequest {
+            access_key,
+            conversation_id,
+            nonce,
+        };
+
+        let request = Request::new(redeem_request);
+        let result = route_service.redeem(request).await;
+
+        assert!(result.is_ok());
+        let source_info = result.unwrap().into_inner().source_info.unwrap();
+        assert_eq!(source_info.from, EXPECTED_UID);
+        assert_eq!(source_info.identity_key, EXPECTED_SENDER_KEY);
+        assert!(source_info.reply_handle.is_empty());
+    }
+
+    #[tokio::test]
+    async fn given_anonymous_conversation_when_redeeming_end_route_then_returns_pseudonym_and_reply_handle()
+     {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+        let socket_address = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
+
+        let sender_access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+
+        let recipient_access_key = session_manager
+            .set_session(
+                EXPECTED_TARGET,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_RECIPIENT_KEY,
+            )
+            .await;
+
+        let init_request = InitRequest {
+            access_key: sender_access_key,
+            to: EXPECTED_TARGET.to_string(),
+            anonymous: true,
+            sender_key: EXPECTED_EPHEMERAL_KEY.to_vec(),
+            ..Default::default()
+        };
+        let conversation_id = route_service
+            .initialize(Request::new(init_request))
+            .await
+            .unwrap()
+            .into_inner()
+            .conversation_id;
+
+        let nonce = route_manager
+            .store_route(
+                &conversation_id,
+                &get_connection_settings(),
+                &HashMap::new(),
+                true,
+            )
+            .await
+            .unwrap();
+
+        let redeem_request = RedeemRequest {
+            access_key: recipient_access_key.clone(),
+            conversation_id,
+            nonce,
+        };
+        let source_info = route_service
+            .redeem(Request::new(redeem_request))
+            .await
+            .unwrap()
+            .into_inner()
+            .source_info
+            .unwrap();
+
+        assert_ne!(source_info.from, EXPECTED_UID);
+        assert!(source_info.from.starts_with("anon-"));
+        assert_eq!(source_info.identity_key, EXPECTED_EPHEMERAL_KEY);
+        assert!(!source_info.reply_handle.is_empty());
+
+        let reply_request = InitRequest {
+            access_key: recipient_access_key,
+            reply_handle: source_info.reply_handle,
+            ..Default::default()
+        };
+        let reply = route_service
+            .initialize(Request::new(reply_request))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(reply.recipient_key, EXPECTED_EPHEMERAL_KEY);
+        let conversation = route_manager
+            .get_conversation(&reply.conversation_id)
+            .await
+            .unwrap();
+        assert_eq!(conversation.from, EXPECTED_TARGET);
+        assert_eq!(conversation.to, EXPECTED_UID);
+        assert_eq!(conversation.recipient_key, EXPECTED_SENDER_KEY);
+    }
+
+    #[tokio::test]
+    async fn given_anonymous_conversation_without_sender_key_when_initializing_then_returns_error()
+    {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager,
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+
+        let init_request = InitRequest {
+            access_key,
+            to: EXPECTED_TARGET.to_string(),
+            anonymous: true,
+            ..Default::default()
+        };
+
+        let result = route_service.initialize(Request::new(init_request)).await;
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn given_unknown_reply_handle_when_initializing_conversation_then_returns_not_found() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager,
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+
+        let init_request = InitRequest {
+            access_key,
+            reply_handle: EXPECTED_NONCE.to_string(),
+            ..Default::default()
+        };
+
+        let result = route_service.initialize(Request::new(init_request)).await;
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    fn create_federation_manager(client: MockFederationClient) -> Arc<FederationManager> {
+        let settings = FederationSettings {
+            domain: Some("alpha".to_string()),
+            peers: vec![FederationPeer {
+                domain: "beta".to_string(),
+                connection_settings: get_connection_settings(),
+            }],
+            ca_certificate: Vec::new(),
+        };
+        Arc::new(FederationManager::with_client(settings, Box::new(client)))
+    }
+
+    fn get_handoff() -> Route {
+        Route {
+            on_ip_address: EXPECTED_IP.to_string(),
+            on_port_number: EXPECTED_PORT,
+            public_key: EXPECTED_PUBLIC_KEY.to_vec(),
+            domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            nonce: EXPECTED_NONCE.to_string(),
+            end_route: false,
+            tags: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn given_remote_recipient_when_initializing_conversation_then_opens_it_on_peer() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let mut federation_client = MockFederationClient::new();
+        federation_client
+            .expect_open()
+            .withf(|peer, request| {
+                peer.domain == "beta"
+                    && request.from == EXPECTED_UID
+                    && request.to == EXPECTED_TARGET
+                    && request.sender_key == EXPECTED_SENDER_KEY
+            })
+            .times(1)
+            .returning(|_, _| {
+                Ok(OpenResponse {
+                    recipient_key: EXPECTED_RECIPIENT_KEY.to_vec(),
+                    ticket: Some(RouteTicket {
+                        ip_address: EXPECTED_IP.to_string(),
+                        port_number: EXPECTED_PORT as u32,
+                        public_key: EXPECTED_PUBLIC_KEY.to_vec(),
+                        domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+                        nonce: EXPECTED_NONCE.to_string(),
+                    }),
+                })
+            });
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            create_federation_manager(federation_client),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+
+        let init_request = InitRequest {
+            access_key,
+            to: format!("{}@beta", EXPECTED_TARGET),
+            ..Default::default()
+        };
+
+        let response = route_service
+            .initialize(Request::new(init_request))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.recipient_key, EXPECTED_RECIPIENT_KEY);
+        let conversation = route_manager
+            .get_conversation(&response.conversation_id)
+            .await
+            .unwrap();
+        assert_eq!(conversation.handoff.unwrap().nonce, EXPECTED_NONCE);
+    }
+
+    #[tokio::test]
+    async fn given_failing_peer_when_initializing_conversation_then_returns_peer_error() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let mut federation_client = MockFederationClient::new();
+        federation_client
+            .expect_open()
+            .returning(|_, _| Err(Box::new(Status::not_found("Recipient not found"))));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager,
+            create_federation_manager(federation_client),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        let init_request = InitRequest {
+            access_key,
+            to: format!("{}@beta", EXPECTED_TARGET),
+            ..Default::default()
+        };
+
+        let result = route_service.initialize(Request::new(init_request)).await;
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn given_federated_conversation_when_reaching_final_route_then_returns_handoff() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Proxy,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        route_manager
+            .initialize_federated(
+                EXPECTED_CONVERSATION_ID,
+                EXPECTED_UID,
+                EXPECTED_TARGET,
+                &[],
+                &[],
+                Some(get_handoff()),
+            )
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            route_manager
+                .store_route(
+                    EXPECTED_CONVERSATION_ID,
+                    &get_connection_settings(),
+                    &HashMap::new(),
+                    false,
+                )
+                .await
+                .unwrap();
+        }
+
+        let route_request = RouteRequest {
+            access_key: access_key.clone(),
+            conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+        };
+
+        let response = route_service
+            .route(Request::new(route_request))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert!(!response.end_route);
+        assert_eq!(response.nonce, EXPECTED_NONCE);
+        assert_eq!(response.ip_address, EXPECTED_IP);
+        assert!(
+            route_manager
+                .get_conversation(EXPECTED_CONVERSATION_ID)
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_federated_conversation_when_requesting_circuit_then_returns_error() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        route_manager
+            .initialize_federated(
+                EXPECTED_CONVERSATION_ID,
+                EXPECTED_UID,
+                EXPECTED_TARGET,
+                &[],
+                &[],
+                Some(get_handoff()),
+            )
+            .await
+            .unwrap();
+
+        let route_request = RouteRequest {
+            access_key,
+            conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+        };
+
+        let result = route_service.circuit(Request::new(route_request)).await;
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn given_blocked_sender_when_initializing_conversation_then_recipient_key_is_withheld() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let contact_manager = Arc::new(ContactManager::new(repository_type));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            contact_manager.clone(),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+        let socket_address = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+
+        session_manager
+            .set_session(
+                EXPECTED_TARGET,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_RECIPIENT_KEY,
+            )
+            .await;
+
+        contact_manager
+            .update(EXPECTED_TARGET, ContactAction::Block, EXPECTED_UID)
+            .await;
+
+        let init_request = InitRequest {
+            access_key,
+            to: EXPECTED_TARGET.to_string(),
+            ..Default::default()
+        };
+
+        let result = route_service.initialize(Request::new(init_request)).await;
+
+        assert!(result.is_ok());
+        assert!(result.unwrap().into_inner().recipient_key.is_empty());
+    }
+
+    #[tokio::test]
+    async fn given_blocked_sender_when_building_circuit_then_returns_not_found() {
+        const EXPECTED_PROXY_UID: &str = "test_proxy";
+
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let contact_manager = Arc::new(ContactManager::new(repository_type));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            contact_manager.clone(),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+        let socket_address = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+
+        for (uid, component) in [
+            (EXPECTED_PROXY_UID, Component::Proxy),
+            (EXPECTED_TARGET, Component::Client),
+        ] {
+            session_manager
+                .set_session(
+                    uid,
+                    component,
+                    &socket_address,
+                    &get_connection_settings(),
+                    EXPECTED_RECIPIENT_KEY,
+                )
+                .await;
+        }
+
+        contact_manager
+            .update(EXPECTED_TARGET, ContactAction::Allow, "someone_else")
+            .await;
+
+        let conversation_id = route_manager
+            .initialize(
+                EXPECTED_UID,
+                EXPECTED_TARGET,
+                EXPECTED_SENDER_KEY,
+                EXPECTED_RECIPIENT_KEY,
+                0,
+            )
+            .await
+            .unwrap();
+
+        let route_request = RouteRequest {
+            access_key,
+            conversation_id,
+        };
+
+        let result = route_service.circuit(Request::new(route_request)).await;
+
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn given_contact_requests_when_updating_contact_then_validates_and_stores_them() {
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let contact_manager = Arc::new(ContactManager::new(repository_type));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager,
+            Arc::default(),
+            contact_manager.clone(),
+            Arc::new(GroupManager::new(repository_type)),
+            Arc::default(),
+        );
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+
+        let create_request = |action: route_proto::ContactAction, uid: &str| {
+            Request::new(ContactRequest {
+                access_key: access_key.clone(),
+                action: action as i32,
+                uid: uid.to_string(),
+            })
+        };
+
+        let result = route_service
+            .update_contact(create_request(
+                route_proto::ContactAction::Unknown,
+                EXPECTED_TARGET,
+            ))
+            .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+        let result = route_service
+            .update_contact(create_request(route_proto::ContactAction::Block, " "))
+            .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+        let result = route_service
+            .update_contact(create_request(
+                route_proto::ContactAction::Block,
+                EXPECTED_TARGET,
+            ))
+            .await;
+        assert!(result.is_ok());
+        assert!(
+            !contact_manager
+                .is_allowed(EXPECTED_TARGET, EXPECTED_UID)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn given_group_requests_when_updating_group_then_only_owner_manages_it() {
+        const EXPECTED_GROUP: &str = "test_group";
+
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let group_manager = Arc::new(GroupManager::new(repository_type));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            Arc::new(RouteManager::new(
+                repository_type,
+                cancellation_token.child_token(),
+            )),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            group_manager.clone(),
+            Arc::default(),
+        );
+        let socket_address = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
+
+        let owner_access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+        let other_access_key = session_manager
+            .set_session(
+                EXPECTED_TARGET,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_RECIPIENT_KEY,
+            )
+            .await;
+
+        let create_request = |access_key: &str, action: route_proto::GroupAction, uid: &str| {
+            Request::new(GroupRequest {
+                access_key: access_key.to_string(),
+                action: action as i32,
+                group: EXPECTED_GROUP.to_string(),
+                uid: uid.to_string(),
+            })
+        };
+
+        let result = route_service
+            .update_group(create_request(
+                &owner_access_key,
+                route_proto::GroupAction::Create,
+                "",
+            ))
+            .await;
+        assert!(result.is_ok());
+
+        let result = route_service
+            .update_group(create_request(
+                &other_access_key,
+                route_proto::GroupAction::Create,
+                "",
+            ))
+            .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::AlreadyExists);
+
+        let result = route_service
+            .update_group(create_request(
+                &other_access_key,
+                route_proto::GroupAction::Add,
+                EXPECTED_TARGET,
+            ))
+            .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::PermissionDenied);
+
+        let result = route_service
+            .update_group(create_request(
+                &owner_access_key,
+                route_proto::GroupAction::Unknown,
+                EXPECTED_TARGET,
+            ))
+            .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+        let result = route_service
+            .update_group(create_request(
+                &owner_access_key,
+                route_proto::GroupAction::Add,
+                EXPECTED_TARGET,
+            ))
+            .await;
+        assert!(result.is_ok());
+        assert!(
+            group_manager
+                .get_group(EXPECTED_GROUP)
+                .await
+                .unwrap()
+                .includes(EXPECTED_TARGET)
+        );
+    }
+
+    #[tokio::test]
+    async fn given_group_when_initializing_then_member_conversations_wait_for_fan_out_point() {
+        const EXPECTED_GROUP: &str = "test_group";
+
+        let cancellation_token = CancellationToken::new();
+        let repository_type = RepositoryType::InMemory;
+        let route_manager = Arc::new(RouteManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let session_manager = Arc::new(SessionManager::new(
+            repository_type,
+            cancellation_token.child_token(),
+        ));
+        let group_manager = Arc::new(GroupManager::new(repository_type));
+        let route_service = RouteServiceImpl::new(
+            session_manager.clone(),
+            route_manager.clone(),
+            Arc::default(),
+            Arc::new(ContactManager::new(repository_type)),
+            group_manager.clone(),
+            Arc::default(),
+        );
+        let socket_address = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
+
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_SENDER_KEY,
+            )
+            .await;
+        session_manager
+            .set_session(
+                EXPECTED_TARGET,
+                Component::Client,
+                &socket_address,
+                &get_connection_settings(),
+                EXPECTED_RECIPIENT_KEY,
+            )
+            .await;
+
+        let group = group_manager
+            .create_group(EXPECTED_GROUP, EXPECTED_UID)
+            .await
+            .unwrap();
+        let group = group_manager
+            .update(group, GroupAction::Add, EXPECTED_TARGET)
+            .await;
+        group_manager
+            .update(group, GroupAction::Add, "offline_member")
+            .await;
+
+        let init_request = GroupInitRequest {
+            access_key: access_key.clone(),
+            group: EXPECTED_GROUP.to_string(),
+        };
+        let response = route_service
+            .init_group(Request::new(init_request))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.recipients.len(), 1);
+        assert_eq!(response.recipients[0].recipient_key, EXPECTED_RECIPIENT_KEY);
+
+        let member_request = || {
+            Request::new(RouteRequest {
+                access_key: access_key.clone(),
+                conversation_id: response.recipients[0].conversation_id.clone(),
+            })
+        };
+
+        let result = route_service.route(member_request()).await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::FailedPrecondition);
+
+        route_manager.finalize(&response.conversation_id).await;
+
+        let result = route_service.route(member_request()).await;
+        assert!(result.unwrap().into_inner().end_route);
+    }
 }
@@ -1,12 +1,81 @@
-use crate::models::SessionInfo;
+use crate::models::{ProxyLoad, ProxyRole, SessionCount, SessionInfo};
 use crate::storage::{self, RepositoryType, SessionRepository};
-use crosscutting::{Component, ConnectionSettings};
+use crate::token::AccessKeySigner;
+use crosscutting::settings::environment;
+use crosscutting::{Component, ConnectionSettings, networking};
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tokio::net::TcpStream;
+use tokio::sync::RwLock;
+use tokio::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
+const DELIVERY_POLICY_KEY: &str = "SESSION_DELIVERY_POLICY";
+const ROUND_TRIP_TIMEOUT: Duration = Duration::from_secs(2);
+const ROUND_TRIP_INTERVAL: Duration = Duration::from_secs(30);
+const REVOCATION_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DeliveryPolicy {
+    #[default]
+    Latest,
+    Oldest,
+}
+
+impl FromStr for DeliveryPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "latest" => Ok(DeliveryPolicy::Latest),
+            "oldest" => Ok(DeliveryPolicy::Oldest),
+            _ => Err(format!("Invalid delivery policy: '{}'", value)),
+        }
+    }
+}
+
+impl DeliveryPolicy {
+    pub fn get_from_env() -> Result<Self, String> {
+        match environment::get_env_variable(DELIVERY_POLICY_KEY) {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(DeliveryPolicy::default()),
+        }
+    }
+
+    fn select(&self, sessions: Vec<SessionInfo>) -> Option<SessionInfo> {
+        let sessions = sessions.into_iter();
+        match self {
+            DeliveryPolicy::Latest => sessions.max_by_key(|session| session.created_at),
+            DeliveryPolicy::Oldest => sessions.min_by_key(|session| session.created_at),
+        }
+    }
+}
+
+/// What a valid access key tells about its session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionClaims {
+    pub uid: String,
+    pub component_type: Component,
+    pub identity_key: Vec<u8>,
+}
+
+/// The signed access keys revoked before they expire, as last read from
+/// storage.
+#[derive(Default)]
+struct RevocationList {
+    refreshed_at: Option<Instant>,
+    access_keys: HashSet<String>,
+}
+
 pub struct SessionManager {
     repository: Box<dyn SessionRepository>,
+    signer: Option<AccessKeySigner>,
+    delivery_policy: DeliveryPolicy,
+    revocations: RwLock<RevocationList>,
+    measured_at: Mutex<HashMap<String, Instant>>,
 }
 
 impl SessionManager {
@@ -14,6 +83,10 @@ impl SessionManager {
         Self {
             repository: storage::create_session_repository(repository_type, cancellation_token)
                 .unwrap(),
+            signer: AccessKeySigner::get_from_env().unwrap(),
+            delivery_policy: DeliveryPolicy::get_from_env().unwrap(),
+            revocations: RwLock::default(),
+            measured_at: Mutex::default(),
         }
     }
 
@@ -23,8 +96,9 @@ impl SessionManager {
         component_type: Component,
         client_ip: &SocketAddr,
         connection_settings: &ConnectionSettings,
+        identity_key: &[u8],
     ) -> String {
-        let access_key = Self::generate_access_key();
+        let access_key = self.generate_access_key(uid, &component_type, identity_key);
         let session_info = SessionInfo {
             access_key: access_key.clone(),
             uid: uid.to_string(),
@@ -34,42 +108,280 @@ impl SessionManager {
             on_port_number: connection_settings.port,
             public_key: connection_settings.certificate.clone(),
             domain_name: connection_settings.domain_name.clone(),
+            identity_key: identity_key.to_vec(),
+            created_at: chrono::Utc::now().timestamp_millis(),
+            rtt_micros: 0,
+            load: None,
+            tags: HashMap::new(),
+            roles: None,
         };
 
+        // Client keys outlive their sessions, so conversations can be sealed
+        // for a member without revealing whether they are online
+        if session_info.component_type == u8::from(Component::Client)
+            && !session_info.identity_key.is_empty()
+        {
+            self.repository
+                .set_identity_key(uid, &session_info.identity_key)
+                .await;
+        }
+
         self.repository.set_session(&session_info).await;
         access_key
     }
 
+    pub async fn get_identity_key(&self, uid: &str) -> Option<Vec<u8>> {
+        self.repository
+            .get_identity_key(uid)
+            .await
+            .filter(|identity_key| !identity_key.is_empty())
+    }
+
+    #[cfg(test)]
     pub async fn get_session(&self, access_key: &str) -> Option<SessionInfo> {
         self.repository.get_session(access_key).await
     }
 
+    /// Signed access keys are verified locally, checking their signature and
+    /// expiry, and against the keys revoked when their sessions were removed.
+    /// The revocation list is read from storage every few seconds, so keys
+    /// revoked by another controller keep working until then. A key whose
+    /// session just expired keeps working until the key itself expires.
+    pub async fn verify_access_key(&self, access_key: &str) -> Option<SessionClaims> {
+        match &self.signer {
+            Some(signer) => {
+                let claims = signer.verify(access_key).ok()?;
+                if self.is_revoked(access_key).await {
+                    return None;
+                }
+
+                Some(SessionClaims {
+                    identity_key: claims.get_identity_key(),
+                    uid: claims.uid,
+                    component_type: Component::from(claims.component_type),
+                })
+            }
+            None => self
+                .repository
+                .get_session(access_key)
+                .await
+                .map(|session_info| SessionClaims {
+                    uid: session_info.uid,
+                    component_type: Component::from(session_info.component_type),
+                    identity_key: session_info.identity_key,
+                }),
+        }
+    }
+
+    async fn is_revoked(&self, access_key: &str) -> bool {
+        {
+            let revocations = self.revocations.read().await;
+            if revocations
+                .refreshed_at
+                .is_some_and(|refreshed_at| refreshed_at.elapsed() < REVOCATION_REFRESH_INTERVAL)
+            {
+                return revocations.access_keys.contains(access_key);
+            }
+        }
+
+        let access_keys = self.repository.get_revoked_access_keys().await;
+        let mut revocations = self.revocations.write().await;
+        revocations.refreshed_at = Some(Instant::now());
+        revocations.access_keys = access_keys.into_iter().collect();
+        revocations.access_keys.contains(access_key)
+    }
+
+    /// Revokes signed access keys until they expire. They stop working here
+    /// at once, and on other controllers once they refresh their list.
+    async fn revoke_access_keys(&self, access_keys: Vec<String>) {
+        let Some(signer) = &self.signer else {
+            return;
+        };
+
+        for access_key in access_keys {
+            let Ok(claims) = signer.verify(&access_key) else {
+                continue;
+            };
+            self.repository
+                .revoke_access_key(&access_key, claims.expires_at)
+                .await;
+            self.revocations
+                .write()
+                .await
+                .access_keys
+                .insert(access_key);
+        }
+    }
+
     pub async fn remove_session(&self, access_key: &str) {
         self.repository.remove_session(access_key).await;
+        self.revoke_access_keys(vec![access_key.to_string()]).await;
+    }
+
+    pub async fn remove_sessions(&self, uid: &str) {
+        let access_keys = self.repository.remove_sessions(uid).await;
+        self.revoke_access_keys(access_keys).await;
+    }
+
+    pub async fn remove_other_sessions(&self, uid: &str, access_key: &str) {
+        let current_session = self.repository.get_session(access_key).await;
+        let access_keys = self.repository.remove_sessions(uid).await;
+
+        if let Some(session_info) = current_session.filter(|session| session.uid == uid) {
+            self.repository.set_session(&session_info).await;
+        }
+
+        let other_keys = access_keys
+            .into_iter()
+            .filter(|other_key| other_key != access_key)
+            .collect();
+        self.revoke_access_keys(other_keys).await;
+    }
+
+    /// Keeps a session alive while its component pings from the address it
+    /// logged in from, storing the load it reports.
+    pub async fn renew_session(
+        &self,
+        access_key: &str,
+        client_ip: &SocketAddr,
+        load: Option<ProxyLoad>,
+    ) -> Result<(), String> {
+        let mut session_info = self
+            .repository
+            .get_session(access_key)
+            .await
+            .ok_or_else(|| "Session not found".to_string())?;
+        if session_info.client_ip != *client_ip {
+            return Err(format!(
+                "Session IP mismatch: expected {}, got {}",
+                session_info.client_ip, client_ip
+            ));
+        }
+
+        if load.is_some() {
+            session_info.load = load;
+            self.repository.set_session(&session_info).await;
+        }
+
+        Ok(())
+    }
+
+    /// Stores the latest figures reported by a component, which expire
+    /// together with its session.
+    pub async fn set_metrics(&self, access_key: &str, rtt_micros: i64, load: Option<ProxyLoad>) {
+        if rtt_micros <= 0 && load.is_none() {
+            return;
+        }
+
+        if let Some(mut session_info) = self.repository.get_session(access_key).await {
+            if rtt_micros > 0 {
+                session_info.rtt_micros = rtt_micros;
+            }
+            if load.is_some() {
+                session_info.load = load;
+            }
+            self.repository.set_session(&session_info).await;
+        }
+    }
+
+    /// Measures the round trip to a proxy by connecting to the endpoint it
+    /// advertises, so routing doesn't rely on figures the proxy reports itself.
+    /// Every session is measured at most once per interval, however often it
+    /// pings.
+    pub async fn measure_round_trip(&self, access_key: &str) {
+        if !self.is_round_trip_due(access_key) {
+            return;
+        }
+
+        let Some(address) = self
+            .repository
+            .get_session(access_key)
+            .await
+            .and_then(|session| {
+                networking::to_socket_address(&session.on_ip_address, session.on_port_number).ok()
+            })
+        else {
+            return;
+        };
+
+        let started_at = Instant::now();
+        if let Ok(Ok(_)) =
+            tokio::time::timeout(ROUND_TRIP_TIMEOUT, TcpStream::connect(address)).await
+        {
+            let rtt_micros = started_at.elapsed().as_micros().max(1) as i64;
+            self.set_metrics(access_key, rtt_micros, None).await;
+        }
+    }
+
+    fn is_round_trip_due(&self, access_key: &str) -> bool {
+        let now = Instant::now();
+        let mut measured_at = self.measured_at.lock().unwrap();
+        measured_at.retain(|_, measured_at| now.duration_since(*measured_at) < ROUND_TRIP_INTERVAL);
+        if measured_at.contains_key(access_key) {
+            return false;
+        }
+
+        measured_at.insert(access_key.to_string(), now);
+        true
+    }
+
+    pub async fn set_proxy_profile(
+        &self,
+        access_key: &str,
+        tags: HashMap<String, String>,
+        roles: Option<Vec<ProxyRole>>,
+    ) {
+        if tags.is_empty() && roles.is_none() {
+            return;
+        }
+
+        if let Some(mut session_info) = self.repository.get_session(access_key).await {
+            session_info.tags = tags;
+            session_info.roles = roles;
+            self.repository.set_session(&session_info).await;
+        }
     }
 
     pub async fn get_proxies(&self, access_key: &str) -> Option<Vec<SessionInfo>> {
         self.repository.get_proxies(access_key).await
     }
 
-    pub async fn get_client(&self, uid: &str) -> Option<SessionInfo> {
-        self.repository.get_client(uid).await
+    pub async fn get_client(&self, uid: &str, identity_key: &[u8]) -> Option<SessionInfo> {
+        let sessions = self.repository.get_clients(uid).await;
+        if identity_key.is_empty() {
+            return self.delivery_policy.select(sessions);
+        }
+
+        sessions
+            .into_iter()
+            .filter(|session| session.identity_key == identity_key)
+            .max_by_key(|session| session.created_at)
     }
 
     pub async fn count_proxies(&self) -> usize {
         self.repository.count_proxies().await
     }
 
-    pub async fn count_clients(&self) -> usize {
+    pub async fn count_clients(&self) -> SessionCount {
         self.repository.count_clients().await
     }
 
-    pub async fn count_controllers(&self) -> usize {
-        self.repository.count_controllers().await
-    }
-
-    fn generate_access_key() -> String {
-        Uuid::new_v4().to_string()
+    fn generate_access_key(
+        &self,
+        uid: &str,
+        component_type: &Component,
+        identity_key: &[u8],
+    ) -> String {
+        let session_id = Uuid::new_v4().to_string();
+        match &self.signer {
+            Some(signer) => signer.issue(
+                uid,
+                u8::from(component_type.clone()),
+                &session_id,
+                identity_key,
+            ),
+            None => session_id,
+        }
     }
 }
 
@@ -78,7 +390,8 @@ mod tests {
 
     use super::*;
     use crate::storage::MockSessionRepository;
-    use crosscutting::networking;
+    use std::sync::Arc;
+    use std::time::Duration;
 
     const EXPECTED_UID: &str = "1234567890";
     const EXPECTED_IP: &str = "127.0.0.1";
@@ -86,10 +399,28 @@ mod tests {
     const EXPECTED_ACCESS_KEY: &str = "test_access_key";
     const EXPECTED_PUBLIC_KEY: &[u8] = b"test_public_key";
     const EXPECTED_DOMAIN_NAME: &str = "test_domain_name";
+    const EXPECTED_IDENTITY_KEY: &[u8] = b"test_identity_key";
 
     impl SessionManager {
         fn with_repository(repository: Box<dyn SessionRepository>) -> Self {
-            Self { repository }
+            Self {
+                repository,
+                signer: None,
+                delivery_policy: DeliveryPolicy::default(),
+                revocations: RwLock::default(),
+                measured_at: Mutex::default(),
+            }
+        }
+
+        fn with_signer(repository: Box<dyn SessionRepository>) -> Self {
+            let keys = vec![("k1".to_string(), b"test_secret".to_vec())];
+            Self {
+                repository,
+                signer: Some(AccessKeySigner::new(keys, Duration::from_secs(60)).unwrap()),
+                delivery_policy: DeliveryPolicy::default(),
+                revocations: RwLock::default(),
+                measured_at: Mutex::default(),
+            }
         }
     }
 
@@ -140,6 +471,7 @@ mod tests {
                 Component::Proxy,
                 &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
                 &get_connection_settings(),
+                &[],
             )
             .await;
 
@@ -157,6 +489,12 @@ mod tests {
             on_port_number: EXPECTED_PORT,
             public_key: EXPECTED_PUBLIC_KEY.to_vec(),
             domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            identity_key: EXPECTED_IDENTITY_KEY.to_vec(),
+            created_at: 0,
+            rtt_micros: 0,
+            load: None,
+            tags: HashMap::new(),
+            roles: None,
         };
 
         let ref_expected_session_info = expected_session_info.clone();
@@ -187,9 +525,91 @@ mod tests {
         session_manager.remove_session(EXPECTED_ACCESS_KEY).await;
     }
 
+    #[tokio::test]
+    async fn given_tags_and_roles_when_setting_proxy_profile_then_session_is_updated() {
+        let mut mock_repo = MockSessionRepository::new();
+        mock_repo
+            .expect_get_session()
+            .withf(|key| key == EXPECTED_ACCESS_KEY)
+            .returning(|_| Some(create_client_session(EXPECTED_ACCESS_KEY, &[], 0)));
+        mock_repo
+            .expect_set_session()
+            .withf(|session_info| {
+                session_info.tags.get("region") == Some(&"eu-west".to_string())
+                    && session_info.tags.get("operator") == Some(&"acme".to_string())
+                    && session_info.roles == Some(vec![ProxyRole::Entry])
+            })
+            .times(1)
+            .returning(|_| ());
+
+        let tags = HashMap::from([
+            ("region".to_string(), "eu-west".to_string()),
+            ("operator".to_string(), "acme".to_string()),
+        ]);
+        let session_manager = SessionManager::with_repository(Box::new(mock_repo));
+        session_manager
+            .set_proxy_profile(EXPECTED_ACCESS_KEY, tags, Some(vec![ProxyRole::Entry]))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn given_reachable_proxy_when_measuring_round_trip_then_session_is_updated() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let session_manager =
+            SessionManager::new(RepositoryType::InMemory, CancellationToken::new());
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Proxy,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &ConnectionSettings {
+                    ip: "127.0.0.1".to_string(),
+                    port,
+                    ..get_connection_settings()
+                },
+                &[],
+            )
+            .await;
+
+        session_manager.measure_round_trip(&access_key).await;
+
+        let session_info = session_manager.get_session(&access_key).await.unwrap();
+        assert!(session_info.rtt_micros > 0);
+    }
+
+    #[tokio::test]
+    async fn given_recently_measured_proxy_when_measuring_round_trip_then_it_is_skipped() {
+        let mut mock_repo = MockSessionRepository::new();
+        mock_repo
+            .expect_get_session()
+            .withf(|key| key == EXPECTED_ACCESS_KEY)
+            .times(1)
+            .returning(|_| None);
+
+        let session_manager = SessionManager::with_repository(Box::new(mock_repo));
+
+        session_manager
+            .measure_round_trip(EXPECTED_ACCESS_KEY)
+            .await;
+        session_manager
+            .measure_round_trip(EXPECTED_ACCESS_KEY)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn given_no_tags_nor_roles_when_setting_proxy_profile_then_storage_is_untouched() {
+        let mock_repo = MockSessionRepository::new();
+
+        let session_manager = SessionManager::with_repository(Box::new(mock_repo));
+        session_manager
+            .set_proxy_profile(EXPECTED_ACCESS_KEY, HashMap::new(), None)
+            .await;
+    }
+
     #[tokio::test]
     async fn get_proxies_returns_proxies() {
-        let expected_proxies = vec![
+        let expected_proxies = [
             SessionInfo {
                 access_key: "other key".to_string(),
                 uid: "other uid".to_string(),
@@ -199,6 +619,12 @@ mod tests {
                 on_port_number: EXPECTED_PORT,
                 public_key: EXPECTED_PUBLIC_KEY.to_vec(),
                 domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+                identity_key: EXPECTED_IDENTITY_KEY.to_vec(),
+                created_at: 0,
+                rtt_micros: 0,
+                load: None,
+                tags: HashMap::new(),
+                roles: None,
             },
             SessionInfo {
                 access_key: EXPECTED_ACCESS_KEY.to_string(),
@@ -209,6 +635,12 @@ mod tests {
                 on_port_number: EXPECTED_PORT,
                 public_key: EXPECTED_PUBLIC_KEY.to_vec(),
                 domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+                identity_key: EXPECTED_IDENTITY_KEY.to_vec(),
+                created_at: 0,
+                rtt_micros: 0,
+                load: None,
+                tags: HashMap::new(),
+                roles: None,
             },
         ];
 
@@ -252,21 +684,99 @@ mod tests {
             on_port_number: EXPECTED_PORT,
             public_key: EXPECTED_PUBLIC_KEY.to_vec(),
             domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            identity_key: EXPECTED_IDENTITY_KEY.to_vec(),
+            created_at: 0,
+            rtt_micros: 0,
+            load: None,
+            tags: HashMap::new(),
+            roles: None,
         };
 
         let ref_expected_client = expected_client.clone();
         let mut mock_repo = MockSessionRepository::new();
         mock_repo
-            .expect_get_client()
+            .expect_get_clients()
             .withf(|key| key == EXPECTED_UID)
-            .returning(move |_| Some(expected_client.clone()));
+            .returning(move |_| vec![expected_client.clone()]);
 
         let session_manager = SessionManager::with_repository(Box::new(mock_repo));
-        let client = session_manager.get_client(EXPECTED_UID).await.unwrap();
+        let client = session_manager.get_client(EXPECTED_UID, &[]).await.unwrap();
 
         assert_eq!(client, ref_expected_client);
     }
 
+    fn create_client_session(
+        access_key: &str,
+        identity_key: &[u8],
+        created_at: i64,
+    ) -> SessionInfo {
+        SessionInfo {
+            access_key: access_key.to_string(),
+            uid: EXPECTED_UID.to_string(),
+            client_ip: networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+            component_type: 1,
+            on_ip_address: EXPECTED_IP.to_string(),
+            on_port_number: EXPECTED_PORT,
+            public_key: EXPECTED_PUBLIC_KEY.to_vec(),
+            domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            identity_key: identity_key.to_vec(),
+            created_at,
+            rtt_micros: 0,
+            load: None,
+            tags: HashMap::new(),
+            roles: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn given_several_devices_when_getting_client_then_applies_delivery_policy() {
+        let mut mock_repo = MockSessionRepository::new();
+        mock_repo.expect_get_clients().returning(|_| {
+            vec![
+                create_client_session("laptop", b"laptop_key", 1),
+                create_client_session("phone", b"phone_key", 2),
+            ]
+        });
+
+        let mut session_manager = SessionManager::with_repository(Box::new(mock_repo));
+        let latest = session_manager.get_client(EXPECTED_UID, &[]).await.unwrap();
+        session_manager.delivery_policy = DeliveryPolicy::Oldest;
+        let oldest = session_manager.get_client(EXPECTED_UID, &[]).await.unwrap();
+
+        assert_eq!(latest.access_key, "phone");
+        assert_eq!(oldest.access_key, "laptop");
+    }
+
+    #[tokio::test]
+    async fn given_identity_key_when_getting_client_then_returns_matching_device() {
+        let mut mock_repo = MockSessionRepository::new();
+        mock_repo.expect_get_clients().returning(|_| {
+            vec![
+                create_client_session("laptop", b"laptop_key", 1),
+                create_client_session("phone", b"phone_key", 2),
+            ]
+        });
+
+        let session_manager = SessionManager::with_repository(Box::new(mock_repo));
+        let laptop = session_manager
+            .get_client(EXPECTED_UID, b"laptop_key")
+            .await
+            .unwrap();
+        let unknown = session_manager
+            .get_client(EXPECTED_UID, b"tablet_key")
+            .await;
+
+        assert_eq!(laptop.access_key, "laptop");
+        assert!(unknown.is_none());
+    }
+
+    #[test]
+    fn given_delivery_policy_setting_when_parsing_then_returns_policy() {
+        assert_eq!("latest".parse(), Ok(DeliveryPolicy::Latest));
+        assert_eq!(" Oldest ".parse(), Ok(DeliveryPolicy::Oldest));
+        assert!("everyone".parse::<DeliveryPolicy>().is_err());
+    }
+
     #[tokio::test]
     async fn count_proxies_returns_count() {
         let expected_count = 5;
@@ -284,7 +794,10 @@ mod tests {
 
     #[tokio::test]
     async fn count_clients_returns_count() {
-        let expected_count = 10;
+        let expected_count = SessionCount {
+            members: 10,
+            sessions: 12,
+        };
 
         let mut mock_repo = MockSessionRepository::new();
         mock_repo
@@ -298,17 +811,153 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn count_controllers_returns_count() {
-        let expected_count = 3;
+    async fn given_signed_mode_when_validating_access_key_then_session_is_not_looked_up() {
+        let mut mock_repo = MockSessionRepository::new();
+        mock_repo.expect_set_session().returning(|_| ());
+        mock_repo.expect_set_identity_key().returning(|_, _| ());
+        mock_repo.expect_get_session().never();
+        mock_repo
+            .expect_get_revoked_access_keys()
+            .times(1)
+            .returning(Vec::new);
 
+        let session_manager = SessionManager::with_signer(Box::new(mock_repo));
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                EXPECTED_IDENTITY_KEY,
+            )
+            .await;
+
+        for _ in 0..2 {
+            let claims = session_manager
+                .verify_access_key(&access_key)
+                .await
+                .unwrap();
+            assert_eq!(claims.uid, EXPECTED_UID);
+            assert_eq!(claims.component_type, Component::Client);
+            assert_eq!(claims.identity_key, EXPECTED_IDENTITY_KEY);
+        }
+    }
+
+    #[tokio::test]
+    async fn given_signed_mode_when_validating_forged_access_key_then_returns_none() {
+        let mut mock_repo = MockSessionRepository::new();
+        mock_repo.expect_get_revoked_access_keys().never();
+
+        let session_manager = SessionManager::with_signer(Box::new(mock_repo));
+
+        assert!(
+            session_manager
+                .verify_access_key("k1.eyJ1aWQiOiJ4In0.c2lnbmF0dXJl")
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_signed_mode_and_key_revoked_elsewhere_when_validating_access_key_then_returns_none()
+     {
+        let revoked_key = Arc::new(std::sync::Mutex::new(String::new()));
+        let stored_key = Arc::clone(&revoked_key);
         let mut mock_repo = MockSessionRepository::new();
+        mock_repo.expect_set_session().returning(|_| ());
         mock_repo
-            .expect_count_controllers()
-            .returning(move || expected_count);
+            .expect_get_revoked_access_keys()
+            .returning(move || vec![stored_key.lock().unwrap().clone()]);
+
+        let session_manager = SessionManager::with_signer(Box::new(mock_repo));
+        let access_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap(),
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+        *revoked_key.lock().unwrap() = access_key.clone();
+
+        assert!(
+            session_manager
+                .verify_access_key(&access_key)
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_signed_mode_when_removing_sessions_then_access_keys_stop_working() {
+        let repository =
+            storage::create_session_repository(RepositoryType::InMemory, CancellationToken::new())
+                .unwrap();
+        let session_manager = SessionManager::with_signer(repository);
+        let client_ip = networking::to_socket_address(EXPECTED_IP, EXPECTED_PORT).unwrap();
+        let current_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Client,
+                &client_ip,
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+        let other_key = session_manager
+            .set_session(
+                EXPECTED_UID,
+                Component::Proxy,
+                &client_ip,
+                &get_connection_settings(),
+                &[],
+            )
+            .await;
+
+        session_manager
+            .remove_other_sessions(EXPECTED_UID, &current_key)
+            .await;
+
+        assert!(
+            session_manager
+                .verify_access_key(&current_key)
+                .await
+                .is_some()
+        );
+        assert!(
+            session_manager
+                .verify_access_key(&other_key)
+                .await
+                .is_none()
+        );
+
+        session_manager.remove_session(&current_key).await;
+
+        assert!(
+            session_manager
+                .verify_access_key(&current_key)
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_opaque_mode_when_validating_access_key_then_storage_is_looked_up() {
+        let mut mock_repo = MockSessionRepository::new();
+        mock_repo
+            .expect_get_session()
+            .withf(|key| key == EXPECTED_ACCESS_KEY)
+            .times(1)
+            .returning(|_| None);
 
         let session_manager = SessionManager::with_repository(Box::new(mock_repo));
-        let count = session_manager.count_controllers().await;
 
-        assert_eq!(count, expected_count);
+        assert!(
+            session_manager
+                .verify_access_key(EXPECTED_ACCESS_KEY)
+                .await
+                .is_none()
+        );
     }
 }
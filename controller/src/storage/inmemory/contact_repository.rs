@@ -0,0 +1,30 @@
+use crate::models::ContactList;
+use crate::storage::ContactRepository;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+use tonic::async_trait;
+
+pub struct InMemoryContactRepository {
+    contacts: Arc<RwLock<HashMap<String, ContactList>>>,
+}
+
+impl InMemoryContactRepository {
+    pub fn new() -> Self {
+        Self {
+            contacts: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl ContactRepository for InMemoryContactRepository {
+    async fn get_contacts(&self, uid: &str) -> Option<ContactList> {
+        let contacts = self.contacts.read().await;
+        contacts.get(uid).cloned()
+    }
+
+    async fn set_contacts(&self, contacts: &ContactList) {
+        let mut collection = self.contacts.write().await;
+        collection.insert(contacts.uid.clone(), contacts.clone());
+    }
+}
@@ -0,0 +1,47 @@
+use super::ExpirationWrapper;
+use crate::models::ControllerInstance;
+use crate::storage::ControllerRepository;
+use std::time::Duration;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+use tonic::async_trait;
+
+type ControllersCollection = HashMap<String, ExpirationWrapper<ControllerInstance>>;
+
+pub struct InMemoryControllerRepository {
+    controllers: Arc<RwLock<ControllersCollection>>,
+}
+
+impl InMemoryControllerRepository {
+    pub fn new() -> Self {
+        Self {
+            controllers: Arc::new(RwLock::new(ControllersCollection::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl ControllerRepository for InMemoryControllerRepository {
+    async fn set_controller(&self, controller: &ControllerInstance, expiration_time: Duration) {
+        let mut controllers = self.controllers.write().await;
+        controllers.retain(|_, wrapper| !wrapper.is_expired());
+        controllers.insert(
+            controller.instance_id.clone(),
+            ExpirationWrapper::new(controller.clone(), expiration_time),
+        );
+    }
+
+    async fn remove_controller(&self, instance_id: &str) -> bool {
+        let mut controllers = self.controllers.write().await;
+        controllers.remove(instance_id).is_some()
+    }
+
+    async fn list_controllers(&self) -> Vec<ControllerInstance> {
+        let controllers = self.controllers.read().await;
+        controllers
+            .values()
+            .filter(|wrapper| !wrapper.is_expired())
+            .map(|wrapper| wrapper.value.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,35 @@
+use crate::models::EntryGuards;
+use crate::storage::EntryGuardRepository;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+use tonic::async_trait;
+
+pub struct InMemoryEntryGuardRepository {
+    guards: Arc<RwLock<HashMap<String, EntryGuards>>>,
+}
+
+impl InMemoryEntryGuardRepository {
+    pub fn new() -> Self {
+        Self {
+            guards: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl EntryGuardRepository for InMemoryEntryGuardRepository {
+    async fn get_guards(&self, uid: &str) -> Option<EntryGuards> {
+        let guards = self.guards.read().await;
+        guards.get(uid).cloned()
+    }
+
+    async fn replace_guards(&self, stored: Option<EntryGuards>, guards: &EntryGuards) -> bool {
+        let mut entries = self.guards.write().await;
+        if entries.get(&guards.uid) != stored.as_ref() {
+            return false;
+        }
+
+        entries.insert(guards.uid.clone(), guards.clone());
+        true
+    }
+}
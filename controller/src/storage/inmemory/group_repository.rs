@@ -0,0 +1,35 @@
+use crate::models::Group;
+use crate::storage::GroupRepository;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+use tonic::async_trait;
+
+pub struct InMemoryGroupRepository {
+    groups: Arc<RwLock<HashMap<String, Group>>>,
+}
+
+impl InMemoryGroupRepository {
+    pub fn new() -> Self {
+        Self {
+            groups: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl GroupRepository for InMemoryGroupRepository {
+    async fn get_group(&self, name: &str) -> Option<Group> {
+        let groups = self.groups.read().await;
+        groups.get(name).cloned()
+    }
+
+    async fn set_group(&self, group: &Group) {
+        let mut groups = self.groups.write().await;
+        groups.insert(group.name.clone(), group.clone());
+    }
+
+    async fn remove_group(&self, name: &str) -> bool {
+        let mut groups = self.groups.write().await;
+        groups.remove(name).is_some()
+    }
+}
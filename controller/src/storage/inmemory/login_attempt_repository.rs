@@ -0,0 +1,65 @@
+use super::ExpirationWrapper;
+use crate::storage::LoginAttemptRepository;
+use std::time::Duration;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use tonic::async_trait;
+
+type FailuresCollection = HashMap<String, ExpirationWrapper<u32>>;
+type LockoutsCollection = HashMap<String, ExpirationWrapper<()>>;
+
+pub struct InMemoryLoginAttemptRepository {
+    failures: Arc<RwLock<FailuresCollection>>,
+    lockouts: Arc<RwLock<LockoutsCollection>>,
+}
+
+impl InMemoryLoginAttemptRepository {
+    pub fn new() -> Self {
+        Self {
+            failures: Arc::new(RwLock::new(FailuresCollection::new())),
+            lockouts: Arc::new(RwLock::new(LockoutsCollection::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl LoginAttemptRepository for InMemoryLoginAttemptRepository {
+    async fn increment_failures(&self, key: &str, expiration_time: Duration) -> u32 {
+        let mut failures = self.failures.write().await;
+        failures.retain(|_, wrapper| !wrapper.is_expired());
+
+        let wrapper = failures
+            .entry(key.to_string())
+            .or_insert_with(|| ExpirationWrapper::new(0, expiration_time));
+        wrapper.value += 1;
+        wrapper.renew();
+        wrapper.value
+    }
+
+    async fn remove_failures(&self, key: &str) {
+        let mut failures = self.failures.write().await;
+        failures.remove(key);
+    }
+
+    async fn set_lockout(&self, key: &str, duration: Duration) {
+        let mut lockouts = self.lockouts.write().await;
+        lockouts.retain(|_, wrapper| !wrapper.is_expired());
+        lockouts.insert(key.to_string(), ExpirationWrapper::new((), duration));
+    }
+
+    async fn get_lockout(&self, key: &str) -> Option<Duration> {
+        let lockouts = self.lockouts.read().await;
+        lockouts
+            .get(key)
+            .filter(|wrapper| !wrapper.is_expired())
+            .map(|wrapper| wrapper.expires_at - Instant::now())
+    }
+
+    async fn remove_lockout(&self, key: &str) -> bool {
+        let mut lockouts = self.lockouts.write().await;
+        lockouts
+            .remove(key)
+            .is_some_and(|wrapper| !wrapper.is_expired())
+    }
+}
@@ -1,6 +1,7 @@
 use crate::models::Member;
 use crate::storage::MemberRepository;
-use std::{collections::HashMap, sync::Arc};
+use std::collections::{HashMap, hash_map::Entry};
+use std::sync::Arc;
 use tokio::sync::RwLock;
 use tonic::async_trait;
 
@@ -25,8 +26,34 @@ impl MemberRepository for InMemorMemberRepository {
         members.insert(member.uid.to_owned(), member.to_owned());
     }
 
+    async fn insert_member(&self, member: &Member) -> bool {
+        let mut members = self.members.write().await;
+        match members.entry(member.uid.to_owned()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(member.to_owned());
+                true
+            }
+        }
+    }
+
     async fn get_member(&self, uid: &str) -> Option<Member> {
         let members = self.members.read().await;
         members.get(uid).cloned()
     }
+
+    async fn remove_member(&self, uid: &str) -> bool {
+        let mut members = self.members.write().await;
+        members.remove(uid).is_some()
+    }
+
+    async fn list_members(&self, offset: usize, limit: usize) -> (Vec<Member>, usize) {
+        let members = self.members.read().await;
+        let mut page: Vec<Member> = members.values().cloned().collect();
+        page.sort_by(|a, b| a.uid.cmp(&b.uid));
+
+        let total = page.len();
+        let page = page.into_iter().skip(offset).take(limit).collect();
+        (page, total)
+    }
 }
@@ -1,5 +1,5 @@
 use super::ExpirationWrapper;
-use crate::models::{Conversation, Route};
+use crate::models::{Conversation, ReplyTarget, Route};
 use crate::storage::{self, RouteRepository};
 use log::debug;
 use tonic::async_trait;
@@ -11,12 +11,16 @@ use tokio_util::sync::CancellationToken;
 
 const EXPIRATION_TIME_CHECK: Duration = Duration::from_millis(30000);
 
-type RoutesCollection = HashMap<String, ExpirationWrapper<Route>>;
+/// Routes are keyed by conversation and nonce, so a nonce can only be redeemed
+/// within the conversation it was issued for.
+type RoutesCollection = HashMap<(String, String), ExpirationWrapper<Route>>;
 type ConversationsCollection = HashMap<String, ExpirationWrapper<Conversation>>;
+type ReplyHandlesCollection = HashMap<String, ExpirationWrapper<ReplyTarget>>;
 
 pub struct InMemoryRepository {
     routes: Arc<RwLock<RoutesCollection>>,
     conversations: Arc<RwLock<ConversationsCollection>>,
+    reply_handles: Arc<RwLock<ReplyHandlesCollection>>,
     _handle: tokio::task::JoinHandle<()>,
 }
 
@@ -24,17 +28,25 @@ impl InMemoryRepository {
     pub fn new(cancellation_token: CancellationToken) -> Self {
         let routes = Arc::new(RwLock::new(HashMap::new()));
         let conversations = Arc::new(RwLock::new(HashMap::new()));
+        let reply_handles = Arc::new(RwLock::new(HashMap::new()));
 
         Self {
             routes: Arc::clone(&routes),
             conversations: Arc::clone(&conversations),
-            _handle: Self::kill_expired_sessions(routes, conversations, cancellation_token),
+            reply_handles: Arc::clone(&reply_handles),
+            _handle: Self::kill_expired_sessions(
+                routes,
+                conversations,
+                reply_handles,
+                cancellation_token,
+            ),
         }
     }
 
     fn kill_expired_sessions(
         routes: Arc<RwLock<RoutesCollection>>,
         conversations: Arc<RwLock<ConversationsCollection>>,
+        reply_handles: Arc<RwLock<ReplyHandlesCollection>>,
         cancellation_token: CancellationToken,
     ) -> tokio::task::JoinHandle<()> {
         tokio::spawn(async move {
@@ -44,8 +56,10 @@ impl InMemoryRepository {
 
                 let mut routes = routes.write().await;
                 let mut conversations = conversations.write().await;
+                let mut reply_handles = reply_handles.write().await;
                 routes.retain(|_, wrapper| !wrapper.is_expired());
                 conversations.retain(|_, wrapper| !wrapper.is_expired());
+                reply_handles.retain(|_, wrapper| !wrapper.is_expired());
             }
 
             debug!("Expired routes and conversations check terminated.");
@@ -89,7 +103,7 @@ impl RouteRepository for InMemoryRepository {
 
         if routes
             .insert(
-                route.nonce.clone(),
+                (conversation_id.to_string(), route.nonce.clone()),
                 ExpirationWrapper::new(route.clone(), storage::ROUTES_EXPIRATION_TIME),
             )
             .is_some()
@@ -106,13 +120,78 @@ impl RouteRepository for InMemoryRepository {
             .and(Some(route.nonce.to_owned()))
     }
 
-    async fn get_route(&self, _: &str, nonce: &str) -> Option<Route> {
-        let routes = self.routes.read().await;
-        routes.get(nonce).map(|wrapper| wrapper.value.to_owned())
+    async fn take_route(&self, conversation_id: &str, nonce: &str) -> Option<Route> {
+        let mut routes = self.routes.write().await;
+        routes
+            .remove(&(conversation_id.to_string(), nonce.to_string()))
+            .filter(|wrapper| !wrapper.is_expired())
+            .map(|wrapper| wrapper.value)
     }
 
-    async fn remove_route(&self, nonce: &str) {
-        let mut routes = self.routes.write().await;
-        routes.remove(nonce);
+    async fn set_reply_handle(&self, reply_handle: &str, target: &ReplyTarget) {
+        let mut reply_handles = self.reply_handles.write().await;
+        reply_handles.insert(
+            reply_handle.to_string(),
+            ExpirationWrapper::new(target.to_owned(), storage::REPLY_HANDLES_EXPIRATION_TIME),
+        );
+    }
+
+    async fn get_reply_handle(&self, reply_handle: &str) -> Option<ReplyTarget> {
+        let reply_handles = self.reply_handles.read().await;
+        reply_handles
+            .get(reply_handle)
+            .filter(|wrapper| !wrapper.is_expired())
+            .map(|wrapper| wrapper.value.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Conversation;
+
+    const EXPECTED_CONVERSATION_ID: &str = "test_conversation";
+    const EXPECTED_NONCE: &str = "test_nonce";
+
+    #[tokio::test]
+    async fn given_route_when_taking_it_then_it_is_only_taken_once_within_its_conversation() {
+        let repository = InMemoryRepository::new(CancellationToken::new());
+        let conversation = Conversation::new(
+            EXPECTED_CONVERSATION_ID.to_string(),
+            "from".to_string(),
+            "to".to_string(),
+            1,
+            Vec::new(),
+        );
+        let route = Route {
+            on_ip_address: "127.0.0.1".to_string(),
+            on_port_number: 8080,
+            public_key: Vec::new(),
+            domain_name: "localhost".to_string(),
+            nonce: EXPECTED_NONCE.to_string(),
+            end_route: false,
+            tags: HashMap::new(),
+        };
+        repository.set_conversation(&conversation).await;
+        repository.set_route(EXPECTED_CONVERSATION_ID, &route).await;
+
+        assert!(
+            repository
+                .take_route("another_conversation", EXPECTED_NONCE)
+                .await
+                .is_none()
+        );
+        assert!(
+            repository
+                .take_route(EXPECTED_CONVERSATION_ID, EXPECTED_NONCE)
+                .await
+                .is_some()
+        );
+        assert!(
+            repository
+                .take_route(EXPECTED_CONVERSATION_ID, EXPECTED_NONCE)
+                .await
+                .is_none()
+        );
     }
 }
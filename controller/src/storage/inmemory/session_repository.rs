@@ -1,10 +1,13 @@
 use super::ExpirationWrapper;
-use crate::models::SessionInfo;
+use crate::models::{SessionCount, SessionInfo};
 use crate::storage;
 use crate::storage::SessionRepository;
 use crosscutting::Component;
 use log::debug;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use tokio::sync::RwLock;
 use tokio::time::Duration;
 use tokio_util::sync::CancellationToken;
@@ -13,15 +16,19 @@ use tonic::async_trait;
 const EXPIRATION_TIME_CHECK: Duration = Duration::from_millis(5000);
 
 type SessionsCollection = HashMap<String, ExpirationWrapper<SessionInfo>>;
-type ClientsCollection = HashMap<String, String>;
-type ControllersCollection = HashMap<String, String>;
-type ProxiesCollection = HashMap<String, String>;
+type ClientsCollection = HashMap<String, HashSet<String>>;
+type ControllersCollection = HashMap<String, HashSet<String>>;
+type ProxiesCollection = HashMap<String, HashSet<String>>;
+type IdentityKeysCollection = HashMap<String, Vec<u8>>;
+type RevokedAccessKeysCollection = HashMap<String, i64>;
 
 pub struct InMemoryRepository {
     sessions: Arc<RwLock<SessionsCollection>>,
     clients: Arc<RwLock<ClientsCollection>>,
     controllers: Arc<RwLock<ControllersCollection>>,
     proxies: Arc<RwLock<ProxiesCollection>>,
+    identity_keys: RwLock<IdentityKeysCollection>,
+    revoked_access_keys: RwLock<RevokedAccessKeysCollection>,
     _handle: tokio::task::JoinHandle<()>,
 }
 
@@ -37,6 +44,8 @@ impl InMemoryRepository {
             clients: Arc::clone(&clients),
             controllers: Arc::clone(&controllers),
             proxies: Arc::clone(&proxies),
+            identity_keys: RwLock::new(HashMap::new()),
+            revoked_access_keys: RwLock::new(HashMap::new()),
             _handle: Self::kill_expired_sessions(
                 Arc::clone(&sessions),
                 Arc::clone(&clients),
@@ -64,25 +73,22 @@ impl InMemoryRepository {
                 let mut controllers = controllers.write().await;
                 let mut proxies = proxies.write().await;
 
-                let expired_sessions: Vec<(String, String)> = sessions
+                let expired_sessions: Vec<String> = sessions
                     .iter()
                     .filter(|(_, wrapper)| wrapper.is_expired())
-                    .map(|(_, wrapper)| {
-                        (
-                            wrapper.value.access_key.to_owned(),
-                            wrapper.value.uid.to_owned(),
-                        )
-                    })
+                    .map(|(access_key, _)| access_key.to_owned())
                     .collect();
 
-                if !expired_sessions.is_empty() {
-                    expired_sessions.iter().for_each(|(access_key, uid)| {
-                        sessions.remove(access_key.as_str());
-                        clients.remove(uid.as_str());
-                        controllers.remove(uid.as_str());
-                        proxies.remove(uid.as_str());
-                    });
-                }
+                expired_sessions.iter().for_each(|access_key| {
+                    if let Some(wrapper) = sessions.remove(access_key.as_str()) {
+                        let members = match Component::from(wrapper.value.component_type) {
+                            Component::Client => &mut *clients,
+                            Component::Proxy => &mut *proxies,
+                            Component::Controller => &mut *controllers,
+                        };
+                        remove_member_session(members, &wrapper.value.uid, access_key);
+                    }
+                });
             }
 
             debug!("Expired sessions check terminated.");
@@ -98,15 +104,15 @@ impl SessionRepository for InMemoryRepository {
         match comonent_type {
             Component::Client => {
                 let mut clients = self.clients.write().await;
-                clients.insert(session_info.uid.clone(), session_info.access_key.clone());
+                add_member_session(&mut clients, session_info);
             }
             Component::Proxy => {
                 let mut proxies = self.proxies.write().await;
-                proxies.insert(session_info.uid.clone(), session_info.access_key.clone());
+                add_member_session(&mut proxies, session_info);
             }
             Component::Controller => {
                 let mut controllers = self.controllers.write().await;
-                controllers.insert(session_info.uid.clone(), session_info.access_key.clone());
+                add_member_session(&mut controllers, session_info);
             }
         }
 
@@ -129,18 +135,53 @@ impl SessionRepository for InMemoryRepository {
         let mut sessions = self.sessions.write().await;
 
         if let Some(wrapper) = sessions.remove(access_key) {
-            let mut clients = self.clients.write().await;
-            clients.remove(wrapper.value.uid.as_str());
+            let mut members = match Component::from(wrapper.value.component_type) {
+                Component::Client => self.clients.write().await,
+                Component::Proxy => self.proxies.write().await,
+                Component::Controller => self.controllers.write().await,
+            };
+            remove_member_session(&mut members, &wrapper.value.uid, access_key);
         }
     }
 
+    async fn remove_sessions(&self, uid: &str) -> Vec<String> {
+        let mut sessions = self.sessions.write().await;
+        let mut clients = self.clients.write().await;
+        let mut controllers = self.controllers.write().await;
+        let mut proxies = self.proxies.write().await;
+
+        let access_keys: Vec<String> = sessions
+            .values()
+            .filter(|wrapper| wrapper.value.uid == uid)
+            .map(|wrapper| wrapper.value.access_key.clone())
+            .collect();
+        sessions.retain(|_, wrapper| wrapper.value.uid != uid);
+        clients.remove(uid);
+        controllers.remove(uid);
+        proxies.remove(uid);
+        access_keys
+    }
+
+    async fn revoke_access_key(&self, access_key: &str, expires_at: i64) {
+        let mut revoked_access_keys = self.revoked_access_keys.write().await;
+        revoked_access_keys.insert(access_key.to_string(), expires_at);
+    }
+
+    async fn get_revoked_access_keys(&self) -> Vec<String> {
+        let now = chrono::Utc::now().timestamp();
+        let mut revoked_access_keys = self.revoked_access_keys.write().await;
+        revoked_access_keys.retain(|_, expires_at| *expires_at > now);
+        revoked_access_keys.keys().cloned().collect()
+    }
+
     async fn get_proxies(&self, access_key: &str) -> Option<Vec<SessionInfo>> {
         let proxies = self.proxies.read().await;
         let sessions = self.sessions.read().await;
 
         let result: Vec<SessionInfo> = proxies
-            .iter()
-            .filter_map(|(_, key)| {
+            .values()
+            .flatten()
+            .filter_map(|key| {
                 if *key == access_key {
                     None
                 } else {
@@ -156,16 +197,16 @@ impl SessionRepository for InMemoryRepository {
         Some(result)
     }
 
-    async fn get_client(&self, uid: &str) -> Option<SessionInfo> {
+    async fn get_clients(&self, uid: &str) -> Vec<SessionInfo> {
         let clients = self.clients.read().await;
-        if let Some(client) = clients.get(uid) {
-            let sessions = self.sessions.read().await;
-            if let Some(wrapper) = sessions.get(client) {
-                return Some(wrapper.value.clone());
-            }
-        }
+        let sessions = self.sessions.read().await;
 
-        None
+        clients
+            .get(uid)
+            .into_iter()
+            .flatten()
+            .filter_map(|key| sessions.get(key).map(|wrapper| wrapper.value.clone()))
+            .collect()
     }
 
     async fn count_proxies(&self) -> usize {
@@ -173,13 +214,41 @@ impl SessionRepository for InMemoryRepository {
         sessions.iter().count()
     }
 
-    async fn count_clients(&self) -> usize {
-        let sessions = self.clients.read().await;
-        sessions.iter().count()
+    async fn count_clients(&self) -> SessionCount {
+        let clients = self.clients.read().await;
+        SessionCount {
+            members: clients.len(),
+            sessions: clients.values().map(HashSet::len).sum(),
+        }
     }
 
-    async fn count_controllers(&self) -> usize {
-        let sessions = self.controllers.read().await;
-        sessions.iter().count()
+    async fn set_identity_key(&self, uid: &str, identity_key: &[u8]) {
+        let mut identity_keys = self.identity_keys.write().await;
+        identity_keys.insert(uid.to_string(), identity_key.to_vec());
+    }
+
+    async fn get_identity_key(&self, uid: &str) -> Option<Vec<u8>> {
+        let identity_keys = self.identity_keys.read().await;
+        identity_keys.get(uid).cloned()
+    }
+}
+
+fn add_member_session(members: &mut HashMap<String, HashSet<String>>, session_info: &SessionInfo) {
+    members
+        .entry(session_info.uid.clone())
+        .or_default()
+        .insert(session_info.access_key.clone());
+}
+
+fn remove_member_session(
+    members: &mut HashMap<String, HashSet<String>>,
+    uid: &str,
+    access_key: &str,
+) {
+    if let Some(access_keys) = members.get_mut(uid) {
+        access_keys.remove(access_key);
+        if access_keys.is_empty() {
+            members.remove(uid);
+        }
     }
 }
@@ -1,7 +1,10 @@
 mod inmemory;
 mod redis;
 
-use crate::models::{Conversation, Member, Route, SessionInfo};
+use crate::models::{
+    ContactList, ControllerInstance, Conversation, EntryGuards, Group, Member, ReplyTarget, Route,
+    SessionCount, SessionInfo,
+};
 use crosscutting::settings;
 use inmemory::route_repository as route_in_memory_repository;
 use inmemory::session_repository as session_in_memory_repository;
@@ -14,11 +17,18 @@ const REPOSITORY_TYPE_KEY: &str = "REPOSITORY";
 const REDIS_SESSION_REPO_ERROR: &str = "Failed to create the session's Redis repository";
 const REDIS_ROUTE_REPO_ERROR: &str = "Failed to create the route's Redis repository";
 const REDIS_MEMBER_REPO_ERROR: &str = "Failed to create the member's Redis repository";
+const REDIS_LOGIN_ATTEMPT_REPO_ERROR: &str =
+    "Failed to create the login attempt's Redis repository";
+const REDIS_CONTROLLER_REPO_ERROR: &str = "Failed to create the controller's Redis repository";
+const REDIS_CONTACT_REPO_ERROR: &str = "Failed to create the contact's Redis repository";
+const REDIS_GROUP_REPO_ERROR: &str = "Failed to create the group's Redis repository";
+const REDIS_ENTRY_GUARD_REPO_ERROR: &str = "Failed to create the entry guard's Redis repository";
 const REDIS_URL_KEY: &str = "REDIS_URL";
 
 const ROUTES_EXPIRATION_TIME: Duration = Duration::from_millis(60000);
 const CONVERSATIONS_EXPIRATION_TIME: Duration = Duration::from_millis(60000);
 const SESSIONS_EXPIRATION_TIME: Duration = Duration::from_millis(10000);
+const REPLY_HANDLES_EXPIRATION_TIME: Duration = Duration::from_secs(3600);
 
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
 pub enum RepositoryType {
@@ -52,11 +62,15 @@ pub trait SessionRepository: Sync + Send {
     async fn set_session(&self, session_info: &SessionInfo);
     async fn get_session(&self, access_key: &str) -> Option<SessionInfo>;
     async fn remove_session(&self, access_key: &str);
+    async fn remove_sessions(&self, uid: &str) -> Vec<String>;
+    async fn revoke_access_key(&self, access_key: &str, expires_at: i64);
+    async fn get_revoked_access_keys(&self) -> Vec<String>;
     async fn get_proxies(&self, access_key: &str) -> Option<Vec<SessionInfo>>;
-    async fn get_client(&self, uid: &str) -> Option<SessionInfo>;
+    async fn get_clients(&self, uid: &str) -> Vec<SessionInfo>;
     async fn count_proxies(&self) -> usize;
-    async fn count_clients(&self) -> usize;
-    async fn count_controllers(&self) -> usize;
+    async fn count_clients(&self) -> SessionCount;
+    async fn set_identity_key(&self, uid: &str, identity_key: &[u8]);
+    async fn get_identity_key(&self, uid: &str) -> Option<Vec<u8>>;
 }
 
 #[automock]
@@ -66,15 +80,59 @@ pub trait RouteRepository: Send + Sync {
     async fn remove_conversation(&self, conversation_id: &str);
     async fn get_conversation(&self, conversation_id: &str) -> Option<Conversation>;
     async fn set_route(&self, conversation_id: &str, route: &Route) -> Option<String>;
-    async fn get_route(&self, conversation_id: &str, nonce: &str) -> Option<Route>;
-    async fn remove_route(&self, nonce: &str);
+    async fn take_route(&self, conversation_id: &str, nonce: &str) -> Option<Route>;
+    async fn set_reply_handle(&self, reply_handle: &str, target: &ReplyTarget);
+    async fn get_reply_handle(&self, reply_handle: &str) -> Option<ReplyTarget>;
 }
 
 #[automock]
 #[async_trait]
 pub trait MemberRepository: Send + Sync {
     async fn set_member(&self, member: &Member);
+    async fn insert_member(&self, member: &Member) -> bool;
     async fn get_member(&self, uid: &str) -> Option<Member>;
+    async fn remove_member(&self, uid: &str) -> bool;
+    async fn list_members(&self, offset: usize, limit: usize) -> (Vec<Member>, usize);
+}
+
+#[automock]
+#[async_trait]
+pub trait LoginAttemptRepository: Send + Sync {
+    async fn increment_failures(&self, key: &str, expiration_time: Duration) -> u32;
+    async fn remove_failures(&self, key: &str);
+    async fn set_lockout(&self, key: &str, duration: Duration);
+    async fn get_lockout(&self, key: &str) -> Option<Duration>;
+    async fn remove_lockout(&self, key: &str) -> bool;
+}
+
+#[automock]
+#[async_trait]
+pub trait ControllerRepository: Send + Sync {
+    async fn set_controller(&self, controller: &ControllerInstance, expiration_time: Duration);
+    async fn remove_controller(&self, instance_id: &str) -> bool;
+    async fn list_controllers(&self) -> Vec<ControllerInstance>;
+}
+
+#[automock]
+#[async_trait]
+pub trait ContactRepository: Send + Sync {
+    async fn get_contacts(&self, uid: &str) -> Option<ContactList>;
+    async fn set_contacts(&self, contacts: &ContactList);
+}
+
+#[automock]
+#[async_trait]
+pub trait GroupRepository: Send + Sync {
+    async fn get_group(&self, name: &str) -> Option<Group>;
+    async fn set_group(&self, group: &Group);
+    async fn remove_group(&self, name: &str) -> bool;
+}
+
+#[automock]
+#[async_trait]
+pub trait EntryGuardRepository: Send + Sync {
+    async fn get_guards(&self, uid: &str) -> Option<EntryGuards>;
+    async fn replace_guards(&self, stored: Option<EntryGuards>, guards: &EntryGuards) -> bool;
 }
 
 pub fn create_session_repository(
@@ -136,6 +194,101 @@ pub fn create_member_repository(
     }
 }
 
+pub fn create_login_attempt_repository(
+    repo_type: RepositoryType,
+) -> Result<Box<dyn LoginAttemptRepository>, String> {
+    match repo_type {
+        RepositoryType::InMemory => Ok(Box::new(
+            inmemory::login_attempt_repository::InMemoryLoginAttemptRepository::new(),
+        )),
+        RepositoryType::Redis => {
+            let redis_url =
+                settings::environment::get_env_variable(REDIS_URL_KEY).unwrap_or_default();
+            let result = std::panic::catch_unwind(|| redis::RedisRepository::new(&redis_url));
+            match result {
+                Ok(redis_repo) => Ok(Box::new(redis_repo)),
+                Err(_) => Err(String::from(REDIS_LOGIN_ATTEMPT_REPO_ERROR)),
+            }
+        }
+    }
+}
+
+pub fn create_controller_repository(
+    repo_type: RepositoryType,
+) -> Result<Box<dyn ControllerRepository>, String> {
+    match repo_type {
+        RepositoryType::InMemory => Ok(Box::new(
+            inmemory::controller_repository::InMemoryControllerRepository::new(),
+        )),
+        RepositoryType::Redis => {
+            let redis_url =
+                settings::environment::get_env_variable(REDIS_URL_KEY).unwrap_or_default();
+            let result = std::panic::catch_unwind(|| redis::RedisRepository::new(&redis_url));
+            match result {
+                Ok(redis_repo) => Ok(Box::new(redis_repo)),
+                Err(_) => Err(String::from(REDIS_CONTROLLER_REPO_ERROR)),
+            }
+        }
+    }
+}
+
+pub fn create_contact_repository(
+    repo_type: RepositoryType,
+) -> Result<Box<dyn ContactRepository>, String> {
+    match repo_type {
+        RepositoryType::InMemory => Ok(Box::new(
+            inmemory::contact_repository::InMemoryContactRepository::new(),
+        )),
+        RepositoryType::Redis => {
+            let redis_url =
+                settings::environment::get_env_variable(REDIS_URL_KEY).unwrap_or_default();
+            let result = std::panic::catch_unwind(|| redis::RedisRepository::new(&redis_url));
+            match result {
+                Ok(redis_repo) => Ok(Box::new(redis_repo)),
+                Err(_) => Err(String::from(REDIS_CONTACT_REPO_ERROR)),
+            }
+        }
+    }
+}
+
+pub fn create_group_repository(
+    repo_type: RepositoryType,
+) -> Result<Box<dyn GroupRepository>, String> {
+    match repo_type {
+        RepositoryType::InMemory => Ok(Box::new(
+            inmemory::group_repository::InMemoryGroupRepository::new(),
+        )),
+        RepositoryType::Redis => {
+            let redis_url =
+                settings::environment::get_env_variable(REDIS_URL_KEY).unwrap_or_default();
+            let result = std::panic::catch_unwind(|| redis::RedisRepository::new(&redis_url));
+            match result {
+                Ok(redis_repo) => Ok(Box::new(redis_repo)),
+                Err(_) => Err(String::from(REDIS_GROUP_REPO_ERROR)),
+            }
+        }
+    }
+}
+
+pub fn create_entry_guard_repository(
+    repo_type: RepositoryType,
+) -> Result<Box<dyn EntryGuardRepository>, String> {
+    match repo_type {
+        RepositoryType::InMemory => Ok(Box::new(
+            inmemory::entry_guard_repository::InMemoryEntryGuardRepository::new(),
+        )),
+        RepositoryType::Redis => {
+            let redis_url =
+                settings::environment::get_env_variable(REDIS_URL_KEY).unwrap_or_default();
+            let result = std::panic::catch_unwind(|| redis::RedisRepository::new(&redis_url));
+            match result {
+                Ok(redis_repo) => Ok(Box::new(redis_repo)),
+                Err(_) => Err(String::from(REDIS_ENTRY_GUARD_REPO_ERROR)),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -207,4 +360,64 @@ mod tests {
         let redis_repo = create_member_repository(RepositoryType::Redis);
         assert!(redis_repo.is_err());
     }
+
+    #[tokio::test]
+    async fn create_in_memory_login_attempt_repository() {
+        let in_memory_repo = create_login_attempt_repository(RepositoryType::InMemory);
+        assert!(in_memory_repo.is_ok());
+    }
+
+    #[tokio::test]
+    async fn create_redis_login_attempt_repository() {
+        let redis_repo = create_login_attempt_repository(RepositoryType::Redis);
+        assert!(redis_repo.is_err());
+    }
+
+    #[tokio::test]
+    async fn create_in_memory_controller_repository() {
+        let in_memory_repo = create_controller_repository(RepositoryType::InMemory);
+        assert!(in_memory_repo.is_ok());
+    }
+
+    #[tokio::test]
+    async fn create_redis_controller_repository() {
+        let redis_repo = create_controller_repository(RepositoryType::Redis);
+        assert!(redis_repo.is_err());
+    }
+
+    #[tokio::test]
+    async fn create_in_memory_contact_repository() {
+        let in_memory_repo = create_contact_repository(RepositoryType::InMemory);
+        assert!(in_memory_repo.is_ok());
+    }
+
+    #[tokio::test]
+    async fn create_redis_contact_repository() {
+        let redis_repo = create_contact_repository(RepositoryType::Redis);
+        assert!(redis_repo.is_err());
+    }
+
+    #[tokio::test]
+    async fn create_in_memory_group_repository() {
+        let in_memory_repo = create_group_repository(RepositoryType::InMemory);
+        assert!(in_memory_repo.is_ok());
+    }
+
+    #[tokio::test]
+    async fn create_redis_group_repository() {
+        let redis_repo = create_group_repository(RepositoryType::Redis);
+        assert!(redis_repo.is_err());
+    }
+
+    #[tokio::test]
+    async fn create_in_memory_entry_guard_repository() {
+        let in_memory_repo = create_entry_guard_repository(RepositoryType::InMemory);
+        assert!(in_memory_repo.is_ok());
+    }
+
+    #[tokio::test]
+    async fn create_redis_entry_guard_repository() {
+        let redis_repo = create_entry_guard_repository(RepositoryType::Redis);
+        assert!(redis_repo.is_err());
+    }
 }
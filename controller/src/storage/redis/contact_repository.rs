@@ -0,0 +1,44 @@
+use super::RedisRepository;
+use crate::models::ContactList;
+use crate::storage::ContactRepository;
+use redis::{Commands, FromRedisValue, ToRedisArgs, Value, from_redis_value};
+use tonic::async_trait;
+
+const CONTACTS_KEY: &str = "ct";
+
+fn get_contacts_key(uid: &str) -> String {
+    format!("{}:{}", CONTACTS_KEY, uid)
+}
+
+#[async_trait]
+impl ContactRepository for RedisRepository {
+    async fn get_contacts(&self, uid: &str) -> Option<ContactList> {
+        let mut connection = self.connection.write().await;
+        connection.get(get_contacts_key(uid)).ok()
+    }
+
+    async fn set_contacts(&self, contacts: &ContactList) {
+        let mut connection = self.connection.write().await;
+        () = connection
+            .set(get_contacts_key(&contacts.uid), contacts)
+            .unwrap();
+    }
+}
+
+impl ToRedisArgs for ContactList {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        let json = serde_json::to_string(self).unwrap();
+        out.write_arg(&json.into_bytes());
+    }
+}
+
+impl FromRedisValue for ContactList {
+    fn from_redis_value(v: &Value) -> redis::RedisResult<Self> {
+        let value: String = from_redis_value(v)?;
+        let contacts: ContactList = serde_json::from_str(&value).unwrap();
+        Ok(contacts)
+    }
+}
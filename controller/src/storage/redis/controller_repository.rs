@@ -0,0 +1,60 @@
+use super::RedisRepository;
+use crate::models::ControllerInstance;
+use crate::storage::ControllerRepository;
+use redis::{Commands, FromRedisValue, ToRedisArgs, Value, from_redis_value};
+use std::time::Duration;
+use tonic::async_trait;
+
+const CONTROLLERS_KEY: &str = "ctrl_reg";
+
+fn get_controller_key(instance_id: &str) -> String {
+    format!("{}:{}", CONTROLLERS_KEY, instance_id)
+}
+
+#[async_trait]
+impl ControllerRepository for RedisRepository {
+    async fn set_controller(&self, controller: &ControllerInstance, expiration_time: Duration) {
+        let mut connection = self.connection.write().await;
+        () = connection
+            .set_ex(
+                get_controller_key(&controller.instance_id),
+                controller,
+                expiration_time.as_secs().max(1),
+            )
+            .unwrap();
+    }
+
+    async fn remove_controller(&self, instance_id: &str) -> bool {
+        let mut connection = self.connection.write().await;
+        let removed: usize = connection.del(get_controller_key(instance_id)).unwrap();
+        removed > 0
+    }
+
+    async fn list_controllers(&self) -> Vec<ControllerInstance> {
+        let mut connection = self.connection.write().await;
+        let key = get_controller_key("*");
+        let keys: Vec<String> = connection.scan_match(&key).unwrap().collect();
+
+        keys.iter()
+            .filter_map(|key| connection.get::<_, ControllerInstance>(key).ok())
+            .collect()
+    }
+}
+
+impl ToRedisArgs for ControllerInstance {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        let json = serde_json::to_string(self).unwrap();
+        out.write_arg(&json.into_bytes());
+    }
+}
+
+impl FromRedisValue for ControllerInstance {
+    fn from_redis_value(v: &Value) -> redis::RedisResult<Self> {
+        let value: String = from_redis_value(v)?;
+        let controller: ControllerInstance = serde_json::from_str(&value).unwrap();
+        Ok(controller)
+    }
+}
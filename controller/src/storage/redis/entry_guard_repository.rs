@@ -0,0 +1,101 @@
+use super::RedisRepository;
+use crate::models::EntryGuards;
+use crate::storage::EntryGuardRepository;
+use redis::{Commands, FromRedisValue, ToRedisArgs, Value, from_redis_value};
+use tonic::async_trait;
+
+const ENTRY_GUARDS_KEY: &str = "eg";
+
+/// Stores the guards only while the stored ones are still those which were
+/// read, so concurrent selections for the same member can't overwrite each
+/// other.
+const REPLACE_GUARDS_SCRIPT: &str = r#"
+local stored = redis.call('GET', KEYS[1])
+if (stored or '') ~= ARGV[1] then
+    return 0
+end
+redis.call('SET', KEYS[1], ARGV[2])
+return 1
+"#;
+
+fn get_guards_key(uid: &str) -> String {
+    format!("{}:{}", ENTRY_GUARDS_KEY, uid)
+}
+
+#[async_trait]
+impl EntryGuardRepository for RedisRepository {
+    async fn get_guards(&self, uid: &str) -> Option<EntryGuards> {
+        let mut connection = self.connection.write().await;
+        connection.get(get_guards_key(uid)).ok()
+    }
+
+    async fn replace_guards(&self, stored: Option<EntryGuards>, guards: &EntryGuards) -> bool {
+        let mut connection = self.connection.write().await;
+        let stored = stored
+            .map(|stored| serde_json::to_string(&stored).unwrap())
+            .unwrap_or_default();
+        redis::Script::new(REPLACE_GUARDS_SCRIPT)
+            .key(get_guards_key(&guards.uid))
+            .arg(stored)
+            .arg(guards)
+            .invoke::<bool>(&mut *connection)
+            .unwrap_or_default()
+    }
+}
+
+impl ToRedisArgs for EntryGuards {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        let json = serde_json::to_string(self).unwrap();
+        out.write_arg(&json.into_bytes());
+    }
+}
+
+impl FromRedisValue for EntryGuards {
+    fn from_redis_value(v: &Value) -> redis::RedisResult<Self> {
+        let value: String = from_redis_value(v)?;
+        let guards: EntryGuards = serde_json::from_str(&value).unwrap();
+        Ok(guards)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EntryGuard;
+    use crosscutting::settings::environment;
+    use uuid::Uuid;
+
+    const REDIS_TEST_URL_KEY: &str = "REDIS_TEST_URL";
+
+    fn create_guards(uid: &str, proxy_uid: &str) -> EntryGuards {
+        EntryGuards {
+            uid: uid.to_string(),
+            guards: vec![EntryGuard {
+                proxy_uid: proxy_uid.to_string(),
+                pinned_at: 0,
+            }],
+        }
+    }
+
+    /// Runs against the server in `REDIS_TEST_URL` and is skipped without one.
+    #[tokio::test]
+    async fn given_redis_when_replacing_stale_guards_then_they_are_kept() {
+        let Ok(uri) = environment::get_env_variable(REDIS_TEST_URL_KEY) else {
+            return;
+        };
+
+        let repository = RedisRepository::new(&uri);
+        let uid = Uuid::new_v4().to_string();
+        let first = create_guards(&uid, "a");
+        let second = create_guards(&uid, "b");
+
+        assert!(repository.replace_guards(None, &first).await);
+        assert!(!repository.replace_guards(None, &second).await);
+        assert_eq!(repository.get_guards(&uid).await, Some(first.clone()));
+        assert!(repository.replace_guards(Some(first), &second).await);
+        assert_eq!(repository.get_guards(&uid).await, Some(second));
+    }
+}
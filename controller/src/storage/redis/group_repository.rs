@@ -0,0 +1,48 @@
+use super::RedisRepository;
+use crate::models::Group;
+use crate::storage::GroupRepository;
+use redis::{Commands, FromRedisValue, ToRedisArgs, Value, from_redis_value};
+use tonic::async_trait;
+
+const GROUPS_KEY: &str = "gr";
+
+fn get_group_key(name: &str) -> String {
+    format!("{}:{}", GROUPS_KEY, name)
+}
+
+#[async_trait]
+impl GroupRepository for RedisRepository {
+    async fn get_group(&self, name: &str) -> Option<Group> {
+        let mut connection = self.connection.write().await;
+        connection.get(get_group_key(name)).ok()
+    }
+
+    async fn set_group(&self, group: &Group) {
+        let mut connection = self.connection.write().await;
+        () = connection.set(get_group_key(&group.name), group).unwrap();
+    }
+
+    async fn remove_group(&self, name: &str) -> bool {
+        let mut connection = self.connection.write().await;
+        let removed: usize = connection.del(get_group_key(name)).unwrap_or_default();
+        removed > 0
+    }
+}
+
+impl ToRedisArgs for Group {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        let json = serde_json::to_string(self).unwrap();
+        out.write_arg(&json.into_bytes());
+    }
+}
+
+impl FromRedisValue for Group {
+    fn from_redis_value(v: &Value) -> redis::RedisResult<Self> {
+        let value: String = from_redis_value(v)?;
+        let group: Group = serde_json::from_str(&value).unwrap();
+        Ok(group)
+    }
+}
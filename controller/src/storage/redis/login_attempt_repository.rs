@@ -0,0 +1,60 @@
+use super::RedisRepository;
+use crate::storage::LoginAttemptRepository;
+use redis::Commands;
+use std::time::Duration;
+use tonic::async_trait;
+
+const FAILURES_KEY: &str = "la_f";
+const LOCKOUT_KEY: &str = "la_l";
+
+fn get_failures_key(key: &str) -> String {
+    format!("{}:{}", FAILURES_KEY, key)
+}
+
+fn get_lockout_key(key: &str) -> String {
+    format!("{}:{}", LOCKOUT_KEY, key)
+}
+
+#[async_trait]
+impl LoginAttemptRepository for RedisRepository {
+    async fn increment_failures(&self, key: &str, expiration_time: Duration) -> u32 {
+        let mut connection = self.connection.write().await;
+        let key = get_failures_key(key);
+        let (failures,): (u32,) = redis::pipe()
+            .atomic()
+            .incr(&key, 1)
+            .pexpire(&key, expiration_time.as_millis() as i64)
+            .ignore()
+            .query(&mut connection)
+            .unwrap();
+        failures
+    }
+
+    async fn remove_failures(&self, key: &str) {
+        let mut connection = self.connection.write().await;
+        () = connection.del(get_failures_key(key)).unwrap();
+    }
+
+    async fn set_lockout(&self, key: &str, duration: Duration) {
+        let mut connection = self.connection.write().await;
+        () = connection
+            .pset_ex(
+                get_lockout_key(key),
+                true,
+                duration.as_millis().max(1) as u64,
+            )
+            .unwrap();
+    }
+
+    async fn get_lockout(&self, key: &str) -> Option<Duration> {
+        let mut connection = self.connection.write().await;
+        let remaining: i64 = connection.pttl(get_lockout_key(key)).unwrap_or_default();
+        (remaining > 0).then(|| Duration::from_millis(remaining as u64))
+    }
+
+    async fn remove_lockout(&self, key: &str) -> bool {
+        let mut connection = self.connection.write().await;
+        let removed: usize = connection.del(get_lockout_key(key)).unwrap_or_default();
+        removed > 0
+    }
+}
@@ -1,7 +1,7 @@
 use super::RedisRepository;
 use crate::models::Member;
 use crate::storage::MemberRepository;
-use redis::{Commands, FromRedisValue, ToRedisArgs, Value, from_redis_value};
+use redis::{Commands, ErrorKind, FromRedisValue, ToRedisArgs, Value, from_redis_value};
 use tonic::async_trait;
 
 const MEMBER_KEY: &str = "mb";
@@ -15,10 +15,42 @@ impl MemberRepository for RedisRepository {
             .unwrap();
     }
 
+    async fn insert_member(&self, member: &Member) -> bool {
+        let mut connection = self.connection.write().await;
+        connection
+            .hset_nx(MEMBER_KEY, member.uid.to_owned(), member)
+            .unwrap_or_default()
+    }
+
     async fn get_member(&self, uid: &str) -> Option<Member> {
         let mut connection = self.connection.write().await;
         connection.hget(MEMBER_KEY, uid).ok()
     }
+
+    async fn remove_member(&self, uid: &str) -> bool {
+        let mut connection = self.connection.write().await;
+        let removed: usize = connection.hdel(MEMBER_KEY, uid).unwrap_or_default();
+        removed > 0
+    }
+
+    async fn list_members(&self, offset: usize, limit: usize) -> (Vec<Member>, usize) {
+        let mut connection = self.connection.write().await;
+        let mut uids: Vec<String> = connection.hkeys(MEMBER_KEY).unwrap_or_default();
+        uids.sort();
+
+        let total = uids.len();
+        let page: Vec<String> = uids.into_iter().skip(offset).take(limit).collect();
+        if page.is_empty() {
+            return (Vec::new(), total);
+        }
+
+        let members: Vec<Member> = redis::cmd("HMGET")
+            .arg(MEMBER_KEY)
+            .arg(page)
+            .query(&mut *connection)
+            .unwrap_or_default();
+        (members, total)
+    }
 }
 
 impl ToRedisArgs for Member {
@@ -34,7 +66,7 @@ impl ToRedisArgs for Member {
 impl FromRedisValue for Member {
     fn from_redis_value(v: &Value) -> redis::RedisResult<Self> {
         let value: String = from_redis_value(v)?;
-        let member: Member = serde_json::from_str(&value).unwrap();
-        Ok(member)
+        serde_json::from_str(&value)
+            .map_err(|e| (ErrorKind::TypeError, "Invalid member", e.to_string()).into())
     }
 }
@@ -1,3 +1,8 @@
+pub mod contact_repository;
+pub mod controller_repository;
+pub mod entry_guard_repository;
+pub mod group_repository;
+pub mod login_attempt_repository;
 pub mod member_repository;
 pub mod route_repository;
 pub mod session_repository;
@@ -1,12 +1,23 @@
-use crate::models::{Conversation, Route};
+use crate::models::{Conversation, ReplyTarget, Route};
 use crate::storage::{self, RouteRepository};
-use redis::{Commands, FromRedisValue, ToRedisArgs, Value, from_redis_value};
+use redis::{Commands, ErrorKind, FromRedisValue, ToRedisArgs, Value, from_redis_value};
 use tonic::async_trait;
 
 use super::RedisRepository;
 
 const CONVERSATIONS_KEY: &str = "cs";
 const ROUTES_KEY: &str = "rs";
+const REPLY_HANDLES_KEY: &str = "rh";
+
+/// Reads and deletes a route in one step, so concurrent redeems of the same
+/// nonce can't both get it.
+const TAKE_ROUTE_SCRIPT: &str = r#"
+local route = redis.call('HGET', KEYS[1], ARGV[1])
+if route then
+    redis.call('HDEL', KEYS[1], ARGV[1])
+end
+return route
+"#;
 
 fn get_conversation_key(conversation_id: &str) -> String {
     format!("{}:{}", CONVERSATIONS_KEY, conversation_id)
@@ -16,6 +27,10 @@ fn get_routes_key(conversation_id: &str) -> String {
     format!("{}:{}", ROUTES_KEY, conversation_id)
 }
 
+fn get_reply_handle_key(reply_handle: &str) -> String {
+    format!("{}:{}", REPLY_HANDLES_KEY, reply_handle)
+}
+
 #[async_trait]
 impl RouteRepository for RedisRepository {
     async fn set_conversation(&self, conversation: &Conversation) -> Option<String> {
@@ -64,16 +79,33 @@ impl RouteRepository for RedisRepository {
         Some(route.nonce.to_owned())
     }
 
-    async fn get_route(&self, conversation_id: &str, nonce: &str) -> Option<Route> {
+    async fn take_route(&self, conversation_id: &str, nonce: &str) -> Option<Route> {
         let mut connection = self.connection.write().await;
         let key = get_routes_key(conversation_id);
-        connection.hget(key, nonce).ok()
+        redis::Script::new(TAKE_ROUTE_SCRIPT)
+            .key(key)
+            .arg(nonce)
+            .invoke::<Option<Route>>(&mut *connection)
+            .ok()
+            .flatten()
     }
 
-    async fn remove_route(&self, nonce: &str) {
+    async fn set_reply_handle(&self, reply_handle: &str, target: &ReplyTarget) {
         let mut connection = self.connection.write().await;
-        let key = get_routes_key(nonce);
-        () = connection.hdel(key, nonce).unwrap();
+        let key = get_reply_handle_key(reply_handle);
+        () = connection
+            .set_ex(
+                key,
+                target,
+                storage::REPLY_HANDLES_EXPIRATION_TIME.as_secs(),
+            )
+            .unwrap();
+    }
+
+    async fn get_reply_handle(&self, reply_handle: &str) -> Option<ReplyTarget> {
+        let mut connection = self.connection.write().await;
+        let key = get_reply_handle_key(reply_handle);
+        connection.get(key).ok()
     }
 }
 
@@ -97,6 +129,16 @@ impl ToRedisArgs for Route {
     }
 }
 
+impl ToRedisArgs for ReplyTarget {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        let json = serde_json::to_string(self).unwrap();
+        out.write_arg(&json.into_bytes());
+    }
+}
+
 impl FromRedisValue for Conversation {
     fn from_redis_value(v: &Value) -> redis::RedisResult<Self> {
         let value: String = from_redis_value(v)?;
@@ -112,3 +154,70 @@ impl FromRedisValue for Route {
         Ok(route)
     }
 }
+
+impl FromRedisValue for ReplyTarget {
+    fn from_redis_value(v: &Value) -> redis::RedisResult<Self> {
+        let value: String = from_redis_value(v)?;
+        serde_json::from_str(&value)
+            .map_err(|e| (ErrorKind::TypeError, "Invalid reply target", e.to_string()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crosscutting::settings::environment;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    const REDIS_TEST_URL_KEY: &str = "REDIS_TEST_URL";
+    const EXPECTED_NONCE: &str = "test_nonce";
+
+    /// Runs against the server in `REDIS_TEST_URL` and is skipped without one.
+    #[tokio::test]
+    async fn given_redis_when_taking_route_then_it_is_only_taken_once_within_its_conversation() {
+        let Ok(uri) = environment::get_env_variable(REDIS_TEST_URL_KEY) else {
+            return;
+        };
+
+        let repository = RedisRepository::new(&uri);
+        let conversation_id = Uuid::new_v4().to_string();
+        let conversation = Conversation::new(
+            conversation_id.clone(),
+            "from".to_string(),
+            "to".to_string(),
+            1,
+            Vec::new(),
+        );
+        let route = Route {
+            on_ip_address: "127.0.0.1".to_string(),
+            on_port_number: 8080,
+            public_key: Vec::new(),
+            domain_name: "localhost".to_string(),
+            nonce: EXPECTED_NONCE.to_string(),
+            end_route: false,
+            tags: HashMap::new(),
+        };
+        repository.set_conversation(&conversation).await;
+        repository.set_route(&conversation_id, &route).await;
+
+        let another_conversation_id = Uuid::new_v4().to_string();
+        assert!(
+            repository
+                .take_route(&another_conversation_id, EXPECTED_NONCE)
+                .await
+                .is_none()
+        );
+        let taken = repository
+            .take_route(&conversation_id, EXPECTED_NONCE)
+            .await
+            .unwrap();
+        assert_eq!(taken.nonce, EXPECTED_NONCE);
+        assert!(
+            repository
+                .take_route(&conversation_id, EXPECTED_NONCE)
+                .await
+                .is_none()
+        );
+    }
+}
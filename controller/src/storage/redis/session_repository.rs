@@ -1,15 +1,17 @@
 use super::RedisRepository;
-use crate::models::SessionInfo;
+use crate::models::{SessionCount, SessionInfo};
 use crate::storage;
 use crate::storage::SessionRepository;
 use crosscutting::Component;
-use redis::{Commands, FromRedisValue, ToRedisArgs, Value, from_redis_value};
+use redis::{Commands, ErrorKind, FromRedisValue, ToRedisArgs, Value, from_redis_value};
 use tonic::async_trait;
 
 const CONTROLLER_SESSION_KEY: &str = "ctrl_ss";
 const CLIENT_SESSION_KEY: &str = "c_ss";
 const PROXY_SESSION_KEY: &str = "p_ss";
 const SESSIONS_KEY: &str = "ss";
+const IDENTITY_KEYS_KEY: &str = "ik";
+const REVOKED_ACCESS_KEYS_KEY: &str = "rak";
 const EXPIRY_TIME: redis::Expiry = redis::Expiry::EX(storage::SESSIONS_EXPIRATION_TIME.as_secs());
 
 fn get_session_key(key: &str) -> String {
@@ -26,18 +28,36 @@ fn get_member_session_key(component_type: &Component, uid: &str) -> String {
     format!("{}:{}", key, uid)
 }
 
+fn get_member_sessions(connection: &mut redis::Connection, member_key: &str) -> Vec<SessionInfo> {
+    let access_keys: Vec<String> = connection.smembers(member_key).unwrap_or_default();
+    let mut sessions = Vec::new();
+
+    for access_key in access_keys {
+        match connection.get::<_, SessionInfo>(get_session_key(&access_key)) {
+            Ok(session) => sessions.push(session),
+            Err(_) => {
+                let _: usize = connection.srem(member_key, &access_key).unwrap_or_default();
+            }
+        }
+    }
+
+    sessions
+}
+
 #[async_trait]
 impl SessionRepository for RedisRepository {
     async fn set_session(&self, session_info: &SessionInfo) {
         let mut connection = self.connection.write().await;
         let component_type = Component::from(session_info.component_type);
 
+        let member_key = get_member_session_key(&component_type, &session_info.uid);
+
         redis::pipe()
             .atomic()
-            .set_ex(
-                get_member_session_key(&component_type, &session_info.uid),
-                session_info.access_key.clone(),
-                storage::SESSIONS_EXPIRATION_TIME.as_secs(),
+            .sadd(&member_key, session_info.access_key.clone())
+            .expire(
+                &member_key,
+                storage::SESSIONS_EXPIRATION_TIME.as_secs() as i64,
             )
             .set_ex(
                 get_session_key(&session_info.access_key),
@@ -56,9 +76,9 @@ impl SessionRepository for RedisRepository {
             Ok(session_info) => {
                 let component_type = Component::from(session_info.component_type);
                 let c_key = get_member_session_key(&component_type, &session_info.uid);
-                () = connection
+                let _: bool = connection
                     .expire(c_key, storage::SESSIONS_EXPIRATION_TIME.as_secs() as i64)
-                    .unwrap();
+                    .unwrap_or_default();
                 Some(session_info)
             }
             _ => None,
@@ -68,7 +88,51 @@ impl SessionRepository for RedisRepository {
     async fn remove_session(&self, access_key: &str) {
         let key = get_session_key(access_key);
         let mut connection = self.connection.write().await;
-        () = connection.del(key).unwrap();
+
+        if let Ok(session_info) = connection.get::<_, SessionInfo>(&key) {
+            let component_type = Component::from(session_info.component_type);
+            let member_key = get_member_session_key(&component_type, &session_info.uid);
+            let _: usize = connection.srem(member_key, access_key).unwrap_or_default();
+        }
+
+        let _: usize = connection.del(key).unwrap_or_default();
+    }
+
+    async fn remove_sessions(&self, uid: &str) -> Vec<String> {
+        let mut connection = self.connection.write().await;
+        let mut keys: Vec<String> = Vec::new();
+        let mut access_keys: Vec<String> = Vec::new();
+
+        for component_type in [Component::Client, Component::Proxy, Component::Controller] {
+            let member_key = get_member_session_key(&component_type, uid);
+            let member_access_keys: Vec<String> = connection.smembers(&member_key).unwrap();
+            for access_key in member_access_keys {
+                keys.push(get_session_key(&access_key));
+                access_keys.push(access_key);
+            }
+            keys.push(member_key);
+        }
+
+        () = connection.del(keys).unwrap();
+        access_keys
+    }
+
+    async fn revoke_access_key(&self, access_key: &str, expires_at: i64) {
+        let mut connection = self.connection.write().await;
+        let _: usize = connection
+            .zadd(REVOKED_ACCESS_KEYS_KEY, access_key, expires_at)
+            .unwrap_or_default();
+    }
+
+    async fn get_revoked_access_keys(&self) -> Vec<String> {
+        let now = chrono::Utc::now().timestamp();
+        let mut connection = self.connection.write().await;
+        let _: usize = connection
+            .zrembyscore(REVOKED_ACCESS_KEYS_KEY, "-inf", now)
+            .unwrap_or_default();
+        connection
+            .zrangebyscore(REVOKED_ACCESS_KEYS_KEY, format!("({}", now), "+inf")
+            .unwrap_or_default()
     }
 
     async fn get_proxies(&self, access_key: &str) -> Option<Vec<SessionInfo>> {
@@ -82,12 +146,12 @@ impl SessionRepository for RedisRepository {
 
         let mut proxies: Vec<SessionInfo> = Vec::new();
         all.iter().for_each(|key| {
-            let key: String = connection.get(key).unwrap();
-            let key = get_session_key(&key);
-            let session: SessionInfo = connection.get(key).unwrap();
-            if session.access_key != access_key {
-                proxies.push(session);
-            }
+            let sessions = get_member_sessions(&mut connection, key);
+            proxies.extend(
+                sessions
+                    .into_iter()
+                    .filter(|session| session.access_key != access_key),
+            );
         });
 
         if proxies.is_empty() {
@@ -97,12 +161,10 @@ impl SessionRepository for RedisRepository {
         Some(proxies)
     }
 
-    async fn get_client(&self, uid: &str) -> Option<SessionInfo> {
+    async fn get_clients(&self, uid: &str) -> Vec<SessionInfo> {
         let key = get_member_session_key(&Component::Client, uid);
         let mut connection = self.connection.write().await;
-        let access_key: String = connection.get(key).unwrap();
-        let key = get_session_key(&access_key);
-        connection.get(key).ok()
+        get_member_sessions(&mut connection, &key)
     }
 
     async fn count_proxies(&self) -> usize {
@@ -112,18 +174,38 @@ impl SessionRepository for RedisRepository {
         keys.len()
     }
 
-    async fn count_clients(&self) -> usize {
+    async fn count_clients(&self) -> SessionCount {
         let mut connection = self.connection.write().await;
         let key = get_member_session_key(&Component::Client, "*");
-        let keys: Vec<String> = connection.scan_match(&key).unwrap().collect();
-        keys.len()
+        let keys: Vec<String> = match connection.scan_match(&key) {
+            Ok(keys) => keys.collect(),
+            Err(_) => return SessionCount::default(),
+        };
+
+        // Member sets may still reference sessions that already expired, which
+        // get_member_sessions drops while loading them
+        let mut count = SessionCount::default();
+        for key in keys {
+            let sessions = get_member_sessions(&mut connection, &key).len();
+            if sessions > 0 {
+                count.members += 1;
+                count.sessions += sessions;
+            }
+        }
+
+        count
     }
 
-    async fn count_controllers(&self) -> usize {
+    async fn set_identity_key(&self, uid: &str, identity_key: &[u8]) {
         let mut connection = self.connection.write().await;
-        let key = get_member_session_key(&Component::Controller, "*");
-        let keys: Vec<String> = connection.scan_match(&key).unwrap().collect();
-        keys.len()
+        let _: usize = connection
+            .hset(IDENTITY_KEYS_KEY, uid, identity_key)
+            .unwrap_or_default();
+    }
+
+    async fn get_identity_key(&self, uid: &str) -> Option<Vec<u8>> {
+        let mut connection = self.connection.write().await;
+        connection.hget(IDENTITY_KEYS_KEY, uid).ok().flatten()
     }
 }
 
@@ -140,7 +222,7 @@ impl ToRedisArgs for SessionInfo {
 impl FromRedisValue for SessionInfo {
     fn from_redis_value(v: &Value) -> redis::RedisResult<Self> {
         let value: String = from_redis_value(v)?;
-        let session: SessionInfo = serde_json::from_str(&value).unwrap();
-        Ok(session)
+        serde_json::from_str(&value)
+            .map_err(|e| (ErrorKind::TypeError, "Invalid session", e.to_string()).into())
     }
 }
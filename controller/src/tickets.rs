@@ -0,0 +1,264 @@
+use crate::models::route_proto::{HopTicket, NonceTicket, RouteResponse};
+use crosscutting::crypto::{self, Identity};
+use crosscutting::settings::{environment, identity, service};
+use log::warn;
+use prost::Message;
+use std::time::Duration;
+
+const SIGNED_NONCES_KEY: &str = "SIGNED_NONCES";
+const TICKETS_EXPIRATION_TIME: Duration = Duration::from_secs(60);
+
+/// Signs the route tickets of source routed conversations and the nonces of
+/// every proxy hop, which let proxies redeem their hop without asking the
+/// controller. Circuit hops are always signed, as onion layers can't be
+/// redeemed at the controller. Every controller sharing the same storage must
+/// load the same identity.
+#[derive(Default)]
+pub struct TicketIssuer {
+    identity: Option<Identity>,
+    source_routing: bool,
+    signed_nonces: bool,
+}
+
+impl TicketIssuer {
+    pub fn new(identity: Identity, source_routing: bool, signed_nonces: bool) -> Self {
+        Self {
+            identity: Some(identity),
+            source_routing,
+            signed_nonces,
+        }
+    }
+
+    pub fn get_from_env() -> Self {
+        let source_routing = service::is_source_routing_enabled();
+        let signed_nonces = environment::get_env_variable(SIGNED_NONCES_KEY)
+            .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or_default();
+
+        match identity::load_identity() {
+            Ok(identity) => Self::new(identity, source_routing, signed_nonces),
+            Err(e) => {
+                warn!(
+                    "Route tickets are disabled as the identity is not available: {}",
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    pub fn is_source_routing_enabled(&self) -> bool {
+        self.identity.is_some() && self.source_routing
+    }
+
+    pub fn is_signing_nonces(&self) -> bool {
+        self.identity.is_some() && self.signed_nonces
+    }
+
+    pub fn get_key(&self) -> Vec<u8> {
+        self.identity
+            .as_ref()
+            .map(|identity| identity.public().to_bytes())
+            .unwrap_or_default()
+    }
+
+    /// Issues a ticket for every proxy on the path, each one holding the nonce
+    /// of its own hop and the address of the next one. Only the last proxy is
+    /// told the conversation it delivers, while every ticket carries it sealed
+    /// for the controllers. A signed ticket is the signature followed by the
+    /// encoded ticket.
+    pub fn issue(&self, conversation_id: &str, hops: &[RouteResponse]) -> Option<Vec<Vec<u8>>> {
+        let identity = self.identity.as_ref().filter(|_| self.source_routing)?;
+        let expires_at = chrono::Utc::now().timestamp() + TICKETS_EXPIRATION_TIME.as_secs() as i64;
+
+        hops.windows(2)
+            .map(|pair| {
+                let landing_conversation_id = if pair[1].end_route {
+                    conversation_id.to_string()
+                } else {
+                    String::default()
+                };
+                let ticket = HopTicket {
+                    conversation_id: landing_conversation_id,
+                    nonce: pair[0].nonce.clone(),
+                    holder_key: pair[0].identity_key.clone(),
+                    next_hop: Some(pair[1].clone()),
+                    expires_at,
+                    conversation_ref: seal_conversation(identity, conversation_id)?,
+                }
+                .encode_to_vec();
+                Some([identity.sign(&ticket), ticket].concat())
+            })
+            .collect()
+    }
+
+    /// Signs the nonce of a hop for the proxy holding the given identity key.
+    /// The conversation is sealed for the controllers, which tells them apart
+    /// once the nonce is reported as spent, while the hops of a conversation
+    /// can't be linked by the proxies holding them. The conversation tag lets
+    /// a proxy told the conversation check the ticket belongs to it.
+    pub fn sign_nonce(&self, conversation_id: &str, hop: &RouteResponse) -> Option<Vec<u8>> {
+        self.signed_nonces
+            .then(|| self.sign_circuit_nonce(conversation_id, hop))
+            .flatten()
+    }
+
+    /// Signs the nonce of a circuit hop, whether nonces are signed or not.
+    pub fn sign_circuit_nonce(
+        &self,
+        conversation_id: &str,
+        hop: &RouteResponse,
+    ) -> Option<Vec<u8>> {
+        let identity = self.identity.as_ref()?;
+        let ticket = NonceTicket {
+            nonce: hop.nonce.clone(),
+            holder_key: hop.identity_key.clone(),
+            expires_at: chrono::Utc::now().timestamp() + TICKETS_EXPIRATION_TIME.as_secs() as i64,
+            conversation_ref: seal_conversation(identity, conversation_id)?,
+            conversation_tag: crypto::conversation_tag(&hop.nonce, conversation_id),
+        }
+        .encode_to_vec();
+
+        Some([identity.sign(&ticket), ticket].concat())
+    }
+
+    /// Opens the conversation sealed within a ticket.
+    pub fn open_conversation_ref(&self, conversation_ref: &[u8]) -> Option<String> {
+        let identity = self.identity.as_ref()?;
+        let conversation_id = identity.open_anonymous(conversation_ref).ok()?;
+        String::from_utf8(conversation_id).ok()
+    }
+}
+
+/// Seals the conversation for the controllers, freshly randomised every time
+/// so the references of its hops can't be linked.
+fn seal_conversation(identity: &Identity, conversation_id: &str) -> Option<Vec<u8>> {
+    crypto::seal_anonymous(&identity.public(), conversation_id.as_bytes()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crosscutting::crypto::{PublicIdentity, SIGNATURE_LENGTH};
+
+    const EXPECTED_CONVERSATION_ID: &str = "test_conversation_id";
+
+    fn create_hop(nonce: &str, end_route: bool) -> RouteResponse {
+        RouteResponse {
+            nonce: nonce.to_string(),
+            end_route,
+            identity_key: nonce.as_bytes().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn given_disabled_issuer_when_issuing_then_returns_none() {
+        let issuer = TicketIssuer::default();
+
+        assert!(!issuer.is_source_routing_enabled());
+        assert!(!issuer.is_signing_nonces());
+        assert!(issuer.get_key().is_empty());
+        assert!(issuer.issue(EXPECTED_CONVERSATION_ID, &[]).is_none());
+        assert!(
+            issuer
+                .sign_nonce(EXPECTED_CONVERSATION_ID, &create_hop("nonce_1", false))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn given_path_when_issuing_then_every_proxy_gets_a_signed_ticket() {
+        let issuer = TicketIssuer::new(Identity::generate(), true, false);
+        let hops = vec![
+            create_hop("nonce_1", false),
+            create_hop("nonce_2", false),
+            create_hop("nonce_3", true),
+        ];
+
+        let tickets = issuer.issue(EXPECTED_CONVERSATION_ID, &hops).unwrap();
+
+        assert_eq!(tickets.len(), 2);
+        let key = PublicIdentity::from_bytes(&issuer.get_key()).unwrap();
+        for (index, signed) in tickets.iter().enumerate() {
+            let (signature, ticket) = signed.split_at(SIGNATURE_LENGTH);
+            assert!(key.verify(ticket, signature).is_ok());
+
+            let ticket = HopTicket::decode(ticket).unwrap();
+            let landing_conversation_id = if hops[index + 1].end_route {
+                EXPECTED_CONVERSATION_ID
+            } else {
+                ""
+            };
+            assert_eq!(ticket.conversation_id, landing_conversation_id);
+            assert_eq!(
+                issuer.open_conversation_ref(&ticket.conversation_ref),
+                Some(EXPECTED_CONVERSATION_ID.to_string())
+            );
+            assert_eq!(ticket.nonce, hops[index].nonce);
+            assert_eq!(ticket.holder_key, hops[index].identity_key);
+            assert_eq!(ticket.next_hop.unwrap(), hops[index + 1]);
+        }
+    }
+
+    #[test]
+    fn given_signed_nonces_when_signing_then_ticket_is_bound_to_the_hop() {
+        let issuer = TicketIssuer::new(Identity::generate(), false, true);
+        let hop = create_hop("nonce_1", false);
+
+        let signed = issuer.sign_nonce(EXPECTED_CONVERSATION_ID, &hop).unwrap();
+
+        let key = PublicIdentity::from_bytes(&issuer.get_key()).unwrap();
+        let (signature, ticket) = signed.split_at(SIGNATURE_LENGTH);
+        assert!(key.verify(ticket, signature).is_ok());
+
+        let ticket = NonceTicket::decode(ticket).unwrap();
+        assert_eq!(ticket.nonce, hop.nonce);
+        assert_eq!(ticket.holder_key, hop.identity_key);
+        assert_eq!(
+            issuer.open_conversation_ref(&ticket.conversation_ref),
+            Some(EXPECTED_CONVERSATION_ID.to_string())
+        );
+        assert_eq!(
+            ticket.conversation_tag,
+            crypto::conversation_tag(&hop.nonce, EXPECTED_CONVERSATION_ID)
+        );
+        assert!(issuer.issue(EXPECTED_CONVERSATION_ID, &[hop]).is_none());
+    }
+
+    #[test]
+    fn given_hops_of_a_conversation_when_signing_then_conversation_refs_differ() {
+        let issuer = TicketIssuer::new(Identity::generate(), false, true);
+        let get_conversation_ref = |nonce: &str| {
+            let signed = issuer
+                .sign_nonce(EXPECTED_CONVERSATION_ID, &create_hop(nonce, false))
+                .unwrap();
+            NonceTicket::decode(&signed[SIGNATURE_LENGTH..])
+                .unwrap()
+                .conversation_ref
+        };
+
+        assert_ne!(
+            get_conversation_ref("nonce_1"),
+            get_conversation_ref("nonce_2")
+        );
+    }
+
+    #[test]
+    fn given_unsigned_nonces_when_signing_circuit_nonce_then_ticket_is_still_issued() {
+        let issuer = TicketIssuer::new(Identity::generate(), false, false);
+        let hop = create_hop("nonce_1", false);
+
+        assert!(issuer.sign_nonce(EXPECTED_CONVERSATION_ID, &hop).is_none());
+        let signed = issuer
+            .sign_circuit_nonce(EXPECTED_CONVERSATION_ID, &hop)
+            .unwrap();
+        let ticket = NonceTicket::decode(&signed[SIGNATURE_LENGTH..]).unwrap();
+        assert_eq!(ticket.nonce, hop.nonce);
+        assert!(
+            TicketIssuer::default()
+                .sign_circuit_nonce(EXPECTED_CONVERSATION_ID, &hop)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use crosscutting::settings::environment;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const ACCESS_KEY_MODE_KEY: &str = "ACCESS_KEY_MODE";
+const ACCESS_KEY_SECRETS_KEY: &str = "ACCESS_KEY_SECRETS";
+const ACCESS_KEY_TTL_KEY: &str = "ACCESS_KEY_TTL_SECS";
+const SIGNED_MODE: &str = "signed";
+const DEFAULT_ACCESS_KEY_TTL: Duration = Duration::from_secs(3600);
+const TOKEN_SEPARATOR: char = '.';
+
+const INVALID_TOKEN: &str = "Invalid access key";
+const UNKNOWN_KEY: &str = "Unknown signing key";
+const EXPIRED_TOKEN: &str = "Expired access key";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessClaims {
+    pub uid: String,
+    pub component_type: u8,
+    pub session_id: String,
+    pub expires_at: i64,
+    #[serde(default)]
+    pub identity_key: String,
+}
+
+impl AccessClaims {
+    pub fn get_identity_key(&self) -> Vec<u8> {
+        URL_SAFE_NO_PAD
+            .decode(&self.identity_key)
+            .unwrap_or_default()
+    }
+}
+
+pub struct AccessKeySigner {
+    signing_kid: String,
+    keys: HashMap<String, Vec<u8>>,
+    ttl: Duration,
+}
+
+impl AccessKeySigner {
+    pub fn new(keys: Vec<(String, Vec<u8>)>, ttl: Duration) -> Result<Self, String> {
+        let (signing_kid, _) = keys.first().ok_or("At least one signing key is required")?;
+        let signing_kid = signing_kid.clone();
+
+        if let Some((kid, _)) = keys.iter().find(|(kid, secret)| {
+            kid.is_empty() || kid.contains(TOKEN_SEPARATOR) || secret.is_empty()
+        }) {
+            return Err(format!("Invalid signing key: '{}'", kid));
+        }
+
+        Ok(Self {
+            signing_kid,
+            keys: keys.into_iter().collect(),
+            ttl,
+        })
+    }
+
+    pub fn get_from_env() -> Result<Option<Self>, String> {
+        let mode = environment::get_env_variable(ACCESS_KEY_MODE_KEY).unwrap_or_default();
+        if !mode.eq_ignore_ascii_case(SIGNED_MODE) {
+            return Ok(None);
+        }
+
+        let secrets = environment::get_env_variable(ACCESS_KEY_SECRETS_KEY).map_err(|_| {
+            format!(
+                "{} is required for signed access keys",
+                ACCESS_KEY_SECRETS_KEY
+            )
+        })?;
+        let ttl = environment::get_env_variable(ACCESS_KEY_TTL_KEY)
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_ACCESS_KEY_TTL);
+
+        Self::new(Self::parse_keys(&secrets)?, ttl).map(Some)
+    }
+
+    pub fn issue(
+        &self,
+        uid: &str,
+        component_type: u8,
+        session_id: &str,
+        identity_key: &[u8],
+    ) -> String {
+        let claims = AccessClaims {
+            uid: uid.to_string(),
+            component_type,
+            session_id: session_id.to_string(),
+            expires_at: chrono::Utc::now().timestamp() + self.ttl.as_secs() as i64,
+            identity_key: URL_SAFE_NO_PAD.encode(identity_key),
+        };
+
+        self.sign(&claims)
+    }
+
+    pub fn sign(&self, claims: &AccessClaims) -> String {
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).unwrap());
+        let signed_part = format!("{}{}{}", self.signing_kid, TOKEN_SEPARATOR, payload);
+        let signature =
+            URL_SAFE_NO_PAD.encode(self.compute_signature(&self.signing_kid, &signed_part));
+        format!("{}{}{}", signed_part, TOKEN_SEPARATOR, signature)
+    }
+
+    pub fn verify(&self, token: &str) -> Result<AccessClaims, &'static str> {
+        let (signed_part, signature) = token.rsplit_once(TOKEN_SEPARATOR).ok_or(INVALID_TOKEN)?;
+        let (kid, payload) = signed_part
+            .split_once(TOKEN_SEPARATOR)
+            .ok_or(INVALID_TOKEN)?;
+        let secret = self.keys.get(kid).ok_or(UNKNOWN_KEY)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| INVALID_TOKEN)?;
+
+        let mut mac = HmacSha256::new_from_slice(secret).map_err(|_| INVALID_TOKEN)?;
+        mac.update(signed_part.as_bytes());
+        mac.verify_slice(&signature).map_err(|_| INVALID_TOKEN)?;
+
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| INVALID_TOKEN)?;
+        let claims: AccessClaims = serde_json::from_slice(&payload).map_err(|_| INVALID_TOKEN)?;
+        if claims.expires_at <= chrono::Utc::now().timestamp() {
+            return Err(EXPIRED_TOKEN);
+        }
+
+        Ok(claims)
+    }
+
+    fn compute_signature(&self, kid: &str, signed_part: &str) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(&self.keys[kid]).unwrap();
+        mac.update(signed_part.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    fn parse_keys(secrets: &str) -> Result<Vec<(String, Vec<u8>)>, String> {
+        secrets
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| {
+                entry
+                    .trim()
+                    .split_once(':')
+                    .map(|(kid, secret)| (kid.to_string(), secret.as_bytes().to_vec()))
+                    .ok_or_else(|| format!("Invalid signing key entry: '{}'", entry))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPECTED_UID: &str = "test_uid";
+    const EXPECTED_SESSION_ID: &str = "test_session_id";
+    const EXPECTED_IDENTITY_KEY: &[u8] = b"test_identity_key";
+    const EXPECTED_TTL: Duration = Duration::from_secs(60);
+
+    fn create_signer(keys: &[(&str, &str)]) -> AccessKeySigner {
+        let keys = keys
+            .iter()
+            .map(|(kid, secret)| (kid.to_string(), secret.as_bytes().to_vec()))
+            .collect();
+        AccessKeySigner::new(keys, EXPECTED_TTL).unwrap()
+    }
+
+    #[test]
+    fn given_issued_token_when_verifying_then_returns_claims() {
+        let signer = create_signer(&[("k1", "secret1")]);
+
+        let token = signer.issue(EXPECTED_UID, 2, EXPECTED_SESSION_ID, EXPECTED_IDENTITY_KEY);
+        let claims = signer.verify(&token).unwrap();
+
+        assert_eq!(claims.uid, EXPECTED_UID);
+        assert_eq!(claims.component_type, 2);
+        assert_eq!(claims.session_id, EXPECTED_SESSION_ID);
+        assert_eq!(claims.get_identity_key(), EXPECTED_IDENTITY_KEY);
+    }
+
+    #[test]
+    fn given_tampered_token_when_verifying_then_returns_error() {
+        let signer = create_signer(&[("k1", "secret1")]);
+        let token = signer.issue(EXPECTED_UID, 2, EXPECTED_SESSION_ID, EXPECTED_IDENTITY_KEY);
+        let (_, signature) = token.rsplit_once(TOKEN_SEPARATOR).unwrap();
+        let forged_claims = AccessClaims {
+            uid: "another_uid".to_string(),
+            component_type: 2,
+            session_id: EXPECTED_SESSION_ID.to_string(),
+            expires_at: chrono::Utc::now().timestamp() + 60,
+            identity_key: String::default(),
+        };
+        let forged_payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged_claims).unwrap());
+        let forged_token = format!("k1.{}.{}", forged_payload, signature);
+
+        assert_eq!(signer.verify(&forged_token), Err(INVALID_TOKEN));
+    }
+
+    #[test]
+    fn given_expired_token_when_verifying_then_returns_error() {
+        let signer = create_signer(&[("k1", "secret1")]);
+        let token = signer.sign(&AccessClaims {
+            uid: EXPECTED_UID.to_string(),
+            component_type: 2,
+            session_id: EXPECTED_SESSION_ID.to_string(),
+            expires_at: chrono::Utc::now().timestamp() - 1,
+            identity_key: String::default(),
+        });
+
+        assert_eq!(signer.verify(&token), Err(EXPIRED_TOKEN));
+    }
+
+    #[test]
+    fn given_rotated_keys_when_verifying_then_previous_key_is_still_accepted() {
+        let old_signer = create_signer(&[("k1", "secret1")]);
+        let rotated_signer = create_signer(&[("k2", "secret2"), ("k1", "secret1")]);
+        let retired_signer = create_signer(&[("k2", "secret2")]);
+
+        let old_token =
+            old_signer.issue(EXPECTED_UID, 2, EXPECTED_SESSION_ID, EXPECTED_IDENTITY_KEY);
+        let new_token =
+            rotated_signer.issue(EXPECTED_UID, 2, EXPECTED_SESSION_ID, EXPECTED_IDENTITY_KEY);
+
+        assert!(rotated_signer.verify(&old_token).is_ok());
+        assert!(new_token.starts_with("k2."));
+        assert!(retired_signer.verify(&new_token).is_ok());
+        assert_eq!(retired_signer.verify(&old_token), Err(UNKNOWN_KEY));
+    }
+
+    #[test]
+    fn given_opaque_key_when_verifying_then_returns_error() {
+        let signer = create_signer(&[("k1", "secret1")]);
+
+        assert!(
+            signer
+                .verify("4f6c1f0a-5b4e-4bb0-9a0a-0e5ad1b1f0c3")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn given_secrets_setting_when_parsing_keys_then_returns_all_keys() {
+        let keys = AccessKeySigner::parse_keys("k2:secret2, k1:secret:with:colons").unwrap();
+
+        assert_eq!(
+            keys,
+            vec![
+                ("k2".to_string(), b"secret2".to_vec()),
+                ("k1".to_string(), b"secret:with:colons".to_vec()),
+            ]
+        );
+        assert!(AccessKeySigner::parse_keys("k1").is_err());
+    }
+
+    #[test]
+    fn given_invalid_kid_when_creating_signer_then_returns_error() {
+        let keys = vec![("k.1".to_string(), b"secret".to_vec())];
+
+        assert!(AccessKeySigner::new(keys, EXPECTED_TTL).is_err());
+        assert!(AccessKeySigner::new(vec![], EXPECTED_TTL).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce, aead::Aead};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use hkdf::Hkdf;
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+pub const PUBLIC_IDENTITY_LENGTH: usize = 64;
+const SECRET_IDENTITY_LENGTH: usize = 64;
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+pub const SIGNATURE_LENGTH: usize = 64;
+const SEAL_HEADER_LENGTH: usize = KEY_LENGTH + NONCE_LENGTH;
+const SEAL_INFO: &[u8] = b"fuzzy-chat/seal/v1";
+const SIGNATURE_CONTEXT: &[u8] = b"fuzzy-chat/signature/v1";
+const CONVERSATION_TAG_CONTEXT: &[u8] = b"fuzzy-chat/conversation-tag/v1";
+
+#[derive(Clone)]
+pub struct Identity {
+    exchange_secret: StaticSecret,
+    signing_key: SigningKey,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublicIdentity {
+    exchange_key: PublicKey,
+    verifying_key: VerifyingKey,
+}
+
+impl Identity {
+    pub fn generate() -> Self {
+        Self {
+            exchange_secret: StaticSecret::random_from_rng(OsRng),
+            signing_key: SigningKey::generate(&mut OsRng),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if bytes.len() != SECRET_IDENTITY_LENGTH {
+            return Err("Invalid identity length".into());
+        }
+
+        let exchange_secret: [u8; KEY_LENGTH] = bytes[..KEY_LENGTH].try_into()?;
+        let signing_key: [u8; KEY_LENGTH] = bytes[KEY_LENGTH..].try_into()?;
+
+        Ok(Self {
+            exchange_secret: StaticSecret::from(exchange_secret),
+            signing_key: SigningKey::from_bytes(&signing_key),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SECRET_IDENTITY_LENGTH);
+        bytes.extend_from_slice(self.exchange_secret.as_bytes());
+        bytes.extend_from_slice(self.signing_key.as_bytes());
+        bytes
+    }
+
+    pub fn public(&self) -> PublicIdentity {
+        PublicIdentity {
+            exchange_key: PublicKey::from(&self.exchange_secret),
+            verifying_key: self.signing_key.verifying_key(),
+        }
+    }
+
+    pub fn seal(
+        &self,
+        recipient: &PublicIdentity,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let sealed = seal_anonymous(recipient, plaintext)?;
+        let signature = self
+            .signing_key
+            .sign(&signature_payload(recipient, &sealed));
+
+        let mut envelope = Vec::with_capacity(SIGNATURE_LENGTH + sealed.len());
+        envelope.extend_from_slice(&signature.to_bytes());
+        envelope.extend_from_slice(&sealed);
+        Ok(envelope)
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.signing_key.sign(message).to_bytes().to_vec()
+    }
+
+    pub fn open(
+        &self,
+        sender: &PublicIdentity,
+        envelope: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        if envelope.len() < SIGNATURE_LENGTH {
+            return Err("Sealed content is too short".into());
+        }
+
+        let (signature, sealed) = envelope.split_at(SIGNATURE_LENGTH);
+        let signature = Signature::from_slice(signature)?;
+        sender
+            .verifying_key
+            .verify(&signature_payload(&self.public(), sealed), &signature)
+            .map_err(|_| "Invalid sender signature")?;
+
+        self.open_anonymous(sealed)
+    }
+
+    pub fn open_anonymous(&self, sealed: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        if sealed.len() < SEAL_HEADER_LENGTH {
+            return Err("Sealed content is too short".into());
+        }
+
+        let (ephemeral_key, rest) = sealed.split_at(KEY_LENGTH);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+        let ephemeral_key: [u8; KEY_LENGTH] = ephemeral_key.try_into()?;
+        let ephemeral_key = PublicKey::from(ephemeral_key);
+
+        let shared_secret = self.exchange_secret.diffie_hellman(&ephemeral_key);
+        let cipher = derive_cipher(
+            shared_secret.as_bytes(),
+            ephemeral_key.as_bytes(),
+            PublicKey::from(&self.exchange_secret).as_bytes(),
+        )?;
+
+        cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Unable to decrypt sealed content".into())
+    }
+}
+
+impl PublicIdentity {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if bytes.len() != PUBLIC_IDENTITY_LENGTH {
+            return Err("Invalid public identity length".into());
+        }
+
+        let exchange_key: [u8; KEY_LENGTH] = bytes[..KEY_LENGTH].try_into()?;
+        let verifying_key: [u8; KEY_LENGTH] = bytes[KEY_LENGTH..].try_into()?;
+
+        Ok(Self {
+            exchange_key: PublicKey::from(exchange_key),
+            verifying_key: VerifyingKey::from_bytes(&verifying_key)?,
+        })
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), Box<dyn Error>> {
+        let signature = Signature::from_slice(signature)?;
+        self.verifying_key
+            .verify(message, &signature)
+            .map_err(|_| "Invalid signature".into())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PUBLIC_IDENTITY_LENGTH);
+        bytes.extend_from_slice(self.exchange_key.as_bytes());
+        bytes.extend_from_slice(self.verifying_key.as_bytes());
+        bytes
+    }
+}
+
+pub fn seal_anonymous(
+    recipient: &PublicIdentity,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let ephemeral_secret = StaticSecret::random_from_rng(OsRng);
+    let ephemeral_key = PublicKey::from(&ephemeral_secret);
+    let shared_secret = ephemeral_secret.diffie_hellman(&recipient.exchange_key);
+    let cipher = derive_cipher(
+        shared_secret.as_bytes(),
+        ephemeral_key.as_bytes(),
+        recipient.exchange_key.as_bytes(),
+    )?;
+
+    let mut nonce = [0u8; NONCE_LENGTH];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| "Unable to seal content")?;
+
+    let mut sealed = Vec::with_capacity(SEAL_HEADER_LENGTH + ciphertext.len());
+    sealed.extend_from_slice(ephemeral_key.as_bytes());
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Binds a nonce to its conversation. Whoever knows both can check the tag,
+/// while tags of different nonces can't be linked to the same conversation.
+pub fn conversation_tag(nonce: &str, conversation_id: &str) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(CONVERSATION_TAG_CONTEXT)
+        .chain_update((nonce.len() as u64).to_be_bytes())
+        .chain_update(nonce.as_bytes())
+        .chain_update(conversation_id.as_bytes())
+        .finalize()
+        .to_vec()
+}
+
+fn derive_cipher(
+    shared_secret: &[u8],
+    ephemeral_key: &[u8],
+    recipient_key: &[u8],
+) -> Result<ChaCha20Poly1305, Box<dyn Error>> {
+    let salt = [ephemeral_key, recipient_key].concat();
+    let mut key = [0u8; KEY_LENGTH];
+    Hkdf::<Sha256>::new(Some(&salt), shared_secret)
+        .expand(SEAL_INFO, &mut key)
+        .map_err(|_| "Unable to derive the content key")?;
+
+    Ok(ChaCha20Poly1305::new(&key.into()))
+}
+
+fn signature_payload(recipient: &PublicIdentity, sealed: &[u8]) -> Vec<u8> {
+    [SIGNATURE_CONTEXT, &recipient.to_bytes(), sealed].concat()
+}
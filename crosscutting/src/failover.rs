@@ -0,0 +1,351 @@
+use crate::ConnectionSettings;
+use crate::settings::{environment, service};
+use log::{debug, warn};
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+
+const CONTROLLER_SELECTION_POLICY_KEY: &str = "CONTROLLER_SELECTION_POLICY";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+static CONTROLLER_SELECTOR: OnceLock<Result<ControllerSelector, String>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SelectionPolicy {
+    #[default]
+    RoundRobin,
+    FirstHealthy,
+    LowestLatency,
+}
+
+impl SelectionPolicy {
+    pub fn get_from_env() -> Self {
+        match environment::get_env_variable(CONTROLLER_SELECTION_POLICY_KEY) {
+            Ok(value) => value.parse().unwrap_or_else(|e| {
+                warn!("Ignoring {}: {}", CONTROLLER_SELECTION_POLICY_KEY, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+impl std::str::FromStr for SelectionPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value
+            .trim()
+            .to_lowercase()
+            .replace(['_', ' '], "-")
+            .as_str()
+        {
+            "round-robin" => Ok(SelectionPolicy::RoundRobin),
+            "first-healthy" => Ok(SelectionPolicy::FirstHealthy),
+            "lowest-latency" => Ok(SelectionPolicy::LowestLatency),
+            _ => Err(format!("Invalid selection policy: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct EndpointHealth {
+    failures: u32,
+    retry_at: Option<Instant>,
+    latency: Option<Duration>,
+}
+
+impl EndpointHealth {
+    fn is_available(&self, now: Instant) -> bool {
+        self.retry_at.is_none_or(|retry_at| retry_at <= now)
+    }
+}
+
+pub struct ControllerSelector {
+    endpoints: Vec<ConnectionSettings>,
+    policy: SelectionPolicy,
+    health: Mutex<Vec<EndpointHealth>>,
+    next: AtomicUsize,
+    session_controller: Mutex<Option<usize>>,
+}
+
+impl ControllerSelector {
+    pub fn new(endpoints: Vec<ConnectionSettings>, policy: SelectionPolicy) -> Self {
+        Self {
+            health: Mutex::new(vec![EndpointHealth::default(); endpoints.len()]),
+            endpoints,
+            policy,
+            next: AtomicUsize::new(0),
+            session_controller: Mutex::new(None),
+        }
+    }
+
+    pub fn get_from_env() -> Result<Self, Box<dyn Error>> {
+        Ok(Self::new(
+            service::get_controller_endpoints()?,
+            SelectionPolicy::get_from_env(),
+        ))
+    }
+
+    pub fn get_endpoint(&self, index: usize) -> &ConnectionSettings {
+        &self.endpoints[index]
+    }
+
+    /// Returns the endpoint indexes in the order they should be tried. Endpoints
+    /// which are backing off go last, the ones closest to their retry time first.
+    pub fn candidates(&self) -> Vec<usize> {
+        let health = self.health.lock().unwrap();
+        let now = Instant::now();
+        let (mut available, mut backing_off): (Vec<usize>, Vec<usize>) =
+            (0..self.endpoints.len()).partition(|&index| health[index].is_available(now));
+
+        match self.policy {
+            SelectionPolicy::RoundRobin => {
+                if !available.is_empty() {
+                    let offset = self.next.fetch_add(1, Ordering::Relaxed) % available.len();
+                    available.rotate_left(offset);
+                }
+            }
+            SelectionPolicy::FirstHealthy => {}
+            SelectionPolicy::LowestLatency => {
+                available.sort_by_key(|&index| health[index].latency.unwrap_or_default());
+            }
+        }
+
+        backing_off.sort_by_key(|&index| health[index].retry_at);
+        available.extend(backing_off);
+        available
+    }
+
+    pub fn report_success(&self, index: usize, latency: Duration) {
+        let mut health = self.health.lock().unwrap();
+        health[index] = EndpointHealth {
+            latency: Some(latency),
+            ..Default::default()
+        };
+    }
+
+    pub fn report_failure(&self, index: usize) {
+        let mut health = self.health.lock().unwrap();
+        let endpoint = &mut health[index];
+        endpoint.failures = endpoint.failures.saturating_add(1);
+        endpoint.retry_at = Some(Instant::now() + get_backoff(endpoint.failures));
+    }
+
+    /// Sessions only exist on the controller which issued them, so every
+    /// client acting on behalf of the session must talk to that one.
+    pub fn set_session_controller(&self, index: Option<usize>) {
+        *self.session_controller.lock().unwrap() = index;
+    }
+
+    pub fn get_session_controller(&self) -> Option<usize> {
+        *self.session_controller.lock().unwrap()
+    }
+
+    pub async fn connect(
+        &self,
+        identity: Option<Identity>,
+    ) -> Result<(Channel, usize), Box<dyn Error>> {
+        let mut last_error = String::from("No controller endpoints available");
+
+        for index in self.candidates() {
+            match self.connect_endpoint(index, identity.clone()).await {
+                Ok(channel) => return Ok((channel, index)),
+                Err(e) => last_error = e.to_string(),
+            }
+        }
+
+        Err(last_error.into())
+    }
+
+    /// Connects to the controller holding the current session, or to any of
+    /// them when there is no session yet.
+    pub async fn connect_session(&self) -> Result<(Channel, usize), Box<dyn Error>> {
+        match self.get_session_controller() {
+            Some(index) => Ok((self.connect_endpoint(index, None).await?, index)),
+            None => self.connect(None).await,
+        }
+    }
+
+    async fn connect_endpoint(
+        &self,
+        index: usize,
+        identity: Option<Identity>,
+    ) -> Result<Channel, Box<dyn Error>> {
+        let settings = self.get_endpoint(index);
+        let mut tls_config = ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(settings.certificate.clone()))
+            .domain_name(settings.domain_name.clone());
+
+        if let Some(identity) = identity {
+            tls_config = tls_config.identity(identity);
+        }
+
+        let started_at = Instant::now();
+        let result = match Channel::builder(settings.get_public_endpoint())
+            .connect_timeout(CONNECT_TIMEOUT)
+            .tls_config(tls_config)
+        {
+            Ok(endpoint) => endpoint.connect().await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(channel) => {
+                debug!(
+                    "Connected to controller at {}:{}",
+                    settings.ip, settings.port
+                );
+                self.report_success(index, started_at.elapsed());
+                Ok(channel)
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to connect to controller at {}:{}: {}",
+                    settings.ip, settings.port, e
+                );
+                self.report_failure(index);
+                Err(format!("Failed to connect to gRPC server: {}", e).into())
+            }
+        }
+    }
+}
+
+pub fn get_controller_selector() -> Result<&'static ControllerSelector, Box<dyn Error>> {
+    CONTROLLER_SELECTOR
+        .get_or_init(|| ControllerSelector::get_from_env().map_err(|e| e.to_string()))
+        .as_ref()
+        .map_err(|e| e.clone().into())
+}
+
+pub async fn connect_controller(
+    identity: Option<Identity>,
+) -> Result<(Channel, usize), Box<dyn Error>> {
+    let selector = get_controller_selector()?;
+    selector.connect(identity).await
+}
+
+pub async fn connect_session_controller() -> Result<(Channel, usize), Box<dyn Error>> {
+    let selector = get_controller_selector()?;
+    selector.connect_session().await
+}
+
+/// Reports the controller as failing when the call didn't reach it, leaving
+/// aside the ones it rejected.
+pub fn report_status(controller: Option<usize>, status: &tonic::Status) {
+    if !matches!(
+        status.code(),
+        tonic::Code::Unavailable | tonic::Code::DeadlineExceeded
+    ) {
+        return;
+    }
+
+    if let (Some(controller), Ok(selector)) = (controller, get_controller_selector()) {
+        selector.report_failure(controller);
+    }
+}
+
+fn get_backoff(failures: u32) -> Duration {
+    let exponent = failures.saturating_sub(1).min(16);
+    INITIAL_BACKOFF
+        .saturating_mul(1 << exponent)
+        .min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_selector(policy: SelectionPolicy) -> ControllerSelector {
+        let endpoints = (0..3)
+            .map(|index| ConnectionSettings {
+                ip: format!("192.168.1.{}", index + 1),
+                port: 50051,
+                domain_name: "controller".to_string(),
+                certificate: Vec::new(),
+            })
+            .collect();
+        ControllerSelector::new(endpoints, policy)
+    }
+
+    #[test]
+    fn given_policy_names_when_parsing_then_returns_policy() {
+        assert_eq!("round-robin".parse(), Ok(SelectionPolicy::RoundRobin));
+        assert_eq!(" First_Healthy ".parse(), Ok(SelectionPolicy::FirstHealthy));
+        assert_eq!("lowest-latency".parse(), Ok(SelectionPolicy::LowestLatency));
+        assert!("random".parse::<SelectionPolicy>().is_err());
+    }
+
+    #[test]
+    fn given_round_robin_when_selecting_then_rotates_endpoints() {
+        let selector = create_selector(SelectionPolicy::RoundRobin);
+
+        assert_eq!(selector.candidates(), vec![0, 1, 2]);
+        assert_eq!(selector.candidates(), vec![1, 2, 0]);
+        assert_eq!(selector.candidates(), vec![2, 0, 1]);
+        assert_eq!(selector.candidates(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn given_first_healthy_when_endpoint_fails_then_next_one_goes_first() {
+        let selector = create_selector(SelectionPolicy::FirstHealthy);
+        assert_eq!(selector.candidates(), vec![0, 1, 2]);
+
+        selector.report_failure(0);
+
+        assert_eq!(selector.candidates(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn given_lowest_latency_when_selecting_then_fastest_endpoint_goes_first() {
+        let selector = create_selector(SelectionPolicy::LowestLatency);
+        selector.report_success(0, Duration::from_millis(30));
+        selector.report_success(1, Duration::from_millis(10));
+        selector.report_success(2, Duration::from_millis(20));
+
+        assert_eq!(selector.candidates(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn given_failed_endpoints_when_selecting_then_closest_retry_goes_first() {
+        let selector = create_selector(SelectionPolicy::FirstHealthy);
+        selector.report_failure(0);
+        selector.report_failure(0);
+        selector.report_failure(1);
+
+        assert_eq!(selector.candidates(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn given_recovered_endpoint_when_reporting_success_then_backoff_is_reset() {
+        let selector = create_selector(SelectionPolicy::FirstHealthy);
+        selector.report_failure(0);
+        selector.report_success(0, Duration::from_millis(10));
+
+        assert_eq!(selector.candidates(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn given_session_controller_when_setting_then_it_is_kept_until_cleared() {
+        let selector = create_selector(SelectionPolicy::RoundRobin);
+        assert_eq!(selector.get_session_controller(), None);
+
+        selector.set_session_controller(Some(2));
+        assert_eq!(selector.get_session_controller(), Some(2));
+
+        selector.set_session_controller(None);
+        assert_eq!(selector.get_session_controller(), None);
+    }
+
+    #[test]
+    fn given_consecutive_failures_when_computing_backoff_then_doubles_up_to_max() {
+        assert_eq!(get_backoff(1), INITIAL_BACKOFF);
+        assert_eq!(get_backoff(2), INITIAL_BACKOFF * 2);
+        assert_eq!(get_backoff(3), INITIAL_BACKOFF * 4);
+        assert_eq!(get_backoff(100), MAX_BACKOFF);
+    }
+}
@@ -1,8 +1,12 @@
 pub mod abstractions;
+pub mod crypto;
+pub mod failover;
 pub mod networking;
+pub mod rate_limit;
 pub mod settings;
 pub mod tracing;
 
+use crypto::Identity;
 use http::Uri;
 use std::{error::Error, net::SocketAddr};
 
@@ -23,10 +27,12 @@ pub enum ComponentDescriptor {
     Proxy {
         credentials: Credentials,
         connection_settings: ConnectionSettings,
+        identity: Identity,
     },
     Client {
         credentials: Credentials,
         connection_settings: ConnectionSettings,
+        identity: Identity,
     },
 }
 
@@ -42,6 +48,14 @@ pub struct ConnectionSettings {
 pub struct Credentials {
     pub uid: String,
     pub pwd: String,
+    pub auth_method: AuthMethod,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum AuthMethod {
+    #[default]
+    Password,
+    Certificate,
 }
 
 impl From<u8> for Component {
@@ -77,6 +91,48 @@ impl From<Component> for i32 {
     }
 }
 
+impl TryFrom<i32> for AuthMethod {
+    type Error = String;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AuthMethod::Password),
+            1 => Ok(AuthMethod::Certificate),
+            _ => Err(format!("Invalid value for AuthMethod: {}", value)),
+        }
+    }
+}
+
+impl From<AuthMethod> for i32 {
+    fn from(value: AuthMethod) -> Self {
+        match value {
+            AuthMethod::Password => 0,
+            AuthMethod::Certificate => 1,
+        }
+    }
+}
+
+impl std::str::FromStr for AuthMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "password" => Ok(AuthMethod::Password),
+            "certificate" => Ok(AuthMethod::Certificate),
+            _ => Err(format!("Invalid authentication method: {}", value)),
+        }
+    }
+}
+
+impl std::fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthMethod::Password => write!(f, "Password"),
+            AuthMethod::Certificate => write!(f, "Certificate"),
+        }
+    }
+}
+
 impl std::fmt::Display for Component {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -111,10 +167,12 @@ impl ComponentDescriptor {
             Component::Proxy => ComponentDescriptor::Proxy {
                 credentials,
                 connection_settings,
+                identity: settings::identity::load_identity()?,
             },
             Component::Client => ComponentDescriptor::Client {
                 credentials,
                 connection_settings,
+                identity: settings::identity::load_identity()?,
             },
         };
 
@@ -145,6 +203,14 @@ impl ComponentDescriptor {
             ComponentDescriptor::Client { credentials, .. } => credentials,
         }
     }
+
+    pub fn get_identity(&self) -> Option<&Identity> {
+        match self {
+            ComponentDescriptor::Controller { .. } => None,
+            ComponentDescriptor::Proxy { identity, .. } => Some(identity),
+            ComponentDescriptor::Client { identity, .. } => Some(identity),
+        }
+    }
 }
 
 impl From<&ComponentDescriptor> for Component {
@@ -182,4 +248,18 @@ mod tests {
         assert_eq!(Component::from(2), Component::Client);
         assert!(std::panic::catch_unwind(|| Component::from(3)).is_err());
     }
+
+    #[test]
+    fn from_str_to_auth_method() {
+        assert_eq!("password".parse(), Ok(AuthMethod::Password));
+        assert_eq!(" Certificate ".parse(), Ok(AuthMethod::Certificate));
+        assert!("token".parse::<AuthMethod>().is_err());
+    }
+
+    #[test]
+    fn from_i32_to_auth_method() {
+        assert_eq!(AuthMethod::try_from(0), Ok(AuthMethod::Password));
+        assert_eq!(AuthMethod::try_from(1), Ok(AuthMethod::Certificate));
+        assert!(AuthMethod::try_from(2).is_err());
+    }
 }
@@ -0,0 +1,476 @@
+use crate::settings::environment;
+use http::{Request, Response};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+use tonic::{Code, Status};
+use tower::Service;
+
+pub const ACCESS_KEY_METADATA: &str = "x-access-key";
+pub const RETRY_AFTER_METADATA: &str = "retry-after";
+
+const RATE_LIMITS_KEY: &str = "RATE_LIMITS";
+const DEFAULT_METHOD: &str = "*";
+const UNKNOWN_PEER: &str = "unknown";
+const OVERFLOW_CALLER: &str = "overflow";
+const MAX_BUCKETS: usize = 10_000;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Tells whether an access key was issued by this server, so it can tell
+/// apart callers sharing the same address.
+pub type KeyVerifier = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    pub fn new(requests: u32, period: Duration) -> Self {
+        Self { requests, period }
+    }
+
+    fn refill_rate(&self) -> f64 {
+        self.requests as f64 / self.period.as_secs_f64()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimits {
+    methods: HashMap<String, RateLimit>,
+    default: Option<RateLimit>,
+}
+
+impl RateLimits {
+    pub fn with_method(mut self, method: &str, limit: RateLimit) -> Self {
+        self.methods.insert(normalize_method(method), limit);
+        self
+    }
+
+    pub fn with_default(mut self, limit: RateLimit) -> Self {
+        self.default = Some(limit);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_empty() && self.default.is_none()
+    }
+
+    pub fn get_from_env() -> Self {
+        match environment::get_env_variable(RATE_LIMITS_KEY) {
+            Ok(value) => Self::parse(&value).unwrap_or_else(|e| {
+                warn!("Ignoring {}: {}", RATE_LIMITS_KEY, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut limits = Self::default();
+
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let invalid_entry = || format!("Invalid rate limit entry: '{}'", entry);
+            let (method, limit) = entry.rsplit_once('=').ok_or_else(invalid_entry)?;
+            let (requests, seconds) = limit.split_once('/').ok_or_else(invalid_entry)?;
+            let requests = requests
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| invalid_entry())?;
+            let seconds = seconds.trim().parse::<u64>().map_err(|_| invalid_entry())?;
+            if requests == 0 || seconds == 0 {
+                return Err(invalid_entry());
+            }
+
+            let limit = RateLimit::new(requests, Duration::from_secs(seconds));
+            limits = match method.trim() {
+                DEFAULT_METHOD => limits.with_default(limit),
+                method => limits.with_method(method, limit),
+            };
+        }
+
+        Ok(limits)
+    }
+
+    fn get_limit(&self, method: &str) -> Option<RateLimit> {
+        self.methods
+            .get(&normalize_method(method))
+            .copied()
+            .or(self.default)
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.requests as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * limit.refill_rate()).min(limit.requests as f64);
+        self.last_refill = now;
+    }
+
+    fn try_acquire(&mut self, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+        self.refill(limit, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - self.tokens;
+            Err(Duration::from_secs_f64(missing / limit.refill_rate()))
+        }
+    }
+
+    fn is_full(&self, limit: &RateLimit, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens + elapsed.as_secs_f64() * limit.refill_rate() >= limit.requests as f64
+    }
+}
+
+struct Buckets {
+    entries: HashMap<(String, String), TokenBucket>,
+    last_pruned: Instant,
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    limits: Arc<RateLimits>,
+    buckets: Arc<Mutex<Buckets>>,
+    max_buckets: usize,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self::with_capacity(limits, MAX_BUCKETS)
+    }
+
+    fn with_capacity(limits: RateLimits, max_buckets: usize) -> Self {
+        Self {
+            limits: Arc::new(limits),
+            buckets: Arc::new(Mutex::new(Buckets {
+                entries: HashMap::new(),
+                last_pruned: Instant::now(),
+            })),
+            max_buckets,
+        }
+    }
+
+    pub fn check(&self, method: &str, caller: &str) -> Result<(), Duration> {
+        self.check_at(method, caller, Instant::now())
+    }
+
+    fn check_at(&self, method: &str, caller: &str, now: Instant) -> Result<(), Duration> {
+        let Some(limit) = self.limits.get_limit(method) else {
+            return Ok(());
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if now.saturating_duration_since(buckets.last_pruned) >= PRUNE_INTERVAL {
+            let limits = &self.limits;
+            buckets.entries.retain(|(method, _), bucket| {
+                limits
+                    .get_limit(method)
+                    .is_some_and(|limit| !bucket.is_full(&limit, now))
+            });
+            buckets.last_pruned = now;
+        }
+
+        // Once the table is full, unknown callers share a single bucket until
+        // the next prune, so it can't grow beyond its capacity
+        let mut key = (normalize_method(method), caller.to_string());
+        if buckets.entries.len() >= self.max_buckets && !buckets.entries.contains_key(&key) {
+            key.1 = OVERFLOW_CALLER.to_string();
+        }
+
+        buckets
+            .entries
+            .entry(key)
+            .or_insert_with(|| TokenBucket::new(&limit, now))
+            .try_acquire(&limit, now)
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Option<RateLimiter>,
+    key_verifier: Option<KeyVerifier>,
+}
+
+impl RateLimitLayer {
+    pub fn new(limits: RateLimits) -> Self {
+        let limiter = (!limits.is_empty()).then(|| RateLimiter::new(limits));
+        Self {
+            limiter,
+            key_verifier: None,
+        }
+    }
+
+    pub fn get_from_env() -> Self {
+        Self::new(RateLimits::get_from_env())
+    }
+
+    pub fn with_key_verifier(mut self, key_verifier: KeyVerifier) -> Self {
+        self.key_verifier = Some(key_verifier);
+        self
+    }
+}
+
+impl<S> tower::Layer<S> for RateLimitLayer {
+    type Service = RateLimitMiddleware<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitMiddleware {
+            inner,
+            limiter: self.limiter.clone(),
+            key_verifier: self.key_verifier.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitMiddleware<S> {
+    inner: S,
+    limiter: Option<RateLimiter>,
+    key_verifier: Option<KeyVerifier>,
+}
+
+impl<S, B, ResBody> Service<Request<B>> for RateLimitMiddleware<S>
+where
+    S: Service<Request<B>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ResBody: Default + Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        if let Some(limiter) = &self.limiter {
+            let caller = get_caller(&req, self.key_verifier.as_ref());
+            if let Err(retry_after) = limiter.check(req.uri().path(), &caller) {
+                debug!("Rate limit exceeded on {} by {}", req.uri().path(), caller);
+                let response = create_rate_limited_status(retry_after).into_http();
+                return Box::pin(async move { Ok(response) });
+            }
+        }
+
+        Box::pin(self.inner.call(req))
+    }
+}
+
+fn create_rate_limited_status(retry_after: Duration) -> Status {
+    let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let mut metadata = MetadataMap::new();
+    metadata.insert(RETRY_AFTER_METADATA, MetadataValue::from(seconds));
+    Status::with_metadata(
+        Code::ResourceExhausted,
+        format!("Rate limit exceeded. Retry after {} seconds", seconds),
+        metadata,
+    )
+}
+
+/// Callers are identified by their address. The access key only tells apart
+/// callers sharing the same address when it can be verified, as anybody can
+/// send a new one on every request.
+fn get_caller<B>(req: &Request<B>, key_verifier: Option<&KeyVerifier>) -> String {
+    let peer = get_peer_address(req)
+        .map(|address| format!("ip:{}", address.ip()))
+        .unwrap_or_else(|| UNKNOWN_PEER.to_string());
+
+    match req
+        .headers()
+        .get(ACCESS_KEY_METADATA)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .filter(|value| key_verifier.is_some_and(|verify| verify(value)))
+    {
+        Some(access_key) => format!("{}/key:{}", peer, access_key),
+        None => peer,
+    }
+}
+
+fn get_peer_address<B>(req: &Request<B>) -> Option<SocketAddr> {
+    let extensions = req.extensions();
+    extensions
+        .get::<TlsConnectInfo<TcpConnectInfo>>()
+        .and_then(|info| info.get_ref().remote_addr())
+        .or_else(|| {
+            extensions
+                .get::<TcpConnectInfo>()
+                .and_then(|info| info.remote_addr())
+        })
+}
+
+fn normalize_method(method: &str) -> String {
+    method.trim().trim_start_matches('/').to_string()
+}
+
+pub fn with_access_key<T>(message: T, access_key: &str) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    if let Ok(value) = access_key.parse() {
+        request.metadata_mut().insert(ACCESS_KEY_METADATA, value);
+    }
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INITIALIZE_METHOD: &str = "/route.RouteService/Initialize";
+    const PING_METHOD: &str = "/auth.AuthService/Ping";
+    const EXPECTED_CALLER: &str = "key:test_access_key";
+
+    fn create_limiter() -> RateLimiter {
+        RateLimiter::new(
+            RateLimits::parse("/route.RouteService/Initialize=2/10, auth.AuthService/Login=1/60")
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn given_rate_limits_setting_when_parsing_then_returns_limits() {
+        let limits = RateLimits::parse("/route.RouteService/Initialize=5/60,*=100/10").unwrap();
+
+        assert_eq!(
+            limits.get_limit(INITIALIZE_METHOD),
+            Some(RateLimit::new(5, Duration::from_secs(60)))
+        );
+        assert_eq!(
+            limits.get_limit(PING_METHOD),
+            Some(RateLimit::new(100, Duration::from_secs(10)))
+        );
+    }
+
+    #[test]
+    fn given_invalid_rate_limits_setting_when_parsing_then_returns_error() {
+        assert!(RateLimits::parse("/route.RouteService/Initialize").is_err());
+        assert!(RateLimits::parse("*=5").is_err());
+        assert!(RateLimits::parse("*=0/10").is_err());
+        assert!(RateLimits::parse("*=a/10").is_err());
+    }
+
+    #[test]
+    fn given_exhausted_bucket_when_checking_then_returns_retry_after() {
+        let limiter = create_limiter();
+        let now = Instant::now();
+
+        assert!(
+            limiter
+                .check_at(INITIALIZE_METHOD, EXPECTED_CALLER, now)
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .check_at(INITIALIZE_METHOD, EXPECTED_CALLER, now)
+                .is_ok()
+        );
+        let retry_after = limiter
+            .check_at(INITIALIZE_METHOD, EXPECTED_CALLER, now)
+            .unwrap_err();
+
+        assert_eq!(retry_after, Duration::from_secs(5));
+        assert!(
+            limiter
+                .check_at(INITIALIZE_METHOD, "key:another", now)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn given_elapsed_period_when_checking_then_bucket_is_refilled() {
+        let limiter = create_limiter();
+        let now = Instant::now();
+        let login_method = "/auth.AuthService/Login";
+
+        assert!(limiter.check_at(login_method, EXPECTED_CALLER, now).is_ok());
+        assert!(
+            limiter
+                .check_at(login_method, EXPECTED_CALLER, now)
+                .is_err()
+        );
+        assert!(
+            limiter
+                .check_at(login_method, EXPECTED_CALLER, now + Duration::from_secs(60))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn given_method_without_limit_when_checking_then_is_allowed() {
+        let limiter = create_limiter();
+        let now = Instant::now();
+
+        for _ in 0..10 {
+            assert!(limiter.check_at(PING_METHOD, EXPECTED_CALLER, now).is_ok());
+        }
+    }
+
+    #[test]
+    fn given_access_key_header_when_getting_caller_then_only_verified_keys_are_used() {
+        let request = Request::builder()
+            .header(ACCESS_KEY_METADATA, "test_access_key")
+            .body(())
+            .unwrap();
+        let verifier: KeyVerifier = Arc::new(|key| key == "test_access_key");
+        let rejecting_verifier: KeyVerifier = Arc::new(|_| false);
+
+        assert_eq!(get_caller(&request, None), UNKNOWN_PEER);
+        assert_eq!(
+            get_caller(&request, Some(&rejecting_verifier)),
+            UNKNOWN_PEER
+        );
+        assert_eq!(
+            get_caller(&request, Some(&verifier)),
+            format!("{}/{}", UNKNOWN_PEER, EXPECTED_CALLER)
+        );
+    }
+
+    #[test]
+    fn given_full_bucket_table_when_checking_new_callers_then_they_share_a_bucket() {
+        let limiter = RateLimiter::with_capacity(
+            RateLimits::parse("/route.RouteService/Initialize=1/10").unwrap(),
+            1,
+        );
+        let now = Instant::now();
+
+        assert!(limiter.check_at(INITIALIZE_METHOD, "ip:1", now).is_ok());
+        assert!(limiter.check_at(INITIALIZE_METHOD, "ip:2", now).is_ok());
+        assert!(limiter.check_at(INITIALIZE_METHOD, "ip:3", now).is_err());
+        assert_eq!(limiter.buckets.lock().unwrap().entries.len(), 2);
+
+        let later = now + PRUNE_INTERVAL;
+        assert!(limiter.check_at(INITIALIZE_METHOD, "ip:3", later).is_ok());
+        assert_eq!(limiter.buckets.lock().unwrap().entries.len(), 1);
+    }
+
+    #[test]
+    fn given_retry_after_when_creating_status_then_metadata_is_rounded_up() {
+        let status = create_rate_limited_status(Duration::from_millis(1500));
+
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert_eq!(status.metadata().get(RETRY_AFTER_METADATA).unwrap(), "2");
+    }
+}
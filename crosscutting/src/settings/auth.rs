@@ -1,10 +1,94 @@
-use crate::Credentials;
+use crate::{AuthMethod, Component, Credentials};
 
 use super::*;
+use std::fs;
+
+const AUTH_METHOD_KEY: &str = "AUTH_METHOD";
+const AUTH_METHODS_PROXY_KEY: &str = "AUTH_METHODS_PROXY";
+const AUTH_METHODS_CLIENT_KEY: &str = "AUTH_METHODS_CLIENT";
+const CLIENT_CA_FILE_KEY: &str = "CLIENT_CA_FILE";
+const CLIENT_CERT_FILE_KEY: &str = "CLIENT_CERT_FILE";
+const CLIENT_KEY_FILE_KEY: &str = "CLIENT_KEY_FILE";
+const DEFAULT_CLIENT_CERT_FILE: &str = "client.crt";
+const DEFAULT_CLIENT_KEY_FILE: &str = "client.key";
 
 pub fn get_credentials() -> Result<Credentials, Box<dyn Error>> {
+    let auth_method = get_auth_method()?;
     let uid = super::environment::get_env_variable("UID").map_err(|_| "UID not set")?;
-    let pwd = super::environment::get_env_variable("PWD").map_err(|_| "PWD not set")?;
+    let pwd = match super::environment::get_env_variable("PWD") {
+        Ok(pwd) => pwd,
+        Err(_) if auth_method == AuthMethod::Certificate => String::default(),
+        Err(_) => return Err("PWD not set".into()),
+    };
+
+    Ok(Credentials {
+        uid,
+        pwd,
+        auth_method,
+    })
+}
+
+pub fn get_auth_method() -> Result<AuthMethod, Box<dyn Error>> {
+    match super::environment::get_env_variable(AUTH_METHOD_KEY) {
+        Ok(value) => Ok(value.parse::<AuthMethod>()?),
+        Err(_) => Ok(AuthMethod::default()),
+    }
+}
+
+pub fn get_allowed_auth_methods(component_type: &Component) -> Vec<AuthMethod> {
+    let key = match component_type {
+        Component::Proxy => AUTH_METHODS_PROXY_KEY,
+        Component::Client => AUTH_METHODS_CLIENT_KEY,
+        Component::Controller => return vec![],
+    };
+
+    super::environment::get_env_variable(key)
+        .map(|value| parse_auth_methods(&value))
+        .unwrap_or_else(|_| vec![AuthMethod::Password])
+}
+
+pub fn get_client_ca_certificate() -> Option<Vec<u8>> {
+    super::environment::get_env_variable(CLIENT_CA_FILE_KEY)
+        .ok()
+        .and_then(|ca_path| fs::read(ca_path).ok())
+}
+
+pub fn get_client_certificate_files() -> (String, String) {
+    let cert_file = super::environment::get_env_variable(CLIENT_CERT_FILE_KEY)
+        .unwrap_or_else(|_| DEFAULT_CLIENT_CERT_FILE.to_string());
+    let key_file = super::environment::get_env_variable(CLIENT_KEY_FILE_KEY)
+        .unwrap_or_else(|_| DEFAULT_CLIENT_KEY_FILE.to_string());
+    (cert_file, key_file)
+}
+
+fn parse_auth_methods(value: &str) -> Vec<AuthMethod> {
+    value
+        .split(',')
+        .filter(|method| !method.trim().is_empty())
+        .filter_map(|method| method.parse::<AuthMethod>().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_auth_methods_ignores_unknown_entries() {
+        let methods = parse_auth_methods("password, certificate,token,");
+
+        assert_eq!(methods, vec![AuthMethod::Password, AuthMethod::Certificate]);
+    }
+
+    #[test]
+    fn parse_auth_methods_with_single_entry() {
+        let methods = parse_auth_methods("certificate");
+
+        assert_eq!(methods, vec![AuthMethod::Certificate]);
+    }
 
-    Ok(Credentials { uid, pwd })
+    #[test]
+    fn controller_has_no_allowed_auth_methods() {
+        assert!(get_allowed_auth_methods(&Component::Controller).is_empty());
+    }
 }
@@ -0,0 +1,75 @@
+use crate::crypto::Identity;
+
+use super::*;
+use std::io::Write;
+use std::{fs, path::Path, path::PathBuf};
+
+const IDENTITY_FILE_KEY: &str = "IDENTITY_FILE";
+const DEFAULT_IDENTITY_FILE: &str = "identity.key";
+
+pub fn load_identity() -> Result<Identity, Box<dyn Error>> {
+    let path = get_identity_path();
+    if path.exists() {
+        check_permissions(&path)?;
+        let bytes = fs::read(&path)?;
+        return Identity::from_bytes(&bytes);
+    }
+
+    let identity = Identity::generate();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    create_private_file(&path)?.write_all(&identity.to_bytes())?;
+    Ok(identity)
+}
+
+#[cfg(unix)]
+fn create_private_file(path: &Path) -> std::io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private_file(path: &Path) -> std::io::Result<fs::File> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+}
+
+/// Refuses identity files which can be read or written by anyone but their
+/// owner, as they hold the long-term secret key.
+#[cfg(unix)]
+fn check_permissions(path: &Path) -> Result<(), Box<dyn Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)?.permissions().mode();
+    if mode & 0o077 != 0 {
+        return Err(format!(
+            "Identity file {} must only be accessible by its owner (mode {:o})",
+            path.display(),
+            mode & 0o777
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_: &Path) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+fn get_identity_path() -> PathBuf {
+    super::environment::get_env_variable(IDENTITY_FILE_KEY)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            PathBuf::from(super::environment::get_certificates_dir()).join(DEFAULT_IDENTITY_FILE)
+        })
+}
@@ -2,5 +2,6 @@ use std::error::Error;
 
 pub mod auth;
 pub mod environment;
+pub mod identity;
 pub mod logging;
 pub mod service;
@@ -1,7 +1,7 @@
 use crate::ConnectionSettings;
 
 use super::*;
-use std::{fs, path::PathBuf};
+use std::{collections::HashMap, fs, path::PathBuf};
 use tonic::transport::Identity;
 
 const CONTROLLER_CERT_FILE_KEY: &str = "CONTROLLER_CERT_FILE";
@@ -11,6 +11,10 @@ const LISTENING_IP_KEY: &str = "LISTENING_IP";
 const LISTENING_PORT_KEY: &str = "LISTENING_PORT";
 const CONTROLLER_IP_KEY: &str = "CONTROLLER_IP";
 const CONTROLLER_PORT_KEY: &str = "CONTROLLER_PORT";
+const CONTROLLER_ENDPOINTS_KEY: &str = "CONTROLLER_ENDPOINTS";
+const PROXY_TAGS_KEY: &str = "PROXY_TAGS";
+const PROXY_ROLES_KEY: &str = "PROXY_ROLES";
+const SOURCE_ROUTING_KEY: &str = "SOURCE_ROUTING";
 
 pub fn load_tls_identity(cert_file: &str, key_file: &str) -> Result<Identity, Box<dyn Error>> {
     let path = PathBuf::from(crate::settings::environment::get_certificates_dir());
@@ -31,6 +35,17 @@ pub fn get_controller_connection_settings() -> Result<ConnectionSettings, Box<dy
     })
 }
 
+pub fn get_controller_endpoints() -> Result<Vec<ConnectionSettings>, Box<dyn Error>> {
+    match super::environment::get_env_variable(CONTROLLER_ENDPOINTS_KEY) {
+        Ok(value) if !value.trim().is_empty() => parse_controller_endpoints(
+            &value,
+            get_domain_name(CONTROLLER_DOMAIN_NAME_KEY).ok().as_deref(),
+            &get_controller_cert_file()?,
+        ),
+        _ => Ok(vec![get_controller_connection_settings()?]),
+    }
+}
+
 pub fn get_connection_settings() -> Result<ConnectionSettings, Box<dyn Error>> {
     let (ip, port) = get_service_endpoint(LISTENING_IP_KEY, LISTENING_PORT_KEY)?;
     Ok(ConnectionSettings {
@@ -52,6 +67,82 @@ fn get_service_endpoint(ip_env_var: &str, port_env_var: &str) -> Result<(String,
     Ok((ip, port))
 }
 
+pub fn parse_controller_endpoints(
+    value: &str,
+    default_domain_name: Option<&str>,
+    certificate: &[u8],
+) -> Result<Vec<ConnectionSettings>, Box<dyn Error>> {
+    let mut endpoints = Vec::new();
+
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (domain_name, address) = match entry.split_once('@') {
+            Some((domain_name, address)) => (Some(domain_name.trim()), address.trim()),
+            None => (default_domain_name, entry),
+        };
+        let domain_name = domain_name
+            .filter(|domain_name| !domain_name.is_empty())
+            .ok_or_else(|| format!("Domain name not set for controller endpoint '{}'", entry))?;
+        let (ip, port) = address
+            .rsplit_once(':')
+            .ok_or_else(|| format!("Invalid controller endpoint '{}'", entry))?;
+        let port = port
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid port number for controller endpoint '{}'", entry))?;
+
+        endpoints.push(ConnectionSettings {
+            ip: ip.to_string(),
+            port,
+            domain_name: domain_name.to_string(),
+            certificate: certificate.to_vec(),
+        });
+    }
+
+    if endpoints.is_empty() {
+        return Err("No controller endpoints set".into());
+    }
+
+    Ok(endpoints)
+}
+
+pub fn get_tags() -> HashMap<String, String> {
+    super::environment::get_env_variable(PROXY_TAGS_KEY)
+        .map(|value| parse_tags(&value))
+        .unwrap_or_default()
+}
+
+/// Parses a comma separated list of `key=value` tags, such as
+/// `region=eu-west,operator=acme,asn=AS64500`, skipping malformed entries.
+pub fn parse_tags(value: &str) -> HashMap<String, String> {
+    value
+        .split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+        .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+        .collect()
+}
+
+/// Returns the roles a proxy accepts (`entry`, `middle` and `exit`), leaving
+/// their validation to the controller.
+pub fn get_roles() -> Vec<String> {
+    super::environment::get_env_variable(PROXY_ROLES_KEY)
+        .map(|value| {
+            value
+                .split(',')
+                .map(|role| role.trim().to_lowercase())
+                .filter(|role| !role.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Tells whether conversations are source routed, which the controller reads
+/// to issue route tickets and clients read to request them.
+pub fn is_source_routing_enabled() -> bool {
+    super::environment::get_env_variable(SOURCE_ROUTING_KEY)
+        .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or_default()
+}
+
 fn get_domain_name(domain_env_var: &str) -> Result<String, Box<dyn Error>> {
     let domain_name =
         super::environment::get_env_variable(domain_env_var).map_err(|_| "Domain name not set")?;
@@ -109,6 +200,46 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn parse_controller_endpoints_with_default_domain_name() {
+        let result = service::parse_controller_endpoints(
+            "192.168.1.1:8080, controller-2@192.168.1.2:8081",
+            Some("controller"),
+            b"cert",
+        );
+        assert!(result.is_ok());
+        let endpoints = result.unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[0].ip, "192.168.1.1");
+        assert_eq!(endpoints[0].port, 8080);
+        assert_eq!(endpoints[0].domain_name, "controller");
+        assert_eq!(endpoints[1].ip, "192.168.1.2");
+        assert_eq!(endpoints[1].port, 8081);
+        assert_eq!(endpoints[1].domain_name, "controller-2");
+        assert_eq!(endpoints[1].certificate, b"cert".to_vec());
+    }
+
+    #[test]
+    fn parse_controller_endpoints_invalid() {
+        assert!(
+            service::parse_controller_endpoints("192.168.1.1", Some("controller"), b"").is_err()
+        );
+        assert!(
+            service::parse_controller_endpoints("192.168.1.1:port", Some("controller"), b"")
+                .is_err()
+        );
+        assert!(service::parse_controller_endpoints("192.168.1.1:8080", None, b"").is_err());
+        assert!(service::parse_controller_endpoints(" , ", Some("controller"), b"").is_err());
+    }
+
+    #[test]
+    fn parse_tags_skips_malformed_entries() {
+        let tags = service::parse_tags("Region=eu-west, operator = acme,asn,=AS64500,network=");
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags.get("region"), Some(&"eu-west".to_string()));
+        assert_eq!(tags.get("operator"), Some(&"acme".to_string()));
+    }
+
     #[test]
     fn get_controller_domain_name_valid() {
         unsafe {
@@ -0,0 +1,134 @@
+use crosscutting::crypto::{self, Identity, PublicIdentity};
+use crosscutting::settings::identity;
+use std::env;
+
+const MESSAGE: &[u8] = b"Hello, World!";
+
+#[test]
+fn seal_and_open_roundtrip() {
+    let sender = Identity::generate();
+    let recipient = Identity::generate();
+
+    let sealed = sender.seal(&recipient.public(), MESSAGE).unwrap();
+    let opened = recipient.open(&sender.public(), &sealed).unwrap();
+
+    assert_ne!(sealed, MESSAGE);
+    assert_eq!(opened, MESSAGE);
+}
+
+#[test]
+fn open_with_wrong_sender_fails() {
+    let sender = Identity::generate();
+    let impostor = Identity::generate();
+    let recipient = Identity::generate();
+
+    let sealed = sender.seal(&recipient.public(), MESSAGE).unwrap();
+    let result = recipient.open(&impostor.public(), &sealed);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn open_with_wrong_recipient_fails() {
+    let sender = Identity::generate();
+    let recipient = Identity::generate();
+    let eavesdropper = Identity::generate();
+
+    let sealed = sender.seal(&recipient.public(), MESSAGE).unwrap();
+    let result = eavesdropper.open(&sender.public(), &sealed);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn open_tampered_content_fails() {
+    let sender = Identity::generate();
+    let recipient = Identity::generate();
+
+    let mut sealed = sender.seal(&recipient.public(), MESSAGE).unwrap();
+    let last = sealed.len() - 1;
+    sealed[last] ^= 0x01;
+    let result = recipient.open(&sender.public(), &sealed);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn seal_and_open_anonymous_roundtrip() {
+    let recipient = Identity::generate();
+
+    let sealed = crypto::seal_anonymous(&recipient.public(), MESSAGE).unwrap();
+    let opened = recipient.open_anonymous(&sealed).unwrap();
+
+    assert_eq!(opened, MESSAGE);
+}
+
+#[test]
+fn conversation_tag_differs_per_conversation_and_nonce() {
+    let tag = crypto::conversation_tag("nonce", "conversation");
+
+    assert_eq!(tag, crypto::conversation_tag("nonce", "conversation"));
+    assert_ne!(tag, crypto::conversation_tag("nonce", "other_conversation"));
+    assert_ne!(tag, crypto::conversation_tag("other_nonce", "conversation"));
+}
+
+#[test]
+fn sign_and_verify_roundtrip() {
+    let signer = Identity::generate();
+
+    let signature = signer.sign(MESSAGE);
+
+    assert!(signer.public().verify(MESSAGE, &signature).is_ok());
+}
+
+#[test]
+fn verify_with_wrong_signer_fails() {
+    let signer = Identity::generate();
+
+    let signature = signer.sign(MESSAGE);
+    let result = Identity::generate().public().verify(MESSAGE, &signature);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn public_identity_bytes_roundtrip() {
+    let identity = Identity::generate();
+    let public = identity.public();
+
+    let restored = PublicIdentity::from_bytes(&public.to_bytes()).unwrap();
+
+    assert_eq!(restored, public);
+}
+
+#[test]
+fn public_identity_from_invalid_bytes_fails() {
+    let result = PublicIdentity::from_bytes(b"too short");
+    assert!(result.is_err());
+}
+
+#[test]
+fn load_identity_persists_generated_identity() {
+    let path = env::temp_dir().join(format!("identity-{}.key", std::process::id()));
+    unsafe {
+        env::set_var("IDENTITY_FILE", &path);
+    }
+
+    let generated = identity::load_identity().unwrap();
+    let loaded = identity::load_identity().unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(identity::load_identity().is_err());
+    }
+
+    _ = std::fs::remove_file(&path);
+
+    assert_eq!(generated.public(), loaded.public());
+}
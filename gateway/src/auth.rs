@@ -12,6 +12,7 @@ const AUTH_DELAY: Duration = Duration::from_millis(1000);
 struct AuthManager {
     auth_client: Arc<RwLock<Box<dyn Authenticator>>>,
     cancellation_token: CancellationToken,
+    reconnect: bool,
 }
 
 impl AuthManager {
@@ -22,16 +23,25 @@ impl AuthManager {
         Self {
             auth_client,
             cancellation_token,
+            reconnect: false,
         }
     }
 
     async fn authenticate(&mut self) -> Result<String, Box<dyn Error>> {
         let mut auth_client = self.auth_client.write().await;
 
-        auth_client
-            .login()
-            .await
-            .map_err(|_| "Impossible to login")?;
+        if self.reconnect {
+            auth_client.initialize().await.map_err(|e| {
+                warn!("Failed to reconnect to a controller: {}", e);
+                "Impossible to reconnect"
+            })?;
+            self.reconnect = false;
+        }
+
+        auth_client.login().await.map_err(|_| {
+            self.reconnect = true;
+            "Impossible to login"
+        })?;
 
         let session = auth_client.get_session().await;
         if !auth_client.is_authenticated().await {
@@ -61,6 +71,7 @@ impl AuthManager {
                 }
                 Err(e) => {
                     debug!("Ping failed: {}", e);
+                    self.reconnect = true;
                     return Err(e);
                 }
             }
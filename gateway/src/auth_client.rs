@@ -1,22 +1,23 @@
 use crosscutting::{
-    Component, ComponentDescriptor, ConnectionSettings, Credentials, abstractions::GrpcClient,
+    AuthMethod, Component, ComponentDescriptor, ConnectionSettings, Credentials,
+    abstractions::GrpcClient, failover, rate_limit::with_access_key, settings,
 };
 use mockall::automock;
-use std::{error::Error, sync::Arc};
+use std::{collections::HashMap, error::Error, sync::Arc};
 use tokio::sync::RwLock;
-use tonic::{
-    Request, async_trait,
-    transport::{self, Channel, ClientTlsConfig},
-};
+use tonic::{Request, async_trait, transport::Channel};
 
 use super::auth_proto::{
-    LoginRequest, LogoutRequest, PingRequest, auth_service_client::AuthServiceClient,
+    ChangePasswordRequest, LoadMetrics, LoginRequest, LogoutRequest, PingRequest,
+    auth_service_client::AuthServiceClient,
 };
+use crate::load::LoadTracker;
 
 #[derive(Debug, Clone, Default)]
 pub struct ClientSession {
     pub access_key: Option<String>,
     pub uid: Option<String>,
+    pub ticket_key: Vec<u8>,
 }
 
 impl ClientSession {
@@ -33,29 +34,32 @@ impl ClientSession {
 struct AuthClient {
     session: Arc<RwLock<ClientSession>>,
     client: Option<AuthServiceClient<Channel>>,
+    controller: Option<usize>,
     credentials: Credentials,
     connection_settings: ConnectionSettings,
     component_type: Component,
+    identity_key: Vec<u8>,
+    tags: HashMap<String, String>,
+    roles: Vec<String>,
+    load_tracker: Option<Arc<LoadTracker>>,
 }
 
 #[async_trait]
 impl GrpcClient for AuthClient {
     async fn initialize(&mut self) -> Result<(), Box<dyn Error>> {
-        let controller_settings = Component::Controller.get_connection_settings()?;
-
-        let tls_config = ClientTlsConfig::new()
-            .ca_certificate(tonic::transport::Certificate::from_pem(
-                controller_settings.certificate.clone(),
-            ))
-            .domain_name(controller_settings.domain_name.clone());
+        let identity = if self.credentials.auth_method == AuthMethod::Certificate {
+            let (cert_file, key_file) = settings::auth::get_client_certificate_files();
+            let identity = settings::service::load_tls_identity(&cert_file, &key_file)
+                .map_err(|e| format!("Failed to load client certificate: {}", e))?;
+            Some(identity)
+        } else {
+            None
+        };
 
-        let channel = Channel::builder(controller_settings.get_public_endpoint())
-            .tls_config(tls_config)?
-            .connect()
-            .await
-            .map_err(|e: transport::Error| format!("Failed to connect to gRPC server: {}", e))?;
+        let (channel, controller) = failover::connect_controller(identity).await?;
 
         self.client = Some(AuthServiceClient::new(channel));
+        self.controller = Some(controller);
         Ok(())
     }
 }
@@ -66,8 +70,11 @@ pub trait Authenticator: GrpcClient {
     async fn login(&mut self) -> Result<(), Box<dyn Error>>;
     async fn logout(&mut self) -> Result<(), Box<dyn Error>>;
     async fn ping(&mut self) -> Result<(String, i64), Box<dyn Error>>;
+    async fn change_password(&mut self, old_pwd: &str, new_pwd: &str)
+    -> Result<(), Box<dyn Error>>;
     async fn get_session(&self) -> ClientSession;
     async fn is_authenticated(&self) -> bool;
+    fn set_load_tracker(&mut self, load_tracker: Arc<LoadTracker>);
 }
 
 #[async_trait]
@@ -81,6 +88,10 @@ impl Authenticator for AuthClient {
             on_port: self.connection_settings.port as u32,
             public_key: self.connection_settings.certificate.clone(),
             domain_name: self.connection_settings.domain_name.clone(),
+            identity_key: self.identity_key.clone(),
+            auth_method: self.credentials.auth_method.into(),
+            tags: self.tags.clone(),
+            roles: self.roles.clone(),
         });
 
         let response = self
@@ -92,21 +103,35 @@ impl Authenticator for AuthClient {
             .map_err(|status| format!("Login failed: {}", status))?
             .into_inner();
 
+        let uid = if response.uid.is_empty() {
+            self.credentials.uid.clone()
+        } else {
+            response.uid
+        };
         let mut session = self.session.write().await;
-        session.set_session(self.credentials.uid.clone(), response.access_key.clone());
+        session.set_session(uid, response.access_key.clone());
+        session.ticket_key = response.ticket_key;
+
+        if let Ok(selector) = failover::get_controller_selector() {
+            selector.set_session_controller(self.controller);
+        }
         Ok(())
     }
 
     async fn logout(&mut self) -> Result<(), Box<dyn Error>> {
-        let request = tonic::Request::new(LogoutRequest {
-            access_key: self
-                .session
-                .read()
-                .await
-                .access_key
-                .clone()
-                .unwrap_or_default(),
-        });
+        let access_key = self
+            .session
+            .read()
+            .await
+            .access_key
+            .clone()
+            .unwrap_or_default();
+        let request = with_access_key(
+            LogoutRequest {
+                access_key: access_key.clone(),
+            },
+            &access_key,
+        );
 
         self.client
             .as_mut()
@@ -116,20 +141,76 @@ impl Authenticator for AuthClient {
             .map_err(|status| format!("Logout failed: {}", status))?;
 
         self.session = Arc::new(RwLock::new(ClientSession::default()));
+        if let Ok(selector) = failover::get_controller_selector() {
+            selector.set_session_controller(None);
+        }
         Ok(())
     }
 
     async fn ping(&mut self) -> Result<(String, i64), Box<dyn Error>> {
         let session = self.session.read().await;
-        let request = Request::new(PingRequest {
-            access_key: session.access_key.clone().unwrap_or_default(),
-        });
+        let access_key = session.access_key.clone().unwrap_or_default();
+        let request = with_access_key(
+            PingRequest {
+                access_key: access_key.clone(),
+                load: self.load_tracker.as_ref().map(|load_tracker| {
+                    let snapshot = load_tracker.snapshot();
+                    LoadMetrics {
+                        in_flight: snapshot.in_flight,
+                        throughput: snapshot.throughput,
+                        available_capacity: snapshot.available_capacity,
+                    }
+                }),
+            },
+            &access_key,
+        );
 
-        let response = self.client.as_mut().unwrap().ping(request).await?;
-        let response = response.into_inner();
+        let response = match self.client.as_mut().unwrap().ping(request).await {
+            Ok(response) => response.into_inner(),
+            Err(status) => {
+                if let (Some(controller), Ok(selector)) =
+                    (self.controller, failover::get_controller_selector())
+                {
+                    selector.report_failure(controller);
+                }
+                return Err(status.into());
+            }
+        };
         Ok((response.status, response.timestamp))
     }
 
+    async fn change_password(
+        &mut self,
+        old_pwd: &str,
+        new_pwd: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let access_key = self
+            .session
+            .read()
+            .await
+            .access_key
+            .clone()
+            .unwrap_or_default();
+        let request = with_access_key(
+            ChangePasswordRequest {
+                access_key: access_key.clone(),
+                old_pwd: old_pwd.to_string(),
+                new_pwd: new_pwd.to_string(),
+            },
+            &access_key,
+        );
+
+        self.client
+            .as_mut()
+            .unwrap()
+            .change_password(request)
+            .await
+            .map_err(|status| format!("Password change failed: {}", status))?;
+
+        self.credentials.pwd = new_pwd.to_string();
+        Ok(())
+    }
+
     async fn get_session(&self) -> ClientSession {
         self.session.read().await.clone()
     }
@@ -138,16 +219,28 @@ impl Authenticator for AuthClient {
         let session = self.get_session().await;
         session.is_authenticated()
     }
+
+    fn set_load_tracker(&mut self, load_tracker: Arc<LoadTracker>) {
+        self.load_tracker = Some(load_tracker);
+    }
 }
 
 impl AuthClient {
     pub fn new(session: Arc<RwLock<ClientSession>>, descriptor: &ComponentDescriptor) -> Self {
         Self {
             client: None,
+            controller: None,
             session,
             component_type: descriptor.into(),
             credentials: descriptor.get_credentials().clone(),
             connection_settings: descriptor.get_connection_settings().clone(),
+            identity_key: descriptor
+                .get_identity()
+                .map(|identity| identity.public().to_bytes())
+                .unwrap_or_default(),
+            tags: settings::service::get_tags(),
+            roles: settings::service::get_roles(),
+            load_tracker: None,
         }
     }
 }
@@ -0,0 +1,80 @@
+use crate::proxy_client::proxy::{GroupCopy, GroupEnvelope};
+use crate::route_client::route::GroupRecipient;
+use crosscutting::crypto::{Identity, PublicIdentity};
+use prost::Message;
+use std::error::Error;
+
+pub fn seal(
+    identity: &Identity,
+    recipients: &[GroupRecipient],
+    content: &[u8],
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let copies = recipients
+        .iter()
+        .map(|recipient| {
+            let recipient_identity = PublicIdentity::from_bytes(&recipient.recipient_key)?;
+            Ok(GroupCopy {
+                conversation_id: recipient.conversation_id.clone(),
+                content: identity.seal(&recipient_identity, content)?,
+            })
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+    Ok(GroupEnvelope { copies }.encode_to_vec())
+}
+
+pub fn open(content: &[u8]) -> Result<Vec<GroupCopy>, Box<dyn Error>> {
+    let envelope = GroupEnvelope::decode(content)?;
+    Ok(envelope.copies)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const EXPECTED_CONTENT: &[u8] = b"test_content";
+
+    #[test]
+    fn given_recipients_when_sealing_then_each_copy_opens_only_for_its_recipient() {
+        let sender = Identity::generate();
+        let recipients = [Identity::generate(), Identity::generate()];
+        let group_recipients: Vec<GroupRecipient> = recipients
+            .iter()
+            .enumerate()
+            .map(|(index, recipient)| GroupRecipient {
+                conversation_id: format!("conversation_{}", index),
+                recipient_key: recipient.public().to_bytes(),
+            })
+            .collect();
+
+        let envelope = seal(&sender, &group_recipients, EXPECTED_CONTENT).unwrap();
+        let copies = open(&envelope).unwrap();
+
+        assert_eq!(copies.len(), recipients.len());
+        for (index, copy) in copies.iter().enumerate() {
+            assert_eq!(copy.conversation_id, format!("conversation_{}", index));
+            let content = recipients[index]
+                .open(&sender.public(), &copy.content)
+                .unwrap();
+            assert_eq!(content, EXPECTED_CONTENT);
+            assert!(
+                recipients[1 - index]
+                    .open(&sender.public(), &copy.content)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn given_invalid_recipient_key_when_sealing_then_returns_error() {
+        let recipients = vec![GroupRecipient {
+            conversation_id: "conversation".to_string(),
+            recipient_key: vec![1, 2, 3],
+        }];
+
+        let result = seal(&Identity::generate(), &recipients, EXPECTED_CONTENT);
+
+        assert!(result.is_err());
+    }
+}
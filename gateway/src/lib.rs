@@ -1,8 +1,13 @@
+pub mod auth;
+pub mod auth_client;
+pub mod group;
+pub mod load;
+pub mod onion;
 pub mod proxy_client;
 pub mod route_client;
-pub mod auth_client;
-pub mod auth;
+pub mod spend;
+pub mod ticket;
 
 mod auth_proto {
     tonic::include_proto!("auth");
-}
\ No newline at end of file
+}
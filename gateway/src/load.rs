@@ -0,0 +1,118 @@
+use crosscutting::settings::environment;
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU32, AtomicU64, Ordering},
+};
+use tokio::time::Instant;
+
+const PROXY_CAPACITY_KEY: &str = "PROXY_CAPACITY";
+const DEFAULT_CAPACITY: u32 = 100;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LoadSnapshot {
+    pub in_flight: u32,
+    pub throughput: f64,
+    pub available_capacity: u32,
+}
+
+pub struct LoadTracker {
+    capacity: u32,
+    in_flight: AtomicU32,
+    completed: AtomicU64,
+    window: Mutex<(Instant, u64)>,
+}
+
+pub struct LoadGuard {
+    tracker: Arc<LoadTracker>,
+}
+
+impl Default for LoadTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl LoadTracker {
+    pub fn new(capacity: u32) -> Self {
+        Self {
+            capacity,
+            in_flight: AtomicU32::new(0),
+            completed: AtomicU64::new(0),
+            window: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    pub fn get_from_env() -> Self {
+        let capacity = environment::get_env_variable(PROXY_CAPACITY_KEY)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_CAPACITY);
+        Self::new(capacity)
+    }
+
+    /// Counts a command as in flight until the returned guard is dropped.
+    pub fn track(self: &Arc<Self>) -> LoadGuard {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        LoadGuard {
+            tracker: Arc::clone(self),
+        }
+    }
+
+    /// Returns the current load, where the throughput is the number of
+    /// commands completed per second since the previous snapshot.
+    pub fn snapshot(&self) -> LoadSnapshot {
+        let in_flight = self.in_flight.load(Ordering::SeqCst);
+        let completed = self.completed.load(Ordering::SeqCst);
+        let mut window = self.window.lock().unwrap();
+        let elapsed = window.0.elapsed().as_secs_f64();
+        let throughput = if elapsed > 0.0 {
+            (completed - window.1) as f64 / elapsed
+        } else {
+            0.0
+        };
+        *window = (Instant::now(), completed);
+
+        LoadSnapshot {
+            in_flight,
+            throughput,
+            available_capacity: self.capacity.saturating_sub(in_flight),
+        }
+    }
+}
+
+impl Drop for LoadGuard {
+    fn drop(&mut self) {
+        self.tracker.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.tracker.completed.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn given_tracked_commands_when_taking_snapshot_then_reports_load() {
+        let tracker = Arc::new(LoadTracker::new(2));
+        let first = tracker.track();
+        let second = tracker.track();
+        let third = tracker.track();
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.in_flight, 3);
+        assert_eq!(snapshot.available_capacity, 0);
+
+        drop(first);
+        drop(second);
+        drop(third);
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.in_flight, 0);
+        assert_eq!(snapshot.available_capacity, 2);
+        assert!(snapshot.throughput > 0.0);
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.throughput, 0.0);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::proxy_client::proxy::{OnionHop, OnionLayer};
+use crate::route_client::route::RouteResponse;
+use crosscutting::crypto::{self, Identity, PublicIdentity};
+use prost::Message;
+use std::error::Error;
+
+/// Wraps the payload in a layer for every proxy on the circuit. Layers only
+/// carry the signed nonce of their own hop, and the conversation is disclosed
+/// to the last proxy alone, so colluding proxies can't tell they're relaying
+/// the same circuit.
+pub fn wrap<'a>(
+    conversation_id: &str,
+    hops: &'a [RouteResponse],
+    payload: &[u8],
+) -> Result<(&'a RouteResponse, Vec<u8>), Box<dyn Error>> {
+    let (end_hop, proxy_hops) = hops.split_last().ok_or("Circuit has no hops")?;
+    if !end_hop.end_route || proxy_hops.is_empty() {
+        return Err("Circuit must traverse at least one proxy before the recipient".into());
+    }
+
+    if proxy_hops.iter().any(|hop| hop.nonce_ticket.is_empty()) {
+        return Err("Circuit requires signed nonces for every proxy".into());
+    }
+
+    let mut next_hop = OnionHop {
+        conversation_id: conversation_id.to_string(),
+        ..to_onion_hop(end_hop)
+    };
+    let mut content = payload.to_vec();
+
+    for hop in proxy_hops.iter().rev() {
+        let layer = OnionLayer {
+            nonce: hop.nonce.clone(),
+            next_hop: Some(next_hop),
+            payload: content,
+            nonce_ticket: hop.nonce_ticket.clone(),
+        };
+
+        let proxy_identity = PublicIdentity::from_bytes(&hop.identity_key)?;
+        content = crypto::seal_anonymous(&proxy_identity, &layer.encode_to_vec())?;
+        next_hop = to_onion_hop(hop);
+    }
+
+    Ok((&proxy_hops[0], content))
+}
+
+pub fn peel(identity: &Identity, content: &[u8]) -> Result<OnionLayer, Box<dyn Error>> {
+    let layer = identity.open_anonymous(content)?;
+    let layer = OnionLayer::decode(layer.as_slice())?;
+    if layer.next_hop.is_none() {
+        return Err("Onion layer has no next hop".into());
+    }
+
+    Ok(layer)
+}
+
+fn to_onion_hop(hop: &RouteResponse) -> OnionHop {
+    OnionHop {
+        ip_address: hop.ip_address.clone(),
+        port_number: hop.port_number,
+        public_key: hop.public_key.clone(),
+        domain_name: hop.domain_name.clone(),
+        end_route: hop.end_route,
+        nonce: if hop.end_route {
+            hop.nonce.clone()
+        } else {
+            String::default()
+        },
+        conversation_id: String::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const EXPECTED_CONVERSATION_ID: &str = "test_conversation";
+    const EXPECTED_PAYLOAD: &[u8] = b"test_payload";
+
+    fn create_hop(identity: &Identity, nonce: &str, end_route: bool) -> RouteResponse {
+        RouteResponse {
+            ip_address: "127.0.0.1".to_string(),
+            port_number: 8080,
+            public_key: vec![],
+            domain_name: format!("{}.domain", nonce),
+            nonce: nonce.to_string(),
+            end_route,
+            identity_key: identity.public().to_bytes(),
+            nonce_ticket: if end_route {
+                Vec::new()
+            } else {
+                format!("{}_ticket", nonce).into_bytes()
+            },
+        }
+    }
+
+    #[test]
+    fn given_circuit_when_peeling_every_layer_then_each_hop_learns_only_next_hop() {
+        let proxies = [
+            Identity::generate(),
+            Identity::generate(),
+            Identity::generate(),
+        ];
+        let recipient = Identity::generate();
+        let hops = vec![
+            create_hop(&proxies[0], "nonce_1", false),
+            create_hop(&proxies[1], "nonce_2", false),
+            create_hop(&proxies[2], "nonce_3", false),
+            create_hop(&recipient, "nonce_4", true),
+        ];
+
+        let (entry_hop, mut content) =
+            wrap(EXPECTED_CONVERSATION_ID, &hops, EXPECTED_PAYLOAD).unwrap();
+        assert_eq!(entry_hop.nonce, "nonce_1");
+
+        for (index, proxy) in proxies.iter().enumerate() {
+            let layer = peel(proxy, &content).unwrap();
+            let next_hop = layer.next_hop.unwrap();
+
+            assert_eq!(layer.nonce, hops[index].nonce);
+            assert_eq!(layer.nonce_ticket, hops[index].nonce_ticket);
+            assert_eq!(next_hop.domain_name, hops[index + 1].domain_name);
+            assert_eq!(next_hop.end_route, hops[index + 1].end_route);
+            if next_hop.end_route {
+                assert_eq!(next_hop.conversation_id, EXPECTED_CONVERSATION_ID);
+            } else {
+                assert!(next_hop.conversation_id.is_empty());
+            }
+            content = layer.payload;
+        }
+
+        assert_eq!(content, EXPECTED_PAYLOAD);
+    }
+
+    #[test]
+    fn given_next_hop_is_proxy_when_peeling_then_nonce_is_not_disclosed() {
+        let proxies = [Identity::generate(), Identity::generate()];
+        let recipient = Identity::generate();
+        let hops = vec![
+            create_hop(&proxies[0], "nonce_1", false),
+            create_hop(&proxies[1], "nonce_2", false),
+            create_hop(&recipient, "nonce_3", true),
+        ];
+
+        let (_, content) = wrap(EXPECTED_CONVERSATION_ID, &hops, EXPECTED_PAYLOAD).unwrap();
+        let layer = peel(&proxies[0], &content).unwrap();
+        assert!(layer.next_hop.unwrap().nonce.is_empty());
+
+        let layer = peel(&proxies[1], &layer.payload).unwrap();
+        assert_eq!(layer.next_hop.unwrap().nonce, "nonce_3");
+    }
+
+    #[test]
+    fn given_wrong_proxy_when_peeling_then_returns_error() {
+        let proxy = Identity::generate();
+        let recipient = Identity::generate();
+        let hops = vec![
+            create_hop(&proxy, "nonce_1", false),
+            create_hop(&recipient, "nonce_2", true),
+        ];
+
+        let (_, content) = wrap(EXPECTED_CONVERSATION_ID, &hops, EXPECTED_PAYLOAD).unwrap();
+        let result = peel(&Identity::generate(), &content);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_circuit_without_proxies_when_wrapping_then_returns_error() {
+        let recipient = Identity::generate();
+        let hops = vec![create_hop(&recipient, "nonce_1", true)];
+
+        let result = wrap(EXPECTED_CONVERSATION_ID, &hops, EXPECTED_PAYLOAD);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_proxy_without_signed_nonce_when_wrapping_then_returns_error() {
+        let mut proxy_hop = create_hop(&Identity::generate(), "nonce_1", false);
+        proxy_hop.nonce_ticket = Vec::new();
+        let hops = vec![
+            proxy_hop,
+            create_hop(&Identity::generate(), "nonce_2", true),
+        ];
+
+        let result = wrap(EXPECTED_CONVERSATION_ID, &hops, EXPECTED_PAYLOAD);
+
+        assert!(result.is_err());
+    }
+}
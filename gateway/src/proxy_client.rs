@@ -36,6 +36,7 @@ pub trait Proxy: GrpcClient {
         &mut self,
         conversation_id: String,
         nonce: String,
+        nonce_ticket: Vec<u8>,
         command: CommandType,
         content: Vec<u8>,
     ) -> Result<CommandResponse, Box<dyn Error>>;
@@ -66,6 +67,7 @@ impl Proxy for ProxyClient {
         &mut self,
         conversation_id: String,
         nonce: String,
+        nonce_ticket: Vec<u8>,
         command: CommandType,
         content: Vec<u8>,
     ) -> Result<CommandResponse, Box<dyn Error>> {
@@ -78,8 +80,11 @@ impl Proxy for ProxyClient {
             nonce,
             command: command.into(),
             content: Some(content),
+            nonce_ticket,
         };
 
+        // No access key is attached: it is a bearer credential for the
+        // controller and proxies rate limit their callers by address anyway
         let response = self
             .client
             .as_mut()
@@ -1,27 +1,37 @@
-use crosscutting::{Component, abstractions::GrpcClient};
+use crosscutting::{abstractions::GrpcClient, failover, rate_limit::with_access_key};
 use mockall::automock;
 use std::error::Error;
-use tonic::{
-    Request, async_trait,
-    transport::{Channel, ClientTlsConfig},
-};
+use tonic::{Status, async_trait, transport::Channel};
 
 pub mod route {
     tonic::include_proto!("route");
 }
 
 use route::{
-    InitRequest, InitResponse, RedeemRequest, RedeemResponse, RouteRequest, RouteResponse,
+    CircuitResponse, ContactAction, ContactRequest, GroupAction, GroupInitRequest,
+    GroupInitResponse, GroupRequest, InitRequest, InitResponse, RedeemRequest, RedeemResponse,
+    RouteRequest, RouteResponse, SpendReport, SpentNonce, VisibilityRequest,
     route_service_client::RouteServiceClient,
 };
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recipient {
+    Uid(String),
+    ReplyHandle(String),
+}
+
 #[async_trait]
 #[automock]
 pub trait Router: GrpcClient {
+    /// Conversations started with an ephemeral `anonymous_key` are anonymous,
+    /// and their content must be sealed with that key.
     async fn init_conversation(
         &mut self,
         access_key: String,
-        to: String,
+        recipient: Recipient,
+        anonymous_key: Option<Vec<u8>>,
+        hops: u32,
+        source_routed: bool,
     ) -> Result<InitResponse, Box<dyn Error>>;
 
     async fn get_route(
@@ -30,35 +40,73 @@ pub trait Router: GrpcClient {
         access_key: String,
     ) -> Result<RouteResponse, Box<dyn Error>>;
 
+    async fn get_circuit(
+        &mut self,
+        conversation_id: String,
+        access_key: String,
+    ) -> Result<CircuitResponse, Box<dyn Error>>;
+
     async fn redeem(
         &mut self,
         conversation_id: String,
         access_key: String,
         nonce: String,
     ) -> Result<RedeemResponse, Box<dyn Error>>;
+
+    async fn report_spent(
+        &mut self,
+        access_key: String,
+        nonces: Vec<SpentNonce>,
+    ) -> Result<(), Box<dyn Error>>;
+
+    async fn update_contact(
+        &mut self,
+        access_key: String,
+        action: ContactAction,
+        uid: String,
+    ) -> Result<(), Box<dyn Error>>;
+
+    async fn set_visibility(
+        &mut self,
+        access_key: String,
+        visible: bool,
+    ) -> Result<(), Box<dyn Error>>;
+
+    async fn update_group(
+        &mut self,
+        access_key: String,
+        action: GroupAction,
+        group: String,
+        uid: String,
+    ) -> Result<(), Box<dyn Error>>;
+
+    async fn init_group(
+        &mut self,
+        access_key: String,
+        group: String,
+    ) -> Result<GroupInitResponse, Box<dyn Error>>;
 }
 
 #[derive(Default)]
 struct RouteClient {
     client: Option<RouteServiceClient<Channel>>,
+    controller: Option<usize>,
+}
+
+impl RouteClient {
+    fn to_error(&self, status: Status, message: &str) -> Box<dyn Error> {
+        failover::report_status(self.controller, &status);
+        format!("{}: {}", message, status).into()
+    }
 }
 
 #[async_trait]
 impl GrpcClient for RouteClient {
     async fn initialize(&mut self) -> Result<(), Box<dyn Error>> {
-        let controller_settings = Component::Controller.get_connection_settings()?;
-        let tls_config = ClientTlsConfig::new()
-            .ca_certificate(tonic::transport::Certificate::from_pem(
-                controller_settings.certificate.clone(),
-            ))
-            .domain_name(controller_settings.domain_name.clone());
-
-        let channel = Channel::builder(controller_settings.get_public_endpoint())
-            .tls_config(tls_config)?
-            .connect()
-            .await?;
+        let (channel, controller) = failover::connect_session_controller().await?;
 
         self.client = Some(RouteServiceClient::new(channel));
+        self.controller = Some(controller);
         Ok(())
     }
 }
@@ -68,17 +116,32 @@ impl Router for RouteClient {
     async fn init_conversation(
         &mut self,
         access_key: String,
-        to: String,
+        recipient: Recipient,
+        anonymous_key: Option<Vec<u8>>,
+        hops: u32,
+        source_routed: bool,
     ) -> Result<InitResponse, Box<dyn Error>> {
-        let request = InitRequest { access_key, to };
+        let (to, reply_handle) = match recipient {
+            Recipient::Uid(to) => (to, String::default()),
+            Recipient::ReplyHandle(reply_handle) => (String::default(), reply_handle),
+        };
+        let request = InitRequest {
+            access_key: access_key.clone(),
+            to,
+            anonymous: anonymous_key.is_some(),
+            reply_handle,
+            hops,
+            source_routed,
+            sender_key: anonymous_key.unwrap_or_default(),
+        };
 
         let response = self
             .client
             .as_mut()
             .unwrap()
-            .initialize(Request::new(request))
+            .initialize(with_access_key(request, &access_key))
             .await
-            .map_err(|status| format!("Impossible to initialize the conversation: {}", status))?;
+            .map_err(|status| self.to_error(status, "Impossible to initialize the conversation"))?;
 
         Ok(response.into_inner())
     }
@@ -89,7 +152,7 @@ impl Router for RouteClient {
         access_key: String,
     ) -> Result<RouteResponse, Box<dyn Error>> {
         let request = RouteRequest {
-            access_key,
+            access_key: access_key.clone(),
             conversation_id,
         };
 
@@ -97,17 +160,38 @@ impl Router for RouteClient {
             .client
             .as_mut()
             .unwrap()
-            .route(Request::new(request))
+            .route(with_access_key(request, &access_key))
             .await
         {
             Ok(response) => {
                 let response = response.into_inner();
                 Ok(response)
             }
-            Err(status) => Err(format!("Impossible to get a route: {}", status).into()),
+            Err(status) => Err(self.to_error(status, "Impossible to get a route")),
         }
     }
 
+    async fn get_circuit(
+        &mut self,
+        conversation_id: String,
+        access_key: String,
+    ) -> Result<CircuitResponse, Box<dyn Error>> {
+        let request = RouteRequest {
+            access_key: access_key.clone(),
+            conversation_id,
+        };
+
+        let response = self
+            .client
+            .as_mut()
+            .unwrap()
+            .circuit(with_access_key(request, &access_key))
+            .await
+            .map_err(|status| self.to_error(status, "Impossible to get a circuit"))?;
+
+        Ok(response.into_inner())
+    }
+
     async fn redeem(
         &mut self,
         conversation_id: String,
@@ -115,7 +199,7 @@ impl Router for RouteClient {
         nonce: String,
     ) -> Result<RedeemResponse, Box<dyn Error>> {
         let request = RedeemRequest {
-            access_key,
+            access_key: access_key.clone(),
             conversation_id,
             nonce,
         };
@@ -124,16 +208,123 @@ impl Router for RouteClient {
             .client
             .as_mut()
             .unwrap()
-            .redeem(Request::new(request))
+            .redeem(with_access_key(request, &access_key))
             .await
         {
             Ok(response) => {
                 let response = response.into_inner();
                 Ok(response)
             }
-            Err(_) => Err("Impossible to redeem a route".into()),
+            Err(status) => Err(self.to_error(status, "Impossible to redeem a route")),
         }
     }
+
+    async fn report_spent(
+        &mut self,
+        access_key: String,
+        nonces: Vec<SpentNonce>,
+    ) -> Result<(), Box<dyn Error>> {
+        let request = SpendReport {
+            access_key: access_key.clone(),
+            nonces,
+        };
+
+        self.client
+            .as_mut()
+            .unwrap()
+            .report_spent(with_access_key(request, &access_key))
+            .await
+            .map_err(|status| self.to_error(status, "Impossible to report the spent nonces"))?;
+
+        Ok(())
+    }
+
+    async fn update_contact(
+        &mut self,
+        access_key: String,
+        action: ContactAction,
+        uid: String,
+    ) -> Result<(), Box<dyn Error>> {
+        let request = ContactRequest {
+            access_key: access_key.clone(),
+            action: action as i32,
+            uid,
+        };
+
+        self.client
+            .as_mut()
+            .unwrap()
+            .update_contact(with_access_key(request, &access_key))
+            .await
+            .map_err(|status| self.to_error(status, "Impossible to update the contact list"))?;
+
+        Ok(())
+    }
+
+    async fn set_visibility(
+        &mut self,
+        access_key: String,
+        visible: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let request = VisibilityRequest {
+            access_key: access_key.clone(),
+            visible,
+        };
+
+        self.client
+            .as_mut()
+            .unwrap()
+            .set_visibility(with_access_key(request, &access_key))
+            .await
+            .map_err(|status| self.to_error(status, "Impossible to update the presence"))?;
+
+        Ok(())
+    }
+
+    async fn update_group(
+        &mut self,
+        access_key: String,
+        action: GroupAction,
+        group: String,
+        uid: String,
+    ) -> Result<(), Box<dyn Error>> {
+        let request = GroupRequest {
+            access_key: access_key.clone(),
+            action: action as i32,
+            group,
+            uid,
+        };
+
+        self.client
+            .as_mut()
+            .unwrap()
+            .update_group(with_access_key(request, &access_key))
+            .await
+            .map_err(|status| self.to_error(status, "Impossible to update the group"))?;
+
+        Ok(())
+    }
+
+    async fn init_group(
+        &mut self,
+        access_key: String,
+        group: String,
+    ) -> Result<GroupInitResponse, Box<dyn Error>> {
+        let request = GroupInitRequest {
+            access_key: access_key.clone(),
+            group,
+        };
+
+        let response = self
+            .client
+            .as_mut()
+            .unwrap()
+            .init_group(with_access_key(request, &access_key))
+            .await
+            .map_err(|status| self.to_error(status, "Impossible to initialize the group"))?;
+
+        Ok(response.into_inner())
+    }
 }
 
 #[derive(Default)]
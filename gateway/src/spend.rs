@@ -0,0 +1,175 @@
+use crate::auth_client::Authenticator;
+use crate::route_client::{RouterFactory, route::SpentNonce};
+use crate::ticket::NonceCache;
+use log::{debug, warn};
+use std::error::Error;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::{Duration, sleep};
+use tokio_util::sync::CancellationToken;
+
+const REPORT_INTERVAL: Duration = Duration::from_millis(5000);
+
+struct SpendReporter {
+    authenticator: Arc<RwLock<Box<dyn Authenticator>>>,
+    nonce_cache: Arc<NonceCache>,
+    router_factory: Box<dyn RouterFactory>,
+}
+
+impl SpendReporter {
+    /// Reports the nonces redeemed locally since the last report. Those which
+    /// can't be reported are queued again for the next one, until they expire.
+    async fn report(&self) -> Result<usize, Box<dyn Error>> {
+        let nonces = self.nonce_cache.take_spent();
+        if nonces.is_empty() {
+            return Ok(0);
+        }
+
+        let count = nonces.len();
+        let batch = nonces.iter().map(|(spent, _)| spent.clone()).collect();
+        self.send(batch).await.inspect_err(|_| {
+            self.nonce_cache.requeue(nonces);
+        })?;
+        Ok(count)
+    }
+
+    async fn send(&self, nonces: Vec<SpentNonce>) -> Result<(), Box<dyn Error>> {
+        let access_key = self
+            .authenticator
+            .read()
+            .await
+            .get_session()
+            .await
+            .access_key
+            .ok_or("Not authenticated")?;
+
+        let mut router = self.router_factory.get_router();
+        router.initialize().await?;
+        router.report_spent(access_key, nonces).await?;
+        Ok(())
+    }
+}
+
+pub fn start_spend_handler(
+    authenticator: Arc<RwLock<Box<dyn Authenticator>>>,
+    nonce_cache: Arc<NonceCache>,
+    router_factory: Box<dyn RouterFactory>,
+    cancellation_token: CancellationToken,
+) -> tokio::task::JoinHandle<()> {
+    let reporter = SpendReporter {
+        authenticator,
+        nonce_cache,
+        router_factory,
+    };
+
+    tokio::spawn(async move {
+        while !cancellation_token.is_cancelled() {
+            tokio::select! {
+                _ = cancellation_token.cancelled() => {}
+                _ = sleep(REPORT_INTERVAL) => {}
+            }
+
+            match reporter.report().await {
+                Ok(0) => {}
+                Ok(count) => debug!("Reported {} spent nonces", count),
+                Err(e) => warn!("Failed to report spent nonces: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::auth_client::{ClientSession, MockAuthenticator};
+    use crate::route_client::{MockRouter, MockRouterFactory};
+
+    const EXPECTED_ACCESS_KEY: &str = "test_access_key";
+
+    fn create_reporter(router_factory: MockRouterFactory) -> SpendReporter {
+        let mut mock_authenticator = MockAuthenticator::new();
+        mock_authenticator.expect_get_session().returning(|| {
+            Box::pin(async {
+                ClientSession {
+                    access_key: Some(EXPECTED_ACCESS_KEY.to_string()),
+                    ..Default::default()
+                }
+            })
+        });
+
+        SpendReporter {
+            authenticator: Arc::new(RwLock::new(Box::new(mock_authenticator))),
+            nonce_cache: Arc::default(),
+            router_factory: Box::new(router_factory),
+        }
+    }
+
+    #[tokio::test]
+    async fn given_no_spent_nonces_when_reporting_then_controller_is_not_called() {
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().never();
+
+        let reporter = create_reporter(router_factory);
+
+        assert_eq!(reporter.report().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn given_spent_nonces_when_reporting_then_they_are_sent_in_one_batch() {
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().times(1).returning(|| {
+            let mut mock_router = MockRouter::new();
+            mock_router
+                .expect_report_spent()
+                .withf(|access_key, nonces| {
+                    access_key == EXPECTED_ACCESS_KEY
+                        && nonces
+                            .iter()
+                            .map(|spent| spent.nonce.as_str())
+                            .eq(["nonce_1", "nonce_2"])
+                })
+                .times(1)
+                .returning(|_, _| Box::pin(async { Ok(()) }));
+            Box::new(mock_router)
+        });
+
+        let reporter = create_reporter(router_factory);
+        for nonce in ["nonce_1", "nonce_2"] {
+            let spent = SpentNonce {
+                conversation_id: "conversation".to_string(),
+                nonce: nonce.to_string(),
+                conversation_ref: Vec::new(),
+            };
+            reporter.nonce_cache.redeem(spent, i64::MAX);
+        }
+
+        assert_eq!(reporter.report().await.unwrap(), 2);
+        assert!(reporter.nonce_cache.take_spent().is_empty());
+    }
+
+    #[tokio::test]
+    async fn given_failed_report_when_reporting_then_nonces_are_queued_again() {
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().times(1).returning(|| {
+            let mut mock_router = MockRouter::new();
+            mock_router
+                .expect_report_spent()
+                .returning(|_, _| Box::pin(async { Err("Unavailable".into()) }));
+            Box::new(mock_router)
+        });
+
+        let reporter = create_reporter(router_factory);
+        let spent = SpentNonce {
+            conversation_id: "conversation".to_string(),
+            nonce: "nonce_1".to_string(),
+            conversation_ref: Vec::new(),
+        };
+        reporter.nonce_cache.redeem(spent, i64::MAX);
+
+        assert!(reporter.report().await.is_err());
+        let spent = reporter.nonce_cache.take_spent();
+        assert_eq!(spent.len(), 1);
+        assert_eq!(spent[0].0.nonce, "nonce_1");
+    }
+}
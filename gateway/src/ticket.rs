@@ -0,0 +1,287 @@
+use crate::route_client::route::{HopTicket, NonceTicket, SpentNonce};
+use crosscutting::crypto::{Identity, PublicIdentity, SIGNATURE_LENGTH};
+use prost::Message;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_SPENT_NONCES: usize = 10_000;
+
+/// Verifies a route ticket signed by the controller and checks that it was
+/// issued to this proxy and hasn't expired yet.
+pub fn open(
+    ticket_key: &[u8],
+    identity: &Identity,
+    signed: &[u8],
+) -> Result<HopTicket, Box<dyn Error>> {
+    let ticket = HopTicket::decode(verify(ticket_key, signed)?)?;
+    check_holder(identity, &ticket.holder_key, ticket.expires_at)?;
+    if ticket.next_hop.is_none() {
+        return Err("Route ticket has no next hop".into());
+    }
+
+    Ok(ticket)
+}
+
+/// Verifies a nonce signed by the controller, so the proxy holding it can
+/// redeem it without asking the controller.
+pub fn open_nonce(
+    ticket_key: &[u8],
+    identity: &Identity,
+    signed: &[u8],
+) -> Result<NonceTicket, Box<dyn Error>> {
+    let ticket = NonceTicket::decode(verify(ticket_key, signed)?)?;
+    check_holder(identity, &ticket.holder_key, ticket.expires_at)?;
+    Ok(ticket)
+}
+
+fn verify<'a>(ticket_key: &[u8], signed: &'a [u8]) -> Result<&'a [u8], Box<dyn Error>> {
+    if signed.len() < SIGNATURE_LENGTH {
+        return Err("Route ticket is too short".into());
+    }
+
+    let (signature, ticket) = signed.split_at(SIGNATURE_LENGTH);
+    PublicIdentity::from_bytes(ticket_key)?.verify(ticket, signature)?;
+    Ok(ticket)
+}
+
+fn check_holder(
+    identity: &Identity,
+    holder_key: &[u8],
+    expires_at: i64,
+) -> Result<(), Box<dyn Error>> {
+    if holder_key != identity.public().to_bytes() {
+        return Err("Route ticket was issued to another proxy".into());
+    }
+
+    if expires_at <= get_timestamp() {
+        return Err("Route ticket has expired".into());
+    }
+
+    Ok(())
+}
+
+/// Keeps the nonces of the redeemed tickets until they expire, so every
+/// ticket can be redeemed only once, and collects them to be reported to the
+/// controller in batches. Nonces are only kept in memory, so a restarted proxy
+/// accepts again the tickets it redeemed which haven't expired yet.
+#[derive(Default)]
+pub struct NonceCache {
+    redeemed: Mutex<HashMap<(String, String), i64>>,
+    spent: Mutex<Vec<(SpentNonce, i64)>>,
+}
+
+impl NonceCache {
+    pub fn redeem(&self, spent: SpentNonce, expires_at: i64) -> bool {
+        let now = get_timestamp();
+        let mut redeemed = self.redeemed.lock().unwrap();
+        redeemed.retain(|_, expires_at| *expires_at > now);
+
+        let key = (spent.conversation_id.clone(), spent.nonce.clone());
+        if redeemed.contains_key(&key) {
+            return false;
+        }
+
+        redeemed.insert(key, expires_at);
+        self.spent.lock().unwrap().push((spent, expires_at));
+        true
+    }
+
+    /// Takes the spent nonces along with their expiry, leaving out those
+    /// which have already expired and so are no longer worth reporting.
+    pub fn take_spent(&self) -> Vec<(SpentNonce, i64)> {
+        let now = get_timestamp();
+        let mut spent = std::mem::take(&mut *self.spent.lock().unwrap());
+        spent.retain(|(_, expires_at)| *expires_at > now);
+        spent
+    }
+
+    /// Puts back nonces which couldn't be reported, ahead of the ones spent
+    /// since they were taken. Expired nonces are dropped, and so are the
+    /// oldest ones once the queue is full.
+    pub fn requeue(&self, nonces: Vec<(SpentNonce, i64)>) {
+        let now = get_timestamp();
+        let mut spent = self.spent.lock().unwrap();
+        spent.splice(0..0, nonces);
+        spent.retain(|(_, expires_at)| *expires_at > now);
+
+        let excess = spent.len().saturating_sub(MAX_SPENT_NONCES);
+        spent.drain(..excess);
+    }
+}
+
+fn get_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::route_client::route::RouteResponse;
+
+    const EXPECTED_CONVERSATION_ID: &str = "test_conversation";
+    const EXPECTED_NONCE: &str = "test_nonce";
+
+    fn create_ticket(holder: &Identity, expires_at: i64) -> HopTicket {
+        HopTicket {
+            conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            nonce: EXPECTED_NONCE.to_string(),
+            holder_key: holder.public().to_bytes(),
+            next_hop: Some(RouteResponse::default()),
+            expires_at,
+            conversation_ref: Vec::new(),
+        }
+    }
+
+    fn sign(controller: &Identity, ticket: &HopTicket) -> Vec<u8> {
+        let ticket = ticket.encode_to_vec();
+        [controller.sign(&ticket), ticket].concat()
+    }
+
+    #[test]
+    fn given_valid_ticket_when_opening_then_returns_ticket() {
+        let controller = Identity::generate();
+        let proxy = Identity::generate();
+        let signed = sign(&controller, &create_ticket(&proxy, get_timestamp() + 60));
+
+        let ticket = open(&controller.public().to_bytes(), &proxy, &signed).unwrap();
+
+        assert_eq!(ticket.conversation_id, EXPECTED_CONVERSATION_ID);
+        assert_eq!(ticket.nonce, EXPECTED_NONCE);
+    }
+
+    #[test]
+    fn given_forged_ticket_when_opening_then_returns_error() {
+        let controller = Identity::generate();
+        let proxy = Identity::generate();
+        let signed = sign(
+            &Identity::generate(),
+            &create_ticket(&proxy, get_timestamp() + 60),
+        );
+
+        let result = open(&controller.public().to_bytes(), &proxy, &signed);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_ticket_for_another_proxy_when_opening_then_returns_error() {
+        let controller = Identity::generate();
+        let signed = sign(
+            &controller,
+            &create_ticket(&Identity::generate(), get_timestamp() + 60),
+        );
+
+        let result = open(
+            &controller.public().to_bytes(),
+            &Identity::generate(),
+            &signed,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_expired_ticket_when_opening_then_returns_error() {
+        let controller = Identity::generate();
+        let proxy = Identity::generate();
+        let signed = sign(&controller, &create_ticket(&proxy, get_timestamp() - 1));
+
+        let result = open(&controller.public().to_bytes(), &proxy, &signed);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_signed_nonce_when_opening_then_returns_ticket() {
+        let controller = Identity::generate();
+        let proxy = Identity::generate();
+        let ticket = NonceTicket {
+            nonce: EXPECTED_NONCE.to_string(),
+            holder_key: proxy.public().to_bytes(),
+            expires_at: get_timestamp() + 60,
+            conversation_ref: Vec::new(),
+            conversation_tag: Vec::new(),
+        }
+        .encode_to_vec();
+        let signed = [controller.sign(&ticket), ticket].concat();
+
+        let ticket = open_nonce(&controller.public().to_bytes(), &proxy, &signed).unwrap();
+        assert_eq!(ticket.nonce, EXPECTED_NONCE);
+
+        let result = open_nonce(
+            &controller.public().to_bytes(),
+            &Identity::generate(),
+            &signed,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_redeemed_nonce_when_redeeming_again_then_returns_false() {
+        let nonce_cache = NonceCache::default();
+        let expires_at = get_timestamp() + 60;
+        let spent = SpentNonce {
+            conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            nonce: EXPECTED_NONCE.to_string(),
+            conversation_ref: Vec::new(),
+        };
+
+        assert!(nonce_cache.redeem(spent.clone(), expires_at));
+        assert!(!nonce_cache.redeem(spent, expires_at));
+
+        let spent = nonce_cache.take_spent();
+        assert_eq!(spent.len(), 1);
+        assert_eq!(spent[0].0.nonce, EXPECTED_NONCE);
+        assert!(nonce_cache.take_spent().is_empty());
+    }
+
+    #[test]
+    fn given_expired_nonces_when_requeueing_then_they_are_dropped() {
+        let nonce_cache = NonceCache::default();
+        let create_spent = |nonce: &str, expires_at: i64| {
+            let spent = SpentNonce {
+                conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+                nonce: nonce.to_string(),
+                conversation_ref: Vec::new(),
+            };
+            (spent, expires_at)
+        };
+
+        nonce_cache.requeue(vec![
+            create_spent("expired_nonce", get_timestamp() - 1),
+            create_spent(EXPECTED_NONCE, get_timestamp() + 60),
+        ]);
+
+        let spent = nonce_cache.take_spent();
+        assert_eq!(spent.len(), 1);
+        assert_eq!(spent[0].0.nonce, EXPECTED_NONCE);
+    }
+
+    #[test]
+    fn given_full_queue_when_requeueing_then_oldest_nonces_are_dropped() {
+        let nonce_cache = NonceCache::default();
+        let expires_at = get_timestamp() + 60;
+        let nonces = (0..=MAX_SPENT_NONCES)
+            .map(|index| {
+                let spent = SpentNonce {
+                    conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+                    nonce: format!("nonce_{}", index),
+                    conversation_ref: Vec::new(),
+                };
+                (spent, expires_at)
+            })
+            .collect();
+
+        nonce_cache.requeue(nonces);
+
+        let spent = nonce_cache.take_spent();
+        assert_eq!(spent.len(), MAX_SPENT_NONCES);
+        assert_eq!(spent[0].0.nonce, "nonce_1");
+    }
+}
@@ -1,55 +1,75 @@
 use super::*;
 use crate::models::info_proto::{
-    StatusRequest, StatusResponse, info_service_client::InfoServiceClient,
+    OnlineContactsRequest, StatusRequest, StatusResponse, info_service_client::InfoServiceClient,
 };
-use crosscutting::{Component, abstractions::GrpcClient};
+use crosscutting::{abstractions::GrpcClient, failover, rate_limit::with_access_key};
 use std::error::Error;
-use tonic::{
-    Request,
-    transport::{Channel, ClientTlsConfig},
-};
+use tonic::transport::Channel;
 
 #[async_trait]
 #[automock]
 pub trait Informer: GrpcClient {
     async fn get_status(&mut self, access_key: String) -> Result<StatusResponse, Box<dyn Error>>;
+
+    async fn get_online_contacts(
+        &mut self,
+        access_key: String,
+        conversation_id: String,
+        uids: Vec<String>,
+    ) -> Result<Vec<String>, Box<dyn Error>>;
 }
 
 #[derive(Default)]
 struct InfoClient {
     client: Option<InfoServiceClient<Channel>>,
+    controller: Option<usize>,
 }
 
 #[async_trait]
 impl Informer for InfoClient {
     async fn get_status(&mut self, access_key: String) -> Result<StatusResponse, Box<dyn Error>> {
-        let request = StatusRequest { access_key };
+        let request = StatusRequest {
+            access_key: access_key.clone(),
+        };
         let response = self
             .client
             .as_mut()
             .unwrap()
-            .status(Request::new(request))
-            .await?;
+            .status(with_access_key(request, &access_key))
+            .await
+            .inspect_err(|status| failover::report_status(self.controller, status))?;
         Ok(response.into_inner())
     }
+
+    async fn get_online_contacts(
+        &mut self,
+        access_key: String,
+        conversation_id: String,
+        uids: Vec<String>,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let request = OnlineContactsRequest {
+            access_key: access_key.clone(),
+            conversation_id,
+            uids,
+        };
+        let response = self
+            .client
+            .as_mut()
+            .unwrap()
+            .online_contacts(with_access_key(request, &access_key))
+            .await
+            .inspect_err(|status| failover::report_status(self.controller, status))?;
+        Ok(response.into_inner().uids)
+    }
 }
 
 #[async_trait]
 impl GrpcClient for InfoClient {
     async fn initialize(&mut self) -> Result<(), Box<dyn Error>> {
-        let controller_settings = Component::Controller.get_connection_settings()?;
-        let tls_config = ClientTlsConfig::new()
-            .ca_certificate(tonic::transport::Certificate::from_pem(
-                controller_settings.certificate.clone(),
-            ))
-            .domain_name(controller_settings.domain_name.clone());
-
-        let channel = Channel::builder(controller_settings.get_public_endpoint())
-            .tls_config(tls_config)?
-            .connect()
-            .await?;
+        let (channel, controller) = failover::connect_session_controller().await?;
 
         self.client = Some(InfoServiceClient::new(channel));
+        self.controller = Some(controller);
 
         Ok(())
     }
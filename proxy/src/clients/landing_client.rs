@@ -1,10 +1,7 @@
 use super::*;
-use crosscutting::abstractions::GrpcClient;
+use crosscutting::{abstractions::GrpcClient, rate_limit::with_access_key};
 use std::error::Error;
-use tonic::{
-    Request,
-    transport::{Channel, ClientTlsConfig, Uri},
-};
+use tonic::transport::{Channel, ClientTlsConfig, Uri};
 
 pub mod client {
     tonic::include_proto!("client");
@@ -51,19 +48,17 @@ impl Lander for LandingClient {
         nonce: String,
         content: Vec<u8>,
     ) -> Result<TextResponse, Box<dyn Error>> {
-        let request = TextRequest {
-            conversation_id,
-            access_key,
-            nonce,
-            content,
-        };
+        let request = with_access_key(
+            TextRequest {
+                conversation_id,
+                access_key: access_key.clone(),
+                nonce,
+                content,
+            },
+            &access_key,
+        );
 
-        let response = self
-            .client
-            .as_mut()
-            .unwrap()
-            .receive(Request::new(request))
-            .await?;
+        let response = self.client.as_mut().unwrap().receive(request).await?;
         Ok(response.into_inner())
     }
 }
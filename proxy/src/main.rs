@@ -2,10 +2,14 @@ mod clients;
 mod models;
 mod services;
 
-use gateway::auth::start_auth_handler;
-use gateway::auth_client::{AuthClientFactory, AuthenticatorFactory, ClientSession};
 use crosscutting::settings;
 use crosscutting::{Component, ComponentDescriptor};
+use gateway::auth::start_auth_handler;
+use gateway::auth_client::{AuthClientFactory, AuthenticatorFactory, ClientSession};
+use gateway::load::LoadTracker;
+use gateway::route_client::RouteClientFactory;
+use gateway::spend::start_spend_handler;
+use gateway::ticket::NonceCache;
 use log::{debug, info};
 use std::error::Error;
 use std::sync::Arc;
@@ -25,17 +29,36 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let authenticator_factory = AuthClientFactory {};
     let mut authenticator = authenticator_factory.get_authenticator(client_session, &descriptor);
     authenticator.initialize().await?;
+    let load_tracker = Arc::new(LoadTracker::get_from_env());
+    authenticator.set_load_tracker(Arc::clone(&load_tracker));
     let authenticator = Arc::new(RwLock::new(authenticator));
     let cancellation_token = CancellationToken::new();
 
     let auth_handle =
         start_auth_handler(Arc::clone(&authenticator), cancellation_token.child_token());
+    let nonce_cache = Arc::new(NonceCache::default());
+    let spend_handle = start_spend_handler(
+        Arc::clone(&authenticator),
+        Arc::clone(&nonce_cache),
+        Box::new(RouteClientFactory),
+        cancellation_token.child_token(),
+    );
 
     let socket_address = descriptor
         .get_connection_settings()
         .get_local_socket_address();
     info!("Starting gRPC server on {}...", socket_address);
-    let server_handle = services::start_server_handler(socket_address, authenticator);
+    let identity = descriptor
+        .get_identity()
+        .ok_or("Proxy identity is not available")?
+        .clone();
+    let server_handle = services::start_server_handler(
+        socket_address,
+        authenticator,
+        identity,
+        load_tracker,
+        nonce_cache,
+    );
     debug!("Press Ctrl+C to exit gracefully");
     _ = signal::ctrl_c().await;
     debug!("Received shutdown signal, terminating gracefully...");
@@ -43,6 +66,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     cancellation_token.cancel();
     server_handle.abort();
     _ = auth_handle.await;
+    _ = spend_handle.await;
     _ = server_handle.await;
 
     info!("Proxy has been shut down gracefully");
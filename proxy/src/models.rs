@@ -1,3 +1,4 @@
+#[allow(dead_code)]
 pub mod proxy_proto {
     tonic::include_proto!("proxy");
 }
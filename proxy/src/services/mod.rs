@@ -2,8 +2,11 @@ pub mod proxy_service;
 
 use crate::models::proxy_proto::proxy_service_server::ProxyServiceServer;
 use gateway::auth_client::Authenticator;
+use gateway::load::LoadTracker;
+use gateway::ticket::NonceCache;
+use crosscutting::crypto::Identity;
 use crosscutting::settings::service;
-use crosscutting::tracing;
+use crosscutting::{rate_limit, tracing};
 use log::{debug, error};
 use proxy_service::ProxyServiceImpl;
 use std::error::Error;
@@ -16,21 +19,35 @@ use tonic::{Request, Response, Status};
 pub struct ProxyGrpcServer {
     authenticator: Arc<RwLock<Box<dyn Authenticator>>>,
     socket_address: SocketAddr,
+    identity: Identity,
+    load_tracker: Arc<LoadTracker>,
+    nonce_cache: Arc<NonceCache>,
 }
 
 impl ProxyGrpcServer {
     pub fn new(
         authenticator: Arc<RwLock<Box<dyn Authenticator>>>,
         socket_address: SocketAddr,
+        identity: Identity,
+        load_tracker: Arc<LoadTracker>,
+        nonce_cache: Arc<NonceCache>,
     ) -> Self {
         Self {
             authenticator,
             socket_address,
+            identity,
+            load_tracker,
+            nonce_cache,
         }
     }
 
     pub async fn start(&self) -> Result<(), Box<dyn Error>> {
-        let proxy_service = ProxyServiceImpl::new(Arc::clone(&self.authenticator));
+        let proxy_service = ProxyServiceImpl::new(
+            Arc::clone(&self.authenticator),
+            self.identity.clone(),
+            Arc::clone(&self.load_tracker),
+            Arc::clone(&self.nonce_cache),
+        );
         let identity = service::load_tls_identity("server.crt", "server.key").unwrap();
         let tls_config = ServerTlsConfig::new().identity(identity);
 
@@ -38,6 +55,7 @@ impl ProxyGrpcServer {
             .tls_config(tls_config)
             .unwrap()
             .layer(tracing::UriTracingLayer)
+            .layer(rate_limit::RateLimitLayer::get_from_env())
             .add_service(ProxyServiceServer::new(proxy_service))
             .serve(self.socket_address)
             .await?;
@@ -49,8 +67,17 @@ impl ProxyGrpcServer {
 pub fn start_server_handler(
     socket_address: SocketAddr,
     authenticator: Arc<RwLock<Box<dyn Authenticator>>>,
+    identity: Identity,
+    load_tracker: Arc<LoadTracker>,
+    nonce_cache: Arc<NonceCache>,
 ) -> tokio::task::JoinHandle<()> {
-    let grpc_server = ProxyGrpcServer::new(authenticator, socket_address);
+    let grpc_server = ProxyGrpcServer::new(
+        authenticator,
+        socket_address,
+        identity,
+        load_tracker,
+        nonce_cache,
+    );
     tokio::spawn(async move {
         if let Err(e) = grpc_server.start().await {
             error!("gRPC server error: {}", e);
@@ -9,17 +9,33 @@ use crate::{
     },
     models::info_proto::StatusResponse,
 };
+use crosscutting::{
+    ConnectionSettings,
+    crypto::{self, Identity},
+};
 use gateway::auth_client::Authenticator;
-use crosscutting::ConnectionSettings;
-use gateway::proxy_client::{ProxyClientFactory, ProxyFactory, proxy::CommandType};
-use gateway::route_client::{RouteClientFactory, RouterFactory};
+use gateway::load::LoadTracker;
+use gateway::proxy_client::{
+    ProxyClientFactory, ProxyFactory,
+    proxy::{CommandType, OnionHop, SourceRoute},
+};
+use gateway::route_client::{RouteClientFactory, RouterFactory, route::SpentNonce};
+use gateway::{
+    group, onion,
+    ticket::{self, NonceCache},
+};
+use log::warn;
+use prost::Message;
 
 pub struct ProxyServiceImpl {
     authenticator: Arc<RwLock<Box<dyn Authenticator>>>,
+    identity: Identity,
     router_factory: Box<dyn RouterFactory>,
     informer_factory: Box<dyn InformerFactory>,
     lander_factory: Box<dyn LanderFactory>,
     proxy_factory: Box<dyn ProxyFactory>,
+    load_tracker: Arc<LoadTracker>,
+    nonce_cache: Arc<NonceCache>,
 }
 
 #[tonic::async_trait]
@@ -29,23 +45,65 @@ impl ProxyService for ProxyServiceImpl {
         request: Request<CommandRequest>,
     ) -> Result<Response<CommandResponse>, Status> {
         let req = request.into_inner();
+        let _load_guard = self.load_tracker.track();
         let access_key = self.check_authentication().await?;
         let content = req.content.unwrap_or_default();
+
+        if req.command == CommandType::Onion as i32 {
+            self.relay(access_key, &content).await?;
+            let response = CommandResponse {
+                result: Some("Message relayed".into()),
+            };
+            return Ok(Response::new(response));
+        }
+
+        if req.command == CommandType::SourceRouted as i32 {
+            self.forward(access_key, &content).await?;
+            let response = CommandResponse {
+                result: Some("Message forwarded".into()),
+            };
+            return Ok(Response::new(response));
+        }
+
         let nonce = req.nonce;
         let conversation_id = req.conversation_id;
 
-        self.redeem(conversation_id.clone(), access_key.clone(), nonce)
-            .await?;
+        self.spend(
+            &conversation_id,
+            access_key.clone(),
+            nonce,
+            &req.nonce_ticket,
+        )
+        .await?;
 
         let result: Option<String> = match CommandType::try_from(req.command) {
             Ok(CommandType::Status) => {
                 let status = self.status(access_key).await?;
                 Some(format!("Status: {:?}", status))
             }
+            Ok(CommandType::Who) => {
+                let uids = String::from_utf8_lossy(&content)
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect();
+                let online = self
+                    .online_contacts(access_key, conversation_id, uids)
+                    .await?;
+                if online.is_empty() {
+                    Some("No contacts online".into())
+                } else {
+                    Some(format!("Online contacts: {}", online.join(", ")))
+                }
+            }
             Ok(CommandType::Send) => {
                 self.send(conversation_id, access_key, &content).await?;
                 Some("Message sent".into())
             }
+            Ok(CommandType::GroupSend) => {
+                self.send_group(conversation_id, access_key, &content)
+                    .await?;
+                Some("Group message sent".into())
+            }
             _ => None,
         };
 
@@ -55,16 +113,33 @@ impl ProxyService for ProxyServiceImpl {
 }
 
 impl ProxyServiceImpl {
-    pub fn new(authenticator: Arc<RwLock<Box<dyn Authenticator>>>) -> Self {
+    pub fn new(
+        authenticator: Arc<RwLock<Box<dyn Authenticator>>>,
+        identity: Identity,
+        load_tracker: Arc<LoadTracker>,
+        nonce_cache: Arc<NonceCache>,
+    ) -> Self {
         ProxyServiceImpl {
             authenticator,
+            identity,
             router_factory: Box::new(RouteClientFactory),
             informer_factory: Box::new(InfoClientFactory),
             lander_factory: Box::new(LandingClientFactory),
             proxy_factory: Box::new(ProxyClientFactory),
+            load_tracker,
+            nonce_cache,
         }
     }
 
+    async fn get_ticket_key(&self) -> Vec<u8> {
+        self.authenticator
+            .read()
+            .await
+            .get_session()
+            .await
+            .ticket_key
+    }
+
     async fn check_authentication(&self) -> Result<String, Status> {
         let authenticator = self.authenticator.write().await;
         if !authenticator.is_authenticated().await {
@@ -87,6 +162,74 @@ impl ProxyServiceImpl {
             .map_err(|_| Status::internal("Failed to get status"))
     }
 
+    async fn online_contacts(
+        &self,
+        access_key: String,
+        conversation_id: String,
+        uids: Vec<String>,
+    ) -> Result<Vec<String>, Status> {
+        let mut informer = self.informer_factory.get_informer();
+        informer
+            .initialize()
+            .await
+            .map_err(|_| Status::internal("Failed to initialize the informer"))?;
+        informer
+            .get_online_contacts(access_key, conversation_id, uids)
+            .await
+            .map_err(|_| Status::internal("Failed to get online contacts"))
+    }
+
+    /// Redeems a nonce locally when the controller signed it for this proxy,
+    /// or at the controller otherwise.
+    async fn spend(
+        &self,
+        conversation_id: &str,
+        access_key: String,
+        nonce: String,
+        nonce_ticket: &[u8],
+    ) -> Result<(), Status> {
+        if nonce_ticket.is_empty() {
+            return self
+                .redeem(conversation_id.to_string(), access_key, nonce)
+                .await;
+        }
+
+        self.spend_ticket(&nonce, nonce_ticket, Some(conversation_id))
+            .await
+    }
+
+    /// Redeems a signed nonce without asking the controller. The ticket only
+    /// refers to its conversation through a reference sealed for the
+    /// controller, which is reported along with the nonce, and a tag checked
+    /// against the conversation whenever the command names it.
+    async fn spend_ticket(
+        &self,
+        nonce: &str,
+        nonce_ticket: &[u8],
+        conversation_id: Option<&str>,
+    ) -> Result<(), Status> {
+        let ticket_key = self.get_ticket_key().await;
+        let ticket = ticket::open_nonce(&ticket_key, &self.identity, nonce_ticket)
+            .ok()
+            .filter(|ticket| ticket.nonce == nonce)
+            .filter(|ticket| {
+                conversation_id.is_none_or(|conversation_id| {
+                    ticket.conversation_tag == crypto::conversation_tag(nonce, conversation_id)
+                })
+            })
+            .ok_or_else(|| Status::permission_denied("Invalid nonce ticket"))?;
+        let spent = SpentNonce {
+            conversation_id: String::default(),
+            nonce: ticket.nonce,
+            conversation_ref: ticket.conversation_ref,
+        };
+        if !self.nonce_cache.redeem(spent, ticket.expires_at) {
+            return Err(Status::permission_denied("Nonce was already redeemed"));
+        }
+
+        Ok(())
+    }
+
     async fn redeem(
         &self,
         conversation_id: String,
@@ -148,8 +291,184 @@ impl ProxyServiceImpl {
                 .await;
         }
 
-        self.route_command(&connection_settings, conversation_id, route.nonce, content)
+        self.route_command(
+            &connection_settings,
+            conversation_id,
+            route.nonce,
+            route.nonce_ticket,
+            CommandType::Send,
+            content,
+        )
+        .await
+    }
+
+    async fn send_group(
+        &self,
+        conversation_id: String,
+        access_key: String,
+        content: &[u8],
+    ) -> Result<(), Status> {
+        let mut router = self.router_factory.get_router();
+        router
+            .initialize()
+            .await
+            .map_err(|_| Status::internal("Failed to initialize the router"))?;
+        let route = router
+            .get_route(conversation_id.clone(), access_key.clone())
             .await
+            .map_err(|_| Status::internal("Failed to get route"))?;
+
+        if !route.end_route {
+            let connection_settings = ConnectionSettings {
+                ip: route.ip_address.clone(),
+                port: route.port_number as u16,
+                domain_name: route.domain_name.clone(),
+                certificate: route.public_key.clone(),
+            };
+
+            return self
+                .route_command(
+                    &connection_settings,
+                    conversation_id,
+                    route.nonce,
+                    route.nonce_ticket,
+                    CommandType::GroupSend,
+                    content,
+                )
+                .await;
+        }
+
+        let copies = group::open(content)
+            .map_err(|_| Status::invalid_argument("Failed to open the group envelope"))?;
+
+        debug!("Fanning out group message to {} members", copies.len());
+        for copy in copies {
+            if let Err(status) = self
+                .send(
+                    copy.conversation_id.clone(),
+                    access_key.clone(),
+                    &copy.content,
+                )
+                .await
+            {
+                warn!(
+                    "Failed to deliver group copy {}: {}",
+                    copy.conversation_id,
+                    status.message()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn relay(&self, access_key: String, content: &[u8]) -> Result<(), Status> {
+        let layer = onion::peel(&self.identity, content)
+            .map_err(|_| Status::invalid_argument("Failed to peel the onion layer"))?;
+
+        // Onion layers can only be redeemed locally, as they don't disclose the
+        // conversation to the proxy
+        self.spend_ticket(&layer.nonce, &layer.nonce_ticket, None)
+            .await?;
+
+        let next_hop: OnionHop = layer.next_hop.unwrap();
+        let connection_settings = ConnectionSettings {
+            ip: next_hop.ip_address.clone(),
+            port: next_hop.port_number as u16,
+            domain_name: next_hop.domain_name.clone(),
+            certificate: next_hop.public_key.clone(),
+        };
+
+        debug!(
+            "Relaying onion to: {:}",
+            connection_settings.get_public_endpoint()
+        );
+
+        if next_hop.end_route {
+            return self
+                .land_command(
+                    &connection_settings,
+                    next_hop.conversation_id,
+                    next_hop.nonce,
+                    access_key,
+                    &layer.payload,
+                )
+                .await;
+        }
+
+        self.route_command(
+            &connection_settings,
+            String::default(),
+            String::default(),
+            Vec::new(),
+            CommandType::Onion,
+            &layer.payload,
+        )
+        .await
+    }
+
+    async fn forward(&self, access_key: String, content: &[u8]) -> Result<(), Status> {
+        let route = SourceRoute::decode(content)
+            .map_err(|_| Status::invalid_argument("Failed to decode the source route"))?;
+        let (signed, tickets) = route
+            .tickets
+            .split_first()
+            .ok_or_else(|| Status::invalid_argument("Source route has no tickets"))?;
+
+        let ticket_key = self.get_ticket_key().await;
+        let ticket = ticket::open(&ticket_key, &self.identity, signed).map_err(|e| {
+            warn!("Rejected route ticket: {}", e);
+            Status::permission_denied("Invalid route ticket")
+        })?;
+        let spent = SpentNonce {
+            conversation_id: String::default(),
+            nonce: ticket.nonce.clone(),
+            conversation_ref: ticket.conversation_ref,
+        };
+        if !self.nonce_cache.redeem(spent, ticket.expires_at) {
+            return Err(Status::permission_denied(
+                "Route ticket was already redeemed",
+            ));
+        }
+
+        let next_hop = ticket.next_hop.unwrap();
+        let connection_settings = ConnectionSettings {
+            ip: next_hop.ip_address.clone(),
+            port: next_hop.port_number as u16,
+            domain_name: next_hop.domain_name.clone(),
+            certificate: next_hop.public_key.clone(),
+        };
+
+        debug!(
+            "Forwarding source routed command to: {:}",
+            connection_settings.get_public_endpoint()
+        );
+
+        if next_hop.end_route {
+            return self
+                .land_command(
+                    &connection_settings,
+                    ticket.conversation_id,
+                    next_hop.nonce,
+                    access_key,
+                    &route.payload,
+                )
+                .await;
+        }
+
+        let route = SourceRoute {
+            tickets: tickets.to_vec(),
+            payload: route.payload,
+        };
+        self.route_command(
+            &connection_settings,
+            String::default(),
+            String::default(),
+            Vec::new(),
+            CommandType::SourceRouted,
+            &route.encode_to_vec(),
+        )
+        .await
     }
 
     async fn land_command(
@@ -182,6 +501,8 @@ impl ProxyServiceImpl {
         connection_settings: &ConnectionSettings,
         conversation_id: String,
         nonce: String,
+        nonce_ticket: Vec<u8>,
+        command: CommandType,
         content: &[u8],
     ) -> Result<(), Status> {
         let mut proxy_client = self.proxy_factory.get_proxy(
@@ -194,7 +515,13 @@ impl ProxyServiceImpl {
         })?;
 
         _ = proxy_client
-            .send_command(conversation_id, nonce, CommandType::Send, content.to_vec())
+            .send_command(
+                conversation_id,
+                nonce,
+                nonce_ticket,
+                command,
+                content.to_vec(),
+            )
             .await
             .map_err(|e| Status::internal(format!("Failed to route command: {}", e)))?;
 
@@ -215,16 +542,17 @@ mod tests {
 
     use super::*;
     use gateway::auth_client::{ClientSession, MockAuthenticator};
-    use protoc_rust::Error;
     use gateway::{
         proxy_client,
-        proxy_client::{MockProxy, MockProxyFactory},
+        proxy_client::{MockProxy, MockProxyFactory, proxy::OnionLayer},
         route_client::{
             MockRouter, MockRouterFactory,
-            route::{RedeemResponse, RouteResponse, SourceInfo},
+            route::{
+                GroupRecipient, HopTicket, NonceTicket, RedeemResponse, RouteResponse, SourceInfo,
+            },
         },
     };
-    use std::io::ErrorKind;
+    use protoc_rust::Error;
 
     const EXPECTED_UID: &str = "L.KD<FCjkSA6AEg@";
     const EXPECTED_ACCESS_KEY: &str = "test_access_key";
@@ -245,11 +573,14 @@ mod tests {
             Arc::new(RwLock::new(Box::new(mock_authenticator)));
 
         let proxy_service = ProxyServiceImpl {
-            authenticator: authenticator,
+            authenticator,
+            identity: Identity::generate(),
             router_factory: Box::new(MockRouterFactory::new()),
             informer_factory: Box::new(MockInformerFactory::new()),
             lander_factory: Box::new(MockLanderFactory::new()),
             proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
         };
 
         let request = Request::new(CommandRequest {
@@ -257,6 +588,7 @@ mod tests {
             content: None,
             nonce: EXPECTED_NONCE.to_string(),
             conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            nonce_ticket: Vec::new(),
         });
 
         let response = proxy_service.execute_command(request).await;
@@ -277,6 +609,7 @@ mod tests {
                 ClientSession {
                     uid: Some(EXPECTED_UID.to_string()),
                     access_key: Some(EXPECTED_ACCESS_KEY.to_string()),
+                    ticket_key: Vec::new(),
                 }
             })
         });
@@ -293,8 +626,7 @@ mod tests {
                 )
                 .returning(|_, _, _| {
                     Box::pin(async {
-                        Err(Box::<dyn std::error::Error>::from(Error::new(
-                            ErrorKind::Other,
+                        Err(Box::<dyn std::error::Error>::from(Error::other(
                             "Redeem failed",
                         )))
                     })
@@ -304,10 +636,13 @@ mod tests {
 
         let proxy_service = ProxyServiceImpl {
             authenticator: Arc::new(RwLock::new(Box::new(mock_authenticator))),
+            identity: Identity::generate(),
             router_factory: Box::new(router_factory),
             informer_factory: Box::new(MockInformerFactory::new()),
             lander_factory: Box::new(MockLanderFactory::new()),
             proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
         };
 
         let request = Request::new(CommandRequest {
@@ -315,6 +650,7 @@ mod tests {
             content: None,
             nonce: EXPECTED_NONCE.to_string(),
             conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            nonce_ticket: Vec::new(),
         });
 
         let response = proxy_service.execute_command(request).await;
@@ -336,6 +672,7 @@ mod tests {
                 ClientSession {
                     uid: Some(EXPECTED_UID.to_string()),
                     access_key: Some(EXPECTED_ACCESS_KEY.to_string()),
+                    ticket_key: Vec::new(),
                 }
             })
         });
@@ -348,6 +685,8 @@ mod tests {
                     Ok(RedeemResponse {
                         source_info: Some(SourceInfo {
                             from: EXPECTED_SENDER_UID.to_string(),
+                            identity_key: vec![],
+                            ..Default::default()
                         }),
                     })
                 })
@@ -357,10 +696,13 @@ mod tests {
 
         let proxy_service = ProxyServiceImpl {
             authenticator: Arc::new(RwLock::new(Box::new(mock_authenticator))),
+            identity: Identity::generate(),
             router_factory: Box::new(router_factory),
             informer_factory: Box::new(MockInformerFactory::new()),
             lander_factory: Box::new(MockLanderFactory::new()),
             proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
         };
 
         let request = Request::new(CommandRequest {
@@ -368,6 +710,7 @@ mod tests {
             content: None,
             nonce: EXPECTED_NONCE.to_string(),
             conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            nonce_ticket: Vec::new(),
         });
 
         let response = proxy_service.execute_command(request).await;
@@ -390,6 +733,7 @@ mod tests {
                 ClientSession {
                     uid: Some(EXPECTED_UID.to_string()),
                     access_key: Some(EXPECTED_ACCESS_KEY.to_string()),
+                    ticket_key: Vec::new(),
                 }
             })
         });
@@ -402,6 +746,8 @@ mod tests {
                     Ok(RedeemResponse {
                         source_info: Some(SourceInfo {
                             from: EXPECTED_SENDER_UID.to_string(),
+                            identity_key: vec![],
+                            ..Default::default()
                         }),
                     })
                 })
@@ -419,6 +765,7 @@ mod tests {
                     Box::pin(async {
                         Ok(StatusResponse {
                             connected_clients: 5,
+                            client_sessions: 7,
                             connected_controllers: 3,
                             connected_proxies: 10,
                             version: "1.0.0".to_string(),
@@ -430,10 +777,13 @@ mod tests {
 
         let proxy_service = ProxyServiceImpl {
             authenticator: Arc::new(RwLock::new(Box::new(mock_authenticator))),
+            identity: Identity::generate(),
             router_factory: Box::new(router_factory),
             informer_factory: Box::new(informer_factory),
             lander_factory: Box::new(MockLanderFactory::new()),
             proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
         };
 
         let request = Request::new(CommandRequest {
@@ -441,6 +791,7 @@ mod tests {
             content: None,
             nonce: EXPECTED_NONCE.to_string(),
             conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            nonce_ticket: Vec::new(),
         });
 
         let response = proxy_service.execute_command(request).await;
@@ -450,6 +801,72 @@ mod tests {
         assert!(cmd_response.result.is_some());
     }
 
+    #[tokio::test]
+    async fn given_authenticated_proxy_and_redeem_succeeds_when_execute_who_command_then_returns_online_contacts()
+     {
+        let mut mock_authenticator = MockAuthenticator::new();
+        mock_authenticator
+            .expect_is_authenticated()
+            .returning(|| Box::pin(async { true }));
+
+        mock_authenticator.expect_get_session().returning(move || {
+            Box::pin(async {
+                ClientSession {
+                    uid: Some(EXPECTED_UID.to_string()),
+                    access_key: Some(EXPECTED_ACCESS_KEY.to_string()),
+                    ticket_key: Vec::new(),
+                }
+            })
+        });
+
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().returning(|| {
+            let mut mock_router = MockRouter::new();
+            mock_router
+                .expect_redeem()
+                .returning(|_, _, _| Box::pin(async { Ok(RedeemResponse { source_info: None }) }));
+            Box::new(mock_router)
+        });
+
+        let mut informer_factory = MockInformerFactory::new();
+        informer_factory.expect_get_informer().returning(|| {
+            let mut mock_informer = MockInformer::new();
+            mock_informer
+                .expect_get_online_contacts()
+                .with(
+                    mockall::predicate::eq(EXPECTED_ACCESS_KEY.to_string()),
+                    mockall::predicate::eq(EXPECTED_CONVERSATION_ID.to_string()),
+                    mockall::predicate::eq(vec!["client2".to_string(), "client3".to_string()]),
+                )
+                .returning(|_, _, _| Box::pin(async { Ok(vec!["client2".to_string()]) }));
+            Box::new(mock_informer)
+        });
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(mock_authenticator))),
+            identity: Identity::generate(),
+            router_factory: Box::new(router_factory),
+            informer_factory: Box::new(informer_factory),
+            lander_factory: Box::new(MockLanderFactory::new()),
+            proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let request = Request::new(CommandRequest {
+            command: CommandType::Who as i32,
+            content: Some(b"client2 client3".to_vec()),
+            nonce: EXPECTED_NONCE.to_string(),
+            conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            nonce_ticket: Vec::new(),
+        });
+
+        let response = proxy_service.execute_command(request).await;
+
+        let cmd_response = response.unwrap().into_inner();
+        assert_eq!(cmd_response.result.unwrap(), "Online contacts: client2");
+    }
+
     #[tokio::test]
     async fn given_authenticated_proxy_and_redeem_succeeds_without_final_destination_when_execute_send_command_then_returns_success()
      {
@@ -463,6 +880,7 @@ mod tests {
                 ClientSession {
                     uid: Some(EXPECTED_UID.to_string()),
                     access_key: Some(EXPECTED_ACCESS_KEY.to_string()),
+                    ticket_key: Vec::new(),
                 }
             })
         });
@@ -475,6 +893,8 @@ mod tests {
                     Ok(RedeemResponse {
                         source_info: Some(SourceInfo {
                             from: EXPECTED_SENDER_UID.to_string(),
+                            identity_key: vec![],
+                            ..Default::default()
                         }),
                     })
                 })
@@ -489,6 +909,8 @@ mod tests {
                         nonce: EXPECTED_NONCE.to_string(),
                         public_key: EXPECTED_PUBLIC_KEY.to_vec(),
                         domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+                        identity_key: vec![],
+                        nonce_ticket: Vec::new(),
                     })
                 })
             });
@@ -504,10 +926,11 @@ mod tests {
                 .with(
                     mockall::predicate::eq(EXPECTED_CONVERSATION_ID.to_string()),
                     mockall::predicate::eq(EXPECTED_NONCE.to_string()),
+                    mockall::predicate::eq(Vec::new()),
                     mockall::predicate::eq(CommandType::Send),
                     mockall::predicate::eq(b"Test message".to_vec()),
                 )
-                .returning(|_, _, _, _| {
+                .returning(|_, _, _, _, _| {
                     Box::pin(async {
                         Ok(proxy_client::CommandResponse {
                             result: Some("Message sent".to_string()),
@@ -519,10 +942,13 @@ mod tests {
 
         let proxy_service = ProxyServiceImpl {
             authenticator: Arc::new(RwLock::new(Box::new(mock_authenticator))),
+            identity: Identity::generate(),
             router_factory: Box::new(router_factory),
             informer_factory: Box::new(MockInformerFactory::new()),
             lander_factory: Box::new(MockLanderFactory::new()),
             proxy_factory: Box::new(proxy_factory),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
         };
 
         let request = Request::new(CommandRequest {
@@ -530,6 +956,7 @@ mod tests {
             content: Some(b"Test message".to_vec()),
             nonce: EXPECTED_NONCE.to_string(),
             conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            nonce_ticket: Vec::new(),
         });
 
         let response = proxy_service.execute_command(request).await;
@@ -553,6 +980,7 @@ mod tests {
                 ClientSession {
                     uid: Some(EXPECTED_UID.to_string()),
                     access_key: Some(EXPECTED_ACCESS_KEY.to_string()),
+                    ticket_key: Vec::new(),
                 }
             })
         });
@@ -565,6 +993,8 @@ mod tests {
                     Ok(RedeemResponse {
                         source_info: Some(SourceInfo {
                             from: EXPECTED_SENDER_UID.to_string(),
+                            identity_key: vec![],
+                            ..Default::default()
                         }),
                     })
                 })
@@ -579,6 +1009,8 @@ mod tests {
                         nonce: EXPECTED_NONCE.to_string(),
                         public_key: EXPECTED_PUBLIC_KEY.to_vec(),
                         domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+                        identity_key: vec![],
+                        nonce_ticket: Vec::new(),
                     })
                 })
             });
@@ -605,10 +1037,13 @@ mod tests {
 
         let proxy_service = ProxyServiceImpl {
             authenticator: Arc::new(RwLock::new(Box::new(mock_authenticator))),
+            identity: Identity::generate(),
             router_factory: Box::new(router_factory),
             informer_factory: Box::new(MockInformerFactory::new()),
             lander_factory: Box::new(lander_factory),
             proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
         };
 
         let request = Request::new(CommandRequest {
@@ -616,6 +1051,7 @@ mod tests {
             content: Some(b"Test message".to_vec()),
             nonce: EXPECTED_NONCE.to_string(),
             conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            nonce_ticket: Vec::new(),
         });
 
         let response = proxy_service.execute_command(request).await;
@@ -625,4 +1061,676 @@ mod tests {
         assert!(command_response.result.is_some());
         assert_eq!(command_response.result.unwrap(), "Message sent");
     }
+
+    #[tokio::test]
+    async fn given_fan_out_point_when_execute_group_send_command_then_lands_every_copy() {
+        let sender = Identity::generate();
+        let members = [Identity::generate(), Identity::generate()];
+        let recipients: Vec<GroupRecipient> = members
+            .iter()
+            .enumerate()
+            .map(|(index, member)| GroupRecipient {
+                conversation_id: format!("member_{}", index),
+                recipient_key: member.public().to_bytes(),
+            })
+            .collect();
+        let envelope = group::seal(&sender, &recipients, b"Test message").unwrap();
+
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().returning(|| {
+            let mut mock_router = MockRouter::new();
+            mock_router
+                .expect_redeem()
+                .returning(|_, _, _| Box::pin(async { Ok(RedeemResponse { source_info: None }) }));
+            mock_router.expect_get_route().returning(|_, _| {
+                Box::pin(async { Ok(create_hop(&Identity::generate(), EXPECTED_NONCE, true)) })
+            });
+
+            Box::new(mock_router)
+        });
+
+        let mut lander_factory = MockLanderFactory::new();
+        lander_factory
+            .expect_get_lander()
+            .times(2)
+            .returning(move |_, _, _| {
+                let mut mock_lander = MockLander::new();
+                mock_lander
+                    .expect_send_message()
+                    .withf(|conversation_id, _, nonce, _| {
+                        conversation_id.starts_with("member_") && nonce == EXPECTED_NONCE
+                    })
+                    .times(1)
+                    .returning(|_, _, _, _| Box::pin(async { Ok(TextResponse {}) }));
+                Box::new(mock_lander)
+            });
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(create_authenticated_mock()))),
+            identity: Identity::generate(),
+            router_factory: Box::new(router_factory),
+            informer_factory: Box::new(MockInformerFactory::new()),
+            lander_factory: Box::new(lander_factory),
+            proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let request = Request::new(CommandRequest {
+            command: CommandType::GroupSend as i32,
+            content: Some(envelope),
+            nonce: EXPECTED_NONCE.to_string(),
+            conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+            nonce_ticket: Vec::new(),
+        });
+
+        let response = proxy_service.execute_command(request).await;
+
+        assert!(response.is_ok());
+        assert_eq!(
+            response.unwrap().into_inner().result.unwrap(),
+            "Group message sent"
+        );
+    }
+
+    fn create_authenticated_mock() -> MockAuthenticator {
+        let mut mock_authenticator = MockAuthenticator::new();
+        mock_authenticator
+            .expect_is_authenticated()
+            .returning(|| Box::pin(async { true }));
+
+        mock_authenticator.expect_get_session().returning(move || {
+            Box::pin(async {
+                ClientSession {
+                    uid: Some(EXPECTED_UID.to_string()),
+                    access_key: Some(EXPECTED_ACCESS_KEY.to_string()),
+                    ticket_key: Vec::new(),
+                }
+            })
+        });
+
+        mock_authenticator
+    }
+
+    fn create_hop(identity: &Identity, nonce: &str, end_route: bool) -> RouteResponse {
+        RouteResponse {
+            end_route,
+            ip_address: "127.0.0.1".to_string(),
+            port_number: 8080,
+            nonce: nonce.to_string(),
+            public_key: EXPECTED_PUBLIC_KEY.to_vec(),
+            domain_name: EXPECTED_DOMAIN_NAME.to_string(),
+            identity_key: identity.public().to_bytes(),
+            nonce_ticket: Vec::new(),
+        }
+    }
+
+    fn create_nonce_ticket(controller: &Identity, holder: &Identity, nonce: &str) -> Vec<u8> {
+        let nonce_ticket = NonceTicket {
+            nonce: nonce.to_string(),
+            holder_key: holder.public().to_bytes(),
+            expires_at: i64::MAX,
+            conversation_ref: EXPECTED_CONVERSATION_ID.as_bytes().to_vec(),
+            conversation_tag: crypto::conversation_tag(nonce, EXPECTED_CONVERSATION_ID),
+        }
+        .encode_to_vec();
+        [controller.sign(&nonce_ticket), nonce_ticket].concat()
+    }
+
+    fn create_signed_hop(controller: &Identity, identity: &Identity, nonce: &str) -> RouteResponse {
+        RouteResponse {
+            nonce_ticket: create_nonce_ticket(controller, identity, nonce),
+            ..create_hop(identity, nonce, false)
+        }
+    }
+
+    #[tokio::test]
+    async fn given_onion_with_next_proxy_when_execute_onion_command_then_relays_peeled_layer() {
+        const NEXT_NONCE: &str = "next_nonce";
+
+        let controller = Identity::generate();
+        let identity = Identity::generate();
+        let next_identity = Identity::generate();
+        let hops = vec![
+            create_signed_hop(&controller, &identity, EXPECTED_NONCE),
+            create_signed_hop(&controller, &next_identity, NEXT_NONCE),
+            create_hop(&Identity::generate(), "landing_nonce", true),
+        ];
+        let (_, content) = onion::wrap(EXPECTED_CONVERSATION_ID, &hops, b"Test message").unwrap();
+
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().never();
+
+        let mut proxy_factory = MockProxyFactory::new();
+        proxy_factory.expect_get_proxy().returning(move |_, _, _| {
+            let next_identity = next_identity.clone();
+            let mut mock_proxy = MockProxy::new();
+            mock_proxy
+                .expect_send_command()
+                .withf(
+                    move |conversation_id, nonce, nonce_ticket, command, content| {
+                        conversation_id.is_empty()
+                            && nonce.is_empty()
+                            && nonce_ticket.is_empty()
+                            && *command == CommandType::Onion
+                            && onion::peel(&next_identity, content)
+                                .is_ok_and(|layer| layer.nonce == NEXT_NONCE)
+                    },
+                )
+                .returning(|_, _, _, _, _| {
+                    Box::pin(async {
+                        Ok(proxy_client::CommandResponse {
+                            result: Some("Message relayed".to_string()),
+                        })
+                    })
+                });
+            Box::new(mock_proxy)
+        });
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(create_source_routing_mock(
+                controller.public().to_bytes(),
+            )))),
+            identity,
+            router_factory: Box::new(router_factory),
+            informer_factory: Box::new(MockInformerFactory::new()),
+            lander_factory: Box::new(MockLanderFactory::new()),
+            proxy_factory: Box::new(proxy_factory),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let request = Request::new(CommandRequest {
+            command: CommandType::Onion as i32,
+            content: Some(content),
+            nonce: String::default(),
+            conversation_id: String::default(),
+            nonce_ticket: Vec::new(),
+        });
+
+        let response = proxy_service.execute_command(request).await;
+
+        assert!(response.is_ok());
+        assert_eq!(
+            response.unwrap().into_inner().result.unwrap(),
+            "Message relayed"
+        );
+    }
+
+    #[tokio::test]
+    async fn given_onion_with_final_destination_when_execute_onion_command_then_lands_payload() {
+        const LANDING_NONCE: &str = "landing_nonce";
+
+        let controller = Identity::generate();
+        let identity = Identity::generate();
+        let hops = vec![
+            create_signed_hop(&controller, &identity, EXPECTED_NONCE),
+            create_hop(&Identity::generate(), LANDING_NONCE, true),
+        ];
+        let (_, content) = onion::wrap(EXPECTED_CONVERSATION_ID, &hops, b"Test message").unwrap();
+
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().never();
+
+        let mut lander_factory = MockLanderFactory::new();
+        lander_factory
+            .expect_get_lander()
+            .returning(move |_, _, _| {
+                let mut mock_lander = MockLander::new();
+                mock_lander
+                    .expect_send_message()
+                    .with(
+                        mockall::predicate::eq(EXPECTED_CONVERSATION_ID.to_string()),
+                        mockall::predicate::eq(EXPECTED_ACCESS_KEY.to_string()),
+                        mockall::predicate::eq(LANDING_NONCE.to_string()),
+                        mockall::predicate::eq(b"Test message".to_vec()),
+                    )
+                    .returning(|_, _, _, _| Box::pin(async { Ok(TextResponse {}) }));
+                Box::new(mock_lander)
+            });
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(create_source_routing_mock(
+                controller.public().to_bytes(),
+            )))),
+            identity,
+            router_factory: Box::new(router_factory),
+            informer_factory: Box::new(MockInformerFactory::new()),
+            lander_factory: Box::new(lander_factory),
+            proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let request = Request::new(CommandRequest {
+            command: CommandType::Onion as i32,
+            content: Some(content),
+            nonce: String::default(),
+            conversation_id: String::default(),
+            nonce_ticket: Vec::new(),
+        });
+
+        let response = proxy_service.execute_command(request).await;
+
+        assert!(response.is_ok());
+    }
+
+    #[tokio::test]
+    async fn given_onion_for_another_proxy_when_execute_onion_command_then_returns_invalid_argument()
+     {
+        let hops = vec![
+            create_signed_hop(&Identity::generate(), &Identity::generate(), EXPECTED_NONCE),
+            create_hop(&Identity::generate(), "landing_nonce", true),
+        ];
+        let (_, content) = onion::wrap(EXPECTED_CONVERSATION_ID, &hops, b"Test message").unwrap();
+
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().never();
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(create_authenticated_mock()))),
+            identity: Identity::generate(),
+            router_factory: Box::new(router_factory),
+            informer_factory: Box::new(MockInformerFactory::new()),
+            lander_factory: Box::new(MockLanderFactory::new()),
+            proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let request = Request::new(CommandRequest {
+            command: CommandType::Onion as i32,
+            content: Some(content),
+            nonce: String::default(),
+            conversation_id: String::default(),
+            nonce_ticket: Vec::new(),
+        });
+
+        let response = proxy_service.execute_command(request).await;
+
+        assert!(response.is_err());
+        assert_eq!(response.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn given_onion_without_signed_nonce_when_relaying_then_returns_permission_denied() {
+        let controller = Identity::generate();
+        let identity = Identity::generate();
+        let hops = vec![
+            create_hop(&identity, EXPECTED_NONCE, false),
+            create_hop(&Identity::generate(), "landing_nonce", true),
+        ];
+        let layer = OnionLayer {
+            nonce: EXPECTED_NONCE.to_string(),
+            next_hop: Some(OnionHop {
+                end_route: true,
+                ..Default::default()
+            }),
+            payload: b"Test message".to_vec(),
+            nonce_ticket: Vec::new(),
+        };
+        let content = crypto::seal_anonymous(&identity.public(), &layer.encode_to_vec()).unwrap();
+        assert!(onion::wrap(EXPECTED_CONVERSATION_ID, &hops, b"Test message").is_err());
+
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().never();
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(create_source_routing_mock(
+                controller.public().to_bytes(),
+            )))),
+            identity,
+            router_factory: Box::new(router_factory),
+            informer_factory: Box::new(MockInformerFactory::new()),
+            lander_factory: Box::new(MockLanderFactory::new()),
+            proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let request = Request::new(CommandRequest {
+            command: CommandType::Onion as i32,
+            content: Some(content),
+            nonce: String::default(),
+            conversation_id: String::default(),
+            nonce_ticket: Vec::new(),
+        });
+
+        let response = proxy_service.execute_command(request).await;
+
+        assert!(response.is_err());
+        assert_eq!(response.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    fn create_source_routing_mock(ticket_key: Vec<u8>) -> MockAuthenticator {
+        let mut mock_authenticator = MockAuthenticator::new();
+        mock_authenticator
+            .expect_is_authenticated()
+            .returning(|| Box::pin(async { true }));
+
+        mock_authenticator.expect_get_session().returning(move || {
+            let ticket_key = ticket_key.clone();
+            Box::pin(async move {
+                ClientSession {
+                    uid: Some(EXPECTED_UID.to_string()),
+                    access_key: Some(EXPECTED_ACCESS_KEY.to_string()),
+                    ticket_key,
+                }
+            })
+        });
+
+        mock_authenticator
+    }
+
+    fn create_ticket(controller: &Identity, holder: &Identity, next_hop: RouteResponse) -> Vec<u8> {
+        let expires_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+            + 60;
+        let landing_conversation_id = if next_hop.end_route {
+            EXPECTED_CONVERSATION_ID.to_string()
+        } else {
+            String::default()
+        };
+        let ticket = HopTicket {
+            conversation_id: landing_conversation_id,
+            nonce: EXPECTED_NONCE.to_string(),
+            holder_key: holder.public().to_bytes(),
+            next_hop: Some(next_hop),
+            expires_at,
+            conversation_ref: EXPECTED_CONVERSATION_ID.as_bytes().to_vec(),
+        }
+        .encode_to_vec();
+        [controller.sign(&ticket), ticket].concat()
+    }
+
+    #[tokio::test]
+    async fn given_source_route_with_next_proxy_when_execute_source_routed_command_then_forwards_remaining_tickets()
+     {
+        let controller = Identity::generate();
+        let identity = Identity::generate();
+        let next_identity = Identity::generate();
+        let route = SourceRoute {
+            tickets: vec![
+                create_ticket(
+                    &controller,
+                    &identity,
+                    create_hop(&next_identity, "", false),
+                ),
+                b"next_ticket".to_vec(),
+            ],
+            payload: b"Test message".to_vec(),
+        };
+
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().never();
+
+        let mut proxy_factory = MockProxyFactory::new();
+        proxy_factory.expect_get_proxy().returning(|_, _, _| {
+            let mut mock_proxy = MockProxy::new();
+            mock_proxy
+                .expect_send_command()
+                .withf(|conversation_id, nonce, nonce_ticket, command, content| {
+                    conversation_id.is_empty()
+                        && nonce.is_empty()
+                        && nonce_ticket.is_empty()
+                        && *command == CommandType::SourceRouted
+                        && SourceRoute::decode(content.as_slice()).is_ok_and(|route| {
+                            route.tickets == vec![b"next_ticket".to_vec()]
+                                && route.payload == b"Test message"
+                        })
+                })
+                .returning(|_, _, _, _, _| {
+                    Box::pin(async {
+                        Ok(proxy_client::CommandResponse {
+                            result: Some("Message forwarded".to_string()),
+                        })
+                    })
+                });
+            Box::new(mock_proxy)
+        });
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(create_source_routing_mock(
+                controller.public().to_bytes(),
+            )))),
+            identity,
+            router_factory: Box::new(router_factory),
+            informer_factory: Box::new(MockInformerFactory::new()),
+            lander_factory: Box::new(MockLanderFactory::new()),
+            proxy_factory: Box::new(proxy_factory),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let request = Request::new(CommandRequest {
+            command: CommandType::SourceRouted as i32,
+            content: Some(route.encode_to_vec()),
+            nonce: String::default(),
+            conversation_id: String::default(),
+            nonce_ticket: Vec::new(),
+        });
+
+        let response = proxy_service.execute_command(request).await;
+
+        assert!(response.is_ok());
+        assert_eq!(
+            response.unwrap().into_inner().result.unwrap(),
+            "Message forwarded"
+        );
+    }
+
+    #[tokio::test]
+    async fn given_source_route_with_final_destination_when_executing_twice_then_lands_payload_once()
+     {
+        const LANDING_NONCE: &str = "landing_nonce";
+
+        let controller = Identity::generate();
+        let identity = Identity::generate();
+        let route = SourceRoute {
+            tickets: vec![create_ticket(
+                &controller,
+                &identity,
+                create_hop(&Identity::generate(), LANDING_NONCE, true),
+            )],
+            payload: b"Test message".to_vec(),
+        };
+
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().never();
+
+        let mut lander_factory = MockLanderFactory::new();
+        lander_factory
+            .expect_get_lander()
+            .times(1)
+            .returning(move |_, _, _| {
+                let mut mock_lander = MockLander::new();
+                mock_lander
+                    .expect_send_message()
+                    .with(
+                        mockall::predicate::eq(EXPECTED_CONVERSATION_ID.to_string()),
+                        mockall::predicate::eq(EXPECTED_ACCESS_KEY.to_string()),
+                        mockall::predicate::eq(LANDING_NONCE.to_string()),
+                        mockall::predicate::eq(b"Test message".to_vec()),
+                    )
+                    .returning(|_, _, _, _| Box::pin(async { Ok(TextResponse {}) }));
+                Box::new(mock_lander)
+            });
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(create_source_routing_mock(
+                controller.public().to_bytes(),
+            )))),
+            identity,
+            router_factory: Box::new(router_factory),
+            informer_factory: Box::new(MockInformerFactory::new()),
+            lander_factory: Box::new(lander_factory),
+            proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let create_request = || {
+            Request::new(CommandRequest {
+                command: CommandType::SourceRouted as i32,
+                content: Some(route.encode_to_vec()),
+                nonce: String::default(),
+                conversation_id: String::default(),
+                nonce_ticket: Vec::new(),
+            })
+        };
+
+        let response = proxy_service.execute_command(create_request()).await;
+        assert!(response.is_ok());
+
+        let response = proxy_service.execute_command(create_request()).await;
+        assert!(response.is_err());
+        assert_eq!(response.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn given_forged_source_route_when_execute_source_routed_command_then_returns_permission_denied()
+     {
+        let identity = Identity::generate();
+        let route = SourceRoute {
+            tickets: vec![create_ticket(
+                &Identity::generate(),
+                &identity,
+                create_hop(&Identity::generate(), "landing_nonce", true),
+            )],
+            payload: b"Test message".to_vec(),
+        };
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(create_source_routing_mock(
+                Identity::generate().public().to_bytes(),
+            )))),
+            identity,
+            router_factory: Box::new(MockRouterFactory::new()),
+            informer_factory: Box::new(MockInformerFactory::new()),
+            lander_factory: Box::new(MockLanderFactory::new()),
+            proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let request = Request::new(CommandRequest {
+            command: CommandType::SourceRouted as i32,
+            content: Some(route.encode_to_vec()),
+            nonce: String::default(),
+            conversation_id: String::default(),
+            nonce_ticket: Vec::new(),
+        });
+
+        let response = proxy_service.execute_command(request).await;
+
+        assert!(response.is_err());
+        assert_eq!(response.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn given_signed_nonce_when_execute_send_command_then_redeems_locally_once() {
+        let controller = Identity::generate();
+        let identity = Identity::generate();
+        let nonce_ticket = create_nonce_ticket(&controller, &identity, EXPECTED_NONCE);
+
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().times(1).returning(|| {
+            let mut mock_router = MockRouter::new();
+            mock_router.expect_redeem().never();
+            mock_router.expect_get_route().returning(|_, _| {
+                Box::pin(async { Ok(create_hop(&Identity::generate(), "next_nonce", false)) })
+            });
+            Box::new(mock_router)
+        });
+
+        let mut proxy_factory = MockProxyFactory::new();
+        proxy_factory
+            .expect_get_proxy()
+            .times(1)
+            .returning(|_, _, _| {
+                let mut mock_proxy = MockProxy::new();
+                mock_proxy.expect_send_command().returning(|_, _, _, _, _| {
+                    Box::pin(async {
+                        Ok(proxy_client::CommandResponse {
+                            result: Some("Message sent".to_string()),
+                        })
+                    })
+                });
+                Box::new(mock_proxy)
+            });
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(create_source_routing_mock(
+                controller.public().to_bytes(),
+            )))),
+            identity,
+            router_factory: Box::new(router_factory),
+            informer_factory: Box::new(MockInformerFactory::new()),
+            lander_factory: Box::new(MockLanderFactory::new()),
+            proxy_factory: Box::new(proxy_factory),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let create_request = || {
+            Request::new(CommandRequest {
+                command: CommandType::Send as i32,
+                content: Some(b"Test message".to_vec()),
+                nonce: EXPECTED_NONCE.to_string(),
+                conversation_id: EXPECTED_CONVERSATION_ID.to_string(),
+                nonce_ticket: nonce_ticket.clone(),
+            })
+        };
+
+        let response = proxy_service.execute_command(create_request()).await;
+        assert!(response.is_ok());
+
+        let response = proxy_service.execute_command(create_request()).await;
+        assert!(response.is_err());
+        assert_eq!(response.unwrap_err().code(), tonic::Code::PermissionDenied);
+
+        let spent = proxy_service.nonce_cache.take_spent();
+        assert_eq!(spent.len(), 1);
+        assert_eq!(spent[0].0.nonce, EXPECTED_NONCE);
+    }
+
+    #[tokio::test]
+    async fn given_signed_nonce_of_another_conversation_when_execute_send_command_then_returns_permission_denied()
+     {
+        let controller = Identity::generate();
+        let identity = Identity::generate();
+        let nonce_ticket = create_nonce_ticket(&controller, &identity, EXPECTED_NONCE);
+
+        let mut router_factory = MockRouterFactory::new();
+        router_factory.expect_get_router().never();
+
+        let proxy_service = ProxyServiceImpl {
+            authenticator: Arc::new(RwLock::new(Box::new(create_source_routing_mock(
+                controller.public().to_bytes(),
+            )))),
+            identity,
+            router_factory: Box::new(router_factory),
+            informer_factory: Box::new(MockInformerFactory::new()),
+            lander_factory: Box::new(MockLanderFactory::new()),
+            proxy_factory: Box::new(MockProxyFactory::new()),
+            load_tracker: Arc::default(),
+            nonce_cache: Arc::default(),
+        };
+
+        let request = Request::new(CommandRequest {
+            command: CommandType::Send as i32,
+            content: Some(b"Test message".to_vec()),
+            nonce: EXPECTED_NONCE.to_string(),
+            conversation_id: "another_conversation_id".to_string(),
+            nonce_ticket,
+        });
+
+        let response = proxy_service.execute_command(request).await;
+
+        assert!(response.is_err());
+        assert_eq!(response.unwrap_err().code(), tonic::Code::PermissionDenied);
+        assert!(proxy_service.nonce_cache.take_spent().is_empty());
+    }
 }